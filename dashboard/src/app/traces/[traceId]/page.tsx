@@ -153,7 +153,8 @@ function StatusBadge({ status }: { status: string }) {
   const classes = {
     active: 'bg-emerald-50 text-emerald-700 ring-emerald-600/20',
     completed: 'bg-slate-50 text-slate-600 ring-slate-500/20',
-    failed: 'bg-red-50 text-red-700 ring-red-600/20',
+    denied: 'bg-amber-50 text-amber-700 ring-amber-600/20',
+    error: 'bg-red-50 text-red-700 ring-red-600/20',
   }[status] || 'bg-slate-50 text-slate-600 ring-slate-500/20';
 
   return (
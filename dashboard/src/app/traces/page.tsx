@@ -5,12 +5,14 @@ import { useTraces } from '@/hooks/useTraces';
 import { formatDistanceToNow, format } from 'date-fns';
 import Link from 'next/link';
 import { Icon } from '@iconify/react';
+import type { TraceOutcome } from '@/lib/types';
 
 export default function TracesPage() {
   const [filters, setFilters] = useState({
     correlation_id: '',
     agent_id: '',
     status: '',
+    outcome: '',
     limit: 20,
     offset: 0,
   });
@@ -46,7 +48,7 @@ export default function TracesPage() {
 
       {/* Filters */}
       <div className="bg-white border border-slate-200 rounded-xl p-5">
-        <div className="grid grid-cols-1 md:grid-cols-4 gap-4">
+        <div className="grid grid-cols-1 md:grid-cols-5 gap-4">
           <div>
             <label className="block text-sm font-medium text-slate-700 mb-1.5">Correlation ID</label>
             <input
@@ -77,12 +79,27 @@ export default function TracesPage() {
               <option value="">All statuses</option>
               <option value="active">Active</option>
               <option value="completed">Completed</option>
-              <option value="failed">Failed</option>
+              <option value="denied">Denied</option>
+              <option value="error">Error</option>
+            </select>
+          </div>
+          <div>
+            <label className="block text-sm font-medium text-slate-700 mb-1.5">Outcome</label>
+            <select
+              value={filters.outcome}
+              onChange={e => handleFilterChange('outcome', e.target.value)}
+              className="w-full px-3 py-2 bg-white border border-slate-300 rounded-lg text-slate-900 focus:outline-none focus:ring-2 focus:ring-pathwell-500 focus:border-transparent"
+            >
+              <option value="">All outcomes</option>
+              <option value="fully_allowed">Allowed</option>
+              <option value="partially_denied">Partially denied</option>
+              <option value="fully_denied">Denied</option>
+              <option value="errored">Errored</option>
             </select>
           </div>
           <div className="flex items-end">
             <button
-              onClick={() => setFilters({ correlation_id: '', agent_id: '', status: '', limit: 20, offset: 0 })}
+              onClick={() => setFilters({ correlation_id: '', agent_id: '', status: '', outcome: '', limit: 20, offset: 0 })}
               className="w-full px-4 py-2 border border-slate-300 text-slate-700 rounded-lg hover:bg-slate-50 transition-colors font-medium text-sm"
             >
               Clear Filters
@@ -120,6 +137,7 @@ export default function TracesPage() {
                   <th className="text-left px-6 py-3 text-xs font-medium text-slate-500 uppercase tracking-wider">Reference</th>
                   <th className="text-left px-6 py-3 text-xs font-medium text-slate-500 uppercase tracking-wider">Agent</th>
                   <th className="text-left px-6 py-3 text-xs font-medium text-slate-500 uppercase tracking-wider">Status</th>
+                  <th className="text-left px-6 py-3 text-xs font-medium text-slate-500 uppercase tracking-wider">Outcome</th>
                   <th className="text-left px-6 py-3 text-xs font-medium text-slate-500 uppercase tracking-wider">Events</th>
                   <th className="text-left px-6 py-3 text-xs font-medium text-slate-500 uppercase tracking-wider">Started</th>
                   <th className="text-left px-6 py-3 text-xs font-medium text-slate-500 uppercase tracking-wider">Last Activity</th>
@@ -142,6 +160,9 @@ export default function TracesPage() {
                     <td className="px-6 py-4">
                       <StatusBadge status={trace.status} />
                     </td>
+                    <td className="px-6 py-4">
+                      {trace.outcome ? <OutcomeBadge outcome={trace.outcome} /> : <span className="text-slate-400 text-sm">-</span>}
+                    </td>
                     <td className="px-6 py-4">
                       <span className="text-slate-900 text-sm">{trace.event_count}</span>
                       {trace.policy_deny_count > 0 && (
@@ -196,7 +217,8 @@ function StatusBadge({ status }: { status: string }) {
   const classes = {
     active: 'bg-emerald-50 text-emerald-700 ring-emerald-600/20',
     completed: 'bg-slate-50 text-slate-600 ring-slate-500/20',
-    failed: 'bg-red-50 text-red-700 ring-red-600/20',
+    denied: 'bg-amber-50 text-amber-700 ring-amber-600/20',
+    error: 'bg-red-50 text-red-700 ring-red-600/20',
   }[status] || 'bg-slate-50 text-slate-600 ring-slate-500/20';
 
   return (
@@ -205,3 +227,18 @@ function StatusBadge({ status }: { status: string }) {
     </span>
   );
 }
+
+function OutcomeBadge({ outcome }: { outcome: TraceOutcome }) {
+  const { label, classes } = {
+    fully_allowed: { label: 'Allowed', classes: 'bg-emerald-50 text-emerald-700 ring-emerald-600/20' },
+    partially_denied: { label: 'Partially denied', classes: 'bg-amber-50 text-amber-700 ring-amber-600/20' },
+    fully_denied: { label: 'Denied', classes: 'bg-red-50 text-red-700 ring-red-600/20' },
+    errored: { label: 'Errored', classes: 'bg-rose-50 text-rose-700 ring-rose-600/20' },
+  }[outcome];
+
+  return (
+    <span className={`inline-flex items-center px-2 py-0.5 rounded-md text-xs font-medium ring-1 ring-inset ${classes}`}>
+      {label}
+    </span>
+  );
+}
@@ -43,8 +43,9 @@ export async function fetchTimeline(traceId: string): Promise<TimelineEvent[]> {
   return response.json();
 }
 
-export async function fetchDecisionTree(traceId: string): Promise<DecisionTree> {
-  const response = await fetch(`${API_BASE}/v1/traces/${traceId}/decisions`);
+export async function fetchDecisionTree(traceId: string, collapse = false): Promise<DecisionTree> {
+  const query = collapse ? '?collapse=true' : '';
+  const response = await fetch(`${API_BASE}/v1/traces/${traceId}/decisions${query}`);
   if (!response.ok) {
     throw new Error(`Failed to fetch decision tree: ${response.statusText}`);
   }
@@ -1,7 +1,9 @@
+export type TraceOutcome = 'fully_allowed' | 'partially_denied' | 'fully_denied' | 'errored';
+
 export interface TraceSummary {
   trace_id: string;
   correlation_id: string | null;
-  status: 'active' | 'completed' | 'failed';
+  status: 'active' | 'completed' | 'denied' | 'error';
   started_at: string;
   last_event_at: string;
   event_count: number;
@@ -9,6 +11,7 @@ export interface TraceSummary {
   initiating_agent_id: string | null;
   initiating_developer_id: string | null;
   enterprise_id: string | null;
+  outcome: TraceOutcome | null;
 }
 
 export interface TraceListResponse {
@@ -16,6 +19,7 @@ export interface TraceListResponse {
   total: number;
   limit: number;
   offset: number;
+  next_cursor?: string | null;
 }
 
 export interface TraceDetailResponse {
@@ -66,8 +70,11 @@ export interface TraceQueryParams {
   agent_id?: string;
   enterprise_id?: string;
   status?: string;
+  outcome?: string;
+  search?: string;
   from?: string;
   to?: string;
   limit?: number;
   offset?: number;
+  cursor?: string;
 }
@@ -0,0 +1,29 @@
+-- Migration 003: Agent Roles
+-- Adds named agent roles and role membership for role-based policy input
+
+CREATE TABLE IF NOT EXISTS agent_roles (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    role_name VARCHAR(255) UNIQUE NOT NULL,
+    description TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_agent_roles_role_name ON agent_roles(role_name);
+
+CREATE TABLE IF NOT EXISTS agent_role_memberships (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    agent_id UUID NOT NULL REFERENCES agents(id),
+    role_id UUID NOT NULL REFERENCES agent_roles(id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT unique_agent_role UNIQUE (agent_id, role_id)
+);
+
+CREATE INDEX idx_agent_role_memberships_agent ON agent_role_memberships(agent_id);
+CREATE INDEX idx_agent_role_memberships_role ON agent_role_memberships(role_id);
+
+CREATE TRIGGER trigger_agent_roles_updated_at
+    BEFORE UPDATE ON agent_roles
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at();
@@ -0,0 +1,23 @@
+-- Migration 004: Agent Delegations
+-- Records which principals (agents or human developers) permit an agent to act
+-- on their behalf, so delegation chains can be validated at the gateway
+
+CREATE TABLE IF NOT EXISTS agent_delegations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    delegate_agent_id UUID NOT NULL REFERENCES agents(id),
+    principal_agent_id UUID REFERENCES agents(id),
+    principal_developer_id UUID REFERENCES developers(id),
+    expires_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Exactly one principal per grant
+    CONSTRAINT delegation_single_principal CHECK (
+        (principal_agent_id IS NULL) <> (principal_developer_id IS NULL)
+    ),
+    CONSTRAINT delegation_not_self CHECK (principal_agent_id IS DISTINCT FROM delegate_agent_id)
+);
+
+CREATE INDEX idx_agent_delegations_delegate ON agent_delegations(delegate_agent_id) WHERE revoked_at IS NULL;
+CREATE INDEX idx_agent_delegations_principal_agent ON agent_delegations(principal_agent_id);
+CREATE INDEX idx_agent_delegations_principal_developer ON agent_delegations(principal_developer_id);
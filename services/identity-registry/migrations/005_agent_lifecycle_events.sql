@@ -0,0 +1,20 @@
+-- Migration 005: Agent Lifecycle Events
+-- Append-only audit trail of agent registration, revocation, renewal and key rotation,
+-- written in the same transaction as the change to the agents row
+
+CREATE TABLE IF NOT EXISTS agent_lifecycle_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    agent_id UUID NOT NULL REFERENCES agents(id),
+    event_type VARCHAR(50) NOT NULL
+        CHECK (event_type IN ('registered', 'revoked', 'renewed', 'key_rotated')),
+    actor VARCHAR(255) NOT NULL,
+    reason TEXT,
+
+    -- Agent state around the change (NULL before registration)
+    before_state JSONB,
+    after_state JSONB NOT NULL,
+
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_agent_lifecycle_agent ON agent_lifecycle_events(agent_id, occurred_at);
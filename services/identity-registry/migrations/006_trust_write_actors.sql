@@ -0,0 +1,7 @@
+-- Migration 006: Trust Write Actors
+-- Who changed a trust score: the actor behind the API key used for each create, update or import
+
+ALTER TABLE trust_score_history ADD COLUMN IF NOT EXISTS actor VARCHAR(255);
+
+-- Last actor to write the score, so a score that was never updated still records its creator
+ALTER TABLE trust_scores ADD COLUMN IF NOT EXISTS updated_by VARCHAR(255);
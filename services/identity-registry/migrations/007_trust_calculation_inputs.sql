@@ -0,0 +1,4 @@
+-- Migration 007: Trust Calculation Inputs
+-- History rows keep the calculation inputs of the score they replaced, alongside its dimensions
+
+ALTER TABLE trust_score_history ADD COLUMN IF NOT EXISTS calculation_inputs JSONB;
@@ -0,0 +1,18 @@
+-- Migration 008: Trust Break-Glass Overrides
+-- Time-boxed overrides that let an agent, or every agent under a tenant, skip the trust threshold
+-- during an incident. Rows are kept after expiry or revocation as the audit trail
+
+CREATE TABLE IF NOT EXISTS trust_break_glass (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    target_type VARCHAR(20) NOT NULL CHECK (target_type IN ('agent', 'tenant')),
+    target_id UUID NOT NULL,
+    reason TEXT NOT NULL,
+    created_by VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ,
+    revoked_by VARCHAR(255)
+);
+
+CREATE INDEX idx_trust_break_glass_target ON trust_break_glass(target_type, target_id, expires_at)
+    WHERE revoked_at IS NULL;
@@ -0,0 +1,24 @@
+-- Migration 009: Agent Certificate Issuance Log
+-- Every certificate issued to an agent, at registration, renewal or key rotation. A certificate is
+-- active until it expires or is revoked; revoked_at may lie in the future while a superseded
+-- certificate's grace period runs
+
+CREATE TABLE IF NOT EXISTS agent_certificates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    agent_id UUID NOT NULL REFERENCES agents(id),
+    issued_reason VARCHAR(50) NOT NULL
+        CHECK (issued_reason IN ('registered', 'renewed', 'key_rotated')),
+    certificate_chain TEXT NOT NULL,
+    certificate_chain_hash VARCHAR(64) NOT NULL,
+    public_key_hash VARCHAR(64) NOT NULL,
+    issued_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    -- Set when a later certificate replaces this one
+    superseded_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_agent_certificates_agent ON agent_certificates(agent_id, issued_at);
+
+-- Agents registered before the log existed are backfilled at startup, using the validity certificates
+-- are issued with (see `backfill_certificate_log`)
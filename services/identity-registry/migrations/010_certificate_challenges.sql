@@ -0,0 +1,14 @@
+-- Migration 010: Certificate Challenges
+-- Single-use challenges an agent signs with its current key to prove it holds that key before a
+-- certificate renewal or key rotation. Used and expired challenges are cleared when the agent asks
+-- for another
+
+CREATE TABLE IF NOT EXISTS certificate_challenges (
+    challenge VARCHAR(64) PRIMARY KEY,
+    agent_id UUID NOT NULL REFERENCES agents(id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_certificate_challenges_agent ON certificate_challenges(agent_id);
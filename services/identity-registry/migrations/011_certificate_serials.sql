@@ -0,0 +1,9 @@
+-- Migration 011: Certificate Serial Numbers
+-- The serial number of each issued certificate, lowercase hex, so revocations can be published in the
+-- CA's certificate revocation list. Certificates issued before serials were recorded have none and are
+-- left off the list; renew them to get a listed certificate
+
+ALTER TABLE agent_certificates ADD COLUMN IF NOT EXISTS serial_number VARCHAR(40);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_agent_certificates_serial
+    ON agent_certificates(serial_number) WHERE serial_number IS NOT NULL;
@@ -0,0 +1,104 @@
+use axum::{
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::api::models::ErrorResponse;
+
+/// Header carrying the caller's API key
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Scope required to create, update or import trust scores
+pub const TRUST_WRITE_SCOPE: &str = "trust:write";
+
+/// Scope required to create or revoke break-glass overrides
+pub const BREAK_GLASS_SCOPE: &str = "trust:break_glass";
+
+/// Scope required to renew an agent's certificate or rotate its key
+pub const CERTIFICATE_SCOPE: &str = "certificates:write";
+
+/// Scope required to grant or revoke a delegation
+pub const DELEGATION_WRITE_SCOPE: &str = "delegation:write";
+
+/// Scope required to create roles and assign them to or remove them from agents
+pub const ROLE_WRITE_SCOPE: &str = "roles:write";
+
+/// Actor recorded for trust writes when authorization is disabled and no key is sent
+const ANONYMOUS_ACTOR: &str = "anonymous";
+
+/// Who an API key belongs to and what it may do
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiKey {
+    /// Recorded as the actor of every change made with the key
+    pub actor: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ApiKeyConfig {
+    keys: HashMap<String, ApiKey>,
+    /// Whether trust writes need a key with `trust:write`
+    pub require_trust_write: bool,
+}
+
+impl ApiKeyConfig {
+    pub fn from_env() -> Self {
+        Self {
+            keys: std::env::var("API_KEYS")
+                .ok()
+                .filter(|v| !v.trim().is_empty())
+                .map(|v| {
+                    serde_json::from_str(&v)
+                        .expect("API_KEYS must be a JSON object keyed by API key")
+                })
+                .unwrap_or_default(),
+            require_trust_write: std::env::var("TRUST_WRITE_AUTH_REQUIRED")
+                .map(|v| v != "false" && v != "0")
+                .unwrap_or(true),
+        }
+    }
+
+    /// Whether any key may write trust scores
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.keys.values().any(|key| key.scopes.iter().any(|s| s == scope))
+    }
+
+    /// The actor behind the request's API key, if the key grants `scope`.
+    /// With trust write authorization disabled, trust writes without a key are let through as `anonymous`;
+    /// every other scope, break-glass included, always needs a key
+    pub fn authorize(
+        &self,
+        headers: &HeaderMap,
+        scope: &str,
+    ) -> Result<String, (StatusCode, Json<ErrorResponse>)> {
+        let presented = headers.get(API_KEY_HEADER).and_then(|v| v.to_str().ok());
+        if presented.is_none() && scope == TRUST_WRITE_SCOPE && !self.require_trust_write {
+            return Ok(ANONYMOUS_ACTOR.to_string());
+        }
+
+        let key = presented.and_then(|k| self.keys.get(k)).ok_or_else(|| {
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(ErrorResponse {
+                    error: "unauthorized".to_string(),
+                    message: format!("A valid {} header is required", API_KEY_HEADER),
+                }),
+            )
+        })?;
+
+        if !key.scopes.iter().any(|s| s == scope) {
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(ErrorResponse {
+                    error: "insufficient_scope".to_string(),
+                    message: format!("API key for {} lacks the {} scope", key.actor, scope),
+                }),
+            ));
+        }
+
+        Ok(key.actor.clone())
+    }
+}
@@ -0,0 +1,253 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+use tracing::warn;
+use uuid::Uuid;
+use pathwell_common::json_body::JsonBody;
+
+use crate::api::auth::BREAK_GLASS_SCOPE;
+use crate::api::models::*;
+use crate::api::role_handlers::resolve_agent_uuid;
+use crate::api::routes::AppState;
+
+/// Override length when the request doesn't give one
+const DEFAULT_BREAK_GLASS_SECS: i64 = 3600;
+
+fn database_error(e: sqlx::Error) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse {
+            error: "database_error".to_string(),
+            message: e.to_string(),
+        }),
+    )
+}
+
+fn invalid_break_glass(message: String) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse {
+            error: "invalid_break_glass".to_string(),
+            message,
+        }),
+    )
+}
+
+/// Active tenants are the only ones an override can name
+async fn resolve_tenant_uuid(
+    pool: &PgPool,
+    tenant_id: &str,
+) -> Result<Uuid, (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query_scalar!(
+        "SELECT id FROM tenants WHERE tenant_id = $1 AND deactivated_at IS NULL",
+        tenant_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "tenant_not_found".to_string(),
+                message: format!("Tenant {} not found", tenant_id),
+            }),
+        )
+    })
+}
+
+/// The unexpired, unrevoked override covering an agent: one naming the agent, or its resolved
+/// tenant or any ancestor of it. When several apply, the one lasting longest is reported
+pub(crate) async fn active_break_glass(
+    pool: &PgPool,
+    agent_uuid: Uuid,
+) -> Result<Option<BreakGlassSummary>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT bg.id, bg.target_type, bg.reason, bg.expires_at
+        FROM trust_break_glass bg
+        WHERE bg.revoked_at IS NULL
+          AND bg.expires_at > NOW()
+          AND (
+              (bg.target_type = 'agent' AND bg.target_id = $1)
+              OR (bg.target_type = 'tenant' AND bg.target_id IN (
+                  SELECT anc.id
+                  FROM agents a
+                  LEFT JOIN developers d ON d.id = a.developer_id
+                  LEFT JOIN enterprises e ON e.id = a.enterprise_id
+                  JOIN tenants t ON t.id = COALESCE(a.tenant_id, d.tenant_id, e.tenant_id)
+                  JOIN tenants anc ON anc.id = t.id OR anc.tenant_id = ANY(t.hierarchy_path)
+                  WHERE a.id = $1
+              ))
+          )
+        ORDER BY bg.expires_at DESC
+        LIMIT 1
+        "#,
+        agent_uuid
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|row| BreakGlassSummary {
+        id: row.id,
+        target_type: row.target_type,
+        reason: row.reason,
+        expires_at: row.expires_at.to_rfc3339(),
+    }))
+}
+
+/// Neither revoked nor expired
+fn is_active(expires_at: DateTime<Utc>, revoked_at: Option<DateTime<Utc>>) -> bool {
+    revoked_at.is_none() && expires_at > Utc::now()
+}
+
+/// Let an agent, or every agent under a tenant, skip its trust threshold until the override expires
+pub async fn create_break_glass(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    JsonBody(payload): JsonBody<CreateBreakGlassRequest>,
+) -> Result<(StatusCode, Json<BreakGlassResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let actor = state.api_keys.authorize(&headers, BREAK_GLASS_SCOPE)?;
+    let pool = &state.pool;
+
+    if payload.reason.trim().is_empty() {
+        return Err(invalid_break_glass("A reason is required".to_string()));
+    }
+    let max_secs = state.break_glass_max_secs;
+    let duration_secs = payload
+        .duration_secs
+        .unwrap_or_else(|| DEFAULT_BREAK_GLASS_SECS.min(max_secs));
+    if duration_secs <= 0 || duration_secs > max_secs {
+        return Err(invalid_break_glass(format!(
+            "duration_secs must be between 1 and {}",
+            max_secs
+        )));
+    }
+
+    let target_uuid = match payload.target_type {
+        BreakGlassTarget::Agent => resolve_agent_uuid(pool, &payload.target_id).await?,
+        BreakGlassTarget::Tenant => resolve_tenant_uuid(pool, &payload.target_id).await?,
+    };
+
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO trust_break_glass (id, target_type, target_id, reason, created_by, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, created_at, expires_at
+        "#,
+        Uuid::new_v4(),
+        payload.target_type.as_str(),
+        target_uuid,
+        payload.reason,
+        actor,
+        Utc::now() + Duration::seconds(duration_secs)
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(database_error)?;
+
+    warn!(
+        "Break-glass override {} created by {} for {} {} until {}: {}",
+        row.id,
+        actor,
+        payload.target_type.as_str(),
+        payload.target_id,
+        row.expires_at.to_rfc3339(),
+        payload.reason
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(BreakGlassResponse {
+            id: row.id,
+            target_type: payload.target_type.as_str().to_string(),
+            target_id: payload.target_id,
+            reason: payload.reason,
+            created_by: actor,
+            created_at: row.created_at.to_rfc3339(),
+            expires_at: row.expires_at.to_rfc3339(),
+            revoked_at: None,
+            revoked_by: None,
+            active: is_active(row.expires_at, None),
+        }),
+    ))
+}
+
+/// Active overrides, newest first; with `include_inactive`, expired and revoked ones too
+pub async fn list_break_glass(
+    State(state): State<AppState>,
+    Query(query): Query<BreakGlassListQuery>,
+) -> Result<Json<BreakGlassListResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT bg.id, bg.target_type, bg.reason, bg.created_by, bg.created_at, bg.expires_at,
+               bg.revoked_at, bg.revoked_by,
+               COALESCE(a.agent_id, t.tenant_id, bg.target_id::text) AS "target_id!"
+        FROM trust_break_glass bg
+        LEFT JOIN agents a ON bg.target_type = 'agent' AND a.id = bg.target_id
+        LEFT JOIN tenants t ON bg.target_type = 'tenant' AND t.id = bg.target_id
+        WHERE $1 OR (bg.revoked_at IS NULL AND bg.expires_at > NOW())
+        ORDER BY bg.created_at DESC
+        "#,
+        query.include_inactive
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(database_error)?;
+
+    let overrides = rows
+        .into_iter()
+        .map(|row| BreakGlassResponse {
+            id: row.id,
+            target_type: row.target_type,
+            target_id: row.target_id,
+            reason: row.reason,
+            created_by: row.created_by,
+            created_at: row.created_at.to_rfc3339(),
+            expires_at: row.expires_at.to_rfc3339(),
+            active: is_active(row.expires_at, row.revoked_at),
+            revoked_at: row.revoked_at.map(|t| t.to_rfc3339()),
+            revoked_by: row.revoked_by,
+        })
+        .collect();
+
+    Ok(Json(BreakGlassListResponse { overrides }))
+}
+
+/// End an active override before it expires
+pub async fn revoke_break_glass(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(override_id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let actor = state.api_keys.authorize(&headers, BREAK_GLASS_SCOPE)?;
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE trust_break_glass SET revoked_at = NOW(), revoked_by = $2
+        WHERE id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+        "#,
+        override_id,
+        actor
+    )
+    .execute(&state.pool)
+    .await
+    .map_err(database_error)?;
+
+    if result.rows_affected() == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "break_glass_not_found".to_string(),
+                message: format!("No active break-glass override {}", override_id),
+            }),
+        ));
+    }
+
+    warn!("Break-glass override {} revoked by {}", override_id, actor);
+    Ok(StatusCode::NO_CONTENT)
+}
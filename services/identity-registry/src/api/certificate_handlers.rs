@@ -0,0 +1,586 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use time::OffsetDateTime;
+use sqlx::{PgConnection, PgExecutor};
+use uuid::Uuid;
+use pathwell_common::json_body::JsonBody;
+
+use crate::api::auth::CERTIFICATE_SCOPE;
+use crate::api::lifecycle_handlers::{agent_snapshot, record_lifecycle_event};
+use crate::api::models::*;
+use crate::api::role_handlers::resolve_agent_uuid;
+use crate::api::routes::AppState;
+use crate::pki::{
+    hash_public_key, verify_key_signature, IssuedCertificate, RevokedCertificate,
+    AGENT_CERTIFICATE_VALIDITY_DAYS, CRL_VALIDITY_HOURS,
+};
+
+/// How long an agent has to sign a certificate challenge and use it
+const CERTIFICATE_CHALLENGE_TTL_SECS: i64 = 300;
+
+fn database_error(e: sqlx::Error) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse {
+            error: "database_error".to_string(),
+            message: e.to_string(),
+        }),
+    )
+}
+
+/// Neither revoked (or past its grace period) nor expired
+fn is_active(expires_at: DateTime<Utc>, revoked_at: Option<DateTime<Utc>>) -> bool {
+    let now = Utc::now();
+    expires_at > now && revoked_at.map_or(true, |t| t > now)
+}
+
+/// Add a newly issued certificate to the agent's issuance log; call inside the transaction that
+/// stored it on the agent
+pub(crate) async fn record_issued_certificate(
+    conn: &mut PgConnection,
+    agent_uuid: Uuid,
+    issued_reason: LifecycleEventType,
+    certificate: &IssuedCertificate,
+    public_key: &str,
+) -> Result<(Uuid, DateTime<Utc>), sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO agent_certificates (agent_id, issued_reason, certificate_chain, certificate_chain_hash,
+                                        public_key_hash, serial_number, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, expires_at
+        "#,
+        agent_uuid,
+        issued_reason.as_str(),
+        certificate.certificate_chain,
+        hash_public_key(&certificate.certificate_chain),
+        hash_public_key(public_key),
+        certificate.serial_number,
+        Utc::now() + Duration::days(AGENT_CERTIFICATE_VALIDITY_DAYS)
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok((row.id, row.expires_at))
+}
+
+/// Certificates issued to the agent that are neither expired nor revoked, other than `excluding`
+pub(crate) async fn active_certificate_count<'e>(
+    executor: impl PgExecutor<'e>,
+    agent_uuid: Uuid,
+    excluding: &[Uuid],
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) as "count!"
+        FROM agent_certificates
+        WHERE agent_id = $1
+          AND expires_at > NOW()
+          AND (revoked_at IS NULL OR revoked_at > NOW())
+          AND id <> ALL($2)
+        "#,
+        agent_uuid,
+        excluding
+    )
+    .fetch_one(executor)
+    .await
+}
+
+/// Revoke every certificate the agent holds, as of `revoked_at`; ones already revoked sooner keep
+/// their earlier time. Returns the certificates revoked
+pub(crate) async fn revoke_agent_certificates(
+    conn: &mut PgConnection,
+    agent_uuid: Uuid,
+    revoked_at: DateTime<Utc>,
+) -> Result<Vec<Uuid>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        UPDATE agent_certificates SET revoked_at = $2
+        WHERE agent_id = $1 AND expires_at > NOW() AND (revoked_at IS NULL OR revoked_at > $2)
+        RETURNING id
+        "#,
+        agent_uuid,
+        revoked_at
+    )
+    .fetch_all(conn)
+    .await
+}
+
+/// Log the certificate on their row for agents registered before the issuance log existed, valid for
+/// `AGENT_CERTIFICATE_VALIDITY_DAYS` from registration like one issued now. Run at startup; agents the
+/// log already covers are left alone. Returns the number of agents backfilled
+pub(crate) async fn backfill_certificate_log<'e>(executor: impl PgExecutor<'e>) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO agent_certificates (agent_id, issued_reason, certificate_chain, certificate_chain_hash,
+                                        public_key_hash, issued_at, expires_at, revoked_at)
+        SELECT a.id, 'registered', a.certificate_chain,
+               encode(sha256(convert_to(a.certificate_chain, 'UTF8')), 'hex'),
+               encode(sha256(convert_to(a.public_key, 'UTF8')), 'hex'),
+               a.created_at AT TIME ZONE 'UTC', (a.created_at AT TIME ZONE 'UTC') + make_interval(days => $1),
+               a.revoked_at AT TIME ZONE 'UTC'
+        FROM agents a
+        WHERE NOT EXISTS (SELECT 1 FROM agent_certificates c WHERE c.agent_id = a.id)
+        "#,
+        AGENT_CERTIFICATE_VALIDITY_DAYS as i32
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+fn invalid_proof(message: &str) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::FORBIDDEN,
+        Json(ErrorResponse {
+            error: "invalid_proof".to_string(),
+            message: message.to_string(),
+        }),
+    )
+}
+
+/// A single-use challenge for the agent to sign with its current key, proving it holds that key before
+/// a renewal or rotation
+pub async fn create_certificate_challenge(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(agent_id): Path<String>,
+) -> Result<Json<CertificateChallengeResponse>, (StatusCode, Json<ErrorResponse>)> {
+    state.api_keys.authorize(&headers, CERTIFICATE_SCOPE)?;
+    let pool = &state.pool;
+    let agent_uuid = resolve_agent_uuid(pool, &agent_id).await?;
+
+    let mut bytes = [0u8; 32];
+    ring::rand::SecureRandom::fill(&ring::rand::SystemRandom::new(), &mut bytes).map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: "challenge_error".to_string(),
+                message: "Failed to generate a challenge".to_string(),
+            }),
+        )
+    })?;
+    let challenge = hex::encode(bytes);
+    let expires_at = Utc::now() + Duration::seconds(CERTIFICATE_CHALLENGE_TTL_SECS);
+
+    let mut tx = pool.begin().await.map_err(database_error)?;
+    sqlx::query!(
+        "DELETE FROM certificate_challenges WHERE agent_id = $1 AND (used_at IS NOT NULL OR expires_at <= NOW())",
+        agent_uuid
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(database_error)?;
+    sqlx::query!(
+        "INSERT INTO certificate_challenges (challenge, agent_id, expires_at) VALUES ($1, $2, $3)",
+        challenge,
+        agent_uuid,
+        expires_at
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(database_error)?;
+    tx.commit().await.map_err(database_error)?;
+
+    Ok(Json(CertificateChallengeResponse {
+        agent_id,
+        challenge,
+        expires_at: expires_at.to_rfc3339(),
+    }))
+}
+
+/// What a renewal or rotation asked for
+struct Reissue {
+    event_type: LifecycleEventType,
+    /// Replacement public key, for a rotation
+    public_key: Option<String>,
+    challenge: String,
+    signature: String,
+    revoke_superseded: Option<bool>,
+    grace_secs: Option<i64>,
+    reason: Option<String>,
+    /// The API key's actor, recorded on the lifecycle event
+    actor: String,
+}
+
+/// Issue the agent a new certificate, superseding (and optionally revoking) the ones it has, unless
+/// that would leave it with more active certificates than the policy allows
+async fn reissue_certificate(
+    state: &AppState,
+    agent_id: String,
+    request: Reissue,
+) -> Result<Json<CertificateIssuedResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let policy = &state.certificate_policy;
+    let revoke_superseded = request.revoke_superseded.unwrap_or(policy.revoke_superseded);
+    let grace_secs = request.grace_secs.unwrap_or(policy.superseded_grace_secs);
+    if grace_secs < 0 || grace_secs > policy.max_grace_secs {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "invalid_certificate_request".to_string(),
+                message: format!("grace_secs must be between 0 and {}", policy.max_grace_secs),
+            }),
+        ));
+    }
+
+    // The agent row is locked so concurrent renewals can't both slip under the quota
+    let mut tx = state.pool.begin().await.map_err(database_error)?;
+    let agent = sqlx::query!(
+        "SELECT id, public_key FROM agents WHERE agent_id = $1 AND revoked_at IS NULL FOR UPDATE",
+        agent_id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "agent_not_found".to_string(),
+                message: format!("Agent {} not found or revoked", agent_id),
+            }),
+        )
+    })?;
+
+    // The challenge is spent even if the signature is wrong, unless the transaction rolls back
+    let issued = sqlx::query_scalar!(
+        r#"
+        UPDATE certificate_challenges SET used_at = NOW()
+        WHERE challenge = $1 AND agent_id = $2 AND used_at IS NULL AND expires_at > NOW()
+        RETURNING challenge
+        "#,
+        request.challenge,
+        agent.id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(database_error)?;
+    if issued.is_none() {
+        return Err(invalid_proof("The challenge is unknown, expired or already used"));
+    }
+    let proven = hex::decode(request.signature.trim())
+        .ok()
+        .and_then(|signature| {
+            verify_key_signature(&agent.public_key, request.challenge.as_bytes(), &signature).ok()
+        })
+        .unwrap_or(false);
+    if !proven {
+        return Err(invalid_proof(
+            "The signature does not verify against the agent's current key",
+        ));
+    }
+
+    sqlx::query!(
+        "UPDATE agent_certificates SET superseded_at = NOW() WHERE agent_id = $1 AND superseded_at IS NULL",
+        agent.id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    let (superseded_revoked_at, superseded) = if revoke_superseded {
+        let revoked_at = Utc::now() + Duration::seconds(grace_secs);
+        let revoked = revoke_agent_certificates(&mut tx, agent.id, revoked_at)
+            .await
+            .map_err(database_error)?;
+        (Some(revoked_at), revoked)
+    } else {
+        (None, Vec::new())
+    };
+
+    // Certificates this reissue revoked stay active through their grace period, but don't count
+    // toward the quota, so a quota of one still allows a renewal with a grace period. Dropping the
+    // transaction on rejection rolls back the supersession above
+    let active = active_certificate_count(&mut *tx, agent.id, &[])
+        .await
+        .map_err(database_error)?;
+    let counted = active_certificate_count(&mut *tx, agent.id, &superseded)
+        .await
+        .map_err(database_error)?;
+    if let Some(max) = policy.max_active_per_agent {
+        if counted >= max {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(ErrorResponse {
+                    error: "certificate_quota_exceeded".to_string(),
+                    message: format!(
+                        "Agent {} already has {} active certificates (maximum {}); revoke the superseded certificates or wait for them to expire",
+                        agent_id, active, max
+                    ),
+                }),
+            ));
+        }
+    }
+
+    let public_key = request.public_key.unwrap_or(agent.public_key);
+    let certificate = state
+        .ca
+        .issue_agent_certificate(&agent_id, &public_key)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: "certificate_error".to_string(),
+                    message: e.to_string(),
+                }),
+            )
+        })?;
+
+    let before = agent_snapshot(&mut tx, agent.id).await.map_err(database_error)?;
+    sqlx::query!(
+        "UPDATE agents SET public_key = $1, certificate_chain = $2, updated_at = $3 WHERE id = $4",
+        public_key,
+        &certificate.certificate_chain,
+        Utc::now().naive_utc(),
+        agent.id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(database_error)?;
+    let after = agent_snapshot(&mut tx, agent.id).await.map_err(database_error)?;
+
+    let (certificate_id, expires_at) = record_issued_certificate(
+        &mut tx,
+        agent.id,
+        request.event_type,
+        &certificate,
+        &public_key,
+    )
+    .await
+    .map_err(database_error)?;
+    record_lifecycle_event(
+        &mut tx,
+        agent.id,
+        request.event_type,
+        &request.actor,
+        request.reason.as_deref(),
+        Some(before),
+        after,
+    )
+    .await
+    .map_err(database_error)?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    Ok(Json(CertificateIssuedResponse {
+        agent_id,
+        certificate_id,
+        certificate_chain: certificate.certificate_chain,
+        serial_number: certificate.serial_number,
+        expires_at: expires_at.to_rfc3339(),
+        superseded_revoked_at: superseded_revoked_at.map(|t| t.to_rfc3339()),
+        active_certificates: active + 1,
+    }))
+}
+
+/// Issue a fresh certificate for the agent's current key
+pub async fn renew_certificate(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(agent_id): Path<String>,
+    JsonBody(payload): JsonBody<RenewCertificateRequest>,
+) -> Result<Json<CertificateIssuedResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let actor = state.api_keys.authorize(&headers, CERTIFICATE_SCOPE)?;
+    reissue_certificate(
+        &state,
+        agent_id,
+        Reissue {
+            event_type: LifecycleEventType::Renewed,
+            public_key: None,
+            challenge: payload.challenge,
+            signature: payload.signature,
+            revoke_superseded: payload.revoke_superseded,
+            grace_secs: payload.grace_secs,
+            reason: payload.reason,
+            actor,
+        },
+    )
+    .await
+}
+
+/// Replace the agent's key and issue a certificate for the new one
+pub async fn rotate_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(agent_id): Path<String>,
+    JsonBody(payload): JsonBody<RotateKeyRequest>,
+) -> Result<Json<CertificateIssuedResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let actor = state.api_keys.authorize(&headers, CERTIFICATE_SCOPE)?;
+    reissue_certificate(
+        &state,
+        agent_id,
+        Reissue {
+            event_type: LifecycleEventType::KeyRotated,
+            public_key: Some(payload.public_key),
+            challenge: payload.challenge,
+            signature: payload.signature,
+            revoke_superseded: payload.revoke_superseded,
+            grace_secs: payload.grace_secs,
+            reason: payload.reason,
+            actor,
+        },
+    )
+    .await
+}
+
+/// The agent's issuance log, newest first
+pub async fn list_agent_certificates(
+    State(state): State<AppState>,
+    Path(agent_id): Path<String>,
+) -> Result<Json<AgentCertificatesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let pool = &state.pool;
+    let agent_uuid = resolve_agent_uuid(pool, &agent_id).await?;
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, issued_reason, certificate_chain_hash, public_key_hash, serial_number, issued_at,
+               expires_at, superseded_at, revoked_at
+        FROM agent_certificates
+        WHERE agent_id = $1
+        ORDER BY issued_at DESC, id
+        "#,
+        agent_uuid
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(database_error)?;
+
+    let certificates: Vec<AgentCertificateResponse> = rows
+        .into_iter()
+        .map(|row| AgentCertificateResponse {
+            id: row.id,
+            issued_reason: row.issued_reason,
+            certificate_chain_hash: row.certificate_chain_hash,
+            public_key_hash: row.public_key_hash,
+            serial_number: row.serial_number,
+            issued_at: row.issued_at.to_rfc3339(),
+            expires_at: row.expires_at.to_rfc3339(),
+            superseded_at: row.superseded_at.map(|t| t.to_rfc3339()),
+            revoked_at: row.revoked_at.map(|t| t.to_rfc3339()),
+            active: is_active(row.expires_at, row.revoked_at),
+        })
+        .collect();
+
+    Ok(Json(AgentCertificatesResponse {
+        agent_id,
+        active_certificates: certificates.iter().filter(|c| c.active).count() as i64,
+        max_active_certificates: state.certificate_policy.max_active_per_agent,
+        certificates,
+    }))
+}
+
+/// The CA's revocation list: every unexpired certificate whose revocation has taken effect. It is
+/// regenerated on each request, and expires when the next scheduled revocation takes effect if that is
+/// sooner than `CRL_VALIDITY_HOURS`
+pub async fn get_ca_crl(
+    State(state): State<AppState>,
+) -> Result<Json<CaCrlResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let pool = &state.pool;
+    let rows = sqlx::query!(
+        r#"
+        SELECT serial_number as "serial_number!", revoked_at as "revoked_at!"
+        FROM agent_certificates
+        WHERE serial_number IS NOT NULL AND revoked_at <= NOW() AND expires_at > NOW()
+        ORDER BY revoked_at, serial_number
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(database_error)?;
+
+    let next_revocation = sqlx::query_scalar!(
+        r#"
+        SELECT MIN(revoked_at)
+        FROM agent_certificates
+        WHERE serial_number IS NOT NULL AND revoked_at > NOW() AND expires_at > revoked_at
+        "#
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(database_error)?;
+
+    let mut next_update = Utc::now() + Duration::hours(CRL_VALIDITY_HOURS);
+    if let Some(at) = next_revocation {
+        next_update = next_update.min(at);
+    }
+
+    let crl_error = |e: anyhow::Error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: "certificate_error".to_string(),
+                message: e.to_string(),
+            }),
+        )
+    };
+    let revoked = rows
+        .into_iter()
+        .map(|row| {
+            Ok(RevokedCertificate {
+                serial_number: row.serial_number,
+                revoked_at: to_offset_date_time(row.revoked_at)?,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map_err(crl_error)?;
+    let crl_pem = state
+        .ca
+        .generate_crl(&revoked, to_offset_date_time(next_update).map_err(crl_error)?)
+        .map_err(crl_error)?;
+
+    Ok(Json(CaCrlResponse {
+        crl_pem,
+        revoked_certificates: revoked.len(),
+        next_update: next_update.to_rfc3339(),
+    }))
+}
+
+/// Revocation status of the certificate with this serial, as hex in either case, optionally
+/// colon-separated as `openssl x509 -serial` and TLS stacks print it. A revocation still in its grace
+/// period hasn't taken effect, so the certificate is `good` until then
+pub async fn get_certificate_status(
+    State(state): State<AppState>,
+    Path(serial): Path<String>,
+) -> Result<Json<CertificateStatusResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let serial_number = serial.replace(':', "").to_lowercase();
+    if serial_number.is_empty() || hex::decode(&serial_number).is_err() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "invalid_serial".to_string(),
+                message: format!("{} is not a hex certificate serial number", serial),
+            }),
+        ));
+    }
+
+    let row = sqlx::query!(
+        "SELECT revoked_at FROM agent_certificates WHERE serial_number = $1",
+        serial_number
+    )
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(database_error)?;
+
+    let (status, revoked_at) = match row {
+        None => ("unknown", None),
+        Some(row) => match row.revoked_at {
+            Some(at) if at <= Utc::now() => ("revoked", Some(at.to_rfc3339())),
+            _ => ("good", None),
+        },
+    };
+
+    Ok(Json(CertificateStatusResponse {
+        serial_number,
+        status: status.to_string(),
+        revoked_at,
+    }))
+}
+
+fn to_offset_date_time(t: DateTime<Utc>) -> anyhow::Result<OffsetDateTime> {
+    Ok(OffsetDateTime::from_unix_timestamp(t.timestamp())?)
+}
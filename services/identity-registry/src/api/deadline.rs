@@ -0,0 +1,42 @@
+//! Request deadlines: a caller (normally the proxy gateway) can send `x-pathwell-timeout-ms` with
+//! what remains of its time budget. A request still being handled when that runs out is abandoned
+//! with a 504, since nobody is waiting for its answer any more.
+
+use axum::{
+    extract::Request,
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use std::time::Duration;
+
+/// Header carrying the caller's remaining time budget in milliseconds
+pub const TIMEOUT_HEADER: &str = "x-pathwell-timeout-ms";
+
+/// Handle the request within the caller's budget, if it sent one
+pub async fn deadline(request: Request, next: Next) -> Response {
+    let budget_ms = request
+        .headers()
+        .get(TIMEOUT_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|ms| *ms > 0);
+    let Some(budget_ms) = budget_ms else {
+        return next.run(request).await;
+    };
+
+    match tokio::time::timeout(Duration::from_millis(budget_ms), next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            tracing::warn!("Abandoned request after its {}ms deadline", budget_ms);
+            (
+                StatusCode::GATEWAY_TIMEOUT,
+                Json(serde_json::json!({
+                    "error": "deadline_exceeded",
+                    "message": format!("Request not completed within {}ms", budget_ms),
+                })),
+            )
+                .into_response()
+        }
+    }
+}
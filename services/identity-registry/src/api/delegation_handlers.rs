@@ -0,0 +1,325 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+use pathwell_common::json_body::JsonBody;
+
+use crate::api::auth::DELEGATION_WRITE_SCOPE;
+use crate::api::models::*;
+use crate::api::role_handlers::resolve_agent_uuid;
+use crate::api::routes::AppState;
+
+/// Longest on-behalf-of chain accepted for verification
+const MAX_DELEGATION_DEPTH: usize = 5;
+
+fn database_error(e: sqlx::Error) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse {
+            error: "database_error".to_string(),
+            message: e.to_string(),
+        }),
+    )
+}
+
+/// Parse `agent:<id>`, `developer:<id>` or a bare agent ID
+fn parse_principal(raw: &str) -> Option<DelegationLink> {
+    let raw = raw.trim();
+    let (principal_type, principal_id) = match raw.split_once(':') {
+        Some(("agent", id)) => (PrincipalType::Agent, id),
+        Some(("developer", id)) => (PrincipalType::Developer, id),
+        Some(_) => return None,
+        None => (PrincipalType::Agent, raw),
+    };
+
+    if principal_id.is_empty() {
+        return None;
+    }
+
+    Some(DelegationLink {
+        principal_type,
+        principal_id: principal_id.to_string(),
+    })
+}
+
+/// Resolve a principal to its internal UUID; revoked agents cannot delegate
+async fn resolve_principal(pool: &PgPool, link: &DelegationLink) -> Result<Option<Uuid>, sqlx::Error> {
+    match link.principal_type {
+        PrincipalType::Agent => {
+            sqlx::query_scalar!(
+                "SELECT id FROM agents WHERE agent_id = $1 AND revoked_at IS NULL",
+                link.principal_id
+            )
+            .fetch_optional(pool)
+            .await
+        }
+        PrincipalType::Developer => {
+            sqlx::query_scalar!(
+                "SELECT id FROM developers WHERE developer_id = $1",
+                link.principal_id
+            )
+            .fetch_optional(pool)
+            .await
+        }
+    }
+}
+
+/// Whether a principal has an active grant allowing the delegate agent to act for it
+async fn delegation_granted(
+    pool: &PgPool,
+    delegate_uuid: Uuid,
+    principal_type: PrincipalType,
+    principal_uuid: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let (principal_agent, principal_developer) = match principal_type {
+        PrincipalType::Agent => (Some(principal_uuid), None),
+        PrincipalType::Developer => (None, Some(principal_uuid)),
+    };
+
+    let granted = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM agent_delegations
+            WHERE delegate_agent_id = $1
+              AND principal_agent_id IS NOT DISTINCT FROM $2
+              AND principal_developer_id IS NOT DISTINCT FROM $3
+              AND revoked_at IS NULL
+              AND (expires_at IS NULL OR expires_at > NOW())
+        ) AS "granted!"
+        "#,
+        delegate_uuid,
+        principal_agent,
+        principal_developer
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(granted)
+}
+
+pub async fn create_delegation(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(agent_id): Path<String>,
+    JsonBody(payload): JsonBody<CreateDelegationRequest>,
+) -> Result<Json<DelegationResponse>, (StatusCode, Json<ErrorResponse>)> {
+    state.api_keys.authorize(&headers, DELEGATION_WRITE_SCOPE)?;
+    let pool = &state.pool;
+    let delegate_uuid = resolve_agent_uuid(pool, &agent_id).await?;
+
+    let link = DelegationLink {
+        principal_type: payload.principal_type,
+        principal_id: payload.principal_id,
+    };
+    let principal_uuid = resolve_principal(pool, &link)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: "principal_not_found".to_string(),
+                    message: format!("Principal {} not found or revoked", link.principal_id),
+                }),
+            )
+        })?;
+
+    if link.principal_type == PrincipalType::Agent && principal_uuid == delegate_uuid {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "invalid_delegation".to_string(),
+                message: "An agent cannot delegate to itself".to_string(),
+            }),
+        ));
+    }
+
+    let (principal_agent, principal_developer) = match link.principal_type {
+        PrincipalType::Agent => (Some(principal_uuid), None),
+        PrincipalType::Developer => (None, Some(principal_uuid)),
+    };
+
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO agent_delegations (
+            id, delegate_agent_id, principal_agent_id, principal_developer_id, expires_at
+        )
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, expires_at, created_at
+        "#,
+        Uuid::new_v4(),
+        delegate_uuid,
+        principal_agent,
+        principal_developer,
+        payload.expires_at
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(database_error)?;
+
+    Ok(Json(DelegationResponse {
+        id: row.id,
+        agent_id,
+        principal_type: link.principal_type,
+        principal_id: link.principal_id,
+        expires_at: row.expires_at.map(|t| t.to_rfc3339()),
+        created_at: row.created_at.to_rfc3339(),
+    }))
+}
+
+pub async fn get_agent_delegations(
+    State(state): State<AppState>,
+    Path(agent_id): Path<String>,
+) -> Result<Json<AgentDelegationsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let pool = &state.pool;
+    let delegate_uuid = resolve_agent_uuid(pool, &agent_id).await?;
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT d.id, d.expires_at, d.created_at,
+               pa.agent_id AS "principal_agent?", pd.developer_id AS "principal_developer?"
+        FROM agent_delegations d
+        LEFT JOIN agents pa ON pa.id = d.principal_agent_id
+        LEFT JOIN developers pd ON pd.id = d.principal_developer_id
+        WHERE d.delegate_agent_id = $1
+          AND d.revoked_at IS NULL
+          AND (d.expires_at IS NULL OR d.expires_at > NOW())
+        ORDER BY d.created_at
+        "#,
+        delegate_uuid
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(database_error)?;
+
+    let delegations = rows
+        .into_iter()
+        .filter_map(|row| {
+            let (principal_type, principal_id) = match (row.principal_agent, row.principal_developer) {
+                (Some(agent), _) => (PrincipalType::Agent, agent),
+                (None, Some(developer)) => (PrincipalType::Developer, developer),
+                (None, None) => return None,
+            };
+            Some(DelegationResponse {
+                id: row.id,
+                agent_id: agent_id.clone(),
+                principal_type,
+                principal_id,
+                expires_at: row.expires_at.map(|t| t.to_rfc3339()),
+                created_at: row.created_at.to_rfc3339(),
+            })
+        })
+        .collect();
+
+    Ok(Json(AgentDelegationsResponse { agent_id, delegations }))
+}
+
+pub async fn revoke_delegation(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((agent_id, delegation_id)): Path<(String, Uuid)>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    state.api_keys.authorize(&headers, DELEGATION_WRITE_SCOPE)?;
+    let pool = &state.pool;
+    let delegate_uuid = resolve_agent_uuid(pool, &agent_id).await?;
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE agent_delegations SET revoked_at = NOW()
+        WHERE id = $1 AND delegate_agent_id = $2 AND revoked_at IS NULL
+        "#,
+        delegation_id,
+        delegate_uuid
+    )
+    .execute(pool)
+    .await
+    .map_err(database_error)?;
+
+    if result.rows_affected() == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "delegation_not_found".to_string(),
+                message: format!("No active delegation {} for agent {}", delegation_id, agent_id),
+            }),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Check that each principal in an on-behalf-of chain exists and has permitted the
+/// link before it: the agent acts for the first principal, which acts for the second, ...
+pub async fn verify_delegation(
+    State(state): State<AppState>,
+    Path(agent_id): Path<String>,
+    JsonBody(payload): JsonBody<VerifyDelegationRequest>,
+) -> Result<Json<VerifyDelegationResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let pool = &state.pool;
+    let mut actor_uuid = resolve_agent_uuid(pool, &agent_id).await?;
+    let mut chain = Vec::with_capacity(payload.on_behalf_of.len());
+
+    let deny = |chain: Vec<DelegationLink>, reason: String| {
+        Ok(Json(VerifyDelegationResponse {
+            valid: false,
+            agent_id: agent_id.clone(),
+            chain,
+            reason: Some(reason),
+        }))
+    };
+
+    if payload.on_behalf_of.is_empty() {
+        return deny(chain, "Delegation chain is empty".to_string());
+    }
+    if payload.on_behalf_of.len() > MAX_DELEGATION_DEPTH {
+        return deny(
+            chain,
+            format!("Delegation chain exceeds {} principals", MAX_DELEGATION_DEPTH),
+        );
+    }
+
+    let last = payload.on_behalf_of.len() - 1;
+    for (i, raw) in payload.on_behalf_of.iter().enumerate() {
+        let link = match parse_principal(raw) {
+            Some(link) => link,
+            None => return deny(chain, format!("Invalid principal '{}'", raw)),
+        };
+
+        // Humans are the end of the line: a developer cannot delegate onward
+        if link.principal_type == PrincipalType::Developer && i != last {
+            return deny(chain, format!("Developer {} must be the final principal", link.principal_id));
+        }
+
+        let principal_uuid = match resolve_principal(pool, &link).await.map_err(database_error)? {
+            Some(id) => id,
+            None => return deny(chain, format!("Principal {} not found or revoked", link.principal_id)),
+        };
+
+        if !delegation_granted(pool, actor_uuid, link.principal_type, principal_uuid)
+            .await
+            .map_err(database_error)?
+        {
+            let actor = chain
+                .last()
+                .map(|l: &DelegationLink| l.principal_id.clone())
+                .unwrap_or_else(|| agent_id.clone());
+            return deny(
+                chain,
+                format!("{} has not permitted delegation to {}", link.principal_id, actor),
+            );
+        }
+
+        actor_uuid = principal_uuid;
+        chain.push(link);
+    }
+
+    Ok(Json(VerifyDelegationResponse {
+        valid: true,
+        agent_id,
+        chain,
+        reason: None,
+    }))
+}
@@ -1,19 +1,40 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Request, State},
+    handler::Handler,
     http::StatusCode,
-    response::Json,
+    response::{Json, Response},
 };
+use sqlx::PgPool;
 use uuid::Uuid;
 use chrono::Utc;
-use rust_decimal::prelude::ToPrimitive;
+use pathwell_common::api_version::ApiVersion;
+use pathwell_common::json_body::JsonBody;
 
+use crate::api::break_glass_handlers::active_break_glass;
+use crate::api::certificate_handlers::{
+    active_certificate_count, record_issued_certificate, revoke_agent_certificates,
+};
+use crate::api::lifecycle_handlers::{agent_snapshot, record_lifecycle_event};
 use crate::api::models::*;
+use crate::api::role_handlers::fetch_agent_roles;
 use crate::api::routes::AppState;
+use crate::api::trust_handlers::score_error;
 use crate::db::models::*;
+use crate::scoring::score_to_f64;
+
+fn database_error(e: sqlx::Error) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse {
+            error: "database_error".to_string(),
+            message: e.to_string(),
+        }),
+    )
+}
 
 pub async fn register_agent(
     State(state): State<AppState>,
-    Json(payload): Json<RegisterAgentRequest>,
+    JsonBody(payload): JsonBody<RegisterAgentRequest>,
 ) -> Result<Json<RegisterAgentResponse>, (StatusCode, Json<ErrorResponse>)> {
     let pool = &state.pool;
     let ca = &state.ca;
@@ -89,7 +110,7 @@ pub async fn register_agent(
     }
 
     // Issue certificate
-    let certificate_chain = ca
+    let certificate = ca
         .issue_agent_certificate(&payload.agent_id, &payload.public_key)
         .map_err(|e| {
             (
@@ -115,6 +136,9 @@ pub async fn register_agent(
         None
     };
 
+    // The agent row and its lifecycle event commit together
+    let mut tx = pool.begin().await.map_err(database_error)?;
+
     sqlx::query!(
         "INSERT INTO agents (id, agent_id, developer_id, enterprise_id, public_key, certificate_chain, created_at, updated_at)
          VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
@@ -123,29 +147,103 @@ pub async fn register_agent(
         developer.id,
         enterprise_id_uuid,
         payload.public_key,
-        certificate_chain,
+        &certificate.certificate_chain,
         now,
         now
     )
-    .execute(pool)
+    .execute(&mut *tx)
     .await
-    .map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "database_error".to_string(),
-                message: e.to_string(),
-            }),
-        )
-    })?;
+    .map_err(database_error)?;
+
+    record_issued_certificate(
+        &mut tx,
+        agent_id_uuid,
+        LifecycleEventType::Registered,
+        &certificate,
+        &payload.public_key,
+    )
+    .await
+    .map_err(database_error)?;
+
+    let after = agent_snapshot(&mut tx, agent_id_uuid).await.map_err(database_error)?;
+    record_lifecycle_event(
+        &mut tx,
+        agent_id_uuid,
+        LifecycleEventType::Registered,
+        &format!("developer:{}", developer.developer_id),
+        None,
+        None,
+        after,
+    )
+    .await
+    .map_err(database_error)?;
+
+    tx.commit().await.map_err(database_error)?;
 
     Ok(Json(RegisterAgentResponse {
         agent_id: payload.agent_id,
-        certificate_chain,
+        certificate_chain: certificate.certificate_chain,
+        serial_number: certificate.serial_number,
         created_at: Utc::now().to_rfc3339(),
     }))
 }
 
+/// Reason reported when an agent is denied because its tenant is deactivated
+pub const TENANT_DEACTIVATED: &str = "tenant_deactivated";
+
+/// Whether the agent's resolved tenant (its own, else its developer's or enterprise's), or any
+/// ancestor of that tenant, has been deactivated
+async fn agent_tenant_deactivated(pool: &PgPool, agent_uuid: Uuid) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1
+            FROM agents a
+            LEFT JOIN developers d ON d.id = a.developer_id
+            LEFT JOIN enterprises e ON e.id = a.enterprise_id
+            JOIN tenants t ON t.id = COALESCE(a.tenant_id, d.tenant_id, e.tenant_id)
+            JOIN tenants anc ON anc.id = t.id OR anc.tenant_id = ANY(t.hierarchy_path)
+            WHERE a.id = $1 AND anc.deactivated_at IS NOT NULL
+        ) as "deactivated!"
+        "#,
+        agent_uuid
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Whether the agent's resolved tenant, or any ancestor of it, sets `require_attribution` in its
+/// governance config, so the agent needs a creator and publisher to operate
+async fn agent_attribution_required(pool: &PgPool, agent_uuid: Uuid) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1
+            FROM agents a
+            LEFT JOIN developers d ON d.id = a.developer_id
+            LEFT JOIN enterprises e ON e.id = a.enterprise_id
+            JOIN tenants t ON t.id = COALESCE(a.tenant_id, d.tenant_id, e.tenant_id)
+            JOIN tenants anc ON anc.id = t.id OR anc.tenant_id = ANY(t.hierarchy_path)
+            WHERE a.id = $1 AND anc.governance_config -> 'require_attribution' = 'true'::jsonb
+        ) as "required!"
+        "#,
+        agent_uuid
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Validity and denial reason for an agent; a deactivated tenant only denies when configured to
+fn agent_validity(revoked: bool, tenant_deactivated: bool, deny_deactivated_tenants: bool) -> (bool, Option<String>) {
+    if revoked {
+        (false, Some("revoked".to_string()))
+    } else if tenant_deactivated && deny_deactivated_tenants {
+        (false, Some(TENANT_DEACTIVATED.to_string()))
+    } else {
+        (true, None)
+    }
+}
+
 pub async fn validate_agent(
     State(state): State<AppState>,
     Path(agent_id): Path<String>,
@@ -179,55 +277,90 @@ pub async fn validate_agent(
         )
     })?;
 
+    let tenant_deactivated = agent_tenant_deactivated(pool, agent.id)
+        .await
+        .map_err(database_error)?;
+    let (valid, reason) = agent_validity(
+        agent.revoked_at.is_some(),
+        tenant_deactivated,
+        state.deny_deactivated_tenants,
+    );
+    let active_certificates = active_certificate_count(pool, agent.id, &[])
+        .await
+        .map_err(database_error)?;
+
     Ok(Json(ValidateAgentResponse {
-        valid: agent.revoked_at.is_none(),
+        valid,
         agent_id: agent.agent_id,
         developer_id: agent.developer_id,
         enterprise_id: agent.enterprise_id,
         revoked: agent.revoked_at.is_some(),
+        tenant_deactivated,
+        reason,
+        active_certificates,
     }))
 }
 
 pub async fn revoke_agent(
     State(state): State<AppState>,
     Path(agent_id): Path<String>,
-    Json(_payload): Json<RevokeAgentRequest>,
+    JsonBody(payload): JsonBody<RevokeAgentRequest>,
 ) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
     let pool = &state.pool;
     let now = Utc::now().naive_utc();
-    let result = sqlx::query!(
-        "UPDATE agents SET revoked_at = $1, updated_at = $1 WHERE agent_id = $2 AND revoked_at IS NULL",
-        now,
+    let mut tx = pool.begin().await.map_err(database_error)?;
+
+    let agent_uuid = sqlx::query_scalar!(
+        "SELECT id FROM agents WHERE agent_id = $1 AND revoked_at IS NULL FOR UPDATE",
         agent_id
     )
-    .execute(pool)
+    .fetch_optional(&mut *tx)
     .await
-    .map_err(|e| {
+    .map_err(database_error)?
+    .ok_or_else(|| {
         (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "database_error".to_string(),
-                message: e.to_string(),
-            }),
-        )
-    })?;
-
-    if result.rows_affected() == 0 {
-        return Err((
             StatusCode::NOT_FOUND,
             Json(ErrorResponse {
                 error: "agent_not_found".to_string(),
                 message: format!("Agent {} not found or already revoked", agent_id),
             }),
-        ));
-    }
+        )
+    })?;
+
+    let before = agent_snapshot(&mut tx, agent_uuid).await.map_err(database_error)?;
+    sqlx::query!(
+        "UPDATE agents SET revoked_at = $1, updated_at = $1 WHERE id = $2",
+        now,
+        agent_uuid
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(database_error)?;
+    revoke_agent_certificates(&mut tx, agent_uuid, now.and_utc())
+        .await
+        .map_err(database_error)?;
+    let after = agent_snapshot(&mut tx, agent_uuid).await.map_err(database_error)?;
+
+    record_lifecycle_event(
+        &mut tx,
+        agent_uuid,
+        LifecycleEventType::Revoked,
+        payload.revoked_by.as_deref().unwrap_or("unknown"),
+        payload.reason.as_deref(),
+        Some(before),
+        after,
+    )
+    .await
+    .map_err(database_error)?;
+
+    tx.commit().await.map_err(database_error)?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
 pub async fn register_developer(
     State(state): State<AppState>,
-    Json(payload): Json<RegisterDeveloperRequest>,
+    JsonBody(payload): JsonBody<RegisterDeveloperRequest>,
 ) -> Result<Json<RegisterDeveloperResponse>, (StatusCode, Json<ErrorResponse>)> {
     let pool = &state.pool;
 
@@ -347,7 +480,7 @@ pub async fn validate_agent_v2(
     let trust_score = if let Some(trust_score_id) = agent.trust_score_id {
         sqlx::query!(
             r#"
-            SELECT composite_score, minimum_threshold, threshold_action
+            SELECT composite_score, minimum_threshold, threshold_action, dimension_scores
             FROM trust_scores WHERE id = $1
             "#,
             trust_score_id
@@ -357,14 +490,23 @@ pub async fn validate_agent_v2(
         .ok()
         .flatten()
         .map(|ts| {
-            let composite = ts.composite_score.to_f64().unwrap_or(0.5);
-            let threshold = ts.minimum_threshold.and_then(|t| t.to_f64());
-            TrustScoreSummary {
+            let composite = score_to_f64(ts.composite_score, state.score_precision)?;
+            let threshold = ts
+                .minimum_threshold
+                .map(|t| score_to_f64(t, state.score_precision))
+                .transpose()?;
+            let dimensions = serde_json::from_value::<TrustDimensionScores>(ts.dimension_scores)
+                .unwrap_or_default();
+            Ok(TrustScoreSummary {
                 composite_score: composite,
                 is_trusted: threshold.map(|t| composite >= t).unwrap_or(true),
                 threshold_action: ts.threshold_action,
-            }
+                dimensions: dimensions.into(),
+                minimum_threshold: threshold,
+            })
         })
+        .transpose()
+        .map_err(score_error)?
     } else {
         None
     };
@@ -376,16 +518,70 @@ pub async fn validate_agent_v2(
             .map(|a| a.into())
     });
 
+    // Roles feed role-based policy rules
+    let roles = fetch_agent_roles(pool, agent.id).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: "database_error".to_string(),
+                message: e.to_string(),
+            }),
+        )
+    })?;
+
+    let tenant_deactivated = agent_tenant_deactivated(pool, agent.id)
+        .await
+        .map_err(database_error)?;
+    let (valid, reason) = agent_validity(
+        agent.revoked_at.is_some(),
+        tenant_deactivated,
+        state.deny_deactivated_tenants,
+    );
+    let attribution_required = agent_attribution_required(pool, agent.id)
+        .await
+        .map_err(database_error)?;
+    let break_glass = active_break_glass(pool, agent.id)
+        .await
+        .map_err(database_error)?;
+    let active_certificates = active_certificate_count(pool, agent.id, &[])
+        .await
+        .map_err(database_error)?;
+
     Ok(Json(ValidateAgentResponseV2 {
-        valid: agent.revoked_at.is_none(),
+        valid,
         agent_id: agent.agent_id,
         developer_id: agent.developer_id,
         enterprise_id: agent.enterprise_id,
         revoked: agent.revoked_at.is_some(),
+        tenant_deactivated,
+        reason,
         tenant_id: agent.tenant_id,
         tenant_hierarchy_path: agent.tenant_hierarchy_path,
         trust_score,
         attribution,
+        attribution_required,
+        roles,
+        break_glass,
+        active_certificates,
     }))
 }
 
+/// Validate with the v1 or v2 handler, whichever `Accept-Version` asks for
+pub async fn validate_agent_negotiated(
+    version: ApiVersion,
+    State(state): State<AppState>,
+    request: Request,
+) -> Response {
+    match version {
+        ApiVersion::V1 => validate_agent.call(request, state).await,
+        ApiVersion::V2 => validate_agent_v2.call(request, state).await,
+    }
+}
+
+/// The CA certificate that issued agent certificate chains, for third parties verifying them
+pub async fn get_ca_certificate(State(state): State<AppState>) -> Json<CaCertificateResponse> {
+    Json(CaCertificateResponse {
+        certificate_pem: state.ca.certificate_pem().to_string(),
+        fingerprint_sha256: state.ca.certificate_fingerprint().to_string(),
+    })
+}
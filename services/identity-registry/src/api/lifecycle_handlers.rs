@@ -0,0 +1,114 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde_json::json;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::api::models::*;
+use crate::api::role_handlers::resolve_agent_uuid;
+use crate::api::routes::AppState;
+use crate::pki::hash_public_key;
+
+/// Capture an agent's current row for a lifecycle snapshot (key material is hashed)
+pub(crate) async fn agent_snapshot(
+    conn: &mut PgConnection,
+    agent_uuid: Uuid,
+) -> Result<serde_json::Value, sqlx::Error> {
+    let agent = sqlx::query!(
+        r#"
+        SELECT agent_id, developer_id, enterprise_id, tenant_id, public_key,
+               certificate_chain, created_at, revoked_at, updated_at
+        FROM agents WHERE id = $1
+        "#,
+        agent_uuid
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(json!({
+        "agent_id": agent.agent_id,
+        "developer_id": agent.developer_id,
+        "enterprise_id": agent.enterprise_id,
+        "tenant_id": agent.tenant_id,
+        "public_key_hash": hash_public_key(&agent.public_key),
+        "certificate_chain_hash": hash_public_key(&agent.certificate_chain),
+        "created_at": agent.created_at.and_utc().to_rfc3339(),
+        "revoked_at": agent.revoked_at.map(|t| t.and_utc().to_rfc3339()),
+        "updated_at": agent.updated_at.and_utc().to_rfc3339(),
+    }))
+}
+
+/// Append a lifecycle event; call inside the transaction that made the change
+pub(crate) async fn record_lifecycle_event(
+    conn: &mut PgConnection,
+    agent_uuid: Uuid,
+    event_type: LifecycleEventType,
+    actor: &str,
+    reason: Option<&str>,
+    before: Option<serde_json::Value>,
+    after: serde_json::Value,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO agent_lifecycle_events (agent_id, event_type, actor, reason, before_state, after_state)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        agent_uuid,
+        event_type.as_str(),
+        actor,
+        reason,
+        before,
+        after
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_agent_lifecycle(
+    State(state): State<AppState>,
+    Path(agent_id): Path<String>,
+) -> Result<Json<AgentLifecycleResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let pool = &state.pool;
+    let agent_uuid = resolve_agent_uuid(pool, &agent_id).await?;
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, event_type, actor, reason, before_state, after_state, occurred_at
+        FROM agent_lifecycle_events
+        WHERE agent_id = $1
+        ORDER BY occurred_at, id
+        "#,
+        agent_uuid
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: "database_error".to_string(),
+                message: e.to_string(),
+            }),
+        )
+    })?;
+
+    let events = rows
+        .into_iter()
+        .map(|row| LifecycleEventResponse {
+            id: row.id,
+            event_type: row.event_type,
+            actor: row.actor,
+            reason: row.reason,
+            before: row.before_state,
+            after: row.after_state,
+            occurred_at: row.occurred_at.to_rfc3339(),
+        })
+        .collect();
+
+    Ok(Json(AgentLifecycleResponse { agent_id, events }))
+}
@@ -1,5 +1,14 @@
+pub mod auth;
+pub mod break_glass_handlers;
+pub mod certificate_handlers;
+pub mod deadline;
+pub mod delegation_handlers;
 pub mod handlers;
+pub mod lifecycle_handlers;
 pub mod models;
+pub mod recompute_handlers;
+pub mod request_id;
+pub mod role_handlers;
 pub mod routes;
 pub mod tenant_handlers;
 pub mod trust_handlers;
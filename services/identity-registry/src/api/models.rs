@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use crate::db::models::{TenantType, Attribution, TrustDimensionScores};
+use crate::scoring::DeltaLimit;
 
 // ========================================
 // Existing Models
@@ -22,6 +24,8 @@ pub struct RegisterAgentRequest {
 pub struct RegisterAgentResponse {
     pub agent_id: String,
     pub certificate_chain: String,
+    /// The agent certificate's serial number, lowercase hex
+    pub serial_number: String,
     pub created_at: String,
 }
 
@@ -32,6 +36,12 @@ pub struct ValidateAgentResponse {
     pub developer_id: Uuid,
     pub enterprise_id: Option<Uuid>,
     pub revoked: bool,
+    /// The agent's tenant or one of its ancestors is deactivated
+    pub tenant_deactivated: bool,
+    /// Why the agent is invalid (`revoked` or `tenant_deactivated`)
+    pub reason: Option<String>,
+    /// Unexpired, unrevoked certificates issued to the agent
+    pub active_certificates: i64,
 }
 
 /// Enhanced validation response with trust and tenant context
@@ -42,16 +52,29 @@ pub struct ValidateAgentResponseV2 {
     pub developer_id: Uuid,
     pub enterprise_id: Option<Uuid>,
     pub revoked: bool,
+    /// The agent's tenant or one of its ancestors is deactivated
+    pub tenant_deactivated: bool,
+    /// Why the agent is invalid (`revoked` or `tenant_deactivated`)
+    pub reason: Option<String>,
     // Phase 1 additions
     pub tenant_id: Option<Uuid>,
     pub tenant_hierarchy_path: Option<Vec<String>>,
     pub trust_score: Option<TrustScoreSummary>,
     pub attribution: Option<AttributionSummary>,
+    /// The agent's tenant (or an ancestor) requires a creator and publisher before it may operate
+    pub attribution_required: bool,
+    pub roles: Vec<String>,
+    /// Active break-glass override exempting the agent from its trust threshold
+    pub break_glass: Option<BreakGlassSummary>,
+    /// Unexpired, unrevoked certificates issued to the agent
+    pub active_certificates: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RevokeAgentRequest {
     pub reason: Option<String>,
+    /// Recorded as the actor on the agent's lifecycle event
+    pub revoked_by: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -74,6 +97,33 @@ pub struct ErrorResponse {
     pub message: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CaCertificateResponse {
+    pub certificate_pem: String,
+    /// Hex SHA-256 of the certificate's DER encoding
+    pub fingerprint_sha256: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CaCrlResponse {
+    /// The CA's certificate revocation list, PEM-encoded and signed by the CA
+    pub crl_pem: String,
+    /// Certificates the list revokes
+    pub revoked_certificates: usize,
+    /// When the list expires; fetch a new one by then
+    pub next_update: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CertificateStatusResponse {
+    /// Lowercase hex
+    pub serial_number: String,
+    /// `good`, `revoked`, or `unknown` for a serial the CA never issued
+    pub status: String,
+    /// When the revocation took effect, if revoked
+    pub revoked_at: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct AgentInfo {
     pub id: Uuid,
@@ -86,6 +136,206 @@ pub struct AgentInfo {
     pub revoked_at: Option<DateTime<Utc>>,
 }
 
+// ========================================
+// Agent Role API Models
+// ========================================
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateRoleRequest {
+    pub role_name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RoleResponse {
+    pub id: Uuid,
+    pub role_name: String,
+    pub description: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AssignRoleRequest {
+    pub role_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgentRolesResponse {
+    pub agent_id: String,
+    pub roles: Vec<String>,
+}
+
+// ========================================
+// Agent Delegation API Models
+// ========================================
+
+/// Kind of identity an agent may act on behalf of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrincipalType {
+    Agent,
+    Developer,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateDelegationRequest {
+    pub principal_type: PrincipalType,
+    pub principal_id: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DelegationResponse {
+    pub id: Uuid,
+    pub agent_id: String,
+    pub principal_type: PrincipalType,
+    pub principal_id: String,
+    pub expires_at: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgentDelegationsResponse {
+    pub agent_id: String,
+    pub delegations: Vec<DelegationResponse>,
+}
+
+/// Principals the agent claims to act for, nearest first (`agent:<id>`, `developer:<id>`, or a bare agent ID)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyDelegationRequest {
+    pub on_behalf_of: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegationLink {
+    pub principal_type: PrincipalType,
+    pub principal_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyDelegationResponse {
+    pub valid: bool,
+    pub agent_id: String,
+    pub chain: Vec<DelegationLink>,
+    pub reason: Option<String>,
+}
+
+// ========================================
+// Agent Lifecycle API Models
+// ========================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecycleEventType {
+    Registered,
+    Revoked,
+    Renewed,
+    KeyRotated,
+}
+
+impl LifecycleEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LifecycleEventType::Registered => "registered",
+            LifecycleEventType::Revoked => "revoked",
+            LifecycleEventType::Renewed => "renewed",
+            LifecycleEventType::KeyRotated => "key_rotated",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LifecycleEventResponse {
+    pub id: Uuid,
+    pub event_type: String,
+    pub actor: String,
+    pub reason: Option<String>,
+    pub before: Option<serde_json::Value>,
+    pub after: serde_json::Value,
+    pub occurred_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgentLifecycleResponse {
+    pub agent_id: String,
+    pub events: Vec<LifecycleEventResponse>,
+}
+
+// ========================================
+// Agent Certificate Models
+// ========================================
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RenewCertificateRequest {
+    /// From `POST /v1/agents/{agent_id}/certificates/challenge`
+    pub challenge: String,
+    /// Hex signature over the challenge with the agent's current key
+    pub signature: String,
+    /// Revoke the certificates the new one supersedes (default: `REVOKE_SUPERSEDED_CERTIFICATES`)
+    pub revoke_superseded: Option<bool>,
+    /// Seconds superseded certificates stay valid (default: `SUPERSEDED_CERTIFICATE_GRACE_SECS`)
+    pub grace_secs: Option<i64>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RotateKeyRequest {
+    /// PEM-encoded public key the new certificate is issued for
+    pub public_key: String,
+    pub challenge: String,
+    /// Hex signature over the challenge with the agent's current key, not the new one
+    pub signature: String,
+    pub revoke_superseded: Option<bool>,
+    pub grace_secs: Option<i64>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CertificateChallengeResponse {
+    pub agent_id: String,
+    /// Sign its UTF-8 bytes with the agent's current key
+    pub challenge: String,
+    pub expires_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CertificateIssuedResponse {
+    pub agent_id: String,
+    pub certificate_id: Uuid,
+    pub certificate_chain: String,
+    /// The agent certificate's serial number, lowercase hex
+    pub serial_number: String,
+    pub expires_at: String,
+    /// When the superseded certificates stop being valid, if they were revoked
+    pub superseded_revoked_at: Option<String>,
+    pub active_certificates: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgentCertificateResponse {
+    pub id: Uuid,
+    /// `registered`, `renewed` or `key_rotated`
+    pub issued_reason: String,
+    pub certificate_chain_hash: String,
+    pub public_key_hash: String,
+    /// Lowercase hex; absent for certificates issued before serials were recorded
+    pub serial_number: Option<String>,
+    pub issued_at: String,
+    pub expires_at: String,
+    pub superseded_at: Option<String>,
+    pub revoked_at: Option<String>,
+    pub active: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgentCertificatesResponse {
+    pub agent_id: String,
+    pub active_certificates: i64,
+    /// Most certificates the agent may have active at once, if limited
+    pub max_active_certificates: Option<i64>,
+    pub certificates: Vec<AgentCertificateResponse>,
+}
+
 // ========================================
 // Tenant API Models (TEN.*)
 // ========================================
@@ -166,26 +416,49 @@ pub struct TrustScoreResponse {
     pub dimensions: TrustDimensionsResponse,
     pub threshold_status: TrustThresholdStatus,
     pub last_calculated_at: String,
+    pub calculation_version: String,
+    /// What the composite was calculated from; absent for scores written before inputs were recorded
+    pub calculation_inputs: Option<serde_json::Value>,
+    /// Present when the read asked to `verify` the stored composite
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub composite_check: Option<CompositeCheck>,
+    /// Present when an update's delta was reduced to stay within the configured limits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delta_clamp: Option<DeltaClamp>,
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub struct TrustScoreQuery {
+    /// Recompute the composite from the stored dimensions and compare it with the stored one
+    #[serde(default)]
+    pub verify: bool,
+}
+
+/// Stored composite against one recomputed from the stored dimensions (at the configured precision)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompositeCheck {
+    pub stored_score: f64,
+    pub computed_score: f64,
+    /// `false` means the dimensions and composite have drifted apart
+    pub matches: bool,
+}
+
+/// A trust update's delta as requested and as applied after `TRUST_MAX_DELTA`/`TRUST_MAX_WINDOW_DELTA`
 #[derive(Debug, Serialize, Deserialize)]
-pub struct TrustDimensionsResponse {
-    pub behavior: f64,
-    pub validation: f64,
-    pub provenance: f64,
-    pub alignment: f64,
-    pub reputation: f64,
+pub struct DeltaClamp {
+    pub requested: f64,
+    pub applied: f64,
+    pub limit: DeltaLimit,
 }
 
+/// Standard dimensions are always present; tenant-declared ones appear alongside them
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TrustDimensionsResponse(pub BTreeMap<String, f64>);
+
 impl From<TrustDimensionScores> for TrustDimensionsResponse {
     fn from(scores: TrustDimensionScores) -> Self {
-        Self {
-            behavior: scores.behavior,
-            validation: scores.validation,
-            provenance: scores.provenance,
-            alignment: scores.alignment,
-            reputation: scores.reputation,
-        }
+        Self(scores.0)
     }
 }
 
@@ -201,6 +474,9 @@ pub struct TrustScoreSummary {
     pub composite_score: f64,
     pub is_trusted: bool,
     pub threshold_action: Option<String>,
+    /// Dimension scores behind the composite, so callers can snapshot the full posture
+    pub dimensions: TrustDimensionsResponse,
+    pub minimum_threshold: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -210,14 +486,10 @@ pub struct CreateTrustScoreRequest {
     pub initial_dimensions: Option<TrustDimensionsRequest>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TrustDimensionsRequest {
-    pub behavior: Option<f64>,
-    pub validation: Option<f64>,
-    pub provenance: Option<f64>,
-    pub alignment: Option<f64>,
-    pub reputation: Option<f64>,
-}
+/// Dimension scores to set; omitted dimensions start at their default
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TrustDimensionsRequest(pub BTreeMap<String, f64>);
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateTrustDimensionRequest {
@@ -227,6 +499,78 @@ pub struct UpdateTrustDimensionRequest {
     pub event_id: Option<Uuid>,
 }
 
+/// What to do when an imported record's entity already has a trust score
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportConflictMode {
+    #[default]
+    Skip,
+    Overwrite,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrustScoreImportRecord {
+    pub entity_type: String,
+    pub entity_id: Uuid,
+    pub dimensions: TrustDimensionsRequest,
+    pub confidence_level: Option<f64>,
+    pub minimum_threshold: Option<f64>,
+    pub threshold_action: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportTrustScoresRequest {
+    #[serde(default)]
+    pub on_conflict: ImportConflictMode,
+    pub records: Vec<TrustScoreImportRecord>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportTrustScoresResponse {
+    pub total: usize,
+    pub created: usize,
+    pub overwritten: usize,
+    pub skipped: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StartTrustRecomputeRequest {
+    /// Only recompute scores for this entity type; all scores when absent
+    pub entity_type: Option<String>,
+}
+
+/// Where a trust recompute job stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrustRecomputeStatus {
+    Running,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrustRecomputeJobResponse {
+    pub job_id: Uuid,
+    pub status: TrustRecomputeStatus,
+    pub entity_type: Option<String>,
+    pub requested_by: String,
+    /// Scores in scope when the job started
+    pub total: i64,
+    pub processed: i64,
+    /// Scores whose stored composite differed and was rewritten
+    pub updated: i64,
+    pub failed: i64,
+    /// Set once a cancel is requested; the job stops after its current batch
+    pub cancel_requested: bool,
+    pub batch_size: i64,
+    pub concurrency: usize,
+    pub batch_delay_ms: u64,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub error: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TrustScoreHistoryResponse {
     pub entries: Vec<TrustScoreHistoryEntry>,
@@ -237,9 +581,115 @@ pub struct TrustScoreHistoryEntry {
     pub composite_score: f64,
     pub dimension_scores: TrustDimensionsResponse,
     pub change_reason: Option<String>,
+    /// Who made the change; absent for changes made before actors were recorded
+    pub actor: Option<String>,
+    /// What the replaced composite was calculated from, if recorded
+    pub calculation_inputs: Option<serde_json::Value>,
     pub recorded_at: String,
 }
 
+/// Filters, sort and keyset position for `GET /v1/trust/below-threshold`
+#[derive(Debug, Deserialize)]
+pub struct BelowThresholdQuery {
+    pub entity_type: Option<String>,
+    /// Entities whose resolved tenant is this tenant or one of its descendants
+    pub tenant_id: Option<String>,
+    pub threshold_action: Option<String>,
+    /// Bounds on `last_calculated_at`
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// `score` (lowest first, the default), `severity` (largest shortfall first) or `age` (oldest first)
+    pub sort: Option<String>,
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BelowThresholdEntry {
+    pub entity_type: String,
+    pub entity_id: Uuid,
+    pub tenant_id: Option<String>,
+    pub composite_score: f64,
+    pub minimum_threshold: f64,
+    /// How far the score falls short of the threshold
+    pub shortfall: f64,
+    pub threshold_action: Option<String>,
+    pub last_calculated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BelowThresholdResponse {
+    pub entries: Vec<BelowThresholdEntry>,
+    /// Entries matching the filters, across all pages
+    pub total: i64,
+    pub limit: i64,
+    /// Pass as `cursor` to fetch the next page; absent on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// What a break-glass override exempts: one agent, or every agent under a tenant and its descendants
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakGlassTarget {
+    Agent,
+    Tenant,
+}
+
+impl BreakGlassTarget {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BreakGlassTarget::Agent => "agent",
+            BreakGlassTarget::Tenant => "tenant",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateBreakGlassRequest {
+    pub target_type: BreakGlassTarget,
+    /// The agent's `agent_id` or the tenant's `tenant_id`
+    pub target_id: String,
+    pub reason: String,
+    /// How long the override lasts (default one hour, at most `BREAK_GLASS_MAX_SECS`)
+    pub duration_secs: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BreakGlassResponse {
+    pub id: Uuid,
+    pub target_type: String,
+    pub target_id: String,
+    pub reason: String,
+    pub created_by: String,
+    pub created_at: String,
+    pub expires_at: String,
+    pub revoked_at: Option<String>,
+    pub revoked_by: Option<String>,
+    /// Neither expired nor revoked
+    pub active: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BreakGlassListQuery {
+    /// Also list expired and revoked overrides
+    #[serde(default)]
+    pub include_inactive: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BreakGlassListResponse {
+    pub overrides: Vec<BreakGlassResponse>,
+}
+
+/// The override a validated agent is under, passed on to the policy engine
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BreakGlassSummary {
+    pub id: Uuid,
+    pub target_type: String,
+    pub reason: String,
+    pub expires_at: String,
+}
+
 // ========================================
 // Attribution API Models (AUTH.OBJ)
 // ========================================
@@ -0,0 +1,379 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use uuid::Uuid;
+use pathwell_common::json_body::JsonBody;
+
+use crate::api::auth::TRUST_WRITE_SCOPE;
+use crate::api::models::*;
+use crate::api::routes::AppState;
+use crate::api::trust_handlers::{dimension_schema, recorded_inputs};
+use crate::db::models::TrustDimensionScores;
+use crate::scoring::{score_to_decimal, CalculationInputs, CalculationSource, CALCULATION_VERSION};
+
+/// How hard a trust recompute job may press on the trust tables
+#[derive(Debug, Clone, Copy)]
+pub struct RecomputeConfig {
+    /// Scores read and recomputed per batch
+    pub batch_size: i64,
+    /// Scores recomputed at once within a batch
+    pub concurrency: usize,
+    /// Pause between batches, leaving room for live trust updates
+    pub batch_delay_ms: u64,
+}
+
+impl RecomputeConfig {
+    pub fn from_env() -> Self {
+        Self {
+            batch_size: std::env::var("TRUST_RECOMPUTE_BATCH_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(100)
+                .max(1),
+            concurrency: std::env::var("TRUST_RECOMPUTE_CONCURRENCY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(4)
+                .max(1),
+            batch_delay_ms: std::env::var("TRUST_RECOMPUTE_BATCH_DELAY_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(100),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct RecomputeProgress {
+    status: TrustRecomputeStatus,
+    total: i64,
+    processed: i64,
+    updated: i64,
+    failed: i64,
+    finished_at: Option<DateTime<Utc>>,
+    error: Option<String>,
+}
+
+/// A running or finished recompute, shared between its worker and the job endpoints
+#[derive(Debug)]
+pub struct RecomputeJob {
+    id: Uuid,
+    entity_type: Option<String>,
+    requested_by: String,
+    config: RecomputeConfig,
+    started_at: DateTime<Utc>,
+    cancel_requested: AtomicBool,
+    progress: Mutex<RecomputeProgress>,
+}
+
+impl RecomputeJob {
+    fn response(&self) -> TrustRecomputeJobResponse {
+        let progress = self.progress.lock().unwrap();
+        TrustRecomputeJobResponse {
+            job_id: self.id,
+            status: progress.status,
+            entity_type: self.entity_type.clone(),
+            requested_by: self.requested_by.clone(),
+            total: progress.total,
+            processed: progress.processed,
+            updated: progress.updated,
+            failed: progress.failed,
+            cancel_requested: self.cancel_requested.load(Ordering::Relaxed),
+            batch_size: self.config.batch_size,
+            concurrency: self.config.concurrency,
+            batch_delay_ms: self.config.batch_delay_ms,
+            started_at: self.started_at.to_rfc3339(),
+            finished_at: progress.finished_at.map(|t| t.to_rfc3339()),
+            error: progress.error.clone(),
+        }
+    }
+
+    fn finish(&self, status: TrustRecomputeStatus, error: Option<String>) {
+        let mut progress = self.progress.lock().unwrap();
+        progress.status = status;
+        progress.finished_at = Some(Utc::now());
+        progress.error = error;
+    }
+}
+
+/// Recompute jobs started on this instance. Jobs are kept in memory, so they are forgotten on restart
+#[derive(Debug, Default)]
+pub struct RecomputeJobs {
+    jobs: Mutex<HashMap<Uuid, Arc<RecomputeJob>>>,
+}
+
+impl RecomputeJobs {
+    fn get(&self, job_id: Uuid) -> Option<Arc<RecomputeJob>> {
+        self.jobs.lock().unwrap().get(&job_id).cloned()
+    }
+
+    /// Register a new job, unless one is already running
+    fn start(
+        &self,
+        entity_type: Option<String>,
+        requested_by: String,
+        config: RecomputeConfig,
+    ) -> Result<Arc<RecomputeJob>, Arc<RecomputeJob>> {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(running) = jobs
+            .values()
+            .find(|job| job.progress.lock().unwrap().status == TrustRecomputeStatus::Running)
+        {
+            return Err(running.clone());
+        }
+
+        let job = Arc::new(RecomputeJob {
+            id: Uuid::new_v4(),
+            entity_type,
+            requested_by,
+            config,
+            started_at: Utc::now(),
+            cancel_requested: AtomicBool::new(false),
+            progress: Mutex::new(RecomputeProgress {
+                status: TrustRecomputeStatus::Running,
+                total: 0,
+                processed: 0,
+                updated: 0,
+                failed: 0,
+                finished_at: None,
+                error: None,
+            }),
+        });
+        jobs.insert(job.id, job.clone());
+        Ok(job)
+    }
+}
+
+fn job_not_found(job_id: Uuid) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse {
+            error: "recompute_job_not_found".to_string(),
+            message: format!("Trust recompute job {} not found", job_id),
+        }),
+    )
+}
+
+/// Recompute one score's composite from its stored dimensions, rewriting it (with a history row) if it
+/// differs. Each score is its own short transaction, so live updates only ever wait on a single row
+async fn recompute_score(state: &AppState, actor: &str, score_id: Uuid) -> Result<bool, String> {
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+    let Some(current) = sqlx::query!(
+        r#"
+        SELECT entity_type, entity_id, composite_score, dimension_scores, calculation_inputs
+        FROM trust_scores
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        score_id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?
+    else {
+        // Deleted since the batch was read
+        return Ok(false);
+    };
+
+    let schema = dimension_schema(&mut *tx, &current.entity_type, current.entity_id)
+        .await
+        .map_err(|(_, Json(e))| e.message)?;
+    let dimensions = serde_json::from_value::<TrustDimensionScores>(current.dimension_scores.clone())
+        .unwrap_or_default()
+        .complete(&schema);
+    let composite = score_to_decimal(dimensions.calculate_composite(&schema), state.score_precision)
+        .map_err(|e| e.to_string())?;
+    if composite == current.composite_score {
+        return Ok(false);
+    }
+
+    let inputs = recorded_inputs(
+        state,
+        CalculationInputs::new(CalculationSource::Recompute, &schema, &dimensions, state.score_precision),
+    );
+    sqlx::query!(
+        r#"
+        INSERT INTO trust_score_history (
+            trust_score_id, composite_score, dimension_scores, change_reason, actor, calculation_inputs
+        )
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        score_id,
+        current.composite_score,
+        current.dimension_scores,
+        Some("Recomputed from stored dimensions".to_string()),
+        actor,
+        current.calculation_inputs
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    sqlx::query!(
+        r#"
+        UPDATE trust_scores SET
+            composite_score = $2,
+            last_calculated_at = NOW(),
+            updated_by = $3,
+            calculation_version = $4,
+            calculation_inputs = $5
+        WHERE id = $1
+        "#,
+        score_id,
+        composite,
+        actor,
+        CALCULATION_VERSION,
+        inputs
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// Work through the job's scores in id order, a batch at a time, until done or cancelled
+async fn run_recompute(state: AppState, job: Arc<RecomputeJob>) -> Result<TrustRecomputeStatus, sqlx::Error> {
+    let config = job.config;
+    let total = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) as "count!" FROM trust_scores WHERE ($1::text IS NULL OR entity_type = $1)"#,
+        job.entity_type
+    )
+    .fetch_one(&state.pool)
+    .await?;
+    job.progress.lock().unwrap().total = total;
+
+    let semaphore = Arc::new(Semaphore::new(config.concurrency));
+    let mut after: Option<Uuid> = None;
+    loop {
+        if job.cancel_requested.load(Ordering::Relaxed) {
+            return Ok(TrustRecomputeStatus::Cancelled);
+        }
+
+        let batch = sqlx::query_scalar!(
+            r#"
+            SELECT id FROM trust_scores
+            WHERE ($1::uuid IS NULL OR id > $1)
+              AND ($2::text IS NULL OR entity_type = $2)
+            ORDER BY id
+            LIMIT $3
+            "#,
+            after,
+            job.entity_type,
+            config.batch_size
+        )
+        .fetch_all(&state.pool)
+        .await?;
+        let Some(last) = batch.last().copied() else {
+            return Ok(TrustRecomputeStatus::Completed);
+        };
+        after = Some(last);
+
+        let mut tasks = JoinSet::new();
+        for score_id in batch {
+            let permit = semaphore.clone().acquire_owned().await.expect("semaphore is never closed");
+            let state = state.clone();
+            let job = job.clone();
+            tasks.spawn(async move {
+                let result = recompute_score(&state, &job.requested_by, score_id).await;
+                drop(permit);
+                (score_id, result)
+            });
+        }
+        while let Some(joined) = tasks.join_next().await {
+            let mut progress = job.progress.lock().unwrap();
+            progress.processed += 1;
+            match joined {
+                Ok((_, Ok(true))) => progress.updated += 1,
+                Ok((_, Ok(false))) => {}
+                Ok((score_id, Err(e))) => {
+                    tracing::warn!("Trust recompute job {} failed on score {}: {}", job.id, score_id, e);
+                    progress.failed += 1;
+                }
+                Err(e) => {
+                    tracing::warn!("Trust recompute job {} task failed: {}", job.id, e);
+                    progress.failed += 1;
+                }
+            }
+        }
+
+        if config.batch_delay_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(config.batch_delay_ms)).await;
+        }
+    }
+}
+
+/// Start recomputing stored composites from their dimensions in the background
+pub async fn start_trust_recompute(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    JsonBody(payload): JsonBody<StartTrustRecomputeRequest>,
+) -> Result<(StatusCode, Json<TrustRecomputeJobResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let actor = state.api_keys.authorize(&headers, TRUST_WRITE_SCOPE)?;
+
+    let job = state
+        .recompute_jobs
+        .start(payload.entity_type, actor, state.recompute_config)
+        .map_err(|running| {
+            (
+                StatusCode::CONFLICT,
+                Json(ErrorResponse {
+                    error: "recompute_in_progress".to_string(),
+                    message: format!("Trust recompute job {} is still running", running.id),
+                }),
+            )
+        })?;
+
+    tracing::info!(
+        "Trust recompute job {} started by {} ({} per batch, {} at once)",
+        job.id, job.requested_by, job.config.batch_size, job.config.concurrency
+    );
+    let worker = job.clone();
+    tokio::spawn(async move {
+        match run_recompute(state, worker.clone()).await {
+            Ok(status) => {
+                tracing::info!("Trust recompute job {} finished: {:?}", worker.id, status);
+                worker.finish(status, None);
+            }
+            Err(e) => {
+                tracing::error!("Trust recompute job {} failed: {}", worker.id, e);
+                worker.finish(TrustRecomputeStatus::Failed, Some(e.to_string()));
+            }
+        }
+    });
+
+    Ok((StatusCode::ACCEPTED, Json(job.response())))
+}
+
+/// A recompute job's progress
+pub async fn get_trust_recompute(
+    State(state): State<AppState>,
+    Path(job_id): Path<Uuid>,
+) -> Result<Json<TrustRecomputeJobResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let job = state.recompute_jobs.get(job_id).ok_or_else(|| job_not_found(job_id))?;
+    Ok(Json(job.response()))
+}
+
+/// Ask a recompute job to stop after its current batch
+pub async fn cancel_trust_recompute(
+    State(state): State<AppState>,
+    Path(job_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, Json<TrustRecomputeJobResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let actor = state.api_keys.authorize(&headers, TRUST_WRITE_SCOPE)?;
+    let job = state.recompute_jobs.get(job_id).ok_or_else(|| job_not_found(job_id))?;
+
+    if !job.cancel_requested.swap(true, Ordering::Relaxed) {
+        tracing::info!("Trust recompute job {} cancelled by {}", job.id, actor);
+    }
+    Ok((StatusCode::ACCEPTED, Json(job.response())))
+}
@@ -0,0 +1,254 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::Utc;
+use pathwell_common::json_body::JsonBody;
+
+use crate::api::auth::ROLE_WRITE_SCOPE;
+use crate::api::models::*;
+use crate::api::routes::AppState;
+use crate::db::models::AgentRole;
+
+/// Fetch the role names assigned to an agent (by internal agent UUID)
+pub async fn fetch_agent_roles(pool: &PgPool, agent_uuid: Uuid) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT r.role_name
+        FROM agent_roles r
+        JOIN agent_role_memberships m ON m.role_id = r.id
+        WHERE m.agent_id = $1
+        ORDER BY r.role_name
+        "#,
+        agent_uuid
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub(crate) async fn resolve_agent_uuid(
+    pool: &PgPool,
+    agent_id: &str,
+) -> Result<Uuid, (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query_scalar!("SELECT id FROM agents WHERE agent_id = $1", agent_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: "database_error".to_string(),
+                    message: e.to_string(),
+                }),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: "agent_not_found".to_string(),
+                    message: format!("Agent {} not found", agent_id),
+                }),
+            )
+        })
+}
+
+pub async fn create_role(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    JsonBody(payload): JsonBody<CreateRoleRequest>,
+) -> Result<Json<RoleResponse>, (StatusCode, Json<ErrorResponse>)> {
+    state.api_keys.authorize(&headers, ROLE_WRITE_SCOPE)?;
+    let pool = &state.pool;
+
+    if payload.role_name.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "invalid_role_name".to_string(),
+                message: "Role name must not be empty".to_string(),
+            }),
+        ));
+    }
+
+    // An existing role, even one created concurrently, inserts nothing
+    let now = Utc::now();
+    let role = sqlx::query_as!(
+        AgentRole,
+        r#"
+        INSERT INTO agent_roles (id, role_name, description, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $4)
+        ON CONFLICT (role_name) DO NOTHING
+        RETURNING id, role_name, description, created_at, updated_at
+        "#,
+        Uuid::new_v4(),
+        payload.role_name,
+        payload.description,
+        now
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: "database_error".to_string(),
+                message: e.to_string(),
+            }),
+        )
+    })?
+    .ok_or_else(|| {
+        (
+            StatusCode::CONFLICT,
+            Json(ErrorResponse {
+                error: "role_exists".to_string(),
+                message: format!("Role {} already exists", payload.role_name),
+            }),
+        )
+    })?;
+
+    Ok(Json(RoleResponse {
+        id: role.id,
+        role_name: role.role_name,
+        description: role.description,
+        created_at: role.created_at.to_rfc3339(),
+    }))
+}
+
+pub async fn get_agent_roles(
+    State(state): State<AppState>,
+    Path(agent_id): Path<String>,
+) -> Result<Json<AgentRolesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let pool = &state.pool;
+    let agent_uuid = resolve_agent_uuid(pool, &agent_id).await?;
+
+    let roles = fetch_agent_roles(pool, agent_uuid).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: "database_error".to_string(),
+                message: e.to_string(),
+            }),
+        )
+    })?;
+
+    Ok(Json(AgentRolesResponse { agent_id, roles }))
+}
+
+pub async fn assign_agent_role(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(agent_id): Path<String>,
+    JsonBody(payload): JsonBody<AssignRoleRequest>,
+) -> Result<Json<AgentRolesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    state.api_keys.authorize(&headers, ROLE_WRITE_SCOPE)?;
+    let pool = &state.pool;
+    let agent_uuid = resolve_agent_uuid(pool, &agent_id).await?;
+
+    let role_id = sqlx::query_scalar!(
+        "SELECT id FROM agent_roles WHERE role_name = $1",
+        payload.role_name
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: "database_error".to_string(),
+                message: e.to_string(),
+            }),
+        )
+    })?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "role_not_found".to_string(),
+                message: format!("Role {} not found", payload.role_name),
+            }),
+        )
+    })?;
+
+    // Assigning an already-held role is a no-op
+    sqlx::query!(
+        r#"
+        INSERT INTO agent_role_memberships (id, agent_id, role_id, created_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (agent_id, role_id) DO NOTHING
+        "#,
+        Uuid::new_v4(),
+        agent_uuid,
+        role_id,
+        Utc::now()
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: "database_error".to_string(),
+                message: e.to_string(),
+            }),
+        )
+    })?;
+
+    let roles = fetch_agent_roles(pool, agent_uuid).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: "database_error".to_string(),
+                message: e.to_string(),
+            }),
+        )
+    })?;
+
+    Ok(Json(AgentRolesResponse { agent_id, roles }))
+}
+
+pub async fn remove_agent_role(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((agent_id, role_name)): Path<(String, String)>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    state.api_keys.authorize(&headers, ROLE_WRITE_SCOPE)?;
+    let pool = &state.pool;
+    let agent_uuid = resolve_agent_uuid(pool, &agent_id).await?;
+
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM agent_role_memberships m
+        USING agent_roles r
+        WHERE m.role_id = r.id AND m.agent_id = $1 AND r.role_name = $2
+        "#,
+        agent_uuid,
+        role_name
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: "database_error".to_string(),
+                message: e.to_string(),
+            }),
+        )
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "role_not_assigned".to_string(),
+                message: format!("Agent {} does not hold role {}", agent_id, role_name),
+            }),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
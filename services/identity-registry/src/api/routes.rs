@@ -1,30 +1,101 @@
 use axum::{
+    middleware,
     routing::{get, post, patch, delete},
     Router,
 };
 use sqlx::PgPool;
+use std::sync::Arc;
 
+use crate::api::auth::ApiKeyConfig;
+
+use crate::api::break_glass_handlers;
+use crate::api::certificate_handlers;
+use crate::api::delegation_handlers;
 use crate::api::handlers;
+use crate::api::lifecycle_handlers;
+use crate::api::recompute_handlers::{self, RecomputeConfig, RecomputeJobs};
+use crate::api::deadline::deadline;
+use crate::api::request_id::request_id;
+use crate::api::role_handlers;
 use crate::api::tenant_handlers;
 use crate::api::trust_handlers;
-use crate::pki::CertificateAuthority;
+use crate::pki::{CertificateAuthority, CertificatePolicy};
+use crate::scoring::DeltaLimits;
 
 #[derive(Clone)]
 pub struct AppState {
     pub pool: PgPool,
     pub ca: CertificateAuthority,
+    /// Decimal places trust scores are rounded to when read or written
+    pub score_precision: u32,
+    /// Whether agents under a deactivated tenant (or ancestor) fail validation
+    pub deny_deactivated_tenants: bool,
+    /// API keys, and whether trust writes require one
+    pub api_keys: Arc<ApiKeyConfig>,
+    /// How far trust dimensions may move per update and per window
+    pub delta_limits: DeltaLimits,
+    /// Whether trust writes store the inputs each score was calculated from
+    pub record_calculation_inputs: bool,
+    /// Longest a break-glass override may last, in seconds
+    pub break_glass_max_secs: i64,
+    /// Active certificate quota and superseded certificate handling for renewals and rotations
+    pub certificate_policy: CertificatePolicy,
+    /// Batch size, concurrency and pacing of trust recompute jobs
+    pub recompute_config: RecomputeConfig,
+    pub recompute_jobs: Arc<RecomputeJobs>,
 }
 
-pub fn create_router(pool: PgPool, ca: CertificateAuthority) -> Router {
-    let state = AppState { pool, ca };
+pub fn create_router(
+    pool: PgPool,
+    ca: CertificateAuthority,
+    score_precision: u32,
+    deny_deactivated_tenants: bool,
+    api_keys: ApiKeyConfig,
+    delta_limits: DeltaLimits,
+    record_calculation_inputs: bool,
+    break_glass_max_secs: i64,
+    certificate_policy: CertificatePolicy,
+    recompute_config: RecomputeConfig,
+) -> Router {
+    let state = AppState {
+        pool,
+        ca,
+        score_precision,
+        deny_deactivated_tenants,
+        api_keys: Arc::new(api_keys),
+        delta_limits,
+        record_calculation_inputs,
+        break_glass_max_secs,
+        certificate_policy,
+        recompute_config,
+        recompute_jobs: Arc::new(RecomputeJobs::default()),
+    };
     Router::new()
         // Existing routes
         .route("/v1/developers/register", post(handlers::register_developer))
         .route("/v1/agents/register", post(handlers::register_agent))
         .route("/v1/agents/:agent_id/validate", get(handlers::validate_agent))
         .route("/v1/agents/:agent_id/revoke", post(handlers::revoke_agent))
+        .route("/v1/agents/:agent_id/lifecycle", get(lifecycle_handlers::get_agent_lifecycle))
+        // Agent certificate issuance log, renewal and key rotation
+        .route("/v1/agents/:agent_id/certificates", get(certificate_handlers::list_agent_certificates))
+        .route("/v1/agents/:agent_id/certificates/challenge", post(certificate_handlers::create_certificate_challenge))
+        .route("/v1/agents/:agent_id/certificates/renew", post(certificate_handlers::renew_certificate))
+        .route("/v1/agents/:agent_id/certificates/rotate", post(certificate_handlers::rotate_key))
         // V2 agent validation with trust/tenant context
         .route("/v2/agents/:agent_id/validate", get(handlers::validate_agent_v2))
+        // Unversioned agent validation: Accept-Version picks v1 or v2
+        .route("/agents/:agent_id/validate", get(handlers::validate_agent_negotiated))
+        // Agent role routes
+        .route("/v1/roles", post(role_handlers::create_role))
+        .route("/v1/agents/:agent_id/roles", get(role_handlers::get_agent_roles))
+        .route("/v1/agents/:agent_id/roles", post(role_handlers::assign_agent_role))
+        .route("/v1/agents/:agent_id/roles/:role_name", delete(role_handlers::remove_agent_role))
+        // Agent delegation routes
+        .route("/v1/agents/:agent_id/delegations", get(delegation_handlers::get_agent_delegations))
+        .route("/v1/agents/:agent_id/delegations", post(delegation_handlers::create_delegation))
+        .route("/v1/agents/:agent_id/delegations/verify", post(delegation_handlers::verify_delegation))
+        .route("/v1/agents/:agent_id/delegations/:delegation_id", delete(delegation_handlers::revoke_delegation))
         // Tenant routes (TEN.*)
         .route("/v1/tenants", post(tenant_handlers::create_tenant))
         .route("/v1/tenants/:tenant_id", get(tenant_handlers::get_tenant))
@@ -32,12 +103,26 @@ pub fn create_router(pool: PgPool, ca: CertificateAuthority) -> Router {
         .route("/v1/tenants/:tenant_id", delete(tenant_handlers::deactivate_tenant))
         .route("/v1/tenants/:tenant_id/hierarchy", get(tenant_handlers::get_tenant_hierarchy))
         // Trust score routes (TRUST.*)
+        .route("/v1/trust/import", post(trust_handlers::import_trust_scores))
+        .route("/v1/trust/recompute", post(recompute_handlers::start_trust_recompute))
+        .route("/v1/trust/recompute/:job_id", get(recompute_handlers::get_trust_recompute))
+        .route("/v1/trust/recompute/:job_id", delete(recompute_handlers::cancel_trust_recompute))
+        .route("/v1/trust/below-threshold", get(trust_handlers::list_below_threshold))
+        .route("/v1/trust/break-glass", get(break_glass_handlers::list_break_glass))
+        .route("/v1/trust/break-glass", post(break_glass_handlers::create_break_glass))
+        .route("/v1/trust/break-glass/:override_id", delete(break_glass_handlers::revoke_break_glass))
         .route("/v1/trust/:entity_type/:entity_id", get(trust_handlers::get_trust_score))
         .route("/v1/trust/:entity_type/:entity_id", post(trust_handlers::create_trust_score))
         .route("/v1/trust/:entity_type/:entity_id", patch(trust_handlers::update_trust_dimension))
         .route("/v1/trust/:entity_type/:entity_id/history", get(trust_handlers::get_trust_score_history))
+        // CA certificate, for verifying issued certificate chains
+        .route("/v1/ca/certificate", get(handlers::get_ca_certificate))
+        .route("/v1/ca/crl", get(certificate_handlers::get_ca_crl))
+        .route("/v1/ca/revocation/:serial", get(certificate_handlers::get_certificate_status))
         // Health check
         .route("/health", get(health_check))
+        .layer(middleware::from_fn(deadline))
+        .layer(middleware::from_fn(request_id))
         .with_state(state)
 }
 
@@ -5,10 +5,12 @@ use axum::{
 };
 use uuid::Uuid;
 use chrono::Utc;
+use pathwell_common::json_body::JsonBody;
 
 use crate::api::models::*;
 use crate::api::routes::AppState;
 use crate::db::models::{Tenant, TenantType};
+use crate::scoring::DimensionSchema;
 
 fn parse_tenant_type(s: &str) -> TenantType {
     match s.to_lowercase().as_str() {
@@ -29,11 +31,43 @@ fn tenant_type_to_string(t: TenantType) -> String {
     }
 }
 
+/// Reject governance configs whose `trust_dimensions` declaration can't be used for scoring, or
+/// whose `require_attribution` isn't a boolean
+fn validate_governance_config(
+    config: Option<&serde_json::Value>,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if config
+        .and_then(|c| c.get("require_attribution"))
+        .map_or(false, |v| !v.is_boolean())
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "invalid_governance_config".to_string(),
+                message: "require_attribution must be a boolean".to_string(),
+            }),
+        ));
+    }
+    let Some(declared) = config.and_then(|c| c.get("trust_dimensions")) else {
+        return Ok(());
+    };
+    DimensionSchema::parse_declared(declared.clone()).map(|_| ()).map_err(|message| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "invalid_governance_config".to_string(),
+                message,
+            }),
+        )
+    })
+}
+
 pub async fn create_tenant(
     State(state): State<AppState>,
-    Json(payload): Json<CreateTenantRequest>,
+    JsonBody(payload): JsonBody<CreateTenantRequest>,
 ) -> Result<Json<CreateTenantResponse>, (StatusCode, Json<ErrorResponse>)> {
     let pool = &state.pool;
+    validate_governance_config(payload.governance_config.as_ref())?;
 
     // Check if tenant already exists
     let existing = sqlx::query!(
@@ -400,10 +434,11 @@ pub async fn get_tenant_hierarchy(
 pub async fn update_tenant(
     State(state): State<AppState>,
     Path(tenant_id): Path<String>,
-    Json(payload): Json<UpdateTenantRequest>,
+    JsonBody(payload): JsonBody<UpdateTenantRequest>,
 ) -> Result<Json<TenantResponse>, (StatusCode, Json<ErrorResponse>)> {
     let pool = &state.pool;
     let now = Utc::now().naive_utc();
+    validate_governance_config(payload.governance_config.as_ref())?;
 
     // Build dynamic update
     let tenant = sqlx::query_as!(
@@ -1,20 +1,146 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
     response::Json,
 };
+use std::collections::HashSet;
 use uuid::Uuid;
 use chrono::Utc;
-use rust_decimal::Decimal;
-use rust_decimal::prelude::ToPrimitive;
+use sqlx::PgExecutor;
+use pathwell_common::json_body::JsonBody;
+use pathwell_common::pagination::{page_size, Cursor};
 
+use crate::api::auth::TRUST_WRITE_SCOPE;
 use crate::api::models::*;
 use crate::api::routes::AppState;
 use crate::db::models::{TrustScore, TrustScoreHistory, TrustDimensionScores};
+use crate::scoring::{
+    score_to_decimal, score_to_f64, CalculationInputs, CalculationSource, DimensionChange,
+    DimensionSchema, ScoreConversionError, CALCULATION_VERSION,
+};
+
+/// Largest batch accepted by `POST /v1/trust/import`
+const MAX_IMPORT_RECORDS: usize = 1000;
+
+/// Orderings offered by `GET /v1/trust/below-threshold`
+const BELOW_THRESHOLD_SORTS: [&str; 3] = ["score", "severity", "age"];
+
+fn database_error(e: sqlx::Error) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse {
+            error: "database_error".to_string(),
+            message: e.to_string(),
+        }),
+    )
+}
+
+/// Surface a trust score conversion failure rather than masking it as a neutral score
+pub(crate) fn score_error(e: ScoreConversionError) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse {
+            error: "invalid_trust_score".to_string(),
+            message: e.to_string(),
+        }),
+    )
+}
+
+/// Dimensions scored for an entity: the standard five plus any its tenant declares
+pub(crate) async fn dimension_schema<'e>(
+    executor: impl PgExecutor<'e>,
+    entity_type: &str,
+    entity_id: Uuid,
+) -> Result<DimensionSchema, (StatusCode, Json<ErrorResponse>)> {
+    let declared = sqlx::query_scalar!(
+        r#"
+        SELECT governance_config -> 'trust_dimensions'
+        FROM tenants
+        WHERE id = CASE $1
+            WHEN 'agent' THEN (SELECT tenant_id FROM agents WHERE id = $2)
+            WHEN 'developer' THEN (SELECT tenant_id FROM developers WHERE id = $2)
+            WHEN 'enterprise' THEN (SELECT tenant_id FROM enterprises WHERE id = $2)
+            WHEN 'tenant' THEN $2
+        END
+        "#,
+        entity_type,
+        entity_id
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(database_error)?
+    .flatten();
+
+    let Some(declared) = declared else {
+        return Ok(DimensionSchema::standard());
+    };
+    // Declarations are validated when the tenant is written, so this only trips on legacy configs
+    let declared = DimensionSchema::parse_declared(declared).map_err(|message| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: "invalid_governance_config".to_string(),
+                message,
+            }),
+        )
+    })?;
+    Ok(DimensionSchema::with_declared(declared))
+}
+
+/// `calculation_inputs` to store with a score, unless recording them is turned off
+pub(crate) fn recorded_inputs(state: &AppState, inputs: CalculationInputs) -> Option<serde_json::Value> {
+    if !state.record_calculation_inputs {
+        return None;
+    }
+    serde_json::to_value(inputs).ok()
+}
+
+fn invalid_dimension(message: String) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse {
+            error: "invalid_dimension".to_string(),
+            message,
+        }),
+    )
+}
+
+fn trust_score_response(
+    score: TrustScore,
+    dimensions: TrustDimensionScores,
+    precision: u32,
+) -> Result<TrustScoreResponse, (StatusCode, Json<ErrorResponse>)> {
+    let composite = score_to_f64(score.composite_score, precision).map_err(score_error)?;
+    let confidence = score_to_f64(score.confidence_level, precision).map_err(score_error)?;
+    let threshold = score
+        .minimum_threshold
+        .map(|t| score_to_f64(t, precision))
+        .transpose()
+        .map_err(score_error)?;
+
+    Ok(TrustScoreResponse {
+        entity_type: score.entity_type,
+        entity_id: score.entity_id,
+        composite_score: composite,
+        confidence_level: confidence,
+        dimensions: dimensions.into(),
+        threshold_status: TrustThresholdStatus {
+            minimum_threshold: threshold,
+            is_above_threshold: threshold.map(|t| composite >= t).unwrap_or(true),
+            action_if_below: score.threshold_action,
+        },
+        last_calculated_at: score.last_calculated_at.and_utc().to_rfc3339(),
+        calculation_version: score.calculation_version,
+        calculation_inputs: score.calculation_inputs,
+        composite_check: None,
+        delta_clamp: None,
+    })
+}
 
 pub async fn get_trust_score(
     State(state): State<AppState>,
     Path((entity_type, entity_id)): Path<(String, Uuid)>,
+    Query(params): Query<TrustScoreQuery>,
 ) -> Result<Json<TrustScoreResponse>, (StatusCode, Json<ErrorResponse>)> {
     let pool = &state.pool;
 
@@ -54,31 +180,45 @@ pub async fn get_trust_score(
         )
     })?;
 
-    let dimensions: TrustDimensionScores =
-        serde_json::from_value(score.dimension_scores.clone()).unwrap_or_default();
-    let composite = score.composite_score.to_f64().unwrap_or(0.5);
-    let threshold = score.minimum_threshold.and_then(|t| t.to_f64());
+    let schema = dimension_schema(pool, &entity_type, entity_id).await?;
+    let dimensions = serde_json::from_value::<TrustDimensionScores>(score.dimension_scores.clone())
+        .unwrap_or_default()
+        .complete(&schema);
 
-    Ok(Json(TrustScoreResponse {
-        entity_type: score.entity_type,
-        entity_id: score.entity_id,
-        composite_score: composite,
-        confidence_level: score.confidence_level.to_f64().unwrap_or(0.5),
-        dimensions: dimensions.into(),
-        threshold_status: TrustThresholdStatus {
-            minimum_threshold: threshold,
-            is_above_threshold: threshold.map(|t| composite >= t).unwrap_or(true),
-            action_if_below: score.threshold_action,
-        },
-        last_calculated_at: score.last_calculated_at.and_utc().to_rfc3339(),
-    }))
+    // Rounded as a write would store it, so only real drift (e.g. a direct dimension edit) mismatches
+    let computed = params
+        .verify
+        .then(|| {
+            score_to_decimal(dimensions.calculate_composite(&schema), state.score_precision)
+                .and_then(|c| score_to_f64(c, state.score_precision))
+        })
+        .transpose()
+        .map_err(score_error)?;
+
+    let mut response = trust_score_response(score, dimensions, state.score_precision)?;
+    if let Some(computed) = computed {
+        if computed != response.composite_score {
+            tracing::warn!(
+                "Stored composite {} for {} {} differs from computed {}",
+                response.composite_score, entity_type, entity_id, computed
+            );
+        }
+        response.composite_check = Some(CompositeCheck {
+            stored_score: response.composite_score,
+            computed_score: computed,
+            matches: computed == response.composite_score,
+        });
+    }
+    Ok(Json(response))
 }
 
 pub async fn create_trust_score(
     State(state): State<AppState>,
     Path((entity_type, entity_id)): Path<(String, Uuid)>,
-    Json(payload): Json<CreateTrustScoreRequest>,
+    headers: HeaderMap,
+    JsonBody(payload): JsonBody<CreateTrustScoreRequest>,
 ) -> Result<Json<TrustScoreResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let actor = state.api_keys.authorize(&headers, TRUST_WRITE_SCOPE)?;
     let pool = &state.pool;
 
     // Check if already exists
@@ -110,21 +250,36 @@ pub async fn create_trust_score(
     }
 
     // Build initial dimensions
-    let dimensions = if let Some(ref init) = payload.initial_dimensions {
-        TrustDimensionScores {
-            behavior: init.behavior.unwrap_or(0.5),
-            validation: init.validation.unwrap_or(0.5),
-            provenance: init.provenance.unwrap_or(0.5),
-            alignment: init.alignment.unwrap_or(0.5),
-            reputation: init.reputation.unwrap_or(0.5),
+    let schema = dimension_schema(pool, &entity_type, entity_id).await?;
+    let mut dimensions = TrustDimensionScores::new(&schema);
+    if let Some(ref init) = payload.initial_dimensions {
+        for (name, &value) in &init.0 {
+            schema.check(name, value).map_err(invalid_dimension)?;
+            dimensions.set(name, value);
         }
-    } else {
-        TrustDimensionScores::default()
-    };
+    }
 
-    let composite = dimensions.calculate_composite();
+    let composite = dimensions.calculate_composite(&schema);
     let dimension_json = serde_json::to_value(&dimensions).unwrap_or_default();
-    let threshold = payload.minimum_threshold.map(|t| Decimal::try_from(t).unwrap_or_default());
+    let inputs = recorded_inputs(
+        &state,
+        CalculationInputs::new(CalculationSource::Create, &schema, &dimensions, state.score_precision),
+    );
+    let threshold = payload
+        .minimum_threshold
+        .map(|t| score_to_decimal(t, state.score_precision))
+        .transpose()
+        .map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: "invalid_threshold".to_string(),
+                    message: e.to_string(),
+                }),
+            )
+        })?;
+    let composite_score = score_to_decimal(composite, state.score_precision).map_err(score_error)?;
+    let confidence_level = score_to_decimal(0.5, state.score_precision).map_err(score_error)?;
 
     let id = Uuid::new_v4();
     let now = Utc::now().naive_utc();
@@ -135,9 +290,10 @@ pub async fn create_trust_score(
         INSERT INTO trust_scores (
             id, entity_type, entity_id, composite_score, confidence_level,
             dimension_scores, calculation_version, last_calculated_at,
-            minimum_threshold, threshold_action, created_at, updated_at
+            minimum_threshold, threshold_action, created_at, updated_at, updated_by,
+            calculation_inputs
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
         RETURNING
             id, entity_type, entity_id, composite_score, confidence_level,
             dimension_scores, calculation_version, last_calculated_at,
@@ -147,15 +303,17 @@ pub async fn create_trust_score(
         id,
         entity_type,
         entity_id,
-        Decimal::try_from(composite).unwrap_or_default(),
-        Decimal::try_from(0.5).unwrap_or_default(), // Initial confidence
+        composite_score,
+        confidence_level, // Initial confidence
         dimension_json,
-        "v1.0.0",
+        CALCULATION_VERSION,
         now,
         threshold,
         payload.threshold_action,
         now,
-        now
+        now,
+        actor,
+        inputs
     )
     .fetch_one(pool)
     .await
@@ -169,29 +327,16 @@ pub async fn create_trust_score(
         )
     })?;
 
-    let composite = score.composite_score.to_f64().unwrap_or(0.5);
-    let threshold = score.minimum_threshold.and_then(|t| t.to_f64());
-
-    Ok(Json(TrustScoreResponse {
-        entity_type: score.entity_type,
-        entity_id: score.entity_id,
-        composite_score: composite,
-        confidence_level: score.confidence_level.to_f64().unwrap_or(0.5),
-        dimensions: dimensions.into(),
-        threshold_status: TrustThresholdStatus {
-            minimum_threshold: threshold,
-            is_above_threshold: threshold.map(|t| composite >= t).unwrap_or(true),
-            action_if_below: score.threshold_action,
-        },
-        last_calculated_at: score.last_calculated_at.and_utc().to_rfc3339(),
-    }))
+    Ok(Json(trust_score_response(score, dimensions, state.score_precision)?))
 }
 
 pub async fn update_trust_dimension(
     State(state): State<AppState>,
     Path((entity_type, entity_id)): Path<(String, Uuid)>,
-    Json(payload): Json<UpdateTrustDimensionRequest>,
+    headers: HeaderMap,
+    JsonBody(payload): JsonBody<UpdateTrustDimensionRequest>,
 ) -> Result<Json<TrustScoreResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let actor = state.api_keys.authorize(&headers, TRUST_WRITE_SCOPE)?;
     let pool = &state.pool;
 
     // Get current score
@@ -231,37 +376,79 @@ pub async fn update_trust_dimension(
     })?;
 
     // Parse current dimensions
-    let mut dimensions: TrustDimensionScores =
-        serde_json::from_value(current.dimension_scores.clone()).unwrap_or_default();
+    let schema = dimension_schema(pool, &entity_type, entity_id).await?;
+    let mut dimensions = serde_json::from_value::<TrustDimensionScores>(current.dimension_scores.clone())
+        .unwrap_or_default()
+        .complete(&schema);
 
     // Apply delta to specified dimension
-    match payload.dimension.to_lowercase().as_str() {
-        "behavior" => dimensions.behavior = (dimensions.behavior + payload.delta).clamp(0.0, 1.0),
-        "validation" => {
-            dimensions.validation = (dimensions.validation + payload.delta).clamp(0.0, 1.0)
-        }
-        "provenance" => {
-            dimensions.provenance = (dimensions.provenance + payload.delta).clamp(0.0, 1.0)
-        }
-        "alignment" => {
-            dimensions.alignment = (dimensions.alignment + payload.delta).clamp(0.0, 1.0)
-        }
-        "reputation" => {
-            dimensions.reputation = (dimensions.reputation + payload.delta).clamp(0.0, 1.0)
-        }
-        _ => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "invalid_dimension".to_string(),
-                    message: format!("Unknown dimension: {}", payload.dimension),
-                }),
-            ))
+    let dimension = payload.dimension.to_lowercase();
+    let spec = schema
+        .get(&dimension)
+        .ok_or_else(|| invalid_dimension(format!("Unknown dimension: {}", payload.dimension)))?;
+    let value = dimensions.get(&dimension).unwrap_or(spec.default);
+
+    // History rows hold the score before each change, so the oldest one in the window is where it started
+    let limits = state.delta_limits;
+    let window_change = if limits.per_window.is_some() {
+        let window_start = Utc::now() - chrono::Duration::seconds(limits.window_secs);
+        let baseline = sqlx::query_scalar!(
+            r#"
+            SELECT dimension_scores
+            FROM trust_score_history
+            WHERE trust_score_id = $1 AND recorded_at >= $2
+            ORDER BY recorded_at ASC
+            LIMIT 1
+            "#,
+            current.id,
+            window_start
+        )
+        .fetch_optional(pool)
+        .await
+        .map_err(database_error)?;
+        baseline
+            .and_then(|dims| serde_json::from_value::<TrustDimensionScores>(dims).ok())
+            .map(|dims| value - dims.complete(&schema).get(&dimension).unwrap_or(spec.default))
+            .unwrap_or(0.0)
+    } else {
+        0.0
+    };
+
+    let (delta, limited_by) = limits.clamp(payload.delta, window_change);
+    let delta_clamp = limited_by.map(|limit| {
+        tracing::warn!(
+            "Trust update to {} {} {} clamped by the {:?} limit: requested {}, applied {}",
+            entity_type,
+            entity_id,
+            dimension,
+            limit,
+            payload.delta,
+            delta
+        );
+        DeltaClamp {
+            requested: payload.delta,
+            applied: delta,
+            limit,
         }
-    }
+    });
+    dimensions.set(&dimension, (value + delta).clamp(0.0, 1.0));
 
-    let new_composite = dimensions.calculate_composite();
+    let new_composite = score_to_decimal(dimensions.calculate_composite(&schema), state.score_precision)
+        .map_err(score_error)?;
     let dimension_json = serde_json::to_value(&dimensions).unwrap_or_default();
+    let inputs = recorded_inputs(
+        &state,
+        CalculationInputs::new(CalculationSource::Update, &schema, &dimensions, state.score_precision)
+            .with_change(DimensionChange {
+                dimension: dimension.clone(),
+                previous: value,
+                requested_delta: payload.delta,
+                applied_delta: delta,
+                limit: limited_by,
+                reason: payload.reason.clone(),
+                event_id: payload.event_id,
+            }),
+    );
     let now = Utc::now().naive_utc();
 
     // Record history
@@ -269,9 +456,9 @@ pub async fn update_trust_dimension(
         r#"
         INSERT INTO trust_score_history (
             id, trust_score_id, composite_score, dimension_scores,
-            change_reason, change_event_id, recorded_at
+            change_reason, change_event_id, recorded_at, actor, calculation_inputs
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
         "#,
         Uuid::new_v4(),
         current.id,
@@ -279,7 +466,9 @@ pub async fn update_trust_dimension(
         current.dimension_scores,
         Some(payload.reason.clone()),
         payload.event_id,
-        now
+        now,
+        actor,
+        current.calculation_inputs
     )
     .execute(pool)
     .await
@@ -293,7 +482,10 @@ pub async fn update_trust_dimension(
             composite_score = $3,
             dimension_scores = $4,
             last_calculated_at = $5,
-            updated_at = $5
+            updated_at = $5,
+            updated_by = $6,
+            calculation_version = $7,
+            calculation_inputs = $8
         WHERE entity_type = $1 AND entity_id = $2
         RETURNING
             id, entity_type, entity_id, composite_score, confidence_level,
@@ -303,9 +495,12 @@ pub async fn update_trust_dimension(
         "#,
         entity_type,
         entity_id,
-        Decimal::try_from(new_composite).unwrap_or_default(),
+        new_composite,
         dimension_json,
-        now
+        now,
+        actor,
+        CALCULATION_VERSION,
+        inputs
     )
     .fetch_one(pool)
     .await
@@ -319,22 +514,9 @@ pub async fn update_trust_dimension(
         )
     })?;
 
-    let composite = score.composite_score.to_f64().unwrap_or(0.5);
-    let threshold = score.minimum_threshold.and_then(|t| t.to_f64());
-
-    Ok(Json(TrustScoreResponse {
-        entity_type: score.entity_type,
-        entity_id: score.entity_id,
-        composite_score: composite,
-        confidence_level: score.confidence_level.to_f64().unwrap_or(0.5),
-        dimensions: dimensions.into(),
-        threshold_status: TrustThresholdStatus {
-            minimum_threshold: threshold,
-            is_above_threshold: threshold.map(|t| composite >= t).unwrap_or(true),
-            action_if_below: score.threshold_action,
-        },
-        last_calculated_at: score.last_calculated_at.and_utc().to_rfc3339(),
-    }))
+    let mut response = trust_score_response(score, dimensions, state.score_precision)?;
+    response.delta_clamp = delta_clamp;
+    Ok(Json(response))
 }
 
 pub async fn get_trust_score_history(
@@ -370,7 +552,7 @@ pub async fn get_trust_score_history(
         TrustScoreHistory,
         r#"
         SELECT id, trust_score_id, composite_score, dimension_scores,
-               change_reason, change_event_id, recorded_at
+               change_reason, change_event_id, recorded_at, actor, calculation_inputs
         FROM trust_score_history
         WHERE trust_score_id = $1
         ORDER BY recorded_at DESC
@@ -390,19 +572,384 @@ pub async fn get_trust_score_history(
         )
     })?;
 
-    Ok(Json(TrustScoreHistoryResponse {
-        entries: history
-            .into_iter()
-            .map(|h| {
-                let dims: TrustDimensionScores =
-                    serde_json::from_value(h.dimension_scores).unwrap_or_default();
-                TrustScoreHistoryEntry {
-                    composite_score: h.composite_score.to_f64().unwrap_or(0.5),
-                    dimension_scores: dims.into(),
-                    change_reason: h.change_reason,
-                    recorded_at: h.recorded_at.and_utc().to_rfc3339(),
-                }
+    let schema = dimension_schema(pool, &entity_type, entity_id).await?;
+    let entries = history
+        .into_iter()
+        .map(|h| {
+            let dims = serde_json::from_value::<TrustDimensionScores>(h.dimension_scores)
+                .unwrap_or_default()
+                .complete(&schema);
+            Ok(TrustScoreHistoryEntry {
+                composite_score: score_to_f64(h.composite_score, state.score_precision)
+                    .map_err(score_error)?,
+                dimension_scores: dims.into(),
+                change_reason: h.change_reason,
+                actor: h.actor,
+                calculation_inputs: h.calculation_inputs,
+                recorded_at: h.recorded_at.and_utc().to_rfc3339(),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Json(TrustScoreHistoryResponse { entries }))
+}
+
+fn list_error(error: &str, message: String) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse {
+            error: error.to_string(),
+            message,
+        }),
+    )
+}
+
+/// Trust scores below their minimum threshold, filtered and keyset-paginated
+pub async fn list_below_threshold(
+    State(state): State<AppState>,
+    Query(params): Query<BelowThresholdQuery>,
+) -> Result<Json<BelowThresholdResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let pool = &state.pool;
+    let limit = page_size(params.limit);
+
+    let sort = params.sort.as_deref().unwrap_or("score");
+    if !BELOW_THRESHOLD_SORTS.contains(&sort) {
+        return Err(list_error(
+            "invalid_sort",
+            format!("sort must be one of {}", BELOW_THRESHOLD_SORTS.join(", ")),
+        ));
+    }
+    let cursor = params
+        .cursor
+        .as_deref()
+        .map(|c| {
+            Cursor::decode(c, sort).ok_or_else(|| {
+                list_error("invalid_cursor", "cursor is malformed or was issued for another sort".to_string())
+            })
+        })
+        .transpose()?;
+
+    // Every sort is ascending on a numeric key: the score, the (negative) margin above the
+    // threshold, or the calculation time. Ties break on id so pages never overlap
+    let rows = sqlx::query!(
+        r#"
+        WITH below AS (
+            SELECT ts.id, ts.entity_type, ts.entity_id, ts.composite_score,
+                   ts.minimum_threshold, ts.threshold_action, ts.last_calculated_at,
+                   t.tenant_id AS tenant_id,
+                   CASE $1
+                       WHEN 'severity' THEN (ts.composite_score - ts.minimum_threshold)::float8
+                       WHEN 'age' THEN EXTRACT(EPOCH FROM ts.last_calculated_at)::float8
+                       ELSE ts.composite_score::float8
+                   END AS sort_key
+            FROM trust_scores ts
+            LEFT JOIN tenants t ON t.id = CASE ts.entity_type
+                WHEN 'agent' THEN (SELECT tenant_id FROM agents WHERE id = ts.entity_id)
+                WHEN 'developer' THEN (SELECT tenant_id FROM developers WHERE id = ts.entity_id)
+                WHEN 'enterprise' THEN (SELECT tenant_id FROM enterprises WHERE id = ts.entity_id)
+                WHEN 'tenant' THEN ts.entity_id
+            END
+            WHERE ts.composite_score < ts.minimum_threshold
+              AND ($2::text IS NULL OR ts.entity_type = $2)
+              AND ($3::text IS NULL OR $3 = ANY(t.hierarchy_path))
+              AND ($4::text IS NULL OR ts.threshold_action = $4)
+              AND ($5::timestamptz IS NULL OR ts.last_calculated_at >= $5)
+              AND ($6::timestamptz IS NULL OR ts.last_calculated_at <= $6)
+        )
+        SELECT id, entity_type, entity_id, composite_score,
+               minimum_threshold as "minimum_threshold!", threshold_action, last_calculated_at,
+               tenant_id as "tenant_id?", sort_key as "sort_key!",
+               COUNT(*) OVER () as "total!"
+        FROM below
+        WHERE $7::float8 IS NULL OR (sort_key, id) > ($7, $8::uuid)
+        ORDER BY sort_key, id
+        LIMIT $9
+        "#,
+        sort,
+        params.entity_type,
+        params.tenant_id,
+        params.threshold_action,
+        params.from,
+        params.to,
+        cursor.as_ref().map(|c| c.key),
+        cursor.as_ref().map(|c| c.id),
+        limit + 1
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(database_error)?;
+
+    let next_cursor = (rows.len() as i64 > limit)
+        .then(|| &rows[limit as usize - 1])
+        .map(|last| {
+            Cursor {
+                sort: sort.to_string(),
+                key: last.sort_key,
+                id: last.id,
+            }
+            .encode()
+        });
+
+    let total = match rows.first() {
+        Some(row) => row.total,
+        // The window count only covers rows past the cursor, so an empty page needs its own count
+        None => sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "total!"
+            FROM trust_scores ts
+            LEFT JOIN tenants t ON t.id = CASE ts.entity_type
+                WHEN 'agent' THEN (SELECT tenant_id FROM agents WHERE id = ts.entity_id)
+                WHEN 'developer' THEN (SELECT tenant_id FROM developers WHERE id = ts.entity_id)
+                WHEN 'enterprise' THEN (SELECT tenant_id FROM enterprises WHERE id = ts.entity_id)
+                WHEN 'tenant' THEN ts.entity_id
+            END
+            WHERE ts.composite_score < ts.minimum_threshold
+              AND ($1::text IS NULL OR ts.entity_type = $1)
+              AND ($2::text IS NULL OR $2 = ANY(t.hierarchy_path))
+              AND ($3::text IS NULL OR ts.threshold_action = $3)
+              AND ($4::timestamptz IS NULL OR ts.last_calculated_at >= $4)
+              AND ($5::timestamptz IS NULL OR ts.last_calculated_at <= $5)
+            "#,
+            params.entity_type,
+            params.tenant_id,
+            params.threshold_action,
+            params.from,
+            params.to
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(database_error)?,
+    };
+
+    let entries = rows
+        .into_iter()
+        .take(limit as usize)
+        .map(|row| {
+            let composite_score = score_to_f64(row.composite_score, state.score_precision)?;
+            let minimum_threshold = score_to_f64(row.minimum_threshold, state.score_precision)?;
+            Ok(BelowThresholdEntry {
+                entity_type: row.entity_type,
+                entity_id: row.entity_id,
+                tenant_id: row.tenant_id,
+                composite_score,
+                minimum_threshold,
+                shortfall: minimum_threshold - composite_score,
+                threshold_action: row.threshold_action,
+                last_calculated_at: row.last_calculated_at.to_rfc3339(),
             })
-            .collect(),
+        })
+        .collect::<Result<Vec<_>, ScoreConversionError>>()
+        .map_err(score_error)?;
+
+    Ok(Json(BelowThresholdResponse {
+        entries,
+        total,
+        limit,
+        next_cursor,
     }))
 }
+
+/// Describe every out-of-range or duplicate field in an import batch
+fn validate_import(records: &[TrustScoreImportRecord]) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut seen = HashSet::new();
+
+    for (i, record) in records.iter().enumerate() {
+        if record.entity_type.trim().is_empty() {
+            problems.push(format!("records[{}].entity_type must not be empty", i));
+        }
+        if !seen.insert((record.entity_type.as_str(), record.entity_id)) {
+            problems.push(format!(
+                "records[{}] duplicates an earlier record for {} {}",
+                i, record.entity_type, record.entity_id
+            ));
+        }
+
+        let dimensions = record
+            .dimensions
+            .0
+            .iter()
+            .map(|(name, &value)| (format!("dimensions.{}", name), Some(value)));
+        let scores = [
+            ("confidence_level".to_string(), record.confidence_level),
+            ("minimum_threshold".to_string(), record.minimum_threshold),
+        ];
+        for (field, value) in dimensions.chain(scores) {
+            if let Some(value) = value {
+                if !(0.0..=1.0).contains(&value) {
+                    problems.push(format!("records[{}].{} must be between 0 and 1", i, field));
+                }
+            }
+        }
+    }
+
+    problems
+}
+
+/// Seed trust scores in bulk (e.g. from a legacy scoring system) in a single transaction
+pub async fn import_trust_scores(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    JsonBody(payload): JsonBody<ImportTrustScoresRequest>,
+) -> Result<Json<ImportTrustScoresResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let actor = state.api_keys.authorize(&headers, TRUST_WRITE_SCOPE)?;
+    let pool = &state.pool;
+    let precision = state.score_precision;
+
+    let mut problems = validate_import(&payload.records);
+    if payload.records.len() > MAX_IMPORT_RECORDS {
+        problems.insert(0, format!("at most {} records may be imported at once", MAX_IMPORT_RECORDS));
+    }
+    if !problems.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "invalid_import".to_string(),
+                message: problems.join("; "),
+            }),
+        ));
+    }
+
+    let on_conflict = payload.on_conflict;
+    let mut summary = ImportTrustScoresResponse {
+        total: payload.records.len(),
+        created: 0,
+        overwritten: 0,
+        skipped: 0,
+    };
+
+    // All records land or none do
+    let mut tx = pool.begin().await.map_err(database_error)?;
+
+    for (i, record) in payload.records.into_iter().enumerate() {
+        // Unknown dimensions depend on each entity's tenant, so they're checked here rather than up front
+        let schema = dimension_schema(&mut *tx, &record.entity_type, record.entity_id).await?;
+        let mut dimensions = TrustDimensionScores::new(&schema);
+        for (name, &value) in &record.dimensions.0 {
+            if schema.get(name).is_none() {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(ErrorResponse {
+                        error: "invalid_import".to_string(),
+                        message: format!("records[{}].dimensions.{} is not a known dimension", i, name),
+                    }),
+                ));
+            }
+            dimensions.set(name, value);
+        }
+        let dimension_json = serde_json::to_value(&dimensions).unwrap_or_default();
+        let inputs = recorded_inputs(
+            &state,
+            CalculationInputs::new(CalculationSource::Import, &schema, &dimensions, precision),
+        );
+        let composite_score = score_to_decimal(dimensions.calculate_composite(&schema), precision)
+            .map_err(score_error)?;
+        let confidence_level = score_to_decimal(record.confidence_level.unwrap_or(0.5), precision)
+            .map_err(score_error)?;
+        let threshold = record
+            .minimum_threshold
+            .map(|t| score_to_decimal(t, precision))
+            .transpose()
+            .map_err(score_error)?;
+
+        let existing = sqlx::query!(
+            r#"
+            SELECT id, composite_score, dimension_scores, calculation_inputs
+            FROM trust_scores
+            WHERE entity_type = $1 AND entity_id = $2
+            FOR UPDATE
+            "#,
+            record.entity_type,
+            record.entity_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(database_error)?;
+
+        match (existing, on_conflict) {
+            (Some(_), ImportConflictMode::Skip) => summary.skipped += 1,
+            (Some(current), ImportConflictMode::Overwrite) => {
+                // Keep the replaced score in history like any other change
+                sqlx::query!(
+                    r#"
+                    INSERT INTO trust_score_history (
+                        trust_score_id, composite_score, dimension_scores, change_reason, actor,
+                        calculation_inputs
+                    )
+                    VALUES ($1, $2, $3, 'import', $4, $5)
+                    "#,
+                    current.id,
+                    current.composite_score,
+                    current.dimension_scores,
+                    actor,
+                    current.calculation_inputs
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(database_error)?;
+
+                sqlx::query!(
+                    r#"
+                    UPDATE trust_scores SET
+                        composite_score = $2,
+                        confidence_level = $3,
+                        dimension_scores = $4,
+                        minimum_threshold = $5,
+                        threshold_action = $6,
+                        last_calculated_at = NOW(),
+                        updated_at = NOW(),
+                        updated_by = $7,
+                        calculation_version = $8,
+                        calculation_inputs = $9
+                    WHERE id = $1
+                    "#,
+                    current.id,
+                    composite_score,
+                    confidence_level,
+                    dimension_json,
+                    threshold,
+                    record.threshold_action,
+                    actor,
+                    CALCULATION_VERSION,
+                    inputs
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(database_error)?;
+
+                summary.overwritten += 1;
+            }
+            (None, _) => {
+                sqlx::query!(
+                    r#"
+                    INSERT INTO trust_scores (
+                        entity_type, entity_id, composite_score, confidence_level,
+                        dimension_scores, minimum_threshold, threshold_action, updated_by,
+                        calculation_version, calculation_inputs
+                    )
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                    "#,
+                    record.entity_type,
+                    record.entity_id,
+                    composite_score,
+                    confidence_level,
+                    dimension_json,
+                    threshold,
+                    record.threshold_action,
+                    actor,
+                    CALCULATION_VERSION,
+                    inputs
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(database_error)?;
+
+                summary.created += 1;
+            }
+        }
+    }
+
+    tx.commit().await.map_err(database_error)?;
+
+    Ok(Json(summary))
+}
@@ -3,6 +3,9 @@ use chrono::{DateTime, Utc, NaiveDateTime};
 use sqlx::FromRow;
 use serde::{Deserialize, Serialize};
 use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+
+use crate::scoring::DimensionSchema;
 
 // ========================================
 // Existing Models
@@ -53,6 +56,19 @@ pub struct Agent {
     pub metadata: Option<serde_json::Value>,
 }
 
+// ========================================
+// Agent Role Models
+// ========================================
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct AgentRole {
+    pub id: Uuid,
+    pub role_name: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
 // ========================================
 // Tenant Hierarchy Models (TEN.*)
 // ========================================
@@ -119,29 +135,44 @@ pub struct TenantRelationship {
 // Trust Models (TRUST.*)
 // ========================================
 
+/// Dimension name to score (0.0 to 1.0), stored as a flat JSON object
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct TrustDimensionScores {
-    pub behavior: f64,
-    pub validation: f64,
-    pub provenance: f64,
-    pub alignment: f64,
-    pub reputation: f64,
-}
+#[serde(transparent)]
+pub struct TrustDimensionScores(pub BTreeMap<String, f64>);
 
 impl TrustDimensionScores {
-    pub fn new() -> Self {
-        Self {
-            behavior: 0.5,
-            validation: 0.5,
-            provenance: 0.5,
-            alignment: 0.5,
-            reputation: 0.5,
+    /// Every dimension in the schema at its default score
+    pub fn new(schema: &DimensionSchema) -> Self {
+        Self(BTreeMap::new()).complete(schema)
+    }
+
+    /// Fill dimensions missing from stored scores with their schema defaults
+    pub fn complete(mut self, schema: &DimensionSchema) -> Self {
+        for spec in schema.dimensions() {
+            self.0.entry(spec.name.clone()).or_insert(spec.default);
         }
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.0.get(name).copied()
     }
 
-    pub fn calculate_composite(&self) -> f64 {
-        // Equal weighting by default
-        (self.behavior + self.validation + self.provenance + self.alignment + self.reputation) / 5.0
+    pub fn set(&mut self, name: &str, value: f64) {
+        self.0.insert(name.to_string(), value);
+    }
+
+    /// Weighted mean over the schema's dimensions (undeclared keys are ignored)
+    pub fn calculate_composite(&self, schema: &DimensionSchema) -> f64 {
+        let (weighted, total_weight) = schema.dimensions().iter().fold((0.0, 0.0), |(sum, weights), spec| {
+            let value = self.get(&spec.name).unwrap_or(spec.default);
+            (sum + value * spec.weight, weights + spec.weight)
+        });
+        if total_weight > 0.0 {
+            weighted / total_weight
+        } else {
+            0.0
+        }
     }
 }
 
@@ -171,6 +202,8 @@ pub struct TrustScoreHistory {
     pub change_reason: Option<String>,
     pub change_event_id: Option<Uuid>,
     pub recorded_at: NaiveDateTime,
+    pub actor: Option<String>,
+    pub calculation_inputs: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
@@ -1,14 +1,20 @@
 use anyhow::Result;
-use tracing::{info, error};
+use tracing::{info, error, warn};
 use tracing_subscriber;
 
 mod db;
 mod pki;
 mod api;
+mod scoring;
+mod vault;
 
 use db::create_pool;
-use pki::CertificateAuthority;
+use pki::{CaSource, CertificateAuthority, CertificatePolicy};
+use api::auth::{ApiKeyConfig, CERTIFICATE_SCOPE, DELEGATION_WRITE_SCOPE, ROLE_WRITE_SCOPE, TRUST_WRITE_SCOPE};
+use api::certificate_handlers::backfill_certificate_log;
 use api::create_router;
+use api::recompute_handlers::RecomputeConfig;
+use vault::{spawn_verifier, VaultVerifierConfig};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -24,6 +30,41 @@ async fn main() -> Result<()> {
         .parse::<u16>()
         .unwrap_or(3001);
 
+    let score_precision = std::env::var("TRUST_SCORE_PRECISION")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(scoring::DEFAULT_SCORE_PRECISION);
+
+    let deny_deactivated_tenants = std::env::var("DENY_DEACTIVATED_TENANTS")
+        .map(|v| v != "false" && v != "0")
+        .unwrap_or(true);
+
+    let record_calculation_inputs = std::env::var("TRUST_RECORD_CALCULATION_INPUTS")
+        .map(|v| v != "false" && v != "0")
+        .unwrap_or(true);
+
+    let break_glass_max_secs = std::env::var("BREAK_GLASS_MAX_SECS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(14400);
+
+    let api_keys = ApiKeyConfig::from_env();
+    if !api_keys.require_trust_write {
+        warn!("TRUST_WRITE_AUTH_REQUIRED is off; anyone can change trust scores");
+    } else if !api_keys.has_scope(TRUST_WRITE_SCOPE) {
+        warn!("No API key has the {} scope; all trust writes will be rejected", TRUST_WRITE_SCOPE);
+    }
+    if !api_keys.has_scope(CERTIFICATE_SCOPE) {
+        warn!("No API key has the {} scope; certificate renewals and rotations will be rejected", CERTIFICATE_SCOPE);
+    }
+    if !api_keys.has_scope(DELEGATION_WRITE_SCOPE) {
+        warn!("No API key has the {} scope; delegations can't be granted or revoked", DELEGATION_WRITE_SCOPE);
+    }
+    if !api_keys.has_scope(ROLE_WRITE_SCOPE) {
+        warn!("No API key has the {} scope; roles can't be created, assigned or removed", ROLE_WRITE_SCOPE);
+    }
+
     info!("Starting Identity Registry service on port {}", port);
     info!("Connecting to database: {}", database_url);
 
@@ -50,13 +91,33 @@ async fn main() -> Result<()> {
         info!("Database tables already exist, skipping migrations");
     }
 
+    // Log the certificates of agents registered before the issuance log existed
+    let backfilled = backfill_certificate_log(&pool).await?;
+    if backfilled > 0 {
+        info!("Backfilled the certificate log for {} agent(s)", backfilled);
+    }
+
     // Initialize Certificate Authority
     info!("Initializing Certificate Authority...");
-    let ca = CertificateAuthority::new()?;
+    let ca = CertificateAuthority::load(&CaSource::from_env()?)?;
     info!("Certificate Authority initialized");
 
+    // Periodically re-verify trust vault keys so their status doesn't go stale
+    spawn_verifier(pool.clone(), ca.clone(), VaultVerifierConfig::from_env());
+
     // Create router
-    let app = create_router(pool, ca);
+    let app = create_router(
+        pool,
+        ca,
+        score_precision,
+        deny_deactivated_tenants,
+        api_keys,
+        scoring::DeltaLimits::from_env(),
+        record_calculation_inputs,
+        break_glass_max_secs,
+        CertificatePolicy::from_env(),
+        RecomputeConfig::from_env(),
+    );
 
     // Start server
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
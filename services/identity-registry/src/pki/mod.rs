@@ -1,99 +1,420 @@
-use anyhow::{Result, Context};
-use rcgen::{Certificate, CertificateParams, DistinguishedName, DnType, KeyPair};
+use anyhow::{bail, Result, Context};
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, CertificateRevocationList,
+    CertificateRevocationListParams, DistinguishedName, DnType, IsCa, KeyIdMethod, KeyPair,
+    RemoteKeyPair, RevokedCertParams, SerialNumber, SignatureAlgorithm,
+};
+use x509_parser::oid_registry::{
+    OID_EC_P256, OID_KEY_TYPE_EC_PUBLIC_KEY, OID_NIST_EC_P384, OID_PKCS1_RSAENCRYPTION,
+    OID_SIG_ED25519,
+};
+use x509_parser::certificate::X509Certificate;
 use x509_parser::pem::parse_x509_pem;
+use x509_parser::prelude::FromDer;
+use x509_parser::x509::SubjectPublicKeyInfo;
 use sha2::{Sha256, Digest};
 use time::{OffsetDateTime, Duration};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// How long an agent certificate is valid from issuance
+pub const AGENT_CERTIFICATE_VALIDITY_DAYS: i64 = 365;
+
+/// Longest a certificate revocation list is valid before relying parties should fetch another
+pub const CRL_VALIDITY_HOURS: i64 = 24;
+
+/// A newly issued agent certificate
+pub struct IssuedCertificate {
+    /// The agent certificate followed by the CA certificate, PEM-encoded
+    pub certificate_chain: String,
+    /// The agent certificate's serial number, lowercase hex
+    pub serial_number: String,
+}
+
+/// A certificate to list in a certificate revocation list
+pub struct RevokedCertificate {
+    /// Lowercase hex, as recorded at issuance
+    pub serial_number: String,
+    pub revoked_at: OffsetDateTime,
+}
+
+/// Limits on the certificates an agent holds at once, applied when one is renewed or its key rotated
+#[derive(Debug, Clone)]
+pub struct CertificatePolicy {
+    /// Most certificates an agent may have active (unexpired and unrevoked); `None` for no limit
+    pub max_active_per_agent: Option<i64>,
+    /// Whether renewal and rotation revoke the certificates they supersede unless the request says otherwise
+    pub revoke_superseded: bool,
+    /// How long superseded certificates stay valid before that revocation takes effect
+    pub superseded_grace_secs: i64,
+    /// Longest grace period a renewal or rotation may ask for
+    pub max_grace_secs: i64,
+}
+
+impl CertificatePolicy {
+    pub fn from_env() -> Self {
+        let max_grace_secs = std::env::var("MAX_SUPERSEDED_CERTIFICATE_GRACE_SECS")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|secs| *secs >= 0)
+            .unwrap_or(604800);
+        Self {
+            max_active_per_agent: std::env::var("MAX_ACTIVE_CERTIFICATES_PER_AGENT")
+                .ok()
+                .and_then(|v| v.parse::<i64>().ok())
+                .or(Some(2))
+                .filter(|max| *max > 0),
+            revoke_superseded: std::env::var("REVOKE_SUPERSEDED_CERTIFICATES")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            superseded_grace_secs: std::env::var("SUPERSEDED_CERTIFICATE_GRACE_SECS")
+                .ok()
+                .and_then(|v| v.parse::<i64>().ok())
+                .filter(|secs| *secs >= 0)
+                .unwrap_or(3600)
+                .min(max_grace_secs),
+            max_grace_secs,
+        }
+    }
+}
+
+/// Where the CA's key and certificate come from
+pub enum CaSource {
+    /// PEMs supplied directly, e.g. from a secret (`CA_KEY_PEM`, `CA_CERT_PEM`)
+    Pem { key_pem: String, cert_pem: String },
+    /// Files read at startup, generated and written on first run (`CA_KEY_PATH`, `CA_CERT_PATH`)
+    Files { key_path: PathBuf, cert_path: PathBuf },
+    /// A new CA for every process, so issued chains stop verifying after a restart
+    Ephemeral,
+}
+
+impl CaSource {
+    pub fn from_env() -> Result<Self> {
+        let var = |name| std::env::var(name).ok().filter(|v: &String| !v.trim().is_empty());
+        match (var("CA_KEY_PEM"), var("CA_CERT_PEM"), var("CA_KEY_PATH"), var("CA_CERT_PATH")) {
+            (Some(key_pem), Some(cert_pem), _, _) => Ok(Self::Pem { key_pem, cert_pem }),
+            (Some(_), None, _, _) | (None, Some(_), _, _) => {
+                bail!("CA_KEY_PEM and CA_CERT_PEM must be set together")
+            }
+            (None, None, Some(key_path), Some(cert_path)) => Ok(Self::Files {
+                key_path: key_path.into(),
+                cert_path: cert_path.into(),
+            }),
+            (None, None, Some(_), None) | (None, None, None, Some(_)) => {
+                bail!("CA_KEY_PATH and CA_CERT_PATH must be set together")
+            }
+            (None, None, None, None) => Ok(Self::Ephemeral),
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct CertificateAuthority {
+    /// Signs agent certificates; rebuilt from the key and certificate when loaded
     ca_cert: Arc<Certificate>,
-    ca_key: Arc<KeyPair>,
+    /// As generated or loaded: rcgen re-signs on every serialization, and ECDSA signatures differ each time
+    ca_cert_pem: Arc<String>,
+    ca_cert_fingerprint: Arc<String>,
 }
 
 impl CertificateAuthority {
+    /// A newly generated CA
     pub fn new() -> Result<Self> {
         let mut params = CertificateParams::new(vec!["pathwell-ca".to_string()]);
         params.distinguished_name = DistinguishedName::new();
         params.distinguished_name.push(DnType::CommonName, "Pathwell CA");
         params.distinguished_name.push(DnType::OrganizationName, "Pathwell");
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params.key_pair = Some(KeyPair::generate(&rcgen::PKCS_ECDSA_P256_SHA256)?);
         
-        let key_pair = KeyPair::generate(&rcgen::PKCS_ECDSA_P256_SHA256)?;
         let ca_cert = Certificate::from_params(params)?;
-        
+        let ca_cert_pem = ca_cert.serialize_pem()?;
+        Self::with_cert(ca_cert, ca_cert_pem)
+    }
+
+    /// The CA with this private key and certificate, e.g. one persisted by an earlier run
+    pub fn from_pem(key_pem: &str, cert_pem: &str) -> Result<Self> {
+        let key_pair = KeyPair::from_pem(key_pem).context("Failed to parse CA private key PEM")?;
+        let (_, pem) = parse_x509_pem(cert_pem.as_bytes())
+            .context("Failed to parse CA certificate PEM")?;
+        let cert = pem.parse_x509().context("Failed to parse CA certificate")?;
+        if cert.public_key().raw != key_pair.public_key_der().as_slice() {
+            bail!("CA private key does not match the CA certificate");
+        }
+
+        let params = CertificateParams::from_ca_cert_pem(cert_pem, key_pair)
+            .context("Failed to load CA certificate for signing")?;
+        let ca_cert = Certificate::from_params(params)?;
+        Self::with_cert(ca_cert, cert_pem.to_string())
+    }
+
+    /// Load the CA from `source`; for files, generate and persist it if neither file exists yet
+    pub fn load(source: &CaSource) -> Result<Self> {
+        match source {
+            CaSource::Pem { key_pem, cert_pem } => Self::from_pem(key_pem, cert_pem),
+            CaSource::Files { key_path, cert_path } => {
+                match (key_path.exists(), cert_path.exists()) {
+                    (true, true) => {
+                        info!("Loading CA from {}", cert_path.display());
+                        let key_pem = std::fs::read_to_string(key_path)
+                            .with_context(|| format!("Failed to read {}", key_path.display()))?;
+                        let cert_pem = std::fs::read_to_string(cert_path)
+                            .with_context(|| format!("Failed to read {}", cert_path.display()))?;
+                        Self::from_pem(&key_pem, &cert_pem)
+                    }
+                    (false, false) => {
+                        info!("No CA at {}, generating one", cert_path.display());
+                        let ca = Self::new()?;
+                        write_private(key_path, &ca.ca_cert.serialize_private_key_pem())?;
+                        std::fs::write(cert_path, ca.certificate_pem())
+                            .with_context(|| format!("Failed to write {}", cert_path.display()))?;
+                        Ok(ca)
+                    }
+                    // Regenerating here would orphan every certificate issued by the missing half's CA
+                    _ => bail!(
+                        "Only one of {} and {} exists; restore the other or remove both",
+                        key_path.display(),
+                        cert_path.display()
+                    ),
+                }
+            }
+            CaSource::Ephemeral => {
+                warn!("No CA configured; issued certificate chains will not verify after a restart");
+                Self::new()
+            }
+        }
+    }
+
+    fn with_cert(ca_cert: Certificate, ca_cert_pem: String) -> Result<Self> {
+        let ca_cert_der = pem::parse(&ca_cert_pem).context("Failed to parse CA certificate PEM")?;
         Ok(Self {
             ca_cert: Arc::new(ca_cert),
-            ca_key: Arc::new(key_pair),
+            ca_cert_fingerprint: Arc::new(hex::encode(Sha256::digest(ca_cert_der.contents()))),
+            ca_cert_pem: Arc::new(ca_cert_pem),
         })
     }
 
+    /// The CA certificate, PEM-encoded
+    pub fn certificate_pem(&self) -> &str {
+        &self.ca_cert_pem
+    }
+
+    /// Hex SHA-256 of the CA certificate's DER encoding
+    pub fn certificate_fingerprint(&self) -> &str {
+        &self.ca_cert_fingerprint
+    }
+
+    /// A certificate for the agent's public key (a PEM SubjectPublicKeyInfo) signed by the CA, followed by
+    /// the CA certificate
     pub fn issue_agent_certificate(
         &self,
         agent_id: &str,
         public_key_pem: &str,
-    ) -> Result<String> {
-        // For MVP, we'll generate a self-signed certificate for the agent
-        // In production, this would be signed by the CA
+    ) -> Result<IssuedCertificate> {
         let mut params = CertificateParams::new(vec![agent_id.to_string()]);
         params.distinguished_name = DistinguishedName::new();
         params.distinguished_name.push(DnType::CommonName, agent_id);
         params.distinguished_name.push(DnType::OrganizationName, "Pathwell Agent");
         
-        // Set validity period (1 year)
         let now = OffsetDateTime::now_utc();
         params.not_before = now;
-        params.not_after = now + Duration::days(365); // 1 year
+        params.not_after = now + Duration::days(AGENT_CERTIFICATE_VALIDITY_DAYS);
+
+        // rcgen otherwise derives the serial from the public key, so a renewal would reuse it and revoking
+        // the superseded certificate would revoke its replacement. Positive and without a leading zero
+        // byte, the serial is encoded exactly as these bytes
+        let mut serial = *Uuid::new_v4().as_bytes();
+        serial[0] = (serial[0] & 0x7f) | 0x01;
+        params.serial_number = Some(SerialNumber::from_slice(&serial));
         
-        // Parse the public key and create key pair
-        // For MVP, we'll generate a new key pair and use the provided public key for validation
-        // In a production system, we'd sign with the CA
-        let agent_key_pair = KeyPair::from_pem(public_key_pem)
-            .or_else(|_| {
-                // If parsing fails, generate a new key pair
-                // The public key will be stored separately for validation
-                KeyPair::generate(&rcgen::PKCS_ECDSA_P256_SHA256)
-            })?;
-        params.key_pair = Some(agent_key_pair);
+        // Only the CA signs, so the agent's public key is all the certificate needs; rcgen still
+        // requires the certificate's algorithm to match that key's
+        let agent_key = AgentPublicKey::from_pem(public_key_pem)?;
+        params.alg = agent_key.algorithm;
+        params.key_pair = Some(KeyPair::from_remote(Box::new(agent_key))?);
         
         let agent_cert = Certificate::from_params(params)?;
-        let agent_cert_pem = agent_cert.serialize_pem()?;
-        let ca_cert_pem = self.ca_cert.serialize_pem()?;
+        let agent_cert_pem = agent_cert.serialize_pem_with_signer(&self.ca_cert)?;
         
         // Return certificate chain: agent cert + CA cert
-        Ok(format!("{}\n{}", agent_cert_pem, ca_cert_pem))
+        Ok(IssuedCertificate {
+            certificate_chain: format!("{}\n{}", agent_cert_pem, self.ca_cert_pem),
+            serial_number: hex::encode(serial),
+        })
     }
 
-    pub fn validate_certificate_chain(&self, certificate_chain: &str) -> Result<bool> {
-        let cert_strings: Vec<String> = certificate_chain
+    /// A CRL signed by the CA listing `revoked`, valid from now until `next_update`, PEM-encoded
+    pub fn generate_crl(
+        &self,
+        revoked: &[RevokedCertificate],
+        next_update: OffsetDateTime,
+    ) -> Result<String> {
+        let revoked_certs = revoked
+            .iter()
+            .map(|cert| {
+                let serial = hex::decode(&cert.serial_number)
+                    .with_context(|| format!("Invalid certificate serial {}", cert.serial_number))?;
+                Ok(RevokedCertParams {
+                    serial_number: SerialNumber::from(serial),
+                    revocation_time: cert.revoked_at,
+                    reason_code: None,
+                    invalidity_date: None,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let this_update = OffsetDateTime::now_utc();
+        let params = CertificateRevocationListParams {
+            this_update,
+            // A revocation due now could otherwise leave the list expiring as it is issued
+            next_update: next_update.max(this_update + Duration::seconds(1)),
+            // Microseconds since the epoch increase with every list generated
+            crl_number: SerialNumber::from((this_update.unix_timestamp_nanos() / 1000) as u64),
+            issuing_distribution_point: None,
+            revoked_certs,
+            alg: self.ca_cert.get_params().alg,
+            key_identifier_method: KeyIdMethod::Sha256,
+        };
+        let crl = CertificateRevocationList::from_params(params)?;
+        Ok(crl.serialize_pem_with_signer(&self.ca_cert)?)
+    }
+
+    /// Check every certificate in the chain, leaf first: each must be signed by the next, and the last
+    /// must be this CA's certificate or signed by it. Errors only if the chain can't be parsed
+    pub fn validate_certificate_chain(&self, certificate_chain: &str) -> Result<ChainValidation> {
+        let pems = certificate_chain
             .split("-----END CERTIFICATE-----")
             .filter(|s| !s.trim().is_empty())
-            .map(|s| format!("{}-----END CERTIFICATE-----", s))
-            .collect();
-        
-        if cert_strings.is_empty() {
-            return Ok(false);
+            .map(|s| {
+                parse_x509_pem(format!("{}-----END CERTIFICATE-----", s).as_bytes())
+                    .map(|(_, pem)| pem)
+                    .context("Failed to parse certificate PEM")
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let certs = pems
+            .iter()
+            .map(|pem| pem.parse_x509().context("Failed to parse certificate"))
+            .collect::<Result<Vec<_>>>()?;
+
+        let (_, ca_pem) = parse_x509_pem(self.ca_cert_pem.as_bytes())
+            .context("Failed to parse CA certificate PEM")?;
+        let ca = ca_pem.parse_x509().context("Failed to parse CA certificate")?;
+
+        let (Some(leaf), Some(last)) = (pems.first(), pems.last()) else {
+            return Ok(ChainValidation::UntrustedIssuer);
+        };
+        // The CA certificate alone isn't a chain for anything
+        let anchored = if last.contents == ca_pem.contents {
+            leaf.contents != ca_pem.contents
+        } else {
+            certs.last().is_some_and(|cert| signed_by(cert, &ca))
+        };
+        let linked = certs.windows(2).all(|pair| signed_by(&pair[0], &pair[1]));
+        if !anchored {
+            // A chain ending in another self-signed "Pathwell CA" was issued by a CA this one replaced,
+            // e.g. an ephemeral CA from before the CA was persisted
+            let earlier_ca = certs
+                .last()
+                .is_some_and(|root| certs.len() > 1 && root.subject() == ca.subject() && signed_by(root, root));
+            if linked && earlier_ca {
+                return Ok(ChainValidation::EarlierCa);
+            }
+            return Ok(ChainValidation::UntrustedIssuer);
         }
-        
-        // Parse the first certificate (agent cert)
-        let (_, pem) = parse_x509_pem(cert_strings[0].as_bytes())
-            .context("Failed to parse certificate PEM")?;
-        
-        // Verify certificate is not expired
-        let cert = pem.parse_x509()?;
-        let validity = cert.validity();
-        let now = OffsetDateTime::now_utc();
-        
-        // Check if certificate is within validity period
-        let not_before = OffsetDateTime::from_unix_timestamp(validity.not_before.timestamp())
-            .map_err(|_| anyhow::anyhow!("Invalid not_before timestamp"))?;
-        let not_after = OffsetDateTime::from_unix_timestamp(validity.not_after.timestamp())
-            .map_err(|_| anyhow::anyhow!("Invalid not_after timestamp"))?;
-        
-        if now < not_before || now > not_after {
-            return Ok(false);
+        if !linked {
+            return Ok(ChainValidation::UntrustedIssuer);
         }
-        
-        Ok(true)
+
+        // Every certificate, the CA's included, must be within its validity period
+        if certs.iter().any(|cert| !cert.validity().is_valid()) {
+            return Ok(ChainValidation::Expired);
+        }
+
+        Ok(ChainValidation::Valid)
+    }
+}
+
+/// The outcome of validating a certificate chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainValidation {
+    Valid,
+    /// Every signature checks out, but a certificate has expired or isn't valid yet
+    Expired,
+    /// A certificate isn't issued and signed by the next one up, or the chain doesn't lead to this CA
+    UntrustedIssuer,
+    /// Every signature checks out, but the chain leads to an earlier CA with this CA's name rather than
+    /// to this one
+    EarlierCa,
+}
+
+/// Whether `issuer` is a CA that issued and signed `cert`
+fn signed_by(cert: &X509Certificate, issuer: &X509Certificate) -> bool {
+    issuer.is_ca()
+        && cert.issuer() == issuer.subject()
+        && cert.verify_signature(Some(issuer.public_key())).is_ok()
+}
+
+/// An agent's public key, for a certificate the CA signs; the agent keeps the private key, so this never signs
+struct AgentPublicKey {
+    raw: Vec<u8>,
+    algorithm: &'static SignatureAlgorithm,
+    /// How the agent's own signatures are checked
+    verification: &'static dyn ring::signature::VerificationAlgorithm,
+}
+
+impl AgentPublicKey {
+    /// An RSA, ECDSA P-256/P-384 or Ed25519 key from a PEM SubjectPublicKeyInfo
+    fn from_pem(public_key_pem: &str) -> Result<Self> {
+        let pem = pem::parse(public_key_pem).context("Failed to parse agent public key PEM")?;
+        let (_, spki) = SubjectPublicKeyInfo::from_der(pem.contents())
+            .map_err(|e| anyhow::anyhow!("Failed to parse agent public key: {}", e))?;
+
+        let key_type = &spki.algorithm.algorithm;
+        let curve = spki.algorithm.parameters.as_ref().and_then(|p| p.as_oid().ok());
+        let (algorithm, verification): (_, &'static dyn ring::signature::VerificationAlgorithm) =
+            if *key_type == OID_PKCS1_RSAENCRYPTION {
+                (&rcgen::PKCS_RSA_SHA256, &ring::signature::RSA_PKCS1_2048_8192_SHA256)
+            } else if *key_type == OID_SIG_ED25519 {
+                (&rcgen::PKCS_ED25519, &ring::signature::ED25519)
+            } else if *key_type == OID_KEY_TYPE_EC_PUBLIC_KEY && curve == Some(OID_EC_P256) {
+                (&rcgen::PKCS_ECDSA_P256_SHA256, &ring::signature::ECDSA_P256_SHA256_ASN1)
+            } else if *key_type == OID_KEY_TYPE_EC_PUBLIC_KEY && curve == Some(OID_NIST_EC_P384) {
+                (&rcgen::PKCS_ECDSA_P384_SHA384, &ring::signature::ECDSA_P384_SHA384_ASN1)
+            } else {
+                bail!("Unsupported agent public key algorithm {}", key_type);
+            };
+
+        Ok(Self {
+            raw: spki.subject_public_key.data.to_vec(),
+            algorithm,
+            verification,
+        })
+    }
+}
+
+/// Whether `signature` was made over `message` with the private half of `public_key_pem` (a PEM
+/// SubjectPublicKeyInfo): RSA PKCS#1 v1.5 with SHA-256, DER-encoded ECDSA with SHA-256 (P-256) or
+/// SHA-384 (P-384), or Ed25519
+pub fn verify_key_signature(public_key_pem: &str, message: &[u8], signature: &[u8]) -> Result<bool> {
+    let key = AgentPublicKey::from_pem(public_key_pem)?;
+    Ok(ring::signature::UnparsedPublicKey::new(key.verification, &key.raw)
+        .verify(message, signature)
+        .is_ok())
+}
+
+impl RemoteKeyPair for AgentPublicKey {
+    fn public_key(&self) -> &[u8] {
+        &self.raw
+    }
+
+    fn sign(&self, _msg: &[u8]) -> Result<Vec<u8>, rcgen::Error> {
+        Err(rcgen::Error::RemoteKeyError)
+    }
+
+    fn algorithm(&self) -> &'static SignatureAlgorithm {
+        self.algorithm
     }
 }
 
@@ -110,3 +431,18 @@ pub fn hash_public_key(public_key: &str) -> String {
     hex::encode(hasher.finalize())
 }
 
+/// Write a private key readable only by its owner
+fn write_private(path: &Path, contents: &str) -> Result<()> {
+    use std::io::Write;
+    #[cfg(unix)]
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
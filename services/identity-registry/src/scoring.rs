@@ -0,0 +1,265 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::db::models::TrustDimensionScores;
+
+// Score conversion is shared with the receipt store, which records the scores written here
+pub use pathwell_common::scoring::{
+    score_to_decimal, score_to_f64, ScoreConversionError, DEFAULT_SCORE_PRECISION,
+};
+
+// ========================================
+// Trust Dimension Schema
+// ========================================
+
+/// Dimensions every trust score carries, kept as well-known keys for existing clients
+pub const STANDARD_DIMENSIONS: [&str; 5] = ["behavior", "validation", "provenance", "alignment", "reputation"];
+
+fn default_dimension_weight() -> f64 {
+    1.0
+}
+
+fn default_dimension_score() -> f64 {
+    0.5
+}
+
+/// A trust dimension declared in a tenant's `governance_config.trust_dimensions`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DimensionSpec {
+    pub name: String,
+    /// Relative weight in the composite score
+    #[serde(default = "default_dimension_weight")]
+    pub weight: f64,
+    /// Score a dimension starts at until it is set
+    #[serde(default = "default_dimension_score")]
+    pub default: f64,
+}
+
+/// The dimensions a trust score may carry and how they weigh into the composite
+#[derive(Debug, Clone)]
+pub struct DimensionSchema {
+    dimensions: Vec<DimensionSpec>,
+}
+
+impl DimensionSchema {
+    /// The five standard dimensions, equally weighted
+    pub fn standard() -> Self {
+        Self {
+            dimensions: STANDARD_DIMENSIONS
+                .iter()
+                .map(|name| DimensionSpec {
+                    name: name.to_string(),
+                    weight: default_dimension_weight(),
+                    default: default_dimension_score(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Standard dimensions plus a tenant's declared ones; declaring a standard name overrides its weight and default
+    pub fn with_declared(declared: Vec<DimensionSpec>) -> Self {
+        let mut schema = Self::standard();
+        for spec in declared {
+            match schema.dimensions.iter_mut().find(|d| d.name == spec.name) {
+                Some(existing) => *existing = spec,
+                None => schema.dimensions.push(spec),
+            }
+        }
+        schema
+    }
+
+    /// Parse and validate a tenant's `trust_dimensions` declaration
+    pub fn parse_declared(value: serde_json::Value) -> Result<Vec<DimensionSpec>, String> {
+        let declared: Vec<DimensionSpec> = serde_json::from_value(value)
+            .map_err(|e| format!("trust_dimensions must be a list of dimension declarations: {}", e))?;
+
+        let mut seen = std::collections::HashSet::new();
+        for spec in &declared {
+            if spec.name.trim().is_empty() {
+                return Err("trust dimension names must not be empty".to_string());
+            }
+            if !seen.insert(spec.name.as_str()) {
+                return Err(format!("trust dimension {} is declared more than once", spec.name));
+            }
+            if !spec.weight.is_finite() || spec.weight <= 0.0 {
+                return Err(format!("trust dimension {} must have a positive weight", spec.name));
+            }
+            if !(0.0..=1.0).contains(&spec.default) {
+                return Err(format!("trust dimension {} default must be between 0 and 1", spec.name));
+            }
+        }
+        Ok(declared)
+    }
+
+    pub fn dimensions(&self) -> &[DimensionSpec] {
+        &self.dimensions
+    }
+
+    pub fn get(&self, name: &str) -> Option<&DimensionSpec> {
+        self.dimensions.iter().find(|d| d.name == name)
+    }
+
+    /// Check a dimension name and value, describing the problem if either is rejected
+    pub fn check(&self, name: &str, value: f64) -> Result<(), String> {
+        if self.get(name).is_none() {
+            return Err(format!("Unknown dimension: {}", name));
+        }
+        if !(0.0..=1.0).contains(&value) {
+            return Err(format!("Dimension {} must be between 0 and 1", name));
+        }
+        Ok(())
+    }
+}
+
+// ========================================
+// Trust Delta Limits
+// ========================================
+
+/// Which limit cut a trust update short
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeltaLimit {
+    PerUpdate,
+    PerWindow,
+}
+
+/// How far a dimension may move in one update, and in net within a rolling window
+#[derive(Debug, Clone, Copy)]
+pub struct DeltaLimits {
+    /// Largest absolute delta one update may apply (`TRUST_MAX_DELTA`)
+    pub per_update: Option<f64>,
+    /// Largest net change a dimension may accumulate within the window (`TRUST_MAX_WINDOW_DELTA`)
+    pub per_window: Option<f64>,
+    pub window_secs: i64,
+}
+
+impl DeltaLimits {
+    pub fn from_env() -> Self {
+        let limit = |name| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|v| v.is_finite() && *v >= 0.0)
+        };
+        Self {
+            per_update: limit("TRUST_MAX_DELTA"),
+            per_window: limit("TRUST_MAX_WINDOW_DELTA"),
+            window_secs: std::env::var("TRUST_DELTA_WINDOW_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3600)
+                .max(1),
+        }
+    }
+
+    /// The delta to apply to a dimension that has already moved `window_change` within the window,
+    /// and the limit that reduced it, if any
+    pub fn clamp(&self, requested: f64, window_change: f64) -> (f64, Option<DeltaLimit>) {
+        let mut applied = requested;
+        let mut limited_by = None;
+
+        if let Some(max) = self.per_update {
+            if applied.abs() > max {
+                applied = max.copysign(applied);
+                limited_by = Some(DeltaLimit::PerUpdate);
+            }
+        }
+
+        if let Some(max) = self.per_window {
+            // A dimension already past the limit (e.g. after it was lowered) may still move back
+            let lower = (-max - window_change).min(0.0);
+            let upper = (max - window_change).max(0.0);
+            if applied < lower || applied > upper {
+                applied = applied.clamp(lower, upper);
+                limited_by = Some(DeltaLimit::PerWindow);
+            }
+        }
+
+        (applied, limited_by)
+    }
+}
+
+// ========================================
+// Calculation Inputs
+// ========================================
+
+/// Version of the composite calculation, recorded as `calculation_version` with every score
+pub const CALCULATION_VERSION: &str = "v1.0.0";
+
+/// Which write calculated a score
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CalculationSource {
+    Create,
+    Update,
+    Import,
+    Recompute,
+}
+
+/// A dimension as it entered the composite
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DimensionInput {
+    pub score: f64,
+    pub weight: f64,
+}
+
+/// The update that moved a dimension
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DimensionChange {
+    pub dimension: String,
+    /// The dimension's score before the update
+    pub previous: f64,
+    pub requested_delta: f64,
+    pub applied_delta: f64,
+    /// The delta limit that reduced `requested_delta`, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<DeltaLimit>,
+    pub reason: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<Uuid>,
+}
+
+/// Everything a composite was calculated from, stored as the score's `calculation_inputs`: the weighted
+/// mean of `dimensions`, rounded to `precision` places, reproduces it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalculationInputs {
+    pub version: String,
+    pub source: CalculationSource,
+    pub dimensions: BTreeMap<String, DimensionInput>,
+    pub precision: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub change: Option<DimensionChange>,
+}
+
+impl CalculationInputs {
+    pub fn new(
+        source: CalculationSource,
+        schema: &DimensionSchema,
+        dimensions: &TrustDimensionScores,
+        precision: u32,
+    ) -> Self {
+        Self {
+            version: CALCULATION_VERSION.to_string(),
+            source,
+            dimensions: schema
+                .dimensions()
+                .iter()
+                .map(|spec| {
+                    let input = DimensionInput {
+                        score: dimensions.get(&spec.name).unwrap_or(spec.default),
+                        weight: spec.weight,
+                    };
+                    (spec.name.clone(), input)
+                })
+                .collect(),
+            precision,
+            change: None,
+        }
+    }
+
+    pub fn with_change(mut self, change: DimensionChange) -> Self {
+        self.change = Some(change);
+        self
+    }
+}
@@ -0,0 +1,363 @@
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use sqlx::PgPool;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::db::models::RiskSeverity;
+use crate::pki::{hash_public_key, CertificateAuthority, ChainValidation};
+
+/// How often and how aggressively trust vault entries are re-verified
+#[derive(Debug, Clone)]
+pub struct VaultVerifierConfig {
+    /// Time between verification sweeps
+    pub interval_secs: u64,
+    /// Entries not verified within this window are re-checked
+    pub stale_after_secs: i64,
+    /// Entries whose `valid_until` falls within this window are re-checked every sweep
+    pub expiry_warning_secs: i64,
+    /// Maximum entries checked per sweep
+    pub batch_size: i64,
+}
+
+impl VaultVerifierConfig {
+    pub fn from_env() -> Self {
+        Self {
+            interval_secs: std::env::var("VAULT_VERIFY_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3600)
+                .max(1),
+            stale_after_secs: std::env::var("VAULT_VERIFY_STALE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(86400),
+            expiry_warning_secs: std::env::var("VAULT_VERIFY_EXPIRY_WARNING_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(7 * 86400),
+            batch_size: std::env::var("VAULT_VERIFY_BATCH_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(100),
+        }
+    }
+}
+
+/// First key of the verifier's advisory lock (the second is the hash of `VERIFIER_LOCK_NAME`)
+const VERIFIER_LOCK_NAMESPACE: i32 = 0x5057_4A42;
+const VERIFIER_LOCK_NAME: &str = "trust_vault_verifier";
+
+/// Outcome of re-checking a single vault entry
+enum Verification {
+    Verified,
+    Expiring,
+    /// The chain was issued by a CA this one replaced; renewing the certificate fixes it
+    ReissueRequired,
+    Failed { reason: String, severity: RiskSeverity },
+}
+
+impl Verification {
+    fn status(&self) -> &'static str {
+        match self {
+            Verification::Verified => "verified",
+            Verification::Expiring => "expiring",
+            Verification::ReissueRequired => "reissue_required",
+            Verification::Failed { .. } => "failed",
+        }
+    }
+}
+
+/// Key material currently registered for a vault entry's entity
+struct RegisteredKey {
+    public_key: String,
+    certificate_chain: Option<String>,
+    revoked: bool,
+}
+
+/// Start the periodic vault re-verification task. Each sweep runs under a Postgres advisory lock, so
+/// with several replicas only one verifies at a time and the others skip that tick
+pub fn spawn_verifier(pool: PgPool, ca: CertificateAuthority, config: VaultVerifierConfig) {
+    info!(
+        "Trust vault verification every {}s (stale after {}s)",
+        config.interval_secs, config.stale_after_secs
+    );
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(config.interval_secs));
+        loop {
+            ticker.tick().await;
+            match verify_locked(&pool, &ca, &config).await {
+                Ok(None) => debug!("Trust vault verification is running on another replica"),
+                Ok(Some(0)) => {}
+                Ok(Some(count)) => info!("Re-verified {} trust vault entries", count),
+                Err(e) => error!("Trust vault verification sweep failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Run a sweep while holding the verifier's advisory lock, or return `None` if another replica holds it.
+/// The lock is a session lock on its own connection, so it is released if this process dies mid-sweep
+async fn verify_locked(
+    pool: &PgPool,
+    ca: &CertificateAuthority,
+    config: &VaultVerifierConfig,
+) -> anyhow::Result<Option<usize>> {
+    let mut conn = pool.acquire().await?;
+    let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1, hashtext($2))")
+        .bind(VERIFIER_LOCK_NAMESPACE)
+        .bind(VERIFIER_LOCK_NAME)
+        .fetch_one(&mut *conn)
+        .await?;
+    if !locked {
+        return Ok(None);
+    }
+
+    let result = verify_due_entries(pool, ca, config).await;
+
+    let unlocked = sqlx::query("SELECT pg_advisory_unlock($1, hashtext($2))")
+        .bind(VERIFIER_LOCK_NAMESPACE)
+        .bind(VERIFIER_LOCK_NAME)
+        .execute(&mut *conn)
+        .await;
+    if unlocked.is_err() {
+        // Closing the session releases the lock
+        drop(conn.detach());
+    }
+    result.map(Some)
+}
+
+/// Re-verify entries that are stale or nearing expiry, returning how many were checked
+pub async fn verify_due_entries(
+    pool: &PgPool,
+    ca: &CertificateAuthority,
+    config: &VaultVerifierConfig,
+) -> anyhow::Result<usize> {
+    // Expiring entries are only re-checked once per interval so a slow sweep can't spin on them
+    let entries = sqlx::query!(
+        r#"
+        SELECT id, entity_type, entity_id, public_key_hash, certificate_chain_hash,
+               verification_status, valid_until
+        FROM trust_vault_entries
+        WHERE revoked_at IS NULL
+          AND (
+              last_verified_at IS NULL
+              OR last_verified_at < NOW() - make_interval(secs => $1::float8)
+              OR (valid_until IS NOT NULL
+                  AND valid_until < NOW() + make_interval(secs => $2::float8)
+                  AND last_verified_at < NOW() - make_interval(secs => $3::float8))
+          )
+        ORDER BY last_verified_at ASC NULLS FIRST
+        LIMIT $4
+        "#,
+        config.stale_after_secs as f64,
+        config.expiry_warning_secs as f64,
+        config.interval_secs as f64,
+        config.batch_size
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let count = entries.len();
+    for entry in entries {
+        let registered = registered_key(pool, &entry.entity_type, entry.entity_id).await?;
+        let outcome = verify_entry(
+            ca,
+            registered,
+            &entry.public_key_hash,
+            entry.certificate_chain_hash.as_deref(),
+            entry.valid_until,
+            config.expiry_warning_secs,
+        );
+
+        sqlx::query!(
+            r#"
+            UPDATE trust_vault_entries
+            SET verification_status = $1, last_verified_at = NOW(),
+                verification_method = 'scheduled', updated_at = NOW()
+            WHERE id = $2
+            "#,
+            outcome.status(),
+            entry.id
+        )
+        .execute(pool)
+        .await?;
+
+        match outcome {
+            Verification::ReissueRequired if entry.verification_status != "reissue_required" => {
+                warn!(
+                    "Trust vault entry {} ({} {}) has a certificate from an earlier CA; renew it",
+                    entry.id, entry.entity_type, entry.entity_id
+                );
+            }
+            Verification::Failed { reason, severity } => {
+                warn!(
+                    "Trust vault entry {} ({} {}) failed verification: {}",
+                    entry.id, entry.entity_type, entry.entity_id, reason
+                );
+                // Only the transition into failure opens a risk event; repeat failures don't pile up
+                if entry.verification_status != "failed" {
+                    open_risk_event(
+                        pool,
+                        &entry.entity_type,
+                        entry.entity_id,
+                        entry.id,
+                        &entry.verification_status,
+                        &reason,
+                        severity,
+                    )
+                    .await?;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(count)
+}
+
+async fn registered_key(
+    pool: &PgPool,
+    entity_type: &str,
+    entity_id: Uuid,
+) -> anyhow::Result<Option<RegisteredKey>> {
+    let key = match entity_type {
+        "agent" => sqlx::query!(
+            "SELECT public_key, certificate_chain, revoked_at FROM agents WHERE id = $1",
+            entity_id
+        )
+        .fetch_optional(pool)
+        .await?
+        .map(|row| RegisteredKey {
+            public_key: row.public_key,
+            certificate_chain: Some(row.certificate_chain),
+            revoked: row.revoked_at.is_some(),
+        }),
+        "developer" => sqlx::query!("SELECT public_key FROM developers WHERE id = $1", entity_id)
+            .fetch_optional(pool)
+            .await?
+            .map(|row| RegisteredKey {
+                public_key: row.public_key,
+                certificate_chain: None,
+                revoked: false,
+            }),
+        "enterprise" => sqlx::query!("SELECT public_key FROM enterprises WHERE id = $1", entity_id)
+            .fetch_optional(pool)
+            .await?
+            .map(|row| RegisteredKey {
+                public_key: row.public_key,
+                certificate_chain: None,
+                revoked: false,
+            }),
+        _ => None,
+    };
+    Ok(key)
+}
+
+fn verify_entry(
+    ca: &CertificateAuthority,
+    registered: Option<RegisteredKey>,
+    public_key_hash: &str,
+    certificate_chain_hash: Option<&str>,
+    valid_until: Option<DateTime<Utc>>,
+    expiry_warning_secs: i64,
+) -> Verification {
+    let failed = |reason: &str, severity| Verification::Failed {
+        reason: reason.to_string(),
+        severity,
+    };
+
+    let Some(registered) = registered else {
+        return failed("entity no longer exists", RiskSeverity::High);
+    };
+    if registered.revoked {
+        return failed("entity has been revoked", RiskSeverity::High);
+    }
+    if hash_public_key(&registered.public_key) != public_key_hash {
+        return failed(
+            "public key hash does not match the registered key",
+            RiskSeverity::Critical,
+        );
+    }
+
+    if let Some(chain) = registered.certificate_chain.as_deref() {
+        if let Some(expected) = certificate_chain_hash {
+            if hash_public_key(chain) != expected {
+                return failed(
+                    "certificate chain hash does not match the registered chain",
+                    RiskSeverity::Critical,
+                );
+            }
+        }
+        match ca.validate_certificate_chain(chain) {
+            Ok(ChainValidation::Valid) => {}
+            Ok(ChainValidation::Expired) => {
+                return failed("certificate chain has expired", RiskSeverity::High)
+            }
+            Ok(ChainValidation::UntrustedIssuer) => {
+                return failed(
+                    "certificate chain is not signed by this CA",
+                    RiskSeverity::Critical,
+                )
+            }
+            Ok(ChainValidation::EarlierCa) => return Verification::ReissueRequired,
+            Err(e) => {
+                return failed(
+                    &format!("certificate chain could not be parsed: {}", e),
+                    RiskSeverity::High,
+                )
+            }
+        }
+    }
+
+    let now = Utc::now();
+    match valid_until {
+        Some(until) if until <= now => failed("vault entry has expired", RiskSeverity::Medium),
+        Some(until) if until <= now + chrono::Duration::seconds(expiry_warning_secs) => {
+            Verification::Expiring
+        }
+        _ => Verification::Verified,
+    }
+}
+
+/// Open a risk event for a failed entry, unless one is still open or under investigation for it
+async fn open_risk_event(
+    pool: &PgPool,
+    entity_type: &str,
+    entity_id: Uuid,
+    vault_entry_id: Uuid,
+    previous_status: &str,
+    reason: &str,
+    severity: RiskSeverity,
+) -> anyhow::Result<()> {
+    let evidence = json!({
+        "vault_entry_id": vault_entry_id,
+        "previous_status": previous_status,
+        "reason": reason,
+    });
+
+    sqlx::query!(
+        r#"
+        INSERT INTO trust_risk_events (entity_type, entity_id, risk_type, severity, description, evidence)
+        SELECT $1::varchar, $2::uuid, 'vault_verification_failed', $3::risk_severity, $4::text, $5::jsonb
+        WHERE NOT EXISTS (
+            SELECT 1 FROM trust_risk_events
+            WHERE entity_type = $1::varchar AND entity_id = $2::uuid
+              AND risk_type = 'vault_verification_failed'
+              AND status IN ('open', 'investigating')
+              AND evidence->>'vault_entry_id' = $5::jsonb->>'vault_entry_id'
+        )
+        "#,
+        entity_type,
+        entity_id,
+        severity as RiskSeverity,
+        format!("Trust vault verification failed: {}", reason),
+        evidence
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+//! API version negotiation. Endpoints with both a v1 and a v2 form are also served at an unversioned
+//! path, where the `Accept-Version` header picks the version, defaulting to v1 when it's absent. A
+//! versioned path (`/v1/...`, `/v2/...`) always serves its own version and ignores the header.
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+    response::Json,
+};
+
+/// Header a client sends to choose the version served at an unversioned path
+pub const ACCEPT_VERSION_HEADER: &str = "accept-version";
+
+/// The handler version a request at an unversioned path is served by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiVersion {
+    V1,
+    V2,
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ApiVersion
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, Json<serde_json::Value>);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(ACCEPT_VERSION_HEADER) else {
+            return Ok(ApiVersion::V1);
+        };
+        match value.to_str().map(str::trim) {
+            Ok("1") => Ok(ApiVersion::V1),
+            Ok("2") => Ok(ApiVersion::V2),
+            _ => Err((
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": "unsupported_api_version",
+                    "message": format!("Accept-Version must be 1 or 2, got {:?}", value),
+                })),
+            )),
+        }
+    }
+}
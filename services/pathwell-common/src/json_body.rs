@@ -0,0 +1,135 @@
+//! JSON request bodies. A body that isn't JSON, or doesn't match the expected shape, is rejected with a
+//! 422 in the usual `{error, message}` envelope, saying where parsing stopped, and logged, instead of
+//! getting axum's plain-text 400.
+//!
+//! Fields the target type doesn't define are ignored by [`JsonBody`]. A service with a stricter
+//! extractor reads the body with [`read_body`] and parses it with [`parse`] in strict mode, where they
+//! are a 422 `unknown_fields`.
+
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::{header, HeaderMap, StatusCode},
+    response::Json,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::error::Category;
+use serde_path_to_error::Segment;
+
+/// Where in the body parsing failed
+#[derive(Debug, Serialize)]
+pub struct JsonErrorLocation {
+    pub line: usize,
+    pub column: usize,
+    /// Path to the field being read, such as `request.headers`, when parsing got that far
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonBodyError {
+    pub error: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<JsonErrorLocation>,
+}
+
+pub type JsonBodyRejection = (StatusCode, Json<JsonBodyError>);
+
+/// A request body deserialized from JSON; use in place of `Json<T>` as an extractor
+pub struct JsonBody<T>(pub T);
+
+fn is_json_content_type(headers: &HeaderMap) -> bool {
+    let Some(content_type) = headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    let essence = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    essence == "application/json" || (essence.starts_with("application/") && essence.ends_with("+json"))
+}
+
+/// A logged rejection in the `{error, message}` envelope
+pub fn reject(
+    status: StatusCode,
+    error: &str,
+    message: String,
+    location: Option<JsonErrorLocation>,
+) -> JsonBodyRejection {
+    tracing::warn!(error = %error, "Rejected request body: {}", message);
+    (
+        status,
+        Json(JsonBodyError {
+            error: error.to_string(),
+            message,
+            location,
+        }),
+    )
+}
+
+/// The raw body of a request that declares a JSON content type
+pub async fn read_body<S: Send + Sync>(req: Request, state: &S) -> Result<Bytes, JsonBodyRejection> {
+    if !is_json_content_type(req.headers()) {
+        return Err(reject(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "unsupported_media_type",
+            "Expected a request with `Content-Type: application/json`".to_string(),
+            None,
+        ));
+    }
+
+    Bytes::from_request(req, state)
+        .await
+        .map_err(|e| reject(e.status(), "invalid_body", e.body_text(), None))
+}
+
+/// Deserialize `bytes`; with `strict`, fields `T` doesn't define are an error, all of them reported
+pub fn parse<T: DeserializeOwned>(bytes: &[u8], strict: bool) -> Result<T, JsonBodyRejection> {
+    let mut unknown = Vec::new();
+    let deserializer = &mut serde_json::Deserializer::from_slice(bytes);
+    let mut track = |path: serde_ignored::Path| unknown.push(path.to_string());
+    let deserializer = serde_ignored::Deserializer::new(deserializer, &mut track);
+    let value = serde_path_to_error::deserialize(deserializer)
+        .map_err(|e| {
+            let known = e.path().iter().any(|s| !matches!(s, Segment::Unknown));
+            let field = known.then(|| e.path().to_string());
+            let inner = e.inner();
+            let error = match inner.classify() {
+                Category::Data => "invalid_json_body",
+                Category::Syntax | Category::Eof | Category::Io => "malformed_json",
+            };
+            let message = match &field {
+                Some(field) => format!("{}: {}", field, inner),
+                None => inner.to_string(),
+            };
+            let location = JsonErrorLocation {
+                line: inner.line(),
+                column: inner.column(),
+                path: field,
+            };
+            reject(StatusCode::UNPROCESSABLE_ENTITY, error, message, Some(location))
+        })?;
+
+    if strict && !unknown.is_empty() {
+        return Err(reject(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "unknown_fields",
+            format!("Unknown fields: {}", unknown.join(", ")),
+            None,
+        ));
+    }
+    Ok(value)
+}
+
+#[async_trait]
+impl<T, S> FromRequest<S> for JsonBody<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = JsonBodyRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let bytes = read_body(req, state).await?;
+        parse(&bytes, false).map(JsonBody)
+    }
+}
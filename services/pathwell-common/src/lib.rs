@@ -0,0 +1,6 @@
+//! Request handling shared by the Pathwell services, so each behaves the same way at its API edge.
+
+pub mod api_version;
+pub mod json_body;
+pub mod pagination;
+pub mod scoring;
@@ -0,0 +1,39 @@
+use uuid::Uuid;
+
+/// Page size when a list request doesn't set `limit`
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+
+/// Largest page a list request may ask for
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+/// Clamp a requested page size to `1..=MAX_PAGE_SIZE`
+pub fn page_size(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+}
+
+/// Keyset position after the last row of a page: the sort it was issued for, that row's sort key
+/// and its id (the tiebreaker). Encoded as opaque hex so clients don't build their own
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor {
+    pub sort: String,
+    pub key: f64,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        hex::encode(format!("{}|{}|{}", self.sort, self.key, self.id))
+    }
+
+    /// Decode a cursor, rejecting one issued for a different sort
+    pub fn decode(value: &str, sort: &str) -> Option<Self> {
+        let decoded = String::from_utf8(hex::decode(value).ok()?).ok()?;
+        let mut parts = decoded.splitn(3, '|');
+        let cursor = Self {
+            sort: parts.next()?.to_string(),
+            key: parts.next()?.parse().ok()?,
+            id: parts.next()?.parse().ok()?,
+        };
+        (cursor.sort == sort && cursor.key.is_finite()).then_some(cursor)
+    }
+}
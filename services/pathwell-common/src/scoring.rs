@@ -0,0 +1,98 @@
+//! Trust score conversion between the `NUMERIC` scores are stored as and the floats the APIs carry. The
+//! registry writes scores and the receipt store records them, so both round the same way.
+
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::{Decimal, RoundingStrategy};
+
+/// Decimal places trust scores are rounded to unless `TRUST_SCORE_PRECISION` is set
+pub const DEFAULT_SCORE_PRECISION: u32 = 4;
+
+/// A trust score that could not be converted between its stored and API forms
+#[derive(Debug, thiserror::Error)]
+pub enum ScoreConversionError {
+    #[error("trust score {0} cannot be represented as a float")]
+    NotRepresentable(Decimal),
+    #[error("trust score {0} is not a finite number in decimal range")]
+    OutOfRange(f64),
+}
+
+fn logged(e: ScoreConversionError) -> ScoreConversionError {
+    tracing::error!("Trust score conversion failed: {}", e);
+    e
+}
+
+fn round(value: Decimal, precision: u32) -> Decimal {
+    value.round_dp_with_strategy(precision, RoundingStrategy::MidpointAwayFromZero)
+}
+
+/// Convert a stored score to its API value, rounded to `precision` decimal places
+pub fn score_to_f64(value: Decimal, precision: u32) -> Result<f64, ScoreConversionError> {
+    round(value, precision)
+        .to_f64()
+        .filter(|f| f.is_finite())
+        .ok_or(ScoreConversionError::NotRepresentable(value))
+        .map_err(logged)
+}
+
+/// Convert an API score to its stored value, rounded to `precision` decimal places
+pub fn score_to_decimal(value: f64, precision: u32) -> Result<Decimal, ScoreConversionError> {
+    Decimal::from_f64(value)
+        .map(|d| round(d, precision))
+        .ok_or(ScoreConversionError::OutOfRange(value))
+        .map_err(logged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn midpoints_round_away_from_zero() {
+        assert_eq!(score_to_f64(dec("0.12345"), 4).unwrap(), 0.1235);
+        assert_eq!(score_to_f64(dec("-0.12345"), 4).unwrap(), -0.1235);
+        assert_eq!(score_to_f64(dec("0.12344"), 4).unwrap(), 0.1234);
+        assert_eq!(score_to_decimal(0.00005, 4).unwrap(), dec("0.0001"));
+        assert_eq!(score_to_decimal(0.5, 0).unwrap(), dec("1"));
+    }
+
+    #[test]
+    fn score_bounds_survive_rounding() {
+        for precision in [0, 4, 28] {
+            assert_eq!(score_to_decimal(0.0, precision).unwrap(), Decimal::ZERO);
+            assert_eq!(score_to_decimal(1.0, precision).unwrap(), Decimal::ONE);
+            assert_eq!(score_to_f64(Decimal::ZERO, precision).unwrap(), 0.0);
+            assert_eq!(score_to_f64(Decimal::ONE, precision).unwrap(), 1.0);
+        }
+        // Just under 1 rounds up to it, never past it
+        assert_eq!(score_to_decimal(0.99995, 4).unwrap(), Decimal::ONE);
+        assert_eq!(score_to_f64(dec("0.99999"), 4).unwrap(), 1.0);
+    }
+
+    #[test]
+    fn precision_beyond_the_value_leaves_it_unchanged() {
+        assert_eq!(score_to_f64(dec("0.75"), 4).unwrap(), 0.75);
+        assert_eq!(score_to_decimal(0.75, 10).unwrap(), dec("0.75"));
+    }
+
+    #[test]
+    fn non_finite_and_out_of_range_floats_are_rejected() {
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, f64::MAX, 1e29] {
+            assert!(
+                matches!(score_to_decimal(value, 4), Err(ScoreConversionError::OutOfRange(_))),
+                "{} should be rejected",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn extreme_decimals_still_convert() {
+        assert_eq!(score_to_f64(Decimal::MAX, 4).unwrap(), Decimal::MAX.to_f64().unwrap());
+        assert_eq!(score_to_f64(dec("0.00001"), 4).unwrap(), 0.0);
+    }
+}
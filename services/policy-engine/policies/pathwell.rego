@@ -16,6 +16,9 @@ allow if {
     
     # Request path must match allowed patterns
     path_matches_allowed_pattern
+
+    # Delegation chain is within the configured depth (if any)
+    delegation_allows
 }
 
 # Allowed HTTP methods
@@ -36,3 +39,13 @@ path_matches_allowed_pattern if {
     some pattern in data.pathwell.allowed_paths
     glob.match(pattern, ["/"], input.request.path)
 }
+
+# No delegation depth configured - allow any verified chain
+delegation_allows if {
+    not data.pathwell.max_delegation_depth
+}
+
+# Delegation depth configured - the on-behalf-of chain must not exceed it
+delegation_allows if {
+    count(object.get(input.agent, "on_behalf_of", [])) <= data.pathwell.max_delegation_depth
+}
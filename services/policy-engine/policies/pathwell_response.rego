@@ -0,0 +1,25 @@
+package pathwell.authz
+
+import rego.v1
+
+# Response-phase policy, evaluated by the gateway after the backend responds
+# (when RESPONSE_POLICY_ENABLED is set). Each rule that matches adds a reason,
+# and any reason blocks the response with a 403.
+
+# Deny responses whose extracted attribute has a denied value, e.g.
+#   { "pathwell": { "response_denials": [ { "attribute": "/classification", "value": "restricted" } ] } }
+response_deny contains sprintf("response attribute %s is %v", [rule.attribute, rule.value]) if {
+    some rule in object.get(data.pathwell, "response_denials", [])
+    input.response.attributes[rule.attribute] == rule.value
+}
+
+# Deny responses whose content type isn't in data.pathwell.response_content_types (when set)
+response_deny contains sprintf("response content type %s is not permitted", [input.response.content_type]) if {
+    allowed := data.pathwell.response_content_types
+    not content_type_permitted(allowed)
+}
+
+content_type_permitted(allowed) if {
+    some prefix in allowed
+    startswith(object.get(input.response, "content_type", ""), prefix)
+}
@@ -39,6 +39,12 @@ allow if {
 
     # Tenant governance allows (if applicable)
     tenant_policy_allows
+
+    # Role-based path restrictions allow (if configured)
+    role_policy_allows
+
+    # Delegation chain is within the configured depth (if any)
+    delegation_allows
 }
 
 # ========================================
@@ -62,7 +68,12 @@ trust_check_passed if {
 # Trust check passes if score is above threshold
 trust_check_passed if {
     input.agent.trust_score
-    input.agent.trust_score.composite_score >= trust_threshold
+    input.agent.trust_score.composite_score >= effective_trust_threshold
+}
+
+# Trust check is skipped while a break-glass override is active
+trust_check_passed if {
+    break_glass_active
 }
 
 # Trust evaluation passed - detailed check
@@ -72,26 +83,37 @@ trust_evaluation_passed if {
 
 trust_evaluation_passed if {
     input.agent.trust_score
-    input.agent.trust_score.composite_score >= trust_threshold
+    input.agent.trust_score.composite_score >= effective_trust_threshold
 }
 
 # Determine trust action based on score
 trust_action := "passed" if {
+    not break_glass_active
     input.agent.trust_score
-    input.agent.trust_score.composite_score >= trust_threshold
+    input.agent.trust_score.composite_score >= effective_trust_threshold
 }
 
 trust_action := "warn" if {
+    not break_glass_active
     input.agent.trust_score
-    input.agent.trust_score.composite_score < trust_threshold
-    input.agent.trust_score.composite_score >= warn_threshold
+    input.agent.trust_score.composite_score < effective_trust_threshold
+    input.agent.trust_score.composite_score >= effective_warn_threshold
 }
 
 trust_action := "block" if {
+    not break_glass_active
     input.agent.trust_score
-    input.agent.trust_score.composite_score < warn_threshold
+    input.agent.trust_score.composite_score < effective_warn_threshold
 }
 
+trust_action := "bypassed" if {
+    break_glass_active
+    input.agent.trust_score
+}
+
+# A low-sensitivity tier may require less than the warn threshold
+effective_warn_threshold := min({warn_threshold, effective_trust_threshold})
+
 # Get trust thresholds from data or use defaults
 trust_threshold := data.pathwell.trust_threshold if {
     data.pathwell.trust_threshold
@@ -109,6 +131,16 @@ warn_threshold := 0.1 if {
     not data.pathwell.warn_threshold
 }
 
+# ========================================
+# BREAK-GLASS OVERRIDE
+# ========================================
+
+# The registry only reports unexpired overrides, but one may lapse between validation and evaluation
+break_glass_active if {
+    input.context.break_glass
+    time.parse_rfc3339_ns(input.context.break_glass.expires_at) > time.now_ns()
+}
+
 # ========================================
 # HTTP METHOD VALIDATION
 # ========================================
@@ -142,6 +174,42 @@ effective_allowed_patterns := default_allowed_patterns if {
 # Default allowed patterns (permissive)
 default_allowed_patterns := ["**"]
 
+# ========================================
+# ROLE-BASED ACCESS
+# ========================================
+
+# Roles assigned to the agent in the identity registry
+agent_roles contains role if {
+    some role in input.agent.roles
+}
+
+# No role restrictions configured - allow by default
+role_policy_allows if {
+    not data.pathwell.role_paths
+}
+
+# Role restrictions configured - one of the agent's roles must permit the path
+role_policy_allows if {
+    data.pathwell.role_paths
+    some role in agent_roles
+    some pattern in data.pathwell.role_paths[role]
+    glob.match(pattern, ["/"], input.request.path)
+}
+
+# ========================================
+# DELEGATION
+# ========================================
+
+# No delegation depth configured - allow any verified chain
+delegation_allows if {
+    not data.pathwell.max_delegation_depth
+}
+
+# Delegation depth configured - the on-behalf-of chain must not exceed it
+delegation_allows if {
+    count(object.get(input.agent, "on_behalf_of", [])) <= data.pathwell.max_delegation_depth
+}
+
 # ========================================
 # TENANT POLICY EVALUATION (TEN.GOV)
 # ========================================
@@ -191,20 +259,33 @@ tenant_custom_policy_allows if {
 }
 
 # Tenant trust threshold override
-effective_trust_threshold := input.context.tenant_governance.trust_threshold_override if {
+tenant_trust_threshold := input.context.tenant_governance.trust_threshold_override if {
     input.context.tenant_governance
     input.context.tenant_governance.trust_threshold_override
 }
 
-effective_trust_threshold := trust_threshold if {
+tenant_trust_threshold := trust_threshold if {
     not input.context.tenant_governance
 }
 
-effective_trust_threshold := trust_threshold if {
+tenant_trust_threshold := trust_threshold if {
     input.context.tenant_governance
     not input.context.tenant_governance.trust_threshold_override
 }
 
+# Sensitivity tier (resolved by the engine from tenant governance) takes precedence
+has_sensitivity_tier if {
+    input.context.sensitivity_tier.trust_threshold
+}
+
+effective_trust_threshold := input.context.sensitivity_tier.trust_threshold if {
+    has_sensitivity_tier
+}
+
+effective_trust_threshold := tenant_trust_threshold if {
+    not has_sensitivity_tier
+}
+
 # ========================================
 # OUTPUT RULES (for policy response enrichment)
 # ========================================
@@ -235,7 +316,7 @@ warnings[warning] if {
     trust_action == "warn"
     warning := {
         "code": "TRUST_BELOW_THRESHOLD",
-        "message": sprintf("Trust score %.2f is below threshold %.2f but above warn threshold", [computed_trust_score, trust_threshold]),
+        "message": sprintf("Trust score %.2f is below threshold %.2f but above warn threshold", [computed_trust_score, effective_trust_threshold]),
         "severity": "warning"
     }
 }
@@ -259,3 +340,12 @@ warnings[warning] if {
         "severity": "info"
     }
 }
+
+warnings[warning] if {
+    break_glass_active
+    warning := {
+        "code": "BREAK_GLASS_ACTIVE",
+        "message": sprintf("Trust threshold bypassed by break-glass override %s until %s", [input.context.break_glass.id, input.context.break_glass.expires_at]),
+        "severity": "warning"
+    }
+}
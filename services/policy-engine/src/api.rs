@@ -1,17 +1,38 @@
 use axum::{
-    extract::State,
-    http::StatusCode,
-    response::Json,
+    extract::{Path, Request, State},
+    handler::Handler,
+    http::{HeaderMap, StatusCode},
+    response::{Json, Response},
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use tracing::warn;
+use pathwell_common::api_version::ApiVersion;
+use pathwell_common::json_body::JsonBody;
 
+use crate::audit::{AuditLog, PolicyDecision};
 use crate::engine::{
-    PolicyEngine, PolicyRequest, PolicyRequestV2,
+    PolicyEngine, PolicyRequest, PolicyRequestV2, ResponsePolicyRequest,
     AgentInfoV2, PolicyContext, TrustContext, TrustDimensions,
-    AttributionContext, TenantGovernance,
+    AttributionContext, TenantGovernance, BreakGlassContext,
     TrustEvaluationResult, PolicyWarning,
 };
+use crate::overrides::{OverrideDecision, OverrideStore, PolicyOverride};
+use crate::request_id::REQUEST_ID_HEADER;
+use crate::sensitivity::SensitivityTier;
+
+/// Header carrying the API key for the override endpoints
+const API_KEY_HEADER: &str = "x-api-key";
+
+#[derive(Clone)]
+pub struct AppState {
+    pub engine: Arc<dyn PolicyEngine>,
+    pub overrides: Arc<OverrideStore>,
+    /// API keys allowed to manage overrides; none closes the endpoints
+    pub admin_api_keys: Arc<Vec<String>>,
+    pub audit: AuditLog,
+}
 
 // ========================================
 // V1 API Types
@@ -28,6 +49,17 @@ pub struct EvaluateResponse {
     pub allowed: bool,
     pub reason: String,
     pub evaluation_time_ms: u64,
+    /// The override that made the decision in place of policy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy_override: Option<PolicyOverride>,
+}
+
+/// Response-phase evaluation: the request that was allowed and the upstream response to check
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EvaluateResponsePhaseRequest {
+    pub agent: crate::engine::AgentInfo,
+    pub request: crate::engine::RequestInfo,
+    pub response: crate::engine::ResponseInfo,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -60,6 +92,10 @@ pub struct AgentInfoV2Request {
     pub tenant_hierarchy_path: Option<Vec<String>>,
     pub trust_score: Option<TrustContextRequest>,
     pub attribution: Option<AttributionContextRequest>,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub on_behalf_of: Vec<crate::engine::DelegationLink>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -106,6 +142,8 @@ pub struct PolicyContextRequest {
     pub trace_id: Option<String>,
     pub correlation_id: Option<String>,
     pub tenant_governance: Option<TenantGovernanceRequest>,
+    /// The `break_glass` from the identity registry's v2 validation, passed through as is
+    pub break_glass: Option<BreakGlassContext>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -114,6 +152,8 @@ pub struct TenantGovernanceRequest {
     pub policy_scope: String,
     pub custom_policies: Option<Vec<String>>,
     pub trust_threshold_override: Option<f64>,
+    #[serde(default)]
+    pub sensitivity_tiers: Vec<SensitivityTier>,
 }
 
 fn default_policy_scope() -> String {
@@ -130,6 +170,132 @@ pub struct EvaluateResponseV2 {
     pub tenant_policy_applied: Option<String>,
     #[serde(default)]
     pub warnings: Vec<PolicyWarning>,
+    /// The override that made the decision in place of policy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy_override: Option<PolicyOverride>,
+}
+
+// ========================================
+// Override API Types
+// ========================================
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetOverrideRequest {
+    pub decision: OverrideDecision,
+    pub reason: String,
+    pub created_by: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OverrideResponse {
+    #[serde(flatten)]
+    pub policy_override: PolicyOverride,
+    pub active: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OverrideListResponse {
+    pub overrides: Vec<OverrideResponse>,
+}
+
+fn error_response(status: StatusCode, error: &str, message: impl Into<String>) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.to_string(),
+            message: message.into(),
+        }),
+    )
+}
+
+/// The agent's active override, if any, logged since it decides the request instead of policy
+fn applied_override(state: &AppState, agent_id: &str) -> Option<PolicyOverride> {
+    let policy_override = state.overrides.active(agent_id)?;
+    warn!("{}", policy_override.decision_reason());
+    Some(policy_override)
+}
+
+/// The agent's active override if it's a force-deny. The response phase honours only these: a
+/// force-allow waives the request policy, not the check on what the upstream sent back
+fn applied_deny_override(state: &AppState, agent_id: &str) -> Option<PolicyOverride> {
+    let policy_override = state.overrides.active(agent_id).filter(|o| !o.allowed())?;
+    warn!("{}", policy_override.decision_reason());
+    Some(policy_override)
+}
+
+/// A v1 decision as the audit feed records it
+fn v1_decision(
+    endpoint: &'static str,
+    headers: &HeaderMap,
+    agent: &crate::engine::AgentInfo,
+    request: &crate::engine::RequestInfo,
+    response: &EvaluateResponse,
+    matched_rules: Vec<String>,
+) -> PolicyDecision {
+    PolicyDecision {
+        decided_at: Utc::now(),
+        endpoint,
+        request_id: request_id(headers),
+        agent_id: agent.agent_id.clone(),
+        developer_id: agent.developer_id.clone(),
+        enterprise_id: agent.enterprise_id.clone(),
+        tenant_id: None,
+        trace_id: None,
+        correlation_id: None,
+        method: request.method.clone(),
+        path: request.path.clone(),
+        allowed: response.allowed,
+        reason: response.reason.clone(),
+        matched_rules,
+        trust_evaluation: None,
+        policy_override: response.policy_override.clone(),
+        evaluation_time_ms: response.evaluation_time_ms,
+    }
+}
+
+/// A v2 decision as the audit feed records it
+fn v2_decision(
+    headers: &HeaderMap,
+    request: &PolicyRequestV2,
+    response: &EvaluateResponseV2,
+    matched_rules: Vec<String>,
+) -> PolicyDecision {
+    PolicyDecision {
+        decided_at: Utc::now(),
+        endpoint: "/v2/evaluate",
+        request_id: request_id(headers),
+        agent_id: request.agent.agent_id.clone(),
+        developer_id: request.agent.developer_id.clone(),
+        enterprise_id: request.agent.enterprise_id.clone(),
+        tenant_id: request.agent.tenant_id.clone(),
+        trace_id: request.context.trace_id.clone(),
+        correlation_id: request.context.correlation_id.clone(),
+        method: request.request.method.clone(),
+        path: request.request.path.clone(),
+        allowed: response.allowed,
+        reason: response.reason.clone(),
+        matched_rules,
+        trust_evaluation: response.trust_evaluation.clone(),
+        policy_override: response.policy_override.clone(),
+        evaluation_time_ms: response.evaluation_time_ms,
+    }
+}
+
+fn request_id(headers: &HeaderMap) -> Option<String> {
+    headers.get(REQUEST_ID_HEADER).and_then(|v| v.to_str().ok()).map(str::to_string)
+}
+
+/// Rule reported for a decision an override made
+const OVERRIDE_RULE: &str = "policy_override";
+
+fn overridden_response(policy_override: PolicyOverride) -> EvaluateResponse {
+    EvaluateResponse {
+        allowed: policy_override.allowed(),
+        reason: policy_override.decision_reason(),
+        evaluation_time_ms: 0,
+        policy_override: Some(policy_override),
+    }
 }
 
 // ========================================
@@ -137,15 +303,29 @@ pub struct EvaluateResponseV2 {
 // ========================================
 
 pub async fn evaluate_policy(
-    State(engine): State<Arc<dyn PolicyEngine>>,
-    Json(payload): Json<EvaluateRequest>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    JsonBody(payload): JsonBody<EvaluateRequest>,
 ) -> Result<Json<EvaluateResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if let Some(policy_override) = applied_override(&state, &payload.agent.agent_id) {
+        let response = overridden_response(policy_override);
+        state.audit.record(v1_decision(
+            "/v1/evaluate",
+            &headers,
+            &payload.agent,
+            &payload.request,
+            &response,
+            vec![OVERRIDE_RULE.to_string()],
+        ));
+        return Ok(Json(response));
+    }
+
     let request = PolicyRequest {
         agent: payload.agent,
         request: payload.request,
     };
 
-    let response = engine.evaluate(&request).await.map_err(|e| {
+    let response = state.engine.evaluate(&request).await.map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse {
@@ -155,11 +335,72 @@ pub async fn evaluate_policy(
         )
     })?;
 
-    Ok(Json(EvaluateResponse {
+    let evaluated = EvaluateResponse {
         allowed: response.allowed,
         reason: response.reason,
         evaluation_time_ms: response.evaluation_time_ms,
-    }))
+        policy_override: None,
+    };
+    state.audit.record(v1_decision(
+        "/v1/evaluate",
+        &headers,
+        &request.agent,
+        &request.request,
+        &evaluated,
+        response.matched_rules,
+    ));
+    Ok(Json(evaluated))
+}
+
+pub async fn evaluate_response_policy(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    JsonBody(payload): JsonBody<EvaluateResponsePhaseRequest>,
+) -> Result<Json<EvaluateResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if let Some(policy_override) = applied_deny_override(&state, &payload.agent.agent_id) {
+        let response = overridden_response(policy_override);
+        state.audit.record(v1_decision(
+            "/v1/evaluate/response",
+            &headers,
+            &payload.agent,
+            &payload.request,
+            &response,
+            vec![OVERRIDE_RULE.to_string()],
+        ));
+        return Ok(Json(response));
+    }
+
+    let request = ResponsePolicyRequest {
+        agent: payload.agent,
+        request: payload.request,
+        response: payload.response,
+    };
+
+    let response = state.engine.evaluate_response(&request).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: "policy_evaluation_error".to_string(),
+                message: e.to_string(),
+            }),
+        )
+    })?;
+
+    let evaluated = EvaluateResponse {
+        allowed: response.allowed,
+        reason: response.reason,
+        evaluation_time_ms: response.evaluation_time_ms,
+        policy_override: None,
+    };
+    state.audit.record(v1_decision(
+        "/v1/evaluate/response",
+        &headers,
+        &request.agent,
+        &request.request,
+        &evaluated,
+        response.matched_rules,
+    ));
+    Ok(Json(evaluated))
 }
 
 // ========================================
@@ -167,8 +408,9 @@ pub async fn evaluate_policy(
 // ========================================
 
 pub async fn evaluate_policy_v2(
-    State(engine): State<Arc<dyn PolicyEngine>>,
-    Json(payload): Json<EvaluateRequestV2>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    JsonBody(payload): JsonBody<EvaluateRequestV2>,
 ) -> Result<Json<EvaluateResponseV2>, (StatusCode, Json<ErrorResponse>)> {
     // Convert request to internal types
     let trust_score = payload.agent.trust_score.map(|ts| TrustContext {
@@ -194,6 +436,7 @@ pub async fn evaluate_policy_v2(
         policy_scope: tg.policy_scope,
         custom_policies: tg.custom_policies,
         trust_threshold_override: tg.trust_threshold_override,
+        sensitivity_tiers: tg.sensitivity_tiers,
     });
 
     let request = PolicyRequestV2 {
@@ -207,16 +450,37 @@ pub async fn evaluate_policy_v2(
             tenant_hierarchy_path: payload.agent.tenant_hierarchy_path,
             trust_score,
             attribution,
+            roles: payload.agent.roles,
+            on_behalf_of: payload.agent.on_behalf_of,
         },
         request: payload.request,
         context: PolicyContext {
             trace_id: payload.context.trace_id,
             correlation_id: payload.context.correlation_id,
             tenant_governance,
+            break_glass: payload.context.break_glass,
         },
     };
 
-    let response = engine.evaluate_v2(&request).await.map_err(|e| {
+    if let Some(policy_override) = applied_override(&state, &request.agent.agent_id) {
+        let response = EvaluateResponseV2 {
+            allowed: policy_override.allowed(),
+            reason: policy_override.decision_reason(),
+            evaluation_time_ms: 0,
+            trust_evaluation: None,
+            tenant_policy_applied: None,
+            warnings: vec![PolicyWarning {
+                code: "POLICY_OVERRIDE".to_string(),
+                message: policy_override.decision_reason(),
+                severity: "high".to_string(),
+            }],
+            policy_override: Some(policy_override),
+        };
+        state.audit.record(v2_decision(&headers, &request, &response, vec![OVERRIDE_RULE.to_string()]));
+        return Ok(Json(response));
+    }
+
+    let response = state.engine.evaluate_v2(&request).await.map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse {
@@ -226,13 +490,125 @@ pub async fn evaluate_policy_v2(
         )
     })?;
 
-    Ok(Json(EvaluateResponseV2 {
+    let evaluated = EvaluateResponseV2 {
         allowed: response.allowed,
         reason: response.reason,
         evaluation_time_ms: response.evaluation_time_ms,
         trust_evaluation: response.trust_evaluation,
         tenant_policy_applied: response.tenant_policy_applied,
         warnings: response.warnings,
-    }))
+        policy_override: None,
+    };
+    state.audit.record(v2_decision(&headers, &request, &evaluated, response.matched_rules));
+    Ok(Json(evaluated))
+}
+
+/// Evaluate with the v1 or v2 handler, whichever `Accept-Version` asks for
+pub async fn evaluate_policy_negotiated(
+    version: ApiVersion,
+    State(state): State<AppState>,
+    request: Request,
+) -> Response {
+    match version {
+        ApiVersion::V1 => evaluate_policy.call(request, state).await,
+        ApiVersion::V2 => evaluate_policy_v2.call(request, state).await,
+    }
+}
+
+// ========================================
+// Override Handlers
+// ========================================
+
+fn authorize_admin(state: &AppState, headers: &HeaderMap) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let authorized = headers
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|key| state.admin_api_keys.iter().any(|k| k == key));
+    if !authorized {
+        return Err(error_response(
+            StatusCode::UNAUTHORIZED,
+            "unauthorized",
+            "An admin X-Api-Key is required",
+        ));
+    }
+    Ok(())
+}
+
+fn storage_error(e: anyhow::Error) -> (StatusCode, Json<ErrorResponse>) {
+    error_response(StatusCode::INTERNAL_SERVER_ERROR, "override_storage_error", e.to_string())
+}
+
+fn override_response(policy_override: PolicyOverride) -> OverrideResponse {
+    OverrideResponse {
+        active: policy_override.is_active(),
+        policy_override,
+    }
+}
+
+/// Every override, expired ones included
+pub async fn list_overrides(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<OverrideListResponse>, (StatusCode, Json<ErrorResponse>)> {
+    authorize_admin(&state, &headers)?;
+    let overrides = state.overrides.list().into_iter().map(override_response).collect();
+    Ok(Json(OverrideListResponse { overrides }))
+}
+
+/// Force-allow or force-deny an agent until `expires_at`, replacing any override it had
+pub async fn set_override(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(agent_id): Path<String>,
+    JsonBody(payload): JsonBody<SetOverrideRequest>,
+) -> Result<Json<OverrideResponse>, (StatusCode, Json<ErrorResponse>)> {
+    authorize_admin(&state, &headers)?;
+    let invalid = |message: &str| error_response(StatusCode::BAD_REQUEST, "invalid_override", message);
+    if payload.reason.trim().is_empty() {
+        return Err(invalid("A reason is required"));
+    }
+    if payload.created_by.trim().is_empty() {
+        return Err(invalid("created_by is required"));
+    }
+    if payload.expires_at <= Utc::now() {
+        return Err(invalid("expires_at must be in the future"));
+    }
+
+    let policy_override = PolicyOverride {
+        agent_id,
+        decision: payload.decision,
+        reason: payload.reason,
+        created_by: payload.created_by,
+        created_at: Utc::now(),
+        expires_at: payload.expires_at,
+    };
+    state.overrides.set(policy_override.clone()).map_err(storage_error)?;
+    warn!(
+        "Policy override set by {}: {}",
+        policy_override.created_by,
+        policy_override.decision_reason()
+    );
+
+    Ok(Json(override_response(policy_override)))
+}
+
+/// Remove an agent's override, returning it to ordinary policy
+pub async fn delete_override(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(agent_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    authorize_admin(&state, &headers)?;
+    match state.overrides.remove(&agent_id).map_err(storage_error)? {
+        Some(_) => {
+            warn!("Policy override for agent {} removed", agent_id);
+            Ok(StatusCode::NO_CONTENT)
+        }
+        None => Err(error_response(
+            StatusCode::NOT_FOUND,
+            "override_not_found",
+            format!("No policy override for agent {}", agent_id),
+        )),
+    }
 }
 
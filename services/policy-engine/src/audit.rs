@@ -0,0 +1,209 @@
+//! Policy decision audit feed. Every decision the evaluate endpoints make is written as a structured
+//! `policy_decision` event to the sink named by `POLICY_AUDIT_SINK`, apart from the request logs, so
+//! security tooling gets a decision feed that doesn't depend on the receipt store. Events are hash-chained
+//! in the order they are written: each carries a sequence number, the previous event's hash and its own,
+//! so an edited, removed or reordered event shows when the feed is replayed.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::engine::TrustEvaluationResult;
+use crate::overrides::PolicyOverride;
+
+/// Syslog priority for audit events: facility 13 (log audit), severity 6 (informational)
+const SYSLOG_PRIORITY: u8 = 13 * 8 + 6;
+
+/// Where decision events are written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditSinkConfig {
+    /// No audit feed
+    None,
+    /// One JSON event per line on stdout
+    Stdout,
+    /// RFC 5424 messages over UDP to `host:port`
+    Syslog(String),
+    /// Each event POSTed as JSON to the URL
+    Http(String),
+}
+
+impl AuditSinkConfig {
+    pub fn from_env() -> Result<Self> {
+        let kind = std::env::var("POLICY_AUDIT_SINK").unwrap_or_default();
+        match kind.trim().to_ascii_lowercase().as_str() {
+            "" | "none" => Ok(AuditSinkConfig::None),
+            "stdout" => Ok(AuditSinkConfig::Stdout),
+            "syslog" => Ok(AuditSinkConfig::Syslog(
+                std::env::var("POLICY_AUDIT_SYSLOG_ADDR")
+                    .context("POLICY_AUDIT_SINK=syslog needs POLICY_AUDIT_SYSLOG_ADDR")?,
+            )),
+            "http" => Ok(AuditSinkConfig::Http(
+                std::env::var("POLICY_AUDIT_HTTP_URL")
+                    .context("POLICY_AUDIT_SINK=http needs POLICY_AUDIT_HTTP_URL")?,
+            )),
+            other => anyhow::bail!("POLICY_AUDIT_SINK must be none, stdout, syslog or http, got {:?}", other),
+        }
+    }
+}
+
+/// One policy decision, as the evaluate endpoints report it
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyDecision {
+    pub decided_at: DateTime<Utc>,
+    /// The evaluation that made the decision, e.g. `/v2/evaluate`
+    pub endpoint: &'static str,
+    pub request_id: Option<String>,
+    pub agent_id: String,
+    pub developer_id: String,
+    pub enterprise_id: Option<String>,
+    pub tenant_id: Option<String>,
+    pub trace_id: Option<String>,
+    pub correlation_id: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub allowed: bool,
+    pub reason: String,
+    pub matched_rules: Vec<String>,
+    pub trust_evaluation: Option<TrustEvaluationResult>,
+    pub policy_override: Option<PolicyOverride>,
+    pub evaluation_time_ms: u64,
+}
+
+/// A decision as written to the sink, chained to the one before it
+#[derive(Serialize)]
+struct AuditEvent<'a> {
+    event: &'static str,
+    sequence: u64,
+    /// Decisions dropped because the queue was full since the previous event was written
+    #[serde(skip_serializing_if = "is_zero")]
+    dropped_before: u64,
+    #[serde(flatten)]
+    decision: &'a PolicyDecision,
+    previous_hash: Option<&'a str>,
+}
+
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
+/// Queue of decisions for the background writer. Recording never waits on the sink: when the queue is
+/// full the decision is dropped and counted, and the count is written on the next event
+#[derive(Clone)]
+pub struct AuditLog {
+    sender: Option<mpsc::Sender<PolicyDecision>>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl AuditLog {
+    /// Connect to the sink and start writing decisions to it
+    pub async fn start(config: AuditSinkConfig, queue_size: usize) -> Result<Self> {
+        let dropped = Arc::new(AtomicU64::new(0));
+        let sink = match config {
+            AuditSinkConfig::None => return Ok(Self { sender: None, dropped }),
+            AuditSinkConfig::Stdout => Sink::Stdout,
+            AuditSinkConfig::Syslog(addr) => {
+                let socket = UdpSocket::bind("0.0.0.0:0").await?;
+                socket
+                    .connect(&addr)
+                    .await
+                    .with_context(|| format!("Cannot reach syslog target {}", addr))?;
+                let hostname = std::env::var("HOSTNAME").unwrap_or_else(|_| "-".to_string());
+                Sink::Syslog { socket, hostname }
+            }
+            AuditSinkConfig::Http(url) => Sink::Http {
+                client: reqwest::Client::builder().timeout(Duration::from_secs(5)).build()?,
+                url,
+            },
+        };
+
+        let (sender, receiver) = mpsc::channel(queue_size.max(1));
+        tokio::spawn(write_events(sink, receiver, dropped.clone()));
+        Ok(Self { sender: Some(sender), dropped })
+    }
+
+    pub fn record(&self, decision: PolicyDecision) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        if let Err(e) = sender.try_send(decision) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            let decision = match e {
+                mpsc::error::TrySendError::Full(d) | mpsc::error::TrySendError::Closed(d) => d,
+            };
+            warn!("Audit queue full; dropped policy decision for agent {}", decision.agent_id);
+        }
+    }
+}
+
+enum Sink {
+    Stdout,
+    Syslog { socket: UdpSocket, hostname: String },
+    Http { client: reqwest::Client, url: String },
+}
+
+impl Sink {
+    async fn write(&self, event: &serde_json::Value) -> Result<()> {
+        match self {
+            Sink::Stdout => {
+                let mut stdout = std::io::stdout().lock();
+                writeln!(stdout, "{}", event)?;
+            }
+            Sink::Syslog { socket, hostname } => {
+                let message = format!(
+                    "<{}>1 {} {} policy-engine - policy_decision - {}",
+                    SYSLOG_PRIORITY,
+                    Utc::now().to_rfc3339(),
+                    hostname,
+                    event
+                );
+                socket.send(message.as_bytes()).await?;
+            }
+            Sink::Http { client, url } => {
+                client.post(url).json(event).send().await?.error_for_status()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Chain and write decisions in the order they were recorded. A decision the sink rejects is logged and
+/// skipped but stays in the chain, so its sequence number shows as missing downstream
+async fn write_events(sink: Sink, mut receiver: mpsc::Receiver<PolicyDecision>, dropped: Arc<AtomicU64>) {
+    let mut sequence = 0u64;
+    let mut previous_hash: Option<String> = None;
+
+    while let Some(decision) = receiver.recv().await {
+        sequence += 1;
+        let event = AuditEvent {
+            event: "policy_decision",
+            sequence,
+            dropped_before: dropped.swap(0, Ordering::Relaxed),
+            decision: &decision,
+            previous_hash: previous_hash.as_deref(),
+        };
+
+        // Hashed over the event's compact JSON without `hash`, keys sorted
+        let mut value = match serde_json::to_value(&event) {
+            Ok(value) => value,
+            Err(e) => {
+                warn!("Failed to serialize policy decision {}: {}", sequence, e);
+                continue;
+            }
+        };
+        let hash = hex::encode(Sha256::digest(value.to_string().as_bytes()));
+        value["hash"] = serde_json::Value::String(hash.clone());
+        previous_hash = Some(hash);
+
+        if let Err(e) = sink.write(&value).await {
+            warn!("Failed to write policy decision {} to the audit sink: {}", sequence, e);
+        }
+    }
+}
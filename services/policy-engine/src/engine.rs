@@ -2,6 +2,8 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use async_trait::async_trait;
 
+use crate::sensitivity::{resolve_tier, SensitivityTier};
+
 // ========================================
 // V1 Types (Backward Compatible)
 // ========================================
@@ -20,6 +22,16 @@ pub struct AgentInfo {
     pub agent_id: String,
     pub developer_id: String,
     pub enterprise_id: Option<String>,
+    /// Verified principals the agent acts for, nearest first
+    #[serde(default)]
+    pub on_behalf_of: Vec<DelegationLink>,
+}
+
+/// One principal in an agent's on-behalf-of chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegationLink {
+    pub principal_type: String,
+    pub principal_id: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,6 +48,26 @@ pub struct PolicyResponse {
     pub allowed: bool,
     pub reason: String,
     pub evaluation_time_ms: u64,
+    /// Policy rules that held (or, for responses, denied), for the audit feed
+    #[serde(default)]
+    pub matched_rules: Vec<String>,
+}
+
+/// Response-phase evaluation request: the allowed request plus what the backend returned
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponsePolicyRequest {
+    pub agent: AgentInfo,
+    pub request: RequestInfo,
+    pub response: ResponseInfo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseInfo {
+    pub status: u16,
+    pub content_type: Option<String>,
+    /// Values the gateway extracted from the response body, keyed by JSON pointer
+    #[serde(default)]
+    pub attributes: serde_json::Map<String, serde_json::Value>,
 }
 
 // ========================================
@@ -64,6 +96,10 @@ pub struct AgentInfoV2 {
     pub tenant_hierarchy_path: Option<Vec<String>>,
     pub trust_score: Option<TrustContext>,
     pub attribution: Option<AttributionContext>,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub on_behalf_of: Vec<DelegationLink>,
 }
 
 /// Trust score context for policy evaluation
@@ -108,6 +144,19 @@ pub struct PolicyContext {
     pub trace_id: Option<String>,
     pub correlation_id: Option<String>,
     pub tenant_governance: Option<TenantGovernance>,
+    /// Break-glass override the identity registry reported for the agent
+    pub break_glass: Option<BreakGlassContext>,
+}
+
+/// A time-boxed override that skips the trust threshold until `expires_at` (RFC 3339)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BreakGlassContext {
+    pub id: String,
+    #[serde(default)]
+    pub target_type: Option<String>,
+    #[serde(default)]
+    pub reason: Option<String>,
+    pub expires_at: String,
 }
 
 /// Tenant governance configuration
@@ -116,6 +165,9 @@ pub struct TenantGovernance {
     pub policy_scope: String, // 'inherit', 'override', 'merge'
     pub custom_policies: Option<Vec<String>>,
     pub trust_threshold_override: Option<f64>,
+    /// Per-request trust requirements; the strictest matching tier replaces the flat threshold
+    #[serde(default)]
+    pub sensitivity_tiers: Vec<SensitivityTier>,
 }
 
 /// Enhanced policy evaluation response with trust evaluation details
@@ -129,6 +181,9 @@ pub struct PolicyResponseV2 {
     pub tenant_policy_applied: Option<String>,
     #[serde(default)]
     pub warnings: Vec<PolicyWarning>,
+    /// Policy rules that held, for the audit feed
+    #[serde(default)]
+    pub matched_rules: Vec<String>,
 }
 
 /// Trust evaluation result details
@@ -139,6 +194,38 @@ pub struct TrustEvaluationResult {
     pub threshold: f64,
     pub passed: bool,
     pub action_taken: Option<String>,
+    /// Sensitivity tier whose threshold was applied, if any matched the request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sensitivity_tier: Option<String>,
+    /// Set when the agent had no trust score and the unscored posture was applied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unscored_policy: Option<UnscoredTrustPolicy>,
+    /// Break-glass override that skipped the threshold, when `action_taken` is `bypassed`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub break_glass_id: Option<String>,
+}
+
+/// How to evaluate agents that have no trust score
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnscoredTrustPolicy {
+    /// Skip the trust check entirely (legacy behaviour)
+    Allow,
+    /// Evaluate as if the agent had the configured default score
+    Default,
+    /// Fail the trust check; agents must be explicitly scored
+    Deny,
+}
+
+impl UnscoredTrustPolicy {
+    pub fn from_env_value(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "allow" => Some(Self::Allow),
+            "default" => Some(Self::Default),
+            "deny" => Some(Self::Deny),
+            _ => None,
+        }
+    }
 }
 
 /// Policy warning
@@ -158,25 +245,60 @@ pub struct PolicyWarning {
 pub trait PolicyEngine: Send + Sync {
     async fn evaluate(&self, request: &PolicyRequest) -> Result<PolicyResponse>;
     async fn evaluate_v2(&self, request: &PolicyRequestV2) -> Result<PolicyResponseV2>;
+    /// Decide whether an upstream response may be returned to the agent
+    async fn evaluate_response(&self, request: &ResponsePolicyRequest) -> Result<PolicyResponse>;
 }
 
 // ========================================
 // OPA Policy Engine Implementation
 // ========================================
 
+/// Trust threshold the v2 policy applies when neither a sensitivity tier nor the tenant sets one
+const DEFAULT_TRUST_THRESHOLD: f64 = 0.3;
+
 /// OPA Policy Engine implementation
 pub struct OPAEngine {
     opa_url: String,
     client: reqwest::Client,
+    unscored_policy: UnscoredTrustPolicy,
+    unscored_default_score: f64,
 }
 
 impl OPAEngine {
-    pub fn new(opa_url: String) -> Self {
+    pub fn new(opa_url: String, unscored_policy: UnscoredTrustPolicy, unscored_default_score: f64) -> Self {
         Self {
             opa_url,
             client: reqwest::Client::new(),
+            unscored_policy,
+            unscored_default_score,
         }
     }
+
+    /// Trust context to evaluate for an agent, applying the unscored posture when it has none
+    fn effective_trust(&self, trust_score: Option<&TrustContext>) -> Option<TrustContext> {
+        if let Some(ts) = trust_score {
+            return Some(ts.clone());
+        }
+
+        let composite_score = match self.unscored_policy {
+            // Under deny the request is refused before OPA is asked, so there's no score to make up
+            UnscoredTrustPolicy::Allow | UnscoredTrustPolicy::Deny => return None,
+            UnscoredTrustPolicy::Default => self.unscored_default_score,
+        };
+
+        Some(TrustContext {
+            composite_score,
+            dimensions: TrustDimensions {
+                behavior: composite_score,
+                validation: composite_score,
+                provenance: composite_score,
+                alignment: composite_score,
+                reputation: composite_score,
+            },
+            threshold: None,
+            threshold_action: None,
+        })
+    }
 }
 
 #[async_trait]
@@ -194,6 +316,7 @@ impl PolicyEngine for OPAEngine {
                     "agent_id": request.agent.agent_id,
                     "developer_id": request.agent.developer_id,
                     "enterprise_id": request.agent.enterprise_id,
+                    "on_behalf_of": request.agent.on_behalf_of,
                 },
                 "request": {
                     "method": request.request.method,
@@ -220,6 +343,7 @@ impl PolicyEngine for OPAEngine {
                 allowed: false,
                 reason: format!("OPA evaluation failed: {}", response.status()),
                 evaluation_time_ms: evaluation_time,
+                matched_rules: Vec::new(),
             });
         }
 
@@ -234,6 +358,89 @@ impl PolicyEngine for OPAEngine {
                 "Policy denies request".to_string()
             },
             evaluation_time_ms: evaluation_time,
+            // Only the package's `allow` rule is queried
+            matched_rules: if allowed { vec!["allow".to_string()] } else { Vec::new() },
+        })
+    }
+
+    /// Response-phase evaluation; the response is blocked if any `response_deny` rule matches
+    async fn evaluate_response(&self, request: &ResponsePolicyRequest) -> Result<PolicyResponse> {
+        let start = std::time::Instant::now();
+
+        let opa_input = serde_json::json!({
+            "input": {
+                "agent": {
+                    "valid": request.agent.valid,
+                    "revoked": request.agent.revoked,
+                    "agent_id": request.agent.agent_id,
+                    "developer_id": request.agent.developer_id,
+                    "enterprise_id": request.agent.enterprise_id,
+                    "on_behalf_of": request.agent.on_behalf_of,
+                },
+                "request": {
+                    "method": request.request.method,
+                    "path": request.request.path,
+                    "headers": request.request.headers,
+                    "body_hash": request.request.body_hash,
+                },
+                "response": {
+                    "status": request.response.status,
+                    "content_type": request.response.content_type,
+                    "attributes": request.response.attributes,
+                }
+            }
+        });
+
+        let url = format!("{}/v1/data/pathwell/authz/response_deny", self.opa_url);
+        let response = self
+            .client
+            .post(&url)
+            .json(&opa_input)
+            .send()
+            .await?;
+
+        let evaluation_time = start.elapsed().as_millis() as u64;
+
+        if !response.status().is_success() {
+            return Ok(PolicyResponse {
+                allowed: false,
+                reason: format!("OPA evaluation failed: {}", response.status()),
+                evaluation_time_ms: evaluation_time,
+                matched_rules: Vec::new(),
+            });
+        }
+
+        // An undefined result means the response policy isn't loaded, so fail closed
+        let opa_result: serde_json::Value = response.json().await?;
+        let denials: Option<Vec<String>> = opa_result
+            .get("result")
+            .and_then(|r| r.as_array())
+            .map(|reasons| {
+                reasons
+                    .iter()
+                    .map(|r| r.as_str().map(str::to_string).unwrap_or_else(|| r.to_string()))
+                    .collect()
+            });
+
+        Ok(match denials {
+            None => PolicyResponse {
+                allowed: false,
+                reason: "Response policy is not loaded".to_string(),
+                evaluation_time_ms: evaluation_time,
+                matched_rules: Vec::new(),
+            },
+            Some(denials) if !denials.is_empty() => PolicyResponse {
+                allowed: false,
+                reason: format!("Response policy denies: {}", denials.join("; ")),
+                evaluation_time_ms: evaluation_time,
+                matched_rules: denials,
+            },
+            Some(_) => PolicyResponse {
+                allowed: true,
+                reason: "Response policy allows response".to_string(),
+                evaluation_time_ms: evaluation_time,
+                matched_rules: Vec::new(),
+            },
         })
     }
 
@@ -241,8 +448,10 @@ impl PolicyEngine for OPAEngine {
     async fn evaluate_v2(&self, request: &PolicyRequestV2) -> Result<PolicyResponseV2> {
         let start = std::time::Instant::now();
 
-        // Build trust score object for OPA
-        let trust_score_json = request.agent.trust_score.as_ref().map(|ts| {
+        // Build trust score object for OPA (unscored agents get the configured posture)
+        let unscored = request.agent.trust_score.is_none();
+        let effective_trust = self.effective_trust(request.agent.trust_score.as_ref());
+        let trust_score_json = effective_trust.as_ref().map(|ts| {
             serde_json::json!({
                 "composite_score": ts.composite_score,
                 "dimensions": {
@@ -266,6 +475,56 @@ impl PolicyEngine for OPAEngine {
             })
         });
 
+        // Resolve the trust requirement for this method/path from the tenant's tiers
+        let sensitivity_tier = request.context.tenant_governance.as_ref().and_then(|tg| {
+            resolve_tier(&tg.sensitivity_tiers, &request.request.method, &request.request.path)
+        });
+        let sensitivity_tier_json = sensitivity_tier.map(|tier| {
+            serde_json::json!({
+                "name": tier.name,
+                "trust_threshold": tier.trust_threshold,
+            })
+        });
+
+        // An unscored agent under the deny posture is refused here rather than evaluated at a made-up score
+        if request.agent.trust_score.is_none() && self.unscored_policy == UnscoredTrustPolicy::Deny {
+            let threshold = sensitivity_tier
+                .map(|tier| tier.trust_threshold)
+                .or_else(|| {
+                    request
+                        .context
+                        .tenant_governance
+                        .as_ref()
+                        .and_then(|tg| tg.trust_threshold_override)
+                })
+                .unwrap_or(DEFAULT_TRUST_THRESHOLD);
+            return Ok(PolicyResponseV2 {
+                allowed: false,
+                reason: "Agent has no trust score and unscored agents are denied".to_string(),
+                evaluation_time_ms: start.elapsed().as_millis() as u64,
+                trust_evaluation: Some(TrustEvaluationResult {
+                    trust_score_checked: true,
+                    trust_score: None,
+                    threshold,
+                    passed: false,
+                    action_taken: Some("block".to_string()),
+                    sensitivity_tier: sensitivity_tier.map(|tier| tier.name.clone()),
+                    unscored_policy: Some(UnscoredTrustPolicy::Deny),
+                    break_glass_id: None,
+                }),
+                tenant_policy_applied: Some(
+                    request
+                        .context
+                        .tenant_governance
+                        .as_ref()
+                        .map(|tg| tg.policy_scope.clone())
+                        .unwrap_or_else(|| "none".to_string()),
+                ),
+                warnings: Vec::new(),
+                matched_rules: Vec::new(),
+            });
+        }
+
         // Prepare OPA input for v2 policy
         let opa_input = serde_json::json!({
             "input": {
@@ -279,6 +538,8 @@ impl PolicyEngine for OPAEngine {
                     "tenant_hierarchy_path": request.agent.tenant_hierarchy_path,
                     "trust_score": trust_score_json,
                     "attribution": request.agent.attribution,
+                    "roles": request.agent.roles,
+                    "on_behalf_of": request.agent.on_behalf_of,
                 },
                 "request": {
                     "method": request.request.method,
@@ -290,6 +551,8 @@ impl PolicyEngine for OPAEngine {
                     "trace_id": request.context.trace_id,
                     "correlation_id": request.context.correlation_id,
                     "tenant_governance": tenant_governance_json,
+                    "sensitivity_tier": sensitivity_tier_json,
+                    "break_glass": request.context.break_glass,
                 }
             }
         });
@@ -313,6 +576,7 @@ impl PolicyEngine for OPAEngine {
                 trust_evaluation: None,
                 tenant_policy_applied: None,
                 warnings: vec![],
+                matched_rules: Vec::new(),
             });
         }
 
@@ -322,7 +586,10 @@ impl PolicyEngine for OPAEngine {
         // Extract policy decision
         let allowed = result.get("allow").and_then(|r| r.as_bool()).unwrap_or(false);
         let trust_action = result.get("trust_action").and_then(|r| r.as_str()).map(String::from);
-        let applied_threshold = result.get("applied_threshold").and_then(|r| r.as_f64()).unwrap_or(0.3);
+        let applied_threshold = result
+            .get("applied_threshold")
+            .and_then(|r| r.as_f64())
+            .unwrap_or(DEFAULT_TRUST_THRESHOLD);
         let applied_tenant_policy = result.get("applied_tenant_policy").and_then(|r| r.as_str()).map(String::from);
 
         // Extract warnings
@@ -342,19 +609,43 @@ impl PolicyEngine for OPAEngine {
             })
             .unwrap_or_default();
 
+        // The package's boolean rules that held (`allow` itself is the decision)
+        let matched_rules: Vec<String> = result
+            .as_object()
+            .map(|rules| {
+                rules
+                    .iter()
+                    .filter(|(name, value)| name.as_str() != "allow" && value.as_bool() == Some(true))
+                    .map(|(name, _)| name.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+
         // Build trust evaluation result
-        let trust_evaluation = request.agent.trust_score.as_ref().map(|ts| {
+        let unscored_policy = if unscored { Some(self.unscored_policy) } else { None };
+        let break_glass_id = request
+            .context
+            .break_glass
+            .as_ref()
+            .filter(|_| trust_action.as_deref() == Some("bypassed"))
+            .map(|bg| bg.id.clone());
+        let trust_evaluation = effective_trust.as_ref().map(|ts| {
             TrustEvaluationResult {
                 trust_score_checked: true,
                 trust_score: Some(ts.composite_score),
                 threshold: applied_threshold,
                 passed: ts.composite_score >= applied_threshold,
                 action_taken: trust_action.clone(),
+                sensitivity_tier: sensitivity_tier.map(|tier| tier.name.clone()),
+                unscored_policy,
+                break_glass_id: break_glass_id.clone(),
             }
         });
 
         // Determine reason
-        let reason = if allowed {
+        let reason = if let (true, Some(id)) = (allowed, &break_glass_id) {
+            format!("Policy allows request under break-glass override {}", id)
+        } else if allowed {
             "Policy allows request".to_string()
         } else if trust_action.as_deref() == Some("block") {
             "Trust score below minimum threshold".to_string()
@@ -369,6 +660,7 @@ impl PolicyEngine for OPAEngine {
             trust_evaluation,
             tenant_policy_applied: applied_tenant_policy,
             warnings,
+            matched_rules,
         })
     }
 }
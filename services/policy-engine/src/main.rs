@@ -1,17 +1,29 @@
 use anyhow::Result;
-use tracing::{info, error};
-use tracing_subscriber;
+use tracing::info;
 use axum::{
-    routing::post,
+    middleware,
+    routing::{get, post, put},
     Router,
 };
 use std::sync::Arc;
 
 mod engine;
 mod api;
+mod audit;
+mod deadline;
+mod overrides;
+mod request_id;
+mod sensitivity;
 
-use engine::{OPAEngine, PolicyEngine};
-use api::{evaluate_policy, evaluate_policy_v2};
+use engine::{OPAEngine, PolicyEngine, UnscoredTrustPolicy};
+use overrides::OverrideStore;
+use audit::{AuditLog, AuditSinkConfig};
+use api::{
+    delete_override, evaluate_policy, evaluate_policy_negotiated, evaluate_policy_v2, evaluate_response_policy,
+    list_overrides, set_override, AppState,
+};
+use deadline::deadline;
+use request_id::request_id;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -27,18 +39,69 @@ async fn main() -> Result<()> {
         .parse::<u16>()
         .unwrap_or(3002);
 
+    // Posture for agents without a trust score: allow (skip check), default, or deny
+    let unscored_policy = std::env::var("UNSCORED_TRUST_POLICY")
+        .ok()
+        .and_then(|v| UnscoredTrustPolicy::from_env_value(&v))
+        .unwrap_or(UnscoredTrustPolicy::Default);
+    let unscored_default_score = std::env::var("UNSCORED_TRUST_DEFAULT_SCORE")
+        .ok()
+        .and_then(|v| v.parse::<f64>().ok())
+        .unwrap_or(0.5);
+
     info!("Starting Policy Engine service on port {}", port);
     info!("OPA URL: {}", opa_url);
+    info!("Unscored trust policy: {:?} (default score {})", unscored_policy, unscored_default_score);
+
+    let overrides_file = std::env::var("POLICY_OVERRIDES_FILE").ok().map(std::path::PathBuf::from);
+    let overrides = OverrideStore::load(overrides_file.clone())?;
+    match overrides_file {
+        Some(path) => info!("Policy overrides: {} ({} loaded)", path.display(), overrides.list().len()),
+        None => info!("Policy overrides: in memory only"),
+    }
+    let admin_api_keys: Vec<String> = std::env::var("ADMIN_API_KEYS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|k| k.trim().to_string())
+        .filter(|k| !k.is_empty())
+        .collect();
+
+    // Structured feed of every policy decision, apart from the request logs
+    let audit_sink = AuditSinkConfig::from_env()?;
+    let audit_queue_size = std::env::var("POLICY_AUDIT_QUEUE_SIZE")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(10_000);
+    info!("Policy audit sink: {:?}", audit_sink);
+    let audit = AuditLog::start(audit_sink, audit_queue_size).await?;
 
     // Create OPA engine
-    let engine: Arc<dyn PolicyEngine> = Arc::new(OPAEngine::new(opa_url));
+    let engine: Arc<dyn PolicyEngine> = Arc::new(OPAEngine::new(
+        opa_url,
+        unscored_policy,
+        unscored_default_score,
+    ));
+
+    let state = AppState {
+        engine,
+        overrides: Arc::new(overrides),
+        admin_api_keys: Arc::new(admin_api_keys),
+        audit,
+    };
 
     // Create router
     let app = Router::new()
         .route("/v1/evaluate", post(evaluate_policy))
         .route("/v2/evaluate", post(evaluate_policy_v2))
+        // Unversioned: Accept-Version picks v1 or v2
+        .route("/evaluate", post(evaluate_policy_negotiated))
+        .route("/v1/evaluate/response", post(evaluate_response_policy))
+        .route("/v1/overrides", get(list_overrides))
+        .route("/v1/overrides/:agent_id", put(set_override).delete(delete_override))
         .route("/health", axum::routing::get(health_check))
-        .with_state(engine);
+        .layer(middleware::from_fn(deadline))
+        .layer(middleware::from_fn(request_id))
+        .with_state(state);
 
     // Start server
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
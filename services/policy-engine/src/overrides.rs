@@ -0,0 +1,120 @@
+//! Per-agent policy overrides. An operator can force-allow or force-deny one agent, with a reason and an
+//! expiry, without editing Rego. An active override is consulted before OPA on every evaluation, and is
+//! returned with the decision so the gateway can record it on the receipt.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverrideDecision {
+    Allow,
+    Deny,
+}
+
+/// A decision forced for one agent until it expires
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyOverride {
+    pub agent_id: String,
+    pub decision: OverrideDecision,
+    pub reason: String,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl PolicyOverride {
+    pub fn is_active(&self) -> bool {
+        self.expires_at > Utc::now()
+    }
+
+    pub fn allowed(&self) -> bool {
+        self.decision == OverrideDecision::Allow
+    }
+
+    /// The reason given for a decision the override made
+    pub fn decision_reason(&self) -> String {
+        let outcome = if self.allowed() { "allowed" } else { "denied" };
+        format!(
+            "Agent {} {} by policy override until {}: {}",
+            self.agent_id,
+            outcome,
+            self.expires_at.to_rfc3339(),
+            self.reason
+        )
+    }
+}
+
+/// Overrides by agent ID. With `POLICY_OVERRIDES_FILE` set they are loaded from that file at startup and
+/// written back on every change; otherwise they last until the engine restarts
+pub struct OverrideStore {
+    overrides: RwLock<HashMap<String, PolicyOverride>>,
+    path: Option<PathBuf>,
+}
+
+impl OverrideStore {
+    pub fn load(path: Option<PathBuf>) -> Result<Self> {
+        let overrides = match &path {
+            Some(path) if path.exists() => {
+                let list: Vec<PolicyOverride> = serde_json::from_slice(&std::fs::read(path)?)?;
+                list.into_iter().map(|o| (o.agent_id.clone(), o)).collect()
+            }
+            _ => HashMap::new(),
+        };
+        Ok(Self {
+            overrides: RwLock::new(overrides),
+            path,
+        })
+    }
+
+    /// The agent's override, unless it has expired
+    pub fn active(&self, agent_id: &str) -> Option<PolicyOverride> {
+        let overrides = self.overrides.read().unwrap();
+        overrides.get(agent_id).filter(|o| o.is_active()).cloned()
+    }
+
+    /// Every override, expired ones included, soonest to expire first
+    pub fn list(&self) -> Vec<PolicyOverride> {
+        let mut list: Vec<PolicyOverride> = self.overrides.read().unwrap().values().cloned().collect();
+        list.sort_by_key(|o| o.expires_at);
+        list
+    }
+
+    /// Set the agent's override, replacing any it had
+    pub fn set(&self, policy_override: PolicyOverride) -> Result<()> {
+        let mut overrides = self.overrides.write().unwrap();
+        let mut updated = overrides.clone();
+        updated.insert(policy_override.agent_id.clone(), policy_override);
+        self.save(&updated)?;
+        *overrides = updated;
+        Ok(())
+    }
+
+    /// Remove the agent's override, returning it if there was one
+    pub fn remove(&self, agent_id: &str) -> Result<Option<PolicyOverride>> {
+        let mut overrides = self.overrides.write().unwrap();
+        let mut updated = overrides.clone();
+        let removed = updated.remove(agent_id);
+        if removed.is_some() {
+            self.save(&updated)?;
+            *overrides = updated;
+        }
+        Ok(removed)
+    }
+
+    /// Replace the file atomically, so a crash mid-write can't lose every override
+    fn save(&self, overrides: &HashMap<String, PolicyOverride>) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let list: Vec<&PolicyOverride> = overrides.values().collect();
+        let temp = path.with_extension("tmp");
+        std::fs::write(&temp, serde_json::to_vec_pretty(&list)?)?;
+        std::fs::rename(&temp, path)?;
+        Ok(())
+    }
+}
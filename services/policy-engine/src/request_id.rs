@@ -0,0 +1,41 @@
+//! Request IDs: every request carries one, the caller's `x-request-id` or else a new UUID. It is echoed
+//! in the response and attached to every log line written while handling the request, so one call can
+//! be followed through the logs of each service it touches.
+
+use axum::{
+    extract::Request,
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+use uuid::Uuid;
+
+/// Header carrying the request ID, on requests and responses
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longer caller-supplied IDs are replaced rather than logged
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Give the request an ID, handle it within a span carrying the ID, and echo it in the response
+pub async fn request_id(mut request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty() && v.len() <= MAX_REQUEST_ID_LEN)
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let value = HeaderValue::from_str(&id).expect("request ID is a valid header value");
+    request.headers_mut().insert(REQUEST_ID_HEADER, value.clone());
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+    let mut response = next.run(request).instrument(span).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    response
+}
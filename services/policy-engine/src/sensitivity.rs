@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+
+/// Trust required for requests matching a method and path pattern (from tenant governance)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensitivityTier {
+    pub name: String,
+    /// HTTP methods covered by the tier; empty covers every method
+    #[serde(default)]
+    pub methods: Vec<String>,
+    /// Path glob: `*` matches within one segment, `**` matches any number of segments
+    pub path_pattern: String,
+    pub trust_threshold: f64,
+}
+
+impl SensitivityTier {
+    fn matches(&self, method: &str, path: &str) -> bool {
+        let method_matches = self.methods.is_empty()
+            || self.methods.iter().any(|m| m.eq_ignore_ascii_case(method));
+        method_matches && path_matches(&self.path_pattern, path)
+    }
+}
+
+/// The strictest tier matching the request, so overlapping tiers can't weaken each other
+pub fn resolve_tier<'a>(
+    tiers: &'a [SensitivityTier],
+    method: &str,
+    path: &str,
+) -> Option<&'a SensitivityTier> {
+    tiers
+        .iter()
+        .filter(|tier| tier.matches(method, path))
+        .max_by(|a, b| a.trust_threshold.total_cmp(&b.trust_threshold))
+}
+
+fn segments(value: &str) -> Vec<&str> {
+    value.split('/').filter(|s| !s.is_empty()).collect()
+}
+
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let path = path.split('?').next().unwrap_or(path);
+    glob_segments(&segments(pattern), &segments(path))
+}
+
+fn glob_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|i| glob_segments(rest, &path[i..])),
+        Some((segment_pattern, rest)) => match path.split_first() {
+            Some((segment, path_rest)) => {
+                glob_segment(segment_pattern, segment) && glob_segments(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+fn glob_segment(pattern: &str, segment: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == segment,
+        Some((prefix, rest)) => segment.strip_prefix(prefix).is_some_and(|tail| {
+            (0..=tail.len())
+                .filter(|&i| tail.is_char_boundary(i))
+                .any(|i| glob_segment(rest, &tail[i..]))
+        }),
+    }
+}
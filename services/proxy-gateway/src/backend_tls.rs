@@ -0,0 +1,64 @@
+use anyhow::{Context, Result};
+use reqwest::{Certificate, Identity};
+use serde::{Deserialize, Serialize};
+
+/// How the gateway's TLS connections to backends are verified and authenticated
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BackendTlsConfig {
+    /// PEM bundle of CAs trusted for backend certificates, in addition to the system roots
+    pub ca_bundle_path: Option<String>,
+    /// PEM certificate (chain) presented to backends that require mutual TLS
+    pub client_cert_path: Option<String>,
+    /// PKCS#8 PEM private key for `client_cert_path`
+    pub client_key_path: Option<String>,
+    /// Accept any backend certificate; for development against self-signed backends only
+    pub insecure_skip_verify: bool,
+}
+
+impl BackendTlsConfig {
+    pub fn from_env() -> Self {
+        Self {
+            ca_bundle_path: std::env::var("BACKEND_CA_BUNDLE").ok().filter(|v| !v.is_empty()),
+            client_cert_path: std::env::var("BACKEND_CLIENT_CERT").ok().filter(|v| !v.is_empty()),
+            client_key_path: std::env::var("BACKEND_CLIENT_KEY").ok().filter(|v| !v.is_empty()),
+            insecure_skip_verify: std::env::var("BACKEND_TLS_INSECURE_SKIP_VERIFY")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+        }
+    }
+
+    /// The client every backend request is sent with. Unreadable or invalid files are an error rather
+    /// than being skipped, since the gateway would otherwise fail every backend call it makes
+    pub fn client(&self) -> Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder();
+
+        if let Some(ref path) = self.ca_bundle_path {
+            let pem = std::fs::read(path).with_context(|| format!("reading BACKEND_CA_BUNDLE {}", path))?;
+            let certificates = Certificate::from_pem_bundle(&pem)
+                .with_context(|| format!("parsing BACKEND_CA_BUNDLE {}", path))?;
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        match (&self.client_cert_path, &self.client_key_path) {
+            (Some(cert_path), Some(key_path)) => {
+                let cert = std::fs::read(cert_path)
+                    .with_context(|| format!("reading BACKEND_CLIENT_CERT {}", cert_path))?;
+                let key = std::fs::read(key_path)
+                    .with_context(|| format!("reading BACKEND_CLIENT_KEY {}", key_path))?;
+                let identity = Identity::from_pkcs8_pem(&cert, &key)
+                    .context("BACKEND_CLIENT_CERT and BACKEND_CLIENT_KEY must be a PEM certificate and its PKCS#8 key")?;
+                builder = builder.identity(identity);
+            }
+            (None, None) => {}
+            _ => anyhow::bail!("BACKEND_CLIENT_CERT and BACKEND_CLIENT_KEY must be set together"),
+        }
+
+        if self.insecure_skip_verify {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+
+        Ok(builder.build()?)
+    }
+}
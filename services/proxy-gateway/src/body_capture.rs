@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Body types captured when `BODY_CAPTURE_CONTENT_TYPES` is unset
+const DEFAULT_BODY_CAPTURE_CONTENT_TYPES: &str = "application/json,text/*";
+
+/// Whether (and which) request bodies are copied into receipts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BodyCaptureConfig {
+    pub enabled: bool,
+    /// `type/subtype`, `type/*` or `*/*`; bodies of other types are only hashed
+    pub content_types: Vec<String>,
+    /// Larger bodies are only hashed
+    pub max_bytes: usize,
+}
+
+impl BodyCaptureConfig {
+    pub fn from_env() -> Self {
+        Self {
+            enabled: std::env::var("BODY_CAPTURE_ENABLED")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            content_types: std::env::var("BODY_CAPTURE_CONTENT_TYPES")
+                .unwrap_or_else(|_| DEFAULT_BODY_CAPTURE_CONTENT_TYPES.to_string())
+                .split(',')
+                .map(|t| t.trim().to_lowercase())
+                .filter(|t| !t.is_empty())
+                .collect(),
+            max_bytes: std::env::var("BODY_CAPTURE_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(64 * 1024),
+        }
+    }
+
+    /// Hash a non-empty body, and capture it too when enabled and its content type is allowlisted
+    pub fn record(&self, content_type: Option<&str>, body: &[u8]) -> BodyRecord {
+        let captured = content_type
+            .filter(|_| self.enabled && !body.is_empty() && body.len() <= self.max_bytes)
+            .filter(|ct| content_type_allowed(&self.content_types, ct))
+            .and_then(|_| std::str::from_utf8(body).ok())
+            .map(str::to_string);
+
+        BodyRecord {
+            // A bodyless request (e.g. a GET) has nothing to attest to, so it records no hash
+            hash: (!body.is_empty()).then(|| hex::encode(Sha256::digest(body))),
+            content_type: captured.as_ref().and(content_type.map(str::to_string)),
+            captured,
+        }
+    }
+}
+
+/// What a receipt records about a request body
+#[derive(Debug, Clone)]
+pub struct BodyRecord {
+    /// SHA-256 of the body; `None` when the body is empty
+    pub hash: Option<String>,
+    /// Only set alongside a captured body
+    pub content_type: Option<String>,
+    pub captured: Option<String>,
+}
+
+/// Whether `content_type` matches an allowlist entry (`type/subtype`, `type/*` or `*/*`);
+/// parameters such as `charset` are ignored
+pub fn content_type_allowed(allowlist: &[String], content_type: &str) -> bool {
+    let media_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    let Some((kind, _)) = media_type.split_once('/') else {
+        return false;
+    };
+
+    allowlist.iter().any(|entry| {
+        let entry = entry.trim().to_ascii_lowercase();
+        entry == "*/*"
+            || entry == media_type
+            || entry.strip_suffix("/*").is_some_and(|prefix| prefix == kind)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> BodyCaptureConfig {
+        BodyCaptureConfig {
+            enabled: true,
+            content_types: vec!["application/json".to_string(), "text/*".to_string()],
+            max_bytes: 16,
+        }
+    }
+
+    #[test]
+    fn allowlisted_content_types_match_exactly_or_by_wildcard() {
+        let allowlist = config().content_types;
+        assert!(content_type_allowed(&allowlist, "application/json"));
+        assert!(content_type_allowed(&allowlist, "Application/JSON; charset=utf-8"));
+        assert!(content_type_allowed(&allowlist, "text/plain"));
+        assert!(!content_type_allowed(&allowlist, "application/xml"));
+        assert!(!content_type_allowed(&allowlist, "application/jsonx"));
+        assert!(!content_type_allowed(&allowlist, "json"));
+        assert!(content_type_allowed(&["*/*".to_string()], "image/png"));
+    }
+
+    #[test]
+    fn record_captures_allowed_bodies() {
+        let record = config().record(Some("application/json"), br#"{"a":1}"#);
+        assert_eq!(record.captured.as_deref(), Some(r#"{"a":1}"#));
+        assert_eq!(record.content_type.as_deref(), Some("application/json"));
+        assert_eq!(record.hash, Some(hex::encode(Sha256::digest(br#"{"a":1}"#))));
+    }
+
+    #[test]
+    fn record_only_hashes_skipped_bodies() {
+        let config = config();
+        for (content_type, body) in [
+            (Some("application/octet-stream"), &b"abc"[..]),
+            (None, &b"abc"[..]),
+            (Some("text/plain"), &b"longer than sixteen bytes"[..]),
+        ] {
+            let record = config.record(content_type, body);
+            assert!(record.captured.is_none(), "{:?} should not be captured", content_type);
+            assert!(record.content_type.is_none());
+            assert!(record.hash.is_some());
+        }
+
+        let disabled = BodyCaptureConfig { enabled: false, ..config };
+        assert!(disabled.record(Some("application/json"), b"{}").captured.is_none());
+    }
+
+    #[test]
+    fn record_leaves_empty_bodies_unhashed() {
+        let record = config().record(Some("application/json"), b"");
+        assert!(record.hash.is_none());
+        assert!(record.captured.is_none());
+    }
+}
@@ -1,20 +1,82 @@
 use serde::{Deserialize, Serialize};
 
+use crate::backend_tls::BackendTlsConfig;
+use crate::body_capture::BodyCaptureConfig;
+use crate::response_policy::ResponsePolicyConfig;
+use crate::routing::{
+    parse_backend_routes, parse_event_type_routes, parse_path_rewrites, BackendRoute,
+    EventTypeRoute, PathRewrite,
+};
+
+/// Which identity and receipt APIs the gateway uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GatewayMode {
+    V1,
+    /// Captures the agent's trust snapshot at validation and writes v2 receipts
+    V2,
+}
+
+/// What the gateway does when the identity registry can't be reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdentityFailureMode {
+    /// Deny with `503 identity_service_unavailable`
+    Closed,
+    /// Continue to policy evaluation unverified; the receipt records that identity was unavailable
+    Open,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
-    pub target_backend_url: String,
+    /// Default backend for paths not matched by `backend_routes`
+    pub target_backend_url: Option<String>,
+    /// Path-prefix routing table; the longest matching prefix wins
+    pub backend_routes: Vec<BackendRoute>,
+    /// CA bundle, client certificate and verification for backend connections
+    pub backend_tls: BackendTlsConfig,
     pub identity_registry_url: String,
     pub policy_engine_url: String,
     pub receipt_store_url: String,
     pub listen_port: u16,
     pub listen_host: String,
+    /// Request headers forwarded to the policy engine (lowercase); all others are stripped
+    pub policy_header_allowlist: Vec<String>,
+    /// Prefix rewrites applied to the path before forwarding to the backend
+    pub path_rewrites: Vec<PathRewrite>,
+    /// Receipt event types by path prefix and method; unmatched requests are `gateway_request`
+    pub event_type_routes: Vec<EventTypeRoute>,
+    /// Whether request bodies are copied into receipts, and which content types
+    pub body_capture: BodyCaptureConfig,
+    /// Whether upstream responses get a second policy evaluation before reaching the agent
+    pub response_policy: ResponsePolicyConfig,
+    pub gateway_mode: GatewayMode,
+    /// How long (v2 mode) an agent's trust snapshot is reused before it is fetched again;
+    /// 0 fetches one for every request
+    pub trust_snapshot_interval_secs: u64,
+    pub identity_failure_mode: IdentityFailureMode,
+    /// Upper bound on the time budget a request can ask for with `x-pathwell-timeout-ms`
+    pub max_request_timeout_ms: u64,
 }
 
+/// Headers passed to the policy engine when `POLICY_HEADER_ALLOWLIST` is unset
+const DEFAULT_POLICY_HEADER_ALLOWLIST: &str =
+    "content-type,accept,user-agent,x-pathwell-agent-id,x-pathwell-trace-id,x-correlation-id";
+
 impl Config {
     pub fn from_env() -> Self {
+        let target_backend_url = std::env::var("TARGET_BACKEND_URL").ok();
+        let backend_routes = parse_backend_routes(
+            &std::env::var("BACKEND_ROUTES").unwrap_or_default(),
+        );
+        if target_backend_url.is_none() && backend_routes.is_empty() {
+            panic!("TARGET_BACKEND_URL or BACKEND_ROUTES must be set");
+        }
+
         Self {
-            target_backend_url: std::env::var("TARGET_BACKEND_URL")
-                .expect("TARGET_BACKEND_URL must be set"),
+            target_backend_url,
+            backend_routes,
+            backend_tls: BackendTlsConfig::from_env(),
             identity_registry_url: std::env::var("IDENTITY_REGISTRY_URL")
                 .unwrap_or_else(|_| "http://localhost:3001".to_string()),
             policy_engine_url: std::env::var("POLICY_ENGINE_URL")
@@ -27,6 +89,36 @@ impl Config {
                 .unwrap_or(8080),
             listen_host: std::env::var("LISTEN_HOST")
                 .unwrap_or_else(|_| "0.0.0.0".to_string()),
+            policy_header_allowlist: std::env::var("POLICY_HEADER_ALLOWLIST")
+                .unwrap_or_else(|_| DEFAULT_POLICY_HEADER_ALLOWLIST.to_string())
+                .split(',')
+                .map(|h| h.trim().to_lowercase())
+                .filter(|h| !h.is_empty())
+                .collect(),
+            path_rewrites: parse_path_rewrites(
+                &std::env::var("PATH_REWRITE_RULES").unwrap_or_default(),
+            ),
+            event_type_routes: parse_event_type_routes(
+                &std::env::var("EVENT_TYPE_ROUTES").unwrap_or_default(),
+            ),
+            body_capture: BodyCaptureConfig::from_env(),
+            response_policy: ResponsePolicyConfig::from_env(),
+            gateway_mode: match std::env::var("GATEWAY_MODE").as_deref() {
+                Ok("v2") => GatewayMode::V2,
+                _ => GatewayMode::V1,
+            },
+            trust_snapshot_interval_secs: std::env::var("TRUST_SNAPSHOT_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            identity_failure_mode: match std::env::var("IDENTITY_FAILURE_MODE").as_deref() {
+                Ok("open") => IdentityFailureMode::Open,
+                _ => IdentityFailureMode::Closed,
+            },
+            max_request_timeout_ms: std::env::var("MAX_REQUEST_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30_000),
         }
     }
 }
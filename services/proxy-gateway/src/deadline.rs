@@ -0,0 +1,61 @@
+//! Request deadlines: a caller bounds the whole request with `x-pathwell-timeout-ms`. The budget
+//! covers identity, policy and upstream alike, and whatever is left of it is sent on with each call so
+//! the services behind the gateway can stop work the caller has already given up on.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Header carrying the request's time budget in milliseconds, on inbound and outbound requests
+pub const TIMEOUT_HEADER: &str = "x-pathwell-timeout-ms";
+
+/// When a request must be finished by
+#[derive(Debug, Clone, Copy)]
+pub struct Deadline {
+    expires_at: Instant,
+}
+
+impl Deadline {
+    /// The deadline a request asked for, if any, with its budget capped at `max_ms`. A value that
+    /// isn't a positive number of milliseconds is ignored
+    pub fn from_headers(headers: &HashMap<String, String>, max_ms: u64) -> Option<Self> {
+        let value = headers.get(TIMEOUT_HEADER)?;
+        match value.trim().parse::<u64>() {
+            Ok(ms) if ms > 0 => Some(Self {
+                expires_at: Instant::now() + Duration::from_millis(ms.min(max_ms)),
+            }),
+            _ => {
+                tracing::warn!("Ignoring invalid {} value: {}", TIMEOUT_HEADER, value);
+                None
+            }
+        }
+    }
+
+    /// Milliseconds left in the budget, for passing on to the next service
+    pub fn remaining_ms(&self) -> u64 {
+        self.expires_at
+            .saturating_duration_since(Instant::now())
+            .as_millis() as u64
+    }
+}
+
+/// Add the remaining budget to an outbound call when the request has a deadline
+pub fn propagate(
+    deadline: Option<Deadline>,
+    request: reqwest::RequestBuilder,
+) -> reqwest::RequestBuilder {
+    match deadline {
+        // Never 0, which the next service would read as no budget at all rather than none left
+        Some(deadline) => request.header(TIMEOUT_HEADER, deadline.remaining_ms().max(1)),
+        None => request,
+    }
+}
+
+/// Run one stage of the request within its deadline; `None` if the budget ran out first
+pub async fn within<F: Future>(deadline: Option<Deadline>, stage: F) -> Option<F::Output> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline.expires_at, stage).await.ok(),
+        None => Some(stage.await),
+    }
+}
@@ -0,0 +1,104 @@
+//! Simulated dependency failures for resilience testing.
+//!
+//! Only active when built with the `failure-injection` feature; otherwise `inject` is a no-op
+//! and the `FAULT_*` environment variables are never read.
+
+/// Downstream service a client call goes to
+#[derive(Debug, Clone, Copy)]
+pub enum Dependency {
+    Identity,
+    Policy,
+    Receipt,
+}
+
+/// Possibly delay or fail a call to `dependency`, according to its configured rates
+#[cfg(not(feature = "failure-injection"))]
+#[inline]
+pub async fn inject(_dependency: Dependency) -> anyhow::Result<()> {
+    Ok(())
+}
+
+#[cfg(feature = "failure-injection")]
+pub use enabled::inject;
+
+#[cfg(feature = "failure-injection")]
+mod enabled {
+    use anyhow::Result;
+    use std::sync::OnceLock;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    use super::Dependency;
+
+    /// Injection settings for one dependency, from `FAULT_<DEPENDENCY>_*`
+    #[derive(Debug, Clone, Default)]
+    struct FaultConfig {
+        /// Fraction of calls (0.0-1.0) that fail outright
+        error_rate: f64,
+        /// Fraction of calls (0.0-1.0) delayed before being made
+        delay_rate: f64,
+        delay_ms: u64,
+    }
+
+    impl FaultConfig {
+        fn from_env(prefix: &str) -> Self {
+            let rate = |name: &str| {
+                std::env::var(format!("FAULT_{}_{}", prefix, name))
+                    .ok()
+                    .and_then(|v| v.parse::<f64>().ok())
+                    .unwrap_or(0.0)
+                    .clamp(0.0, 1.0)
+            };
+            Self {
+                error_rate: rate("ERROR_RATE"),
+                delay_rate: rate("DELAY_RATE"),
+                delay_ms: std::env::var(format!("FAULT_{}_DELAY_MS", prefix))
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(1000),
+            }
+        }
+    }
+
+    struct FaultSettings {
+        identity: FaultConfig,
+        policy: FaultConfig,
+        receipt: FaultConfig,
+    }
+
+    fn settings() -> &'static FaultSettings {
+        static SETTINGS: OnceLock<FaultSettings> = OnceLock::new();
+        SETTINGS.get_or_init(|| {
+            tracing::warn!("Failure injection is compiled in; dependency calls may be delayed or failed");
+            FaultSettings {
+                identity: FaultConfig::from_env("IDENTITY"),
+                policy: FaultConfig::from_env("POLICY"),
+                receipt: FaultConfig::from_env("RECEIPT"),
+            }
+        })
+    }
+
+    /// Uniform sample in [0, 1), taken from the random bits of a v4 UUID
+    fn roll() -> f64 {
+        let bits = Uuid::new_v4().as_u128() as u64 & ((1 << 53) - 1);
+        bits as f64 / (1u64 << 53) as f64
+    }
+
+    pub async fn inject(dependency: Dependency) -> Result<()> {
+        let settings = settings();
+        let config = match dependency {
+            Dependency::Identity => &settings.identity,
+            Dependency::Policy => &settings.policy,
+            Dependency::Receipt => &settings.receipt,
+        };
+
+        if roll() < config.delay_rate {
+            tracing::debug!("Injecting {}ms delay into {:?} call", config.delay_ms, dependency);
+            tokio::time::sleep(Duration::from_millis(config.delay_ms)).await;
+        }
+        if roll() < config.error_rate {
+            anyhow::bail!("Injected failure in {:?} call", dependency);
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,12 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
+use crate::deadline::{propagate, Deadline};
+use crate::fault_injection::{inject, Dependency};
+use crate::request_id::REQUEST_ID_HEADER;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ValidateAgentResponse {
     pub valid: bool,
@@ -9,6 +14,75 @@ pub struct ValidateAgentResponse {
     pub developer_id: Uuid,
     pub enterprise_id: Option<Uuid>,
     pub revoked: bool,
+    /// Why the agent is invalid, e.g. `tenant_deactivated`
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// The registry answered a validation with a client error (e.g. `404` for an unknown agent):
+/// the agent is invalid, as opposed to the registry being unavailable
+#[derive(Debug, thiserror::Error)]
+#[error("Identity validation failed: {0}")]
+pub struct ValidationRejected(pub reqwest::StatusCode);
+
+/// v2 validation: the v1 result plus the agent's tenant, trust score and attribution
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidateAgentResponseV2 {
+    #[serde(flatten)]
+    pub agent: ValidateAgentResponse,
+    pub tenant_id: Option<Uuid>,
+    pub tenant_hierarchy_path: Option<Vec<String>>,
+    pub trust_score: Option<TrustScoreSummary>,
+    pub attribution: Option<AttributionSummary>,
+    /// The agent's tenant requires complete attribution (creator and publisher)
+    #[serde(default)]
+    pub attribution_required: bool,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    /// Active break-glass override exempting the agent from its trust threshold
+    #[serde(default)]
+    pub break_glass: Option<BreakGlassSummary>,
+}
+
+/// A time-boxed override that skips the agent's trust threshold until `expires_at`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BreakGlassSummary {
+    pub id: String,
+    pub target_type: Option<String>,
+    pub reason: Option<String>,
+    pub expires_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrustScoreSummary {
+    pub composite_score: f64,
+    pub threshold_action: Option<String>,
+    #[serde(default)]
+    pub dimensions: BTreeMap<String, f64>,
+    #[serde(default)]
+    pub minimum_threshold: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AttributionSummary {
+    pub creator_id: Option<Uuid>,
+    pub publisher_id: Option<Uuid>,
+    pub audit_visibility_scope: String,
+}
+
+/// One principal in an on-behalf-of chain, as verified by the identity registry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegationLink {
+    pub principal_type: String,
+    pub principal_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyDelegationResponse {
+    pub valid: bool,
+    pub agent_id: String,
+    pub chain: Vec<DelegationLink>,
+    pub reason: Option<String>,
 }
 
 pub struct IdentityClient {
@@ -24,10 +98,22 @@ impl IdentityClient {
         }
     }
 
-    pub async fn validate_agent(&self, agent_id: &str) -> Result<ValidateAgentResponse> {
+    pub async fn validate_agent(
+        &self,
+        agent_id: &str,
+        request_id: &str,
+        deadline: Option<Deadline>,
+    ) -> Result<ValidateAgentResponse> {
+        inject(Dependency::Identity).await?;
         let url = format!("{}/v1/agents/{}/validate", self.base_url, agent_id);
-        let response = self.client.get(&url).send().await?;
+        let response = propagate(deadline, self.client.get(&url))
+            .header(REQUEST_ID_HEADER, request_id)
+            .send()
+            .await?;
         
+        if response.status().is_client_error() {
+            return Err(ValidationRejected(response.status()).into());
+        }
         if !response.status().is_success() {
             anyhow::bail!("Identity validation failed: {}", response.status());
         }
@@ -35,5 +121,53 @@ impl IdentityClient {
         let result: ValidateAgentResponse = response.json().await?;
         Ok(result)
     }
-}
 
+    /// Validate via the v2 endpoint, which also returns the agent's trust context
+    pub async fn validate_agent_v2(
+        &self,
+        agent_id: &str,
+        request_id: &str,
+        deadline: Option<Deadline>,
+    ) -> Result<ValidateAgentResponseV2> {
+        inject(Dependency::Identity).await?;
+        let url = format!("{}/v2/agents/{}/validate", self.base_url, agent_id);
+        let response = propagate(deadline, self.client.get(&url))
+            .header(REQUEST_ID_HEADER, request_id)
+            .send()
+            .await?;
+
+        if response.status().is_client_error() {
+            return Err(ValidationRejected(response.status()).into());
+        }
+        if !response.status().is_success() {
+            anyhow::bail!("Identity validation failed: {}", response.status());
+        }
+
+        let result: ValidateAgentResponseV2 = response.json().await?;
+        Ok(result)
+    }
+
+    /// Verify that the agent may act on behalf of each principal in the chain (nearest first)
+    pub async fn verify_delegation(
+        &self,
+        agent_id: &str,
+        on_behalf_of: &[String],
+        request_id: &str,
+        deadline: Option<Deadline>,
+    ) -> Result<VerifyDelegationResponse> {
+        inject(Dependency::Identity).await?;
+        let url = format!("{}/v1/agents/{}/delegations/verify", self.base_url, agent_id);
+        let response = propagate(deadline, self.client.post(&url))
+            .header(REQUEST_ID_HEADER, request_id)
+            .json(&serde_json::json!({ "on_behalf_of": on_behalf_of }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Delegation verification failed: {}", response.status());
+        }
+
+        let result: VerifyDelegationResponse = response.json().await?;
+        Ok(result)
+    }
+}
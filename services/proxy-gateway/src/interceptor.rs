@@ -1,30 +1,46 @@
 use anyhow::Result;
 use hyper::{Request, Response, StatusCode};
-use hyper::header::HeaderValue;
 use std::collections::HashMap;
-use sha2::{Sha256, Digest};
-use hex;
-use reqwest;
 
-use crate::identity_client::IdentityClient;
-use crate::policy_client::PolicyClient;
+use crate::deadline::{propagate, within, Deadline};
+use crate::identity_client::{DelegationLink, IdentityClient, ValidateAgentResponse, ValidationRejected};
+use crate::request_id::REQUEST_ID_HEADER;
+use crate::policy_client::{
+    AgentInfo, PolicyClient, PolicyResponse, RequestInfo as PolicyRequestInfo, ResponseInfo,
+};
 use crate::receipt_client::{
     ReceiptClient, ReceiptRequest, RequestInfo as ReceiptRequestInfo,
-    PolicyResult, IdentityResult, EventType, EventSource
+    PolicyResult, IdentityResult, EventType, EventSource, LatencyBreakdown,
 };
-use crate::config::Config;
+use crate::body_capture::BodyRecord;
+use crate::config::{Config, GatewayMode, IdentityFailureMode};
+use crate::routing::{rewrite_path, select_backend, select_event_type};
+use crate::trust_snapshot::{TrustSnapshot, TrustSnapshotCache};
+use crate::usage::extract_usage;
 use uuid::Uuid;
 
 const AGENT_ID_HEADER: &str = "x-pathwell-agent-id";
-const SIGNATURE_HEADER: &str = "x-pathwell-signature";
 const CORRELATION_ID_HEADER: &str = "x-correlation-id";
 const TRACE_ID_HEADER: &str = "x-pathwell-trace-id";
+/// Comma-separated principals the agent acts for, nearest first
+const ON_BEHALF_OF_HEADER: &str = "x-pathwell-on-behalf-of";
+/// Receipt event type for this request (e.g. `human_action`), overriding `EVENT_TYPE_ROUTES`
+const EVENT_TYPE_HEADER: &str = "x-pathwell-event-type";
+/// Recorded when the identity registry can't be reached, as distinct from an invalid agent
+const IDENTITY_UNAVAILABLE_REASON: &str = "identity_service_unavailable";
+/// Recorded when the agent's tenant requires a creator and publisher and the agent lacks one
+const ATTRIBUTION_REQUIRED_REASON: &str = "attribution_required";
+/// Recorded when the request's time budget runs out before it completes
+const DEADLINE_EXCEEDED_REASON: &str = "deadline_exceeded";
 
 pub struct Interceptor {
     config: Config,
     identity_client: IdentityClient,
     policy_client: PolicyClient,
     receipt_client: ReceiptClient,
+    trust_snapshots: TrustSnapshotCache,
+    /// Shared by every backend request, with the configured backend TLS settings
+    backend_client: reqwest::Client,
 }
 
 /// Trace context extracted from or generated for a request
@@ -32,16 +48,70 @@ struct TraceContext {
     trace_id: Uuid,
     correlation_id: Option<String>,
     span_id: Uuid,
+    /// Set by the request ID middleware, and sent on to every service the request reaches
+    request_id: String,
 }
 
-impl Interceptor {
-    pub fn new(config: Config) -> Self {
+/// The request as its receipt records it, for every denial along the way
+#[derive(Clone, Copy)]
+struct RequestRecord<'a> {
+    agent_id: &'a str,
+    trace_ctx: &'a TraceContext,
+    method: &'a str,
+    path: &'a str,
+    headers: &'a HashMap<String, String>,
+    body_record: &'a BodyRecord,
+    /// The verified delegation chain; empty until it has been verified
+    on_behalf_of: &'a [DelegationLink],
+}
+
+/// Timing captured as a request moves through the gateway phases
+struct RequestTiming {
+    start_time: std::time::Instant,
+    occurred_at: chrono::DateTime<chrono::Utc>,
+    identity_ms: Option<u64>,
+    policy_ms: Option<u64>,
+    upstream_ms: Option<u64>,
+}
+
+impl RequestTiming {
+    fn start() -> Self {
         Self {
+            start_time: std::time::Instant::now(),
+            occurred_at: chrono::Utc::now(),
+            identity_ms: None,
+            policy_ms: None,
+            upstream_ms: None,
+        }
+    }
+
+    fn latency(&self) -> LatencyBreakdown {
+        LatencyBreakdown {
+            identity_ms: self.identity_ms,
+            policy_ms: self.policy_ms,
+            upstream_ms: self.upstream_ms,
+            total_ms: self.start_time.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+impl Interceptor {
+    pub fn new(config: Config) -> Result<Self> {
+        Ok(Self {
+            backend_client: config.backend_tls.client()?,
             identity_client: IdentityClient::new(config.identity_registry_url.clone()),
-            policy_client: PolicyClient::new(config.policy_engine_url.clone()),
-            receipt_client: ReceiptClient::new(config.receipt_store_url.clone()),
+            policy_client: PolicyClient::new(
+                config.policy_engine_url.clone(),
+                config.policy_header_allowlist.clone(),
+                config.gateway_mode,
+            ),
+            receipt_client: ReceiptClient::new(
+                config.receipt_store_url.clone(),
+                config.gateway_mode,
+            ),
+            trust_snapshots: TrustSnapshotCache::new(config.trust_snapshot_interval_secs),
             config,
-        }
+        })
     }
 
     /// Extract trace context from headers or generate new one
@@ -62,19 +132,64 @@ impl Interceptor {
         // Always generate a new span ID for this request
         let span_id = Uuid::new_v4();
 
+        let request_id = headers.get(REQUEST_ID_HEADER).cloned().unwrap_or_default();
+
         TraceContext {
             trace_id,
             correlation_id,
             span_id,
+            request_id,
         }
     }
 
+    /// The receipt event type and where it came from: the event type header, else
+    /// `EVENT_TYPE_ROUTES`, else `gateway_request`
+    fn event_type_for(
+        &self,
+        method: &str,
+        path: &str,
+        headers: &HashMap<String, String>,
+    ) -> (EventType, &'static str) {
+        if let Some(value) = headers.get(EVENT_TYPE_HEADER) {
+            match value.trim().parse() {
+                Ok(event_type) => return (event_type, "header"),
+                Err(()) => tracing::warn!("Ignoring unknown {} value: {}", EVENT_TYPE_HEADER, value),
+            }
+        }
+        match select_event_type(&self.config.event_type_routes, method, path) {
+            Some(event_type) => (event_type.clone(), "route"),
+            None => (EventType::GatewayRequest, "default"),
+        }
+    }
+
+    /// Validate the agent. In v2 mode this also captures its trust snapshot, reusing one
+    /// captured within the snapshot interval (validity itself is always checked afresh)
+    async fn validate_identity(
+        &self,
+        agent_id: &str,
+        request_id: &str,
+        deadline: Option<Deadline>,
+    ) -> Result<(ValidateAgentResponse, Option<TrustSnapshot>)> {
+        let identity = &self.identity_client;
+        if self.config.gateway_mode == GatewayMode::V1 {
+            return Ok((identity.validate_agent(agent_id, request_id, deadline).await?, None));
+        }
+        if let Some(snapshot) = self.trust_snapshots.get(agent_id) {
+            return Ok((identity.validate_agent(agent_id, request_id, deadline).await?, Some(snapshot)));
+        }
+
+        let response = identity.validate_agent_v2(agent_id, request_id, deadline).await?;
+        let snapshot = TrustSnapshot::from(&response);
+        self.trust_snapshots.insert(agent_id, snapshot.clone());
+        Ok((response.agent, Some(snapshot)))
+    }
+
     pub async fn intercept(
         &self,
-        mut parts: http::request::Parts,
+        parts: http::request::Parts,
         body_bytes: hyper::body::Bytes,
     ) -> Result<Response<hyper::body::Bytes>> {
-        let start_time = std::time::Instant::now();
+        let mut timing = RequestTiming::start();
 
         // Extract agent ID from headers before moving parts
         let agent_id = parts.headers
@@ -83,10 +198,24 @@ impl Interceptor {
             .map(|s| s.to_string())
             .ok_or_else(|| anyhow::anyhow!("Missing {} header", AGENT_ID_HEADER))?;
 
-        let body_hash = Some(hex::encode(Sha256::digest(&body_bytes)));
+        let on_behalf_of: Vec<String> = parts.headers
+            .get(ON_BEHALF_OF_HEADER)
+            .and_then(|h| h.to_str().ok())
+            .map(|v| {
+                v.split(',')
+                    .map(|p| p.trim().to_string())
+                    .filter(|p| !p.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let content_type = parts.headers
+            .get(hyper::header::CONTENT_TYPE)
+            .and_then(|h| h.to_str().ok());
+        let body_record = self.config.body_capture.record(content_type, &body_bytes);
 
         // Reconstruct request with body for extracting details
-        let req = Request::from_parts(parts, hyper::body::Bytes::from(body_bytes.clone()));
+        let req = Request::from_parts(parts, body_bytes.clone());
 
         // Extract request details
         let method = req.method().to_string();
@@ -98,91 +227,236 @@ impl Interceptor {
 
         // Extract or generate trace context
         let trace_ctx = Self::extract_trace_context(&headers);
+        // Every step from here on, upstream included, shares the caller's time budget
+        let deadline = Deadline::from_headers(&headers, self.config.max_request_timeout_ms);
+        let request = RequestRecord {
+            agent_id: &agent_id,
+            trace_ctx: &trace_ctx,
+            method: &method,
+            path: &path,
+            headers: &headers,
+            body_record: &body_record,
+            on_behalf_of: &[],
+        };
 
         // Step 1: Validate identity
-        let identity_result = match self.identity_client.validate_agent(&agent_id).await {
-            Ok(result) => {
+        let mut trust_snapshot = None;
+        let mut identity_unavailable = false;
+        let phase_start = std::time::Instant::now();
+        let identity_outcome = within(
+            deadline,
+            self.validate_identity(&agent_id, &trace_ctx.request_id, deadline),
+        ).await;
+        timing.identity_ms = Some(phase_start.elapsed().as_millis() as u64);
+        let identity_result = match identity_outcome {
+            Some(Ok((result, snapshot))) => {
+                trust_snapshot = snapshot;
                 if !result.valid || result.revoked {
+                    // A deactivated tenant is reported by its reason code so callers can tell it apart
+                    let reason = match result.reason.as_deref() {
+                        Some(reason) if !result.revoked => reason,
+                        _ => "Agent identity invalid or revoked",
+                    };
                     return self.create_error_response(
                         StatusCode::FORBIDDEN,
-                        "Agent identity invalid or revoked",
-                        &agent_id,
-                        &trace_ctx,
-                        method,
-                        path,
-                        headers,
-                        body_hash,
-                        start_time,
+                        reason,
+                        request,
+                        trust_snapshot.as_ref(),
+                        &timing,
                     ).await;
                 }
                 result
             }
-            Err(e) => {
-                tracing::error!("Identity validation failed: {}", e);
+            Some(Err(e)) if e.downcast_ref::<ValidationRejected>().is_some() => {
                 return self.create_error_response(
                     StatusCode::FORBIDDEN,
-                    &format!("Identity validation failed: {}", e),
-                    &agent_id,
-                    &trace_ctx,
-                    method,
-                    path,
-                    headers,
-                    body_hash,
-                    start_time,
+                    &e.to_string(),
+                    request,
+                    trust_snapshot.as_ref(),
+                    &timing,
+                ).await;
+            }
+            Some(Err(e)) => {
+                tracing::error!("Identity registry unavailable: {}", e);
+                if self.config.identity_failure_mode == IdentityFailureMode::Closed {
+                    return self.create_error_response(
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        IDENTITY_UNAVAILABLE_REASON,
+                        request,
+                        trust_snapshot.as_ref(),
+                        &timing,
+                    ).await;
+                }
+                // Fail open: policy sees the agent as valid, but the receipt does not
+                identity_unavailable = true;
+                ValidateAgentResponse {
+                    valid: true,
+                    agent_id: agent_id.clone(),
+                    developer_id: Uuid::nil(),
+                    enterprise_id: None,
+                    revoked: false,
+                    reason: None,
+                }
+            }
+            None => {
+                tracing::warn!("Request deadline exceeded during identity validation");
+                return self.create_error_response(
+                    StatusCode::GATEWAY_TIMEOUT,
+                    DEADLINE_EXCEEDED_REASON,
+                    request,
+                    trust_snapshot.as_ref(),
+                    &timing,
                 ).await;
             }
         };
 
-        // Step 2: Evaluate policy
-        let policy_result = match self.policy_client.evaluate(
-            &agent_id,
-            identity_result.valid,
-            identity_result.revoked,
-            identity_result.developer_id,
-            identity_result.enterprise_id,
-            &method,
-            &path,
-            &headers,
-            body_hash.clone(),
-        ).await {
-            Ok(result) => {
-                if !result.allowed {
+        // Verify the delegation chain (counted as identity time) so accountability flows to the principal
+        let delegation_chain = if on_behalf_of.is_empty() {
+            Vec::new()
+        } else {
+            let delegation_outcome = within(
+                deadline,
+                self.identity_client
+                    .verify_delegation(&agent_id, &on_behalf_of, &trace_ctx.request_id, deadline),
+            ).await;
+            timing.identity_ms = Some(phase_start.elapsed().as_millis() as u64);
+            match delegation_outcome {
+                Some(Ok(result)) if result.valid => result.chain,
+                Some(Ok(result)) => {
                     return self.create_error_response(
+                        StatusCode::FORBIDDEN,
+                        &format!(
+                            "Delegation invalid: {}",
+                            result.reason.unwrap_or_else(|| "not permitted".to_string())
+                        ),
+                        request,
+                        trust_snapshot.as_ref(),
+                        &timing,
+                    ).await;
+                }
+                Some(Err(e)) => {
+                    // A delegation chain can't be vouched for without the registry, whatever the failure mode
+                    tracing::error!("Delegation verification failed: {}", e);
+                    return self.create_error_response(
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        IDENTITY_UNAVAILABLE_REASON,
+                        request,
+                        trust_snapshot.as_ref(),
+                        &timing,
+                    ).await;
+                }
+                None => {
+                    tracing::warn!("Request deadline exceeded during delegation verification");
+                    return self.create_error_response(
+                        StatusCode::GATEWAY_TIMEOUT,
+                        DEADLINE_EXCEEDED_REASON,
+                        request,
+                        trust_snapshot.as_ref(),
+                        &timing,
+                    ).await;
+                }
+            }
+        };
+
+        let request = RequestRecord { on_behalf_of: &delegation_chain, ..request };
+
+        // Step 2: Evaluate policy, starting with the tenant's attribution requirement (v2 mode only,
+        // since the snapshot is what carries attribution)
+        if trust_snapshot
+            .as_ref()
+            .is_some_and(|s| s.attribution_required && !s.attribution_complete())
+        {
+            return self.create_error_response(
+                StatusCode::FORBIDDEN,
+                ATTRIBUTION_REQUIRED_REASON,
+                request,
+                trust_snapshot.as_ref(),
+                &timing,
+            ).await;
+        }
+
+        let agent_info = AgentInfo {
+            valid: identity_result.valid,
+            revoked: identity_result.revoked,
+            agent_id: agent_id.clone(),
+            developer_id: identity_result.developer_id.to_string(),
+            enterprise_id: identity_result.enterprise_id.map(|id| id.to_string()),
+            on_behalf_of: delegation_chain.clone(),
+        };
+        let policy_request = PolicyRequestInfo {
+            method: method.clone(),
+            path: path.clone(),
+            headers: headers.clone(),
+            body_hash: body_record.hash.clone(),
+        };
+
+        let phase_start = std::time::Instant::now();
+        let policy_outcome = within(deadline, self.policy_client.evaluate(
+            agent_info.clone(),
+            policy_request.clone(),
+            trust_snapshot.as_ref(),
+            &trace_ctx.request_id,
+            deadline,
+        )).await;
+        timing.policy_ms = Some(phase_start.elapsed().as_millis() as u64);
+        let policy_result = match policy_outcome {
+            Some(Ok(result)) => {
+                if !result.allowed {
+                    return self.create_error_response_with_policy(
                         StatusCode::FORBIDDEN,
                         &result.reason,
-                        &agent_id,
-                        &trace_ctx,
-                        method,
-                        path,
-                        headers,
-                        body_hash,
-                        start_time,
+                        request,
+                        trust_snapshot.as_ref(),
+                        &timing,
+                        Some(&result),
                     ).await;
                 }
                 result
             }
-            Err(e) => {
+            Some(Err(e)) => {
                 tracing::error!("Policy evaluation failed: {}", e);
                 // Fail closed - deny on policy engine error
                 return self.create_error_response(
                     StatusCode::INTERNAL_SERVER_ERROR,
                     &format!("Policy evaluation failed: {}", e),
-                    &agent_id,
-                    &trace_ctx,
-                    method,
-                    path,
-                    headers,
-                    body_hash,
-                    start_time,
+                    request,
+                    trust_snapshot.as_ref(),
+                    &timing,
+                ).await;
+            }
+            None => {
+                tracing::warn!("Request deadline exceeded during policy evaluation");
+                return self.create_error_response(
+                    StatusCode::GATEWAY_TIMEOUT,
+                    DEADLINE_EXCEEDED_REASON,
+                    request,
+                    trust_snapshot.as_ref(),
+                    &timing,
                 ).await;
             }
         };
 
-        // Step 3: Forward request to target backend
-        let target_uri = format!("{}{}", self.config.target_backend_url, path);
+        // Step 3: Forward request to the backend routed for this path
+        let backend_url = match select_backend(
+            &self.config.backend_routes,
+            self.config.target_backend_url.as_deref(),
+            &path,
+        ) {
+            Some(url) => url.to_string(),
+            None => {
+                return self.create_error_response(
+                    StatusCode::NOT_FOUND,
+                    "No backend route matches path",
+                    request,
+                    trust_snapshot.as_ref(),
+                    &timing,
+                ).await;
+            }
+        };
+        let backend_path = rewrite_path(&self.config.path_rewrites, &path);
+        let target_uri = format!("{}{}", backend_url, backend_path);
 
-        // Use reqwest for forwarding
-        let client = reqwest::Client::new();
+        let client = &self.backend_client;
         let mut target_req = match method.as_str() {
             "GET" => client.get(&target_uri),
             "POST" => client.post(&target_uri),
@@ -193,13 +467,9 @@ impl Interceptor {
                 return self.create_error_response(
                     StatusCode::METHOD_NOT_ALLOWED,
                     "Unsupported HTTP method",
-                    &agent_id,
-                    &trace_ctx,
-                    method,
-                    path,
-                    headers,
-                    body_hash,
-                    start_time,
+                    request,
+                    trust_snapshot.as_ref(),
+                    &timing,
                 ).await;
             }
         };
@@ -218,26 +488,36 @@ impl Interceptor {
         if let Some(ref corr_id) = trace_ctx.correlation_id {
             target_req = target_req.header(CORRELATION_ID_HEADER, corr_id);
         }
+        target_req = propagate(deadline, target_req);
 
         // Add body if present
         if !body_bytes.is_empty() {
             target_req = target_req.body(body_bytes.clone());
         }
 
-        let response = match target_req.send().await {
-            Ok(resp) => resp,
-            Err(e) => {
+        let phase_start = std::time::Instant::now();
+        let upstream_outcome = within(deadline, target_req.send()).await;
+        timing.upstream_ms = Some(phase_start.elapsed().as_millis() as u64);
+        let response = match upstream_outcome {
+            Some(Ok(resp)) => resp,
+            Some(Err(e)) => {
                 tracing::error!("Failed to forward request: {}", e);
                 return self.create_error_response(
                     StatusCode::BAD_GATEWAY,
                     &format!("Failed to forward request: {}", e),
-                    &agent_id,
-                    &trace_ctx,
-                    method,
-                    path,
-                    headers,
-                    body_hash,
-                    start_time,
+                    request,
+                    trust_snapshot.as_ref(),
+                    &timing,
+                ).await;
+            }
+            None => {
+                tracing::warn!("Request deadline exceeded during upstream request");
+                return self.create_error_response(
+                    StatusCode::GATEWAY_TIMEOUT,
+                    DEADLINE_EXCEEDED_REASON,
+                    request,
+                    trust_snapshot.as_ref(),
+                    &timing,
                 ).await;
             }
         };
@@ -257,8 +537,99 @@ impl Interceptor {
         // Add trace ID to response for client tracking
         hyper_response = hyper_response.header(TRACE_ID_HEADER, trace_ctx.trace_id.to_string());
 
-        let body = response.bytes().await.unwrap_or_default();
-        let hyper_response = hyper_response.body(hyper::body::Bytes::from(body))?;
+        let response_headers = response.headers().clone();
+        let body_outcome = within(deadline, response.bytes()).await;
+        timing.upstream_ms = Some(phase_start.elapsed().as_millis() as u64);
+        let body = match body_outcome {
+            Some(body) => body.unwrap_or_default(),
+            None => {
+                tracing::warn!("Request deadline exceeded during upstream response");
+                return self.create_error_response(
+                    StatusCode::GATEWAY_TIMEOUT,
+                    DEADLINE_EXCEEDED_REASON,
+                    request,
+                    trust_snapshot.as_ref(),
+                    &timing,
+                ).await;
+            }
+        };
+        let usage = extract_usage(&response_headers, &body);
+
+        let mut metadata = if backend_path != path {
+            serde_json::json!({
+                "backend": backend_url,
+                "original_path": path,
+                "rewritten_path": backend_path,
+            })
+        } else {
+            serde_json::json!({ "backend": backend_url })
+        };
+        if identity_unavailable {
+            metadata["identity_status"] = serde_json::json!(IDENTITY_UNAVAILABLE_REASON);
+        }
+        if let Some(ref policy_override) = policy_result.policy_override {
+            metadata["policy_override"] = serde_json::json!(policy_override);
+        }
+        let (event_type, event_type_source) = self.event_type_for(&method, &path, &headers);
+        metadata["event_type_source"] = serde_json::json!(event_type_source);
+        let mut policy_evaluation_ms = policy_result.evaluation_time_ms;
+        let mut response_denial = None;
+
+        // Step 3b: Re-check the response before it reaches the agent (counted as policy time)
+        if self.config.response_policy.enabled {
+            let phase_start = std::time::Instant::now();
+            let response_outcome = within(deadline, self.policy_client.evaluate_response(
+                agent_info,
+                policy_request,
+                ResponseInfo {
+                    status: status.as_u16(),
+                    content_type: response_headers
+                        .get(reqwest::header::CONTENT_TYPE)
+                        .and_then(|v| v.to_str().ok())
+                        .map(str::to_string),
+                    attributes: self.config.response_policy.extract_attributes(&response_headers, &body),
+                },
+                &trace_ctx.request_id,
+                deadline,
+            )).await;
+            let elapsed_ms = phase_start.elapsed().as_millis() as u64;
+            timing.policy_ms = Some(timing.policy_ms.unwrap_or(0) + elapsed_ms);
+
+            let (allowed, reason, evaluation_time_ms, denial_status) = match response_outcome {
+                Some(Ok(result)) => (result.allowed, result.reason, result.evaluation_time_ms, StatusCode::FORBIDDEN),
+                Some(Err(e)) => {
+                    tracing::error!("Response policy evaluation failed: {}", e);
+                    // Fail closed - withhold the response on policy engine error
+                    (
+                        false,
+                        format!("Response policy evaluation failed: {}", e),
+                        elapsed_ms,
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                    )
+                }
+                None => {
+                    tracing::warn!("Request deadline exceeded during response policy evaluation");
+                    (false, DEADLINE_EXCEEDED_REASON.to_string(), elapsed_ms, StatusCode::GATEWAY_TIMEOUT)
+                }
+            };
+            policy_evaluation_ms += evaluation_time_ms;
+            metadata["response_policy"] = serde_json::json!({
+                "allowed": allowed,
+                "reason": reason,
+                "upstream_status": status.as_u16(),
+                "evaluation_time_ms": evaluation_time_ms,
+            });
+            if !allowed {
+                metadata["error_reason"] = serde_json::json!(reason);
+                metadata["status_code"] = serde_json::json!(denial_status.as_u16());
+                response_denial = Some((denial_status, reason));
+            }
+        }
+
+        let hyper_response = match response_denial {
+            Some((status, ref reason)) => Self::denial_response(status, reason, &trace_ctx)?,
+            None => hyper_response.body(body)?,
+        };
 
         // Step 4: Generate receipt (async, non-blocking)
         let receipt = ReceiptRequest {
@@ -267,29 +638,39 @@ impl Interceptor {
             span_id: trace_ctx.span_id,
             parent_span_id: None,
             agent_id: agent_id.to_string(),
-            event_type: EventType::GatewayRequest,
+            on_behalf_of: delegation_chain,
+            event_type,
             event_source: EventSource::default(),
             request: ReceiptRequestInfo {
                 method: method.clone(),
                 path: path.clone(),
                 headers: headers.clone(),
-                body_hash: body_hash.clone(),
+                body_hash: body_record.hash.clone(),
+                content_type: body_record.content_type.clone(),
+                body: body_record.captured.clone(),
             },
             policy_result: PolicyResult {
-                allowed: policy_result.allowed,
-                policy_version: "v1".to_string(),
-                evaluation_time_ms: policy_result.evaluation_time_ms,
+                allowed: policy_result.allowed && response_denial.is_none(),
+                policy_version: self.policy_client.policy_version().to_string(),
+                evaluation_time_ms: policy_evaluation_ms,
+                trust_evaluation: policy_result.trust_evaluation,
+                tenant_policy_applied: policy_result.tenant_policy_applied,
+                warnings: policy_result.warnings,
             },
             identity_result: IdentityResult {
-                valid: identity_result.valid,
+                valid: identity_result.valid && !identity_unavailable,
                 developer_id: identity_result.developer_id,
                 enterprise_id: identity_result.enterprise_id,
+                trust_snapshot,
             },
-            metadata: None,
+            metadata: Some(metadata),
+            occurred_at: timing.occurred_at,
+            latency: timing.latency(),
+            usage,
         };
 
         // Store receipt asynchronously
-        let _ = self.receipt_client.store_receipt(receipt).await;
+        let _ = self.receipt_client.store_receipt(receipt, &trace_ctx.request_id).await;
 
         Ok(hyper_response)
     }
@@ -298,48 +679,88 @@ impl Interceptor {
         &self,
         status: StatusCode,
         reason: &str,
-        agent_id: &str,
-        trace_ctx: &TraceContext,
-        method: String,
-        path: String,
-        headers: HashMap<String, String>,
-        body_hash: Option<String>,
-        start_time: std::time::Instant,
+        request: RequestRecord<'_>,
+        trust_snapshot: Option<&TrustSnapshot>,
+        timing: &RequestTiming,
     ) -> Result<Response<hyper::body::Bytes>> {
+        self.create_error_response_with_policy(status, reason, request, trust_snapshot, timing, None).await
+    }
+
+    /// `create_error_response`, for a request the policy engine denied: the receipt also records the
+    /// override that decided it, if any, and the trust evaluation and warnings
+    async fn create_error_response_with_policy(
+        &self,
+        status: StatusCode,
+        reason: &str,
+        request: RequestRecord<'_>,
+        trust_snapshot: Option<&TrustSnapshot>,
+        timing: &RequestTiming,
+        policy_result: Option<&PolicyResponse>,
+    ) -> Result<Response<hyper::body::Bytes>> {
+        let (event_type, event_type_source) =
+            self.event_type_for(request.method, request.path, request.headers);
+        let trace_ctx = request.trace_ctx;
+        let body_record = request.body_record;
+
+        let mut metadata = serde_json::json!({
+            "error_reason": reason,
+            "status_code": status.as_u16(),
+            "event_type_source": event_type_source,
+        });
+        if let Some(policy_override) = policy_result.and_then(|r| r.policy_override.as_ref()) {
+            metadata["policy_override"] = serde_json::json!(policy_override);
+        }
+
         // Generate receipt for denied request
         let receipt = ReceiptRequest {
             trace_id: trace_ctx.trace_id,
             correlation_id: trace_ctx.correlation_id.clone(),
             span_id: trace_ctx.span_id,
             parent_span_id: None,
-            agent_id: agent_id.to_string(),
-            event_type: EventType::GatewayRequest,
+            agent_id: request.agent_id.to_string(),
+            on_behalf_of: request.on_behalf_of.to_vec(),
+            event_type,
             event_source: EventSource::default(),
             request: ReceiptRequestInfo {
-                method,
-                path,
-                headers,
-                body_hash,
+                method: request.method.to_string(),
+                path: request.path.to_string(),
+                headers: request.headers.clone(),
+                body_hash: body_record.hash.clone(),
+                content_type: body_record.content_type.clone(),
+                body: body_record.captured.clone(),
             },
             policy_result: PolicyResult {
                 allowed: false,
-                policy_version: "v1".to_string(),
-                evaluation_time_ms: start_time.elapsed().as_millis() as u64,
+                policy_version: self.policy_client.policy_version().to_string(),
+                evaluation_time_ms: timing.start_time.elapsed().as_millis() as u64,
+                trust_evaluation: policy_result.and_then(|r| r.trust_evaluation.clone()),
+                tenant_policy_applied: policy_result.and_then(|r| r.tenant_policy_applied.clone()),
+                warnings: policy_result.map(|r| r.warnings.clone()).unwrap_or_default(),
             },
             identity_result: IdentityResult {
                 valid: false,
                 developer_id: Uuid::nil(),
                 enterprise_id: None,
+                trust_snapshot: trust_snapshot.cloned(),
             },
-            metadata: Some(serde_json::json!({
-                "error_reason": reason,
-                "status_code": status.as_u16(),
-            })),
+            metadata: Some(metadata),
+            occurred_at: timing.occurred_at,
+            latency: timing.latency(),
+            usage: None,
         };
 
         // Store receipt asynchronously
-        let _ = self.receipt_client.store_receipt(receipt).await;
+        let _ = self.receipt_client.store_receipt(receipt, &trace_ctx.request_id).await;
 
+        Self::denial_response(status, reason, trace_ctx)
+    }
+
+    /// The JSON body returned to the agent in place of a denied or failed request
+    fn denial_response(
+        status: StatusCode,
+        reason: &str,
+        trace_ctx: &TraceContext,
+    ) -> Result<Response<hyper::body::Bytes>> {
         let body = serde_json::json!({
             "error": "request_denied",
             "reason": reason,
@@ -1,23 +1,32 @@
 use anyhow::Result;
-use tracing::{info, error};
-use tracing_subscriber;
+use tracing::{info, warn, error};
 use axum::{
     body::Body,
-    extract::{Request, State, Path},
+    extract::{Request, State},
     http::{Response, StatusCode},
-    routing::any,
+    middleware,
     Router,
 };
 use std::sync::Arc;
 
+mod backend_tls;
+mod body_capture;
 mod config;
+mod deadline;
+mod fault_injection;
 mod interceptor;
 mod identity_client;
 mod policy_client;
 mod receipt_client;
+mod request_id;
+mod response_policy;
+mod routing;
+mod trust_snapshot;
+mod usage;
 
-use config::Config;
+use config::{Config, GatewayMode};
 use interceptor::Interceptor;
+use request_id::request_id;
 
 async fn handle_all(
     State(interceptor): State<Arc<Interceptor>>,
@@ -30,7 +39,7 @@ async fn handle_all(
     
     // axum and hyper both use http::request::Parts, so we can pass directly
     // Pass body bytes directly to interceptor
-    match interceptor.intercept(parts, hyper::body::Bytes::from(body_bytes)).await {
+    match interceptor.intercept(parts, body_bytes).await {
         Ok(resp) => {
             let (parts, body) = resp.into_parts();
             Ok(Response::from_parts(parts, Body::from(body)))
@@ -52,16 +61,37 @@ async fn main() -> Result<()> {
     
     info!("Starting Proxy Gateway");
     info!("Listening on {}:{}", config.listen_host, config.listen_port);
-    info!("Target backend: {}", config.target_backend_url);
+    if let Some(ref url) = config.target_backend_url {
+        info!("Target backend: {}", url);
+    }
+    for route in &config.backend_routes {
+        info!("Backend route: {} -> {}", route.prefix, route.url);
+    }
+    if let Some(ref path) = config.backend_tls.ca_bundle_path {
+        info!("Backend CA bundle: {}", path);
+    }
+    if let Some(ref path) = config.backend_tls.client_cert_path {
+        info!("Backend client certificate: {}", path);
+    }
+    if config.backend_tls.insecure_skip_verify {
+        warn!("Backend TLS certificate verification is disabled");
+    }
     info!("Identity Registry: {}", config.identity_registry_url);
     info!("Policy Engine: {}", config.policy_engine_url);
     info!("Receipt Store: {}", config.receipt_store_url);
+    if config.gateway_mode == GatewayMode::V2 {
+        info!(
+            "Gateway mode: v2 (trust snapshot interval {}s)",
+            config.trust_snapshot_interval_secs
+        );
+    }
 
-    let interceptor = Arc::new(Interceptor::new(config.clone()));
+    let interceptor = Arc::new(Interceptor::new(config.clone())?);
 
     let app = Router::new()
         .route("/health", axum::routing::get(|| async { "OK" }))
         .fallback(handle_all)
+        .layer(middleware::from_fn(request_id))
         .with_state(interceptor);
 
     let listener = tokio::net::TcpListener::bind(format!("{}:{}", config.listen_host, config.listen_port)).await?;
@@ -1,6 +1,13 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::config::GatewayMode;
+use crate::deadline::{propagate, Deadline};
+use crate::fault_injection::{inject, Dependency};
+use crate::identity_client::{BreakGlassSummary, DelegationLink};
+use crate::request_id::REQUEST_ID_HEADER;
+use crate::trust_snapshot::TrustSnapshot;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PolicyRequest {
@@ -15,69 +22,235 @@ pub struct AgentInfo {
     pub agent_id: String,
     pub developer_id: String,
     pub enterprise_id: Option<String>,
+    /// Verified principals the agent acts for, nearest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_behalf_of: Vec<DelegationLink>,
+}
+
+/// v2 evaluation: the v1 request plus the agent's trust posture, roles and break-glass override, as the
+/// identity registry's v2 validation reported them
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyRequestV2 {
+    pub agent: AgentInfoV2,
+    pub request: RequestInfo,
+    pub context: PolicyContextV2,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentInfoV2 {
+    #[serde(flatten)]
+    pub agent: AgentInfo,
+    pub tenant_id: Option<String>,
+    pub tenant_hierarchy_path: Option<Vec<String>>,
+    pub trust_score: Option<TrustContextV2>,
+    pub attribution: Option<AttributionContextV2>,
+    pub roles: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrustContextV2 {
+    pub composite_score: f64,
+    pub dimensions: BTreeMap<String, f64>,
+    pub threshold: Option<f64>,
+    pub threshold_action: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AttributionContextV2 {
+    pub creator_id: Option<String>,
+    pub publisher_id: Option<String>,
+    /// Left out when unknown, so the policy engine applies its default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audit_visibility_scope: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyContextV2 {
+    pub break_glass: Option<BreakGlassSummary>,
+}
+
+impl PolicyRequestV2 {
+    /// Without a snapshot (identity failed open) the agent is evaluated as unscored and without roles
+    fn new(agent: AgentInfo, request: RequestInfo, snapshot: Option<&TrustSnapshot>) -> Self {
+        Self {
+            agent: AgentInfoV2 {
+                agent,
+                tenant_id: snapshot.and_then(|s| s.tenant_id).map(|id| id.to_string()),
+                tenant_hierarchy_path: snapshot.and_then(|s| s.tenant_hierarchy_path.clone()),
+                trust_score: snapshot.and_then(|s| s.trust_score.as_ref()).map(|score| TrustContextV2 {
+                    composite_score: score.composite_score,
+                    dimensions: score.dimensions.clone(),
+                    threshold: Some(score.threshold_applied).filter(|t| *t > 0.0),
+                    threshold_action: score.trust_action.clone(),
+                }),
+                attribution: snapshot.and_then(|s| s.attribution.as_ref()).map(|attr| AttributionContextV2 {
+                    creator_id: attr.creator_id.clone(),
+                    publisher_id: attr.publisher_id.clone(),
+                    audit_visibility_scope: attr.audit_visibility_scope.clone(),
+                }),
+                roles: snapshot.map(|s| s.roles.clone()).unwrap_or_default(),
+            },
+            request,
+            context: PolicyContextV2 {
+                break_glass: snapshot.and_then(|s| s.break_glass.clone()),
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RequestInfo {
     pub method: String,
     pub path: String,
-    pub headers: std::collections::HashMap<String, String>,
+    pub headers: HashMap<String, String>,
     pub body_hash: Option<String>,
 }
 
+/// Response-phase evaluation: the allowed request and what the backend returned
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponsePolicyRequest {
+    pub agent: AgentInfo,
+    pub request: RequestInfo,
+    pub response: ResponseInfo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseInfo {
+    pub status: u16,
+    pub content_type: Option<String>,
+    /// Values extracted from the response body, keyed by JSON pointer
+    pub attributes: serde_json::Map<String, serde_json::Value>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PolicyResponse {
     pub allowed: bool,
     pub reason: String,
     pub evaluation_time_ms: u64,
+    /// Set when an operator's override for the agent decided the request instead of policy
+    #[serde(default)]
+    pub policy_override: Option<PolicyOverride>,
+    /// v2 only: how the trust check went, passed through to the receipt
+    #[serde(default)]
+    pub trust_evaluation: Option<serde_json::Value>,
+    #[serde(default)]
+    pub tenant_policy_applied: Option<String>,
+    /// v2 only: e.g. `BREAK_GLASS_ACTIVE`, passed through to the receipt
+    #[serde(default)]
+    pub warnings: Vec<serde_json::Value>,
+}
+
+/// A force-allow or force-deny set for one agent in the policy engine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyOverride {
+    /// `allow` or `deny`
+    pub decision: String,
+    pub reason: String,
+    pub created_by: String,
+    pub expires_at: String,
 }
 
 pub struct PolicyClient {
     base_url: String,
     client: reqwest::Client,
+    header_allowlist: Vec<String>,
+    mode: GatewayMode,
 }
 
 impl PolicyClient {
-    pub fn new(base_url: String) -> Self {
+    pub fn new(base_url: String, header_allowlist: Vec<String>, mode: GatewayMode) -> Self {
         Self {
             base_url,
             client: reqwest::Client::new(),
+            header_allowlist,
+            mode,
+        }
+    }
+
+    /// The policy version receipts record: which evaluate endpoint decides requests
+    pub fn policy_version(&self) -> &'static str {
+        match self.mode {
+            GatewayMode::V1 => "v1",
+            GatewayMode::V2 => "v2",
         }
     }
 
+    /// Keep only allowlisted headers so credentials never reach the policy engine or its decision log
+    fn filter_headers(&self, headers: &HashMap<String, String>) -> HashMap<String, String> {
+        headers
+            .iter()
+            .filter(|(k, _)| self.header_allowlist.iter().any(|h| h.eq_ignore_ascii_case(k)))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+    }
+
+    /// Decide whether the agent's request may be forwarded; `request` headers are filtered here.
+    /// In v2 mode this is `/v2/evaluate`, with the agent's trust snapshot, roles and break-glass override
     pub async fn evaluate(
         &self,
-        agent_id: &str,
-        agent_valid: bool,
-        agent_revoked: bool,
-        developer_id: Uuid,
-        enterprise_id: Option<Uuid>,
-        method: &str,
-        path: &str,
-        headers: &std::collections::HashMap<String, String>,
-        body_hash: Option<String>,
+        agent: AgentInfo,
+        request: RequestInfo,
+        trust_snapshot: Option<&TrustSnapshot>,
+        request_id: &str,
+        deadline: Option<Deadline>,
     ) -> Result<PolicyResponse> {
-        let request = PolicyRequest {
-            agent: AgentInfo {
-                valid: agent_valid,
-                revoked: agent_revoked,
-                agent_id: agent_id.to_string(),
-                developer_id: developer_id.to_string(),
-                enterprise_id: enterprise_id.map(|id| id.to_string()),
-            },
+        let request = RequestInfo {
+            headers: self.filter_headers(&request.headers),
+            ..request
+        };
+
+        inject(Dependency::Policy).await?;
+        let builder = match self.mode {
+            GatewayMode::V1 => self
+                .client
+                .post(format!("{}/v1/evaluate", self.base_url))
+                .json(&PolicyRequest { agent, request }),
+            GatewayMode::V2 => self
+                .client
+                .post(format!("{}/v2/evaluate", self.base_url))
+                .json(&PolicyRequestV2::new(agent, request, trust_snapshot)),
+        };
+        let response = propagate(deadline, builder)
+            .header(REQUEST_ID_HEADER, request_id)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Policy evaluation failed: {}", response.status());
+        }
+
+        let result: PolicyResponse = response.json().await?;
+        Ok(result)
+    }
+
+    /// Decide whether an upstream response may be returned to the agent
+    pub async fn evaluate_response(
+        &self,
+        agent: AgentInfo,
+        request: RequestInfo,
+        response: ResponseInfo,
+        request_id: &str,
+        deadline: Option<Deadline>,
+    ) -> Result<PolicyResponse> {
+        let request = ResponsePolicyRequest {
+            agent,
             request: RequestInfo {
-                method: method.to_string(),
-                path: path.to_string(),
-                headers: headers.clone(),
-                body_hash,
+                headers: self.filter_headers(&request.headers),
+                ..request
             },
+            response,
         };
 
-        let url = format!("{}/v1/evaluate", self.base_url);
-        let response = self.client.post(&url).json(&request).send().await?;
+        inject(Dependency::Policy).await?;
+        let url = format!("{}/v1/evaluate/response", self.base_url);
+        let response = propagate(deadline, self.client.post(&url))
+            .header(REQUEST_ID_HEADER, request_id)
+            .json(&request)
+            .send()
+            .await?;
 
         if !response.status().is_success() {
-            anyhow::bail!("Policy evaluation failed: {}", response.status());
+            anyhow::bail!("Response policy evaluation failed: {}", response.status());
         }
 
         let result: PolicyResponse = response.json().await?;
@@ -85,3 +258,95 @@ impl PolicyClient {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trust_snapshot::{AttributionContext, TrustContext};
+
+    fn agent() -> AgentInfo {
+        AgentInfo {
+            valid: true,
+            revoked: false,
+            agent_id: "agent-1".to_string(),
+            developer_id: "dev-1".to_string(),
+            enterprise_id: None,
+            on_behalf_of: Vec::new(),
+        }
+    }
+
+    fn request() -> RequestInfo {
+        RequestInfo {
+            method: "DELETE".to_string(),
+            path: "/accounts/1".to_string(),
+            headers: HashMap::new(),
+            body_hash: None,
+        }
+    }
+
+    #[test]
+    fn filter_headers_matches_the_allowlist_case_insensitively() {
+        let client = PolicyClient::new(
+            "http://policy".to_string(),
+            vec!["content-type".to_string(), "x-pathwell-agent-id".to_string()],
+            GatewayMode::V1,
+        );
+        let headers = HashMap::from([
+            ("Content-Type".to_string(), "application/json".to_string()),
+            ("X-Pathwell-Agent-Id".to_string(), "agent-1".to_string()),
+            ("Authorization".to_string(), "Bearer secret".to_string()),
+            ("cookie".to_string(), "session=secret".to_string()),
+        ]);
+
+        let filtered = client.filter_headers(&headers);
+        assert_eq!(filtered.len(), 2);
+        assert_eq!(filtered["Content-Type"], "application/json");
+        assert_eq!(filtered["X-Pathwell-Agent-Id"], "agent-1");
+        assert!(!filtered.contains_key("Authorization"));
+        assert!(!filtered.contains_key("cookie"));
+    }
+
+    #[test]
+    fn v2_request_carries_roles_trust_and_break_glass() {
+        let snapshot = TrustSnapshot {
+            tenant_id: None,
+            tenant_hierarchy_path: None,
+            trust_score: Some(TrustContext {
+                composite_score: 0.2,
+                dimensions: BTreeMap::from([("behavior".to_string(), 0.1)]),
+                threshold_applied: 0.0,
+                trust_action: Some("deny".to_string()),
+            }),
+            attribution: Some(AttributionContext {
+                creator_id: None,
+                publisher_id: None,
+                audit_visibility_scope: None,
+            }),
+            attribution_required: false,
+            roles: vec!["billing-agents".to_string()],
+            break_glass: Some(BreakGlassSummary {
+                id: "override-1".to_string(),
+                target_type: Some("agent".to_string()),
+                reason: Some("incident".to_string()),
+                expires_at: "2026-10-16T12:00:00+00:00".to_string(),
+            }),
+        };
+
+        let body = serde_json::to_value(PolicyRequestV2::new(agent(), request(), Some(&snapshot))).unwrap();
+        assert_eq!(body["agent"]["agent_id"], "agent-1");
+        assert_eq!(body["agent"]["roles"], serde_json::json!(["billing-agents"]));
+        assert_eq!(body["agent"]["trust_score"]["composite_score"], 0.2);
+        assert_eq!(body["agent"]["trust_score"]["dimensions"]["behavior"], 0.1);
+        assert!(body["agent"]["trust_score"]["threshold"].is_null());
+        assert!(body["agent"]["attribution"].get("audit_visibility_scope").is_none());
+        assert_eq!(body["context"]["break_glass"]["id"], "override-1");
+    }
+
+    #[test]
+    fn v2_request_without_snapshot_is_unscored() {
+        let body = serde_json::to_value(PolicyRequestV2::new(agent(), request(), None)).unwrap();
+        assert!(body["agent"]["trust_score"].is_null());
+        assert_eq!(body["agent"]["roles"], serde_json::json!([]));
+        assert!(body["context"]["break_glass"].is_null());
+    }
+}
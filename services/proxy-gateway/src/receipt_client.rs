@@ -1,7 +1,15 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use tracing::Instrument;
 use uuid::Uuid;
 
+use crate::config::GatewayMode;
+use crate::fault_injection::{inject, Dependency};
+use crate::identity_client::DelegationLink;
+use crate::request_id::REQUEST_ID_HEADER;
+use crate::trust_snapshot::TrustSnapshot;
+
 /// Event types for categorizing receipt events
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -13,6 +21,22 @@ pub enum EventType {
     HumanAction,
 }
 
+impl std::str::FromStr for EventType {
+    type Err = ();
+
+    /// Parse the snake_case name used on the wire (e.g. `human_action`)
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "gateway_request" => Ok(EventType::GatewayRequest),
+            "policy_evaluation" => Ok(EventType::PolicyEvaluation),
+            "identity_validation" => Ok(EventType::IdentityValidation),
+            "external_event" => Ok(EventType::ExternalEvent),
+            "human_action" => Ok(EventType::HumanAction),
+            _ => Err(()),
+        }
+    }
+}
+
 /// Source system information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventSource {
@@ -38,12 +62,45 @@ pub struct ReceiptRequest {
     pub span_id: Uuid,
     pub parent_span_id: Option<Uuid>,
     pub agent_id: String,
+    /// Verified principals the agent acted for, nearest first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub on_behalf_of: Vec<DelegationLink>,
     pub event_type: EventType,
     pub event_source: EventSource,
     pub request: RequestInfo,
     pub policy_result: PolicyResult,
     pub identity_result: IdentityResult,
     pub metadata: Option<serde_json::Value>,
+    /// When the gateway received the request (used as the receipt timestamp)
+    pub occurred_at: DateTime<Utc>,
+    pub latency: LatencyBreakdown,
+    /// Tokens and cost reported by the backend
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+}
+
+/// Token and cost usage of an LLM-backed request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Usage {
+    pub input_tokens: Option<u64>,
+    pub output_tokens: Option<u64>,
+    pub cost: Option<f64>,
+}
+
+impl Usage {
+    /// Whether the backend reported anything at all
+    pub fn is_reported(&self) -> bool {
+        self.input_tokens.is_some() || self.output_tokens.is_some() || self.cost.is_some()
+    }
+}
+
+/// Time spent in each gateway phase; phases not reached are `None`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyBreakdown {
+    pub identity_ms: Option<u64>,
+    pub policy_ms: Option<u64>,
+    pub upstream_ms: Option<u64>,
+    pub total_ms: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -52,6 +109,12 @@ pub struct RequestInfo {
     pub path: String,
     pub headers: std::collections::HashMap<String, String>,
     pub body_hash: Option<String>,
+    /// Request `Content-Type`, sent only with a captured body
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Captured request body (see `BODY_CAPTURE_ENABLED`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -59,6 +122,13 @@ pub struct PolicyResult {
     pub allowed: bool,
     pub policy_version: String,
     pub evaluation_time_ms: u64,
+    /// v2 mode only: the policy engine's trust evaluation and warnings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trust_evaluation: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant_policy_applied: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -66,31 +136,49 @@ pub struct IdentityResult {
     pub valid: bool,
     pub developer_id: Uuid,
     pub enterprise_id: Option<Uuid>,
+    /// v2 mode only: tenant and trust posture at validation time
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub trust_snapshot: Option<TrustSnapshot>,
 }
 
 pub struct ReceiptClient {
     base_url: String,
     client: reqwest::Client,
+    mode: GatewayMode,
 }
 
 impl ReceiptClient {
-    pub fn new(base_url: String) -> Self {
+    pub fn new(base_url: String, mode: GatewayMode) -> Self {
         Self {
             base_url,
             client: reqwest::Client::new(),
+            mode,
         }
     }
 
-    pub async fn store_receipt(&self, receipt: ReceiptRequest) -> Result<()> {
-        let url = format!("{}/v1/receipts", self.base_url);
+    /// Store via `/v1/receipts`, or `/v2/receipts` (which keeps the trust snapshot) in v2 mode
+    pub async fn store_receipt(&self, receipt: ReceiptRequest, request_id: &str) -> Result<()> {
+        let url = match self.mode {
+            GatewayMode::V1 => format!("{}/v1/receipts", self.base_url),
+            GatewayMode::V2 => format!("{}/v2/receipts", self.base_url),
+        };
         // Fire and forget - don't block on receipt storage
         let client = self.client.clone();
         let url_clone = url.clone();
+        let request_id = request_id.to_string();
         tokio::spawn(async move {
-            if let Err(e) = client.post(&url_clone).json(&receipt).send().await {
+            if let Err(e) = inject(Dependency::Receipt).await {
+                tracing::warn!("Failed to store receipt: {}", e);
+                return;
+            }
+            let request = client
+                .post(&url_clone)
+                .header(REQUEST_ID_HEADER, request_id)
+                .json(&receipt);
+            if let Err(e) = request.send().await {
                 tracing::warn!("Failed to store receipt: {}", e);
             }
-        });
+        }.in_current_span());
         Ok(())
     }
 }
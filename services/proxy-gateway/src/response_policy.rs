@@ -0,0 +1,132 @@
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use serde::{Deserialize, Serialize};
+
+/// Responses larger than this aren't parsed for attributes
+const DEFAULT_RESPONSE_POLICY_MAX_BYTES: usize = 1024 * 1024;
+
+/// Whether upstream responses are re-checked against policy before they reach the agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponsePolicyConfig {
+    pub enabled: bool,
+    /// JSON pointers (e.g. `/classification`) extracted from JSON response bodies for the policy
+    pub attributes: Vec<String>,
+    /// Larger bodies are evaluated without attributes
+    pub max_bytes: usize,
+}
+
+impl ResponsePolicyConfig {
+    pub fn from_env() -> Self {
+        Self {
+            enabled: std::env::var("RESPONSE_POLICY_ENABLED")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            attributes: std::env::var("RESPONSE_POLICY_ATTRIBUTES")
+                .unwrap_or_default()
+                .split(',')
+                .map(|p| p.trim().to_string())
+                .filter(|p| p.starts_with('/'))
+                .collect(),
+            max_bytes: std::env::var("RESPONSE_POLICY_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_RESPONSE_POLICY_MAX_BYTES),
+        }
+    }
+
+    /// The configured attributes present in a JSON response body, keyed by pointer
+    pub fn extract_attributes(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> serde_json::Map<String, serde_json::Value> {
+        let is_json = headers
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.contains("json"));
+        if self.attributes.is_empty() || !is_json || body.len() > self.max_bytes {
+            return serde_json::Map::new();
+        }
+
+        let Ok(payload) = serde_json::from_slice::<serde_json::Value>(body) else {
+            return serde_json::Map::new();
+        };
+        self.attributes
+            .iter()
+            .filter_map(|pointer| Some((pointer.clone(), payload.pointer(pointer)?.clone())))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn config(max_bytes: usize) -> ResponsePolicyConfig {
+        ResponsePolicyConfig {
+            enabled: true,
+            attributes: vec!["/classification".to_string(), "/owner/region".to_string(), "/missing".to_string()],
+            max_bytes,
+        }
+    }
+
+    fn headers(content_type: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_str(content_type).unwrap());
+        headers
+    }
+
+    const BODY: &[u8] = br#"{"classification":"secret","owner":{"region":"eu"},"other":1}"#;
+
+    #[test]
+    fn extracts_configured_pointers_present_in_json_bodies() {
+        for content_type in ["application/json", "application/json; charset=utf-8", "application/vnd.api+json"] {
+            let attributes = config(1024).extract_attributes(&headers(content_type), BODY);
+            assert_eq!(attributes.len(), 2, "{}", content_type);
+            assert_eq!(attributes["/classification"], "secret");
+            assert_eq!(attributes["/owner/region"], "eu");
+            assert!(!attributes.contains_key("/missing"));
+        }
+    }
+
+    #[test]
+    fn non_json_bodies_yield_no_attributes() {
+        let config = config(1024);
+        assert!(config.extract_attributes(&headers("text/plain"), BODY).is_empty());
+        assert!(config.extract_attributes(&HeaderMap::new(), BODY).is_empty());
+        assert!(config.extract_attributes(&headers("application/json"), b"not json").is_empty());
+    }
+
+    #[test]
+    fn bodies_over_the_size_limit_yield_no_attributes() {
+        let json = headers("application/json");
+        assert_eq!(config(BODY.len()).extract_attributes(&json, BODY).len(), 2);
+        assert!(config(BODY.len() - 1).extract_attributes(&json, BODY).is_empty());
+    }
+
+    #[test]
+    fn no_configured_attributes_yield_none() {
+        let config = ResponsePolicyConfig { attributes: Vec::new(), ..config(1024) };
+        assert!(config.extract_attributes(&headers("application/json"), BODY).is_empty());
+    }
+
+    #[test]
+    fn from_env_keeps_only_json_pointers() {
+        std::env::set_var("RESPONSE_POLICY_ENABLED", "1");
+        std::env::set_var("RESPONSE_POLICY_ATTRIBUTES", " /classification, region,,/owner/region ");
+        std::env::set_var("RESPONSE_POLICY_MAX_BYTES", "2048");
+        let config = ResponsePolicyConfig::from_env();
+        assert!(config.enabled);
+        assert_eq!(config.attributes, ["/classification", "/owner/region"]);
+        assert_eq!(config.max_bytes, 2048);
+
+        std::env::remove_var("RESPONSE_POLICY_ENABLED");
+        std::env::remove_var("RESPONSE_POLICY_ATTRIBUTES");
+        std::env::set_var("RESPONSE_POLICY_MAX_BYTES", "lots");
+        let config = ResponsePolicyConfig::from_env();
+        assert!(!config.enabled);
+        assert!(config.attributes.is_empty());
+        assert_eq!(config.max_bytes, DEFAULT_RESPONSE_POLICY_MAX_BYTES);
+        std::env::remove_var("RESPONSE_POLICY_MAX_BYTES");
+    }
+}
@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+
+use crate::receipt_client::EventType;
+
+/// Rewrites a gateway-facing path prefix to the backend's prefix (empty `to` strips it)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathRewrite {
+    pub from: String,
+    pub to: String,
+}
+
+/// Parse rules of the form `from=to,from2=to2` (e.g. `/pathwell/api=/api,/legacy=`)
+pub fn parse_path_rewrites(raw: &str) -> Vec<PathRewrite> {
+    raw.split(',')
+        .filter_map(|rule| {
+            let (from, to) = rule.split_once('=')?;
+            let from = from.trim().trim_end_matches('/');
+            if from.is_empty() {
+                return None;
+            }
+            Some(PathRewrite {
+                from: from.to_string(),
+                to: to.trim().trim_end_matches('/').to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Routes requests under a path prefix to a specific backend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendRoute {
+    pub prefix: String,
+    pub url: String,
+}
+
+/// Parse routes of the form `prefix=url,prefix2=url2` (e.g. `/invoices=http://invoice-svc:8000`)
+pub fn parse_backend_routes(raw: &str) -> Vec<BackendRoute> {
+    raw.split(',')
+        .filter_map(|route| {
+            let (prefix, url) = route.split_once('=')?;
+            let prefix = prefix.trim().trim_end_matches('/');
+            let url = url.trim().trim_end_matches('/');
+            if url.is_empty() {
+                return None;
+            }
+            Some(BackendRoute {
+                prefix: prefix.to_string(),
+                url: url.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Pick the backend for `path` by longest-prefix match, falling back to `default`
+pub fn select_backend<'a>(
+    routes: &'a [BackendRoute],
+    default: Option<&'a str>,
+    path: &str,
+) -> Option<&'a str> {
+    routes
+        .iter()
+        .filter(|r| matches_prefix(path, &r.prefix))
+        .max_by_key(|r| r.prefix.len())
+        .map(|r| r.url.as_str())
+        .or(default)
+}
+
+/// Whether `path` is `prefix` itself or lies beneath it on a segment boundary
+fn matches_prefix(path: &str, prefix: &str) -> bool {
+    // An empty prefix (from "/") matches every path
+    path.strip_prefix(prefix)
+        .map(|rest| rest.is_empty() || rest.starts_with('/'))
+        .unwrap_or(false)
+}
+
+/// Apply the longest matching rewrite rule to `path`, returning it unchanged if none match
+pub fn rewrite_path(rules: &[PathRewrite], path: &str) -> String {
+    let rule = rules
+        .iter()
+        .filter(|r| matches_prefix(path, &r.from))
+        .max_by_key(|r| r.from.len());
+
+    match rule {
+        Some(rule) => {
+            let rewritten = format!("{}{}", rule.to, &path[rule.from.len()..]);
+            if rewritten.is_empty() {
+                "/".to_string()
+            } else {
+                rewritten
+            }
+        }
+        None => path.to_string(),
+    }
+}
+
+/// Tags receipts for requests under a path prefix (optionally only for one method) with an event type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventTypeRoute {
+    pub method: Option<String>,
+    pub prefix: String,
+    pub event_type: EventType,
+}
+
+/// Parse rules of the form `[METHOD ]prefix=event_type,...`
+/// (e.g. `DELETE /accounts=human_action,/admin=policy_evaluation`); unknown event types are skipped
+pub fn parse_event_type_routes(raw: &str) -> Vec<EventTypeRoute> {
+    raw.split(',')
+        .filter_map(|rule| {
+            let (target, event_type) = rule.split_once('=')?;
+            let (method, prefix) = match target.trim().split_once(' ') {
+                Some((method, prefix)) => (Some(method.trim().to_uppercase()), prefix),
+                None => (None, target),
+            };
+            Some(EventTypeRoute {
+                method,
+                prefix: prefix.trim().trim_end_matches('/').to_string(),
+                event_type: event_type.trim().parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// The event type of the longest matching prefix; at equal length a method-specific rule wins
+pub fn select_event_type<'a>(
+    routes: &'a [EventTypeRoute],
+    method: &str,
+    path: &str,
+) -> Option<&'a EventType> {
+    routes
+        .iter()
+        .filter(|r| matches_prefix(path, &r.prefix))
+        .filter(|r| r.method.as_deref().is_none_or(|m| m.eq_ignore_ascii_case(method)))
+        .max_by_key(|r| (r.prefix.len(), r.method.is_some()))
+        .map(|r| &r.event_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrite_strips_a_prefix() {
+        let rules = parse_path_rewrites("/legacy=");
+        assert_eq!(rewrite_path(&rules, "/legacy/users/1"), "/users/1");
+        assert_eq!(rewrite_path(&rules, "/legacy"), "/");
+        assert_eq!(rewrite_path(&rules, "/legacy/"), "/");
+    }
+
+    #[test]
+    fn rewrite_replaces_a_prefix_and_prefers_the_longest() {
+        let rules = parse_path_rewrites("/pathwell=/svc,/pathwell/api=/api");
+        assert_eq!(rewrite_path(&rules, "/pathwell/api/orders"), "/api/orders");
+        assert_eq!(rewrite_path(&rules, "/pathwell/status"), "/svc/status");
+        assert_eq!(rewrite_path(&rules, "/pathwell"), "/svc");
+    }
+
+    #[test]
+    fn rewrite_only_matches_on_segment_boundaries() {
+        let rules = parse_path_rewrites("/api=/v2");
+        assert_eq!(rewrite_path(&rules, "/apiary"), "/apiary");
+        assert_eq!(rewrite_path(&rules, "/other/api"), "/other/api");
+    }
+
+    #[test]
+    fn rewrite_rules_ignore_trailing_slashes() {
+        let rules = parse_path_rewrites("/api/=/v2/");
+        assert_eq!(rewrite_path(&rules, "/api/orders"), "/v2/orders");
+        assert_eq!(rewrite_path(&rules, "/api/"), "/v2/");
+    }
+
+    #[test]
+    fn root_rewrite_rules_are_dropped() {
+        assert!(parse_path_rewrites("/=/api").is_empty());
+        assert_eq!(rewrite_path(&[], "/"), "/");
+    }
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::identity_client::{BreakGlassSummary, ValidateAgentResponseV2};
+
+/// The agent's tenant and trust posture as known when a request was validated; sent with the
+/// receipt's identity result in v2 mode so the receipt store records it as `trust_snapshot`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustSnapshot {
+    pub tenant_id: Option<Uuid>,
+    pub tenant_hierarchy_path: Option<Vec<String>>,
+    pub trust_score: Option<TrustContext>,
+    pub attribution: Option<AttributionContext>,
+    /// Enforced by the gateway, not recorded in the receipt
+    #[serde(skip)]
+    pub attribution_required: bool,
+    /// Passed to v2 policy evaluation, not recorded in the receipt
+    #[serde(skip)]
+    pub roles: Vec<String>,
+    #[serde(skip)]
+    pub break_glass: Option<BreakGlassSummary>,
+}
+
+impl TrustSnapshot {
+    /// Whether the agent has both a creator and a publisher
+    pub fn attribution_complete(&self) -> bool {
+        self.attribution
+            .as_ref()
+            .is_some_and(|attr| attr.creator_id.is_some() && attr.publisher_id.is_some())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustContext {
+    pub composite_score: f64,
+    pub dimensions: BTreeMap<String, f64>,
+    /// The agent's minimum threshold (0.0 when it has none)
+    pub threshold_applied: f64,
+    pub trust_action: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttributionContext {
+    pub creator_id: Option<String>,
+    pub publisher_id: Option<String>,
+    pub audit_visibility_scope: Option<String>,
+}
+
+impl From<&ValidateAgentResponseV2> for TrustSnapshot {
+    fn from(response: &ValidateAgentResponseV2) -> Self {
+        Self {
+            tenant_id: response.tenant_id,
+            tenant_hierarchy_path: response.tenant_hierarchy_path.clone(),
+            trust_score: response.trust_score.as_ref().map(|score| TrustContext {
+                composite_score: score.composite_score,
+                dimensions: score.dimensions.clone(),
+                threshold_applied: score.minimum_threshold.unwrap_or(0.0),
+                trust_action: score.threshold_action.clone(),
+            }),
+            attribution: response.attribution.as_ref().map(|attr| AttributionContext {
+                creator_id: attr.creator_id.map(|id| id.to_string()),
+                publisher_id: attr.publisher_id.map(|id| id.to_string()),
+                audit_visibility_scope: Some(attr.audit_visibility_scope.clone()),
+            }),
+            attribution_required: response.attribution_required,
+            roles: response.roles.clone(),
+            break_glass: response.break_glass.clone(),
+        }
+    }
+}
+
+/// Per-agent snapshots reused until they are older than the snapshot interval.
+/// With a zero interval every request fetches a fresh snapshot
+pub struct TrustSnapshotCache {
+    interval: Duration,
+    entries: Mutex<HashMap<String, (Instant, TrustSnapshot)>>,
+}
+
+impl TrustSnapshotCache {
+    pub fn new(interval_secs: u64) -> Self {
+        Self {
+            interval: Duration::from_secs(interval_secs),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The agent's snapshot, if one was captured within the interval
+    pub fn get(&self, agent_id: &str) -> Option<TrustSnapshot> {
+        if self.interval.is_zero() {
+            return None;
+        }
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(agent_id)
+            .filter(|(captured_at, _)| captured_at.elapsed() < self.interval)
+            .map(|(_, snapshot)| snapshot.clone())
+    }
+
+    pub fn insert(&self, agent_id: &str, snapshot: TrustSnapshot) {
+        if self.interval.is_zero() {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert(agent_id.to_string(), (Instant::now(), snapshot));
+    }
+}
@@ -0,0 +1,46 @@
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+
+use crate::receipt_client::Usage;
+
+const INPUT_TOKENS_HEADER: &str = "x-pathwell-usage-input-tokens";
+const OUTPUT_TOKENS_HEADER: &str = "x-pathwell-usage-output-tokens";
+const COST_HEADER: &str = "x-pathwell-usage-cost";
+
+/// Responses larger than this aren't parsed for a `usage` object
+const MAX_USAGE_BODY_BYTES: usize = 1024 * 1024;
+
+/// Usage reported in a backend response: `X-Pathwell-Usage-*` headers, otherwise a
+/// top-level `usage` object in a JSON response (OpenAI and Anthropic field names are accepted)
+pub fn extract_usage(headers: &HeaderMap, body: &[u8]) -> Option<Usage> {
+    from_headers(headers).or_else(|| from_body(headers, body))
+}
+
+fn from_headers(headers: &HeaderMap) -> Option<Usage> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim);
+    let usage = Usage {
+        input_tokens: header(INPUT_TOKENS_HEADER).and_then(|v| v.parse().ok()),
+        output_tokens: header(OUTPUT_TOKENS_HEADER).and_then(|v| v.parse().ok()),
+        cost: header(COST_HEADER).and_then(|v| v.parse().ok()),
+    };
+    usage.is_reported().then_some(usage)
+}
+
+fn from_body(headers: &HeaderMap, body: &[u8]) -> Option<Usage> {
+    let is_json = headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.contains("json"));
+    if !is_json || body.len() > MAX_USAGE_BODY_BYTES {
+        return None;
+    }
+
+    let payload: serde_json::Value = serde_json::from_slice(body).ok()?;
+    let usage = payload.get("usage")?;
+    let field = |names: &[&str]| names.iter().find_map(|name| usage.get(*name)).cloned();
+    let usage = Usage {
+        input_tokens: field(&["input_tokens", "prompt_tokens"]).and_then(|v| v.as_u64()),
+        output_tokens: field(&["output_tokens", "completion_tokens"]).and_then(|v| v.as_u64()),
+        cost: field(&["cost"]).and_then(|v| v.as_f64()),
+    };
+    usage.is_reported().then_some(usage)
+}
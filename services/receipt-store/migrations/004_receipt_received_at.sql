@@ -0,0 +1,12 @@
+-- Migration 004: Separate event time from server receive time
+-- `timestamp` now holds the client-supplied occurred_at when provided;
+-- `received_at` always records when the receipt store accepted the event.
+
+ALTER TABLE receipt_events
+ADD COLUMN IF NOT EXISTS received_at TIMESTAMPTZ;
+
+UPDATE receipt_events SET received_at = timestamp WHERE received_at IS NULL;
+
+ALTER TABLE receipt_events
+ALTER COLUMN received_at SET DEFAULT NOW(),
+ALTER COLUMN received_at SET NOT NULL;
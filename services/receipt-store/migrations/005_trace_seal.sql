@@ -0,0 +1,11 @@
+-- Migration 005: Trace sealing
+-- A sealed trace is marked 'completed' and records the Merkle root over its
+-- receipt hashes; further receipts for the trace are rejected.
+
+ALTER TABLE traces
+ADD COLUMN IF NOT EXISTS sealed_at TIMESTAMPTZ,
+ADD COLUMN IF NOT EXISTS seal_root VARCHAR(64),
+ADD COLUMN IF NOT EXISTS seal_receipt_count INTEGER,
+ADD COLUMN IF NOT EXISTS seal_receipt_id UUID;
+
+CREATE INDEX IF NOT EXISTS idx_traces_sealed ON traces(sealed_at) WHERE sealed_at IS NOT NULL;
@@ -0,0 +1,54 @@
+-- Migration 006: Agent activity tracking
+-- Maintains last-seen and lifetime request/denial counts per agent, for
+-- decommissioning dormant agents and feeding trust decay.
+
+CREATE TABLE IF NOT EXISTS agent_activity (
+    agent_id VARCHAR(255) PRIMARY KEY,
+    first_seen_at TIMESTAMPTZ NOT NULL,
+    last_seen_at TIMESTAMPTZ NOT NULL,
+    request_count BIGINT NOT NULL DEFAULT 0,
+    denial_count BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_agent_activity_last_seen ON agent_activity(last_seen_at);
+
+-- Window queries scan receipt_events by agent and time
+CREATE INDEX IF NOT EXISTS idx_receipt_events_agent_timestamp ON receipt_events(agent_id, timestamp DESC);
+
+-- Function to update agent activity on new receipt events
+CREATE OR REPLACE FUNCTION update_agent_activity_on_event()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.agent_id IS NULL OR NEW.event_type = 'trace_seal' THEN
+        RETURN NEW;
+    END IF;
+
+    INSERT INTO agent_activity (agent_id, first_seen_at, last_seen_at, request_count, denial_count)
+    VALUES (
+        NEW.agent_id, NEW.timestamp, NEW.timestamp, 1,
+        CASE WHEN NEW.policy_allowed = false THEN 1 ELSE 0 END
+    )
+    ON CONFLICT (agent_id) DO UPDATE SET
+        first_seen_at = LEAST(agent_activity.first_seen_at, EXCLUDED.first_seen_at),
+        last_seen_at = GREATEST(agent_activity.last_seen_at, EXCLUDED.last_seen_at),
+        request_count = agent_activity.request_count + 1,
+        denial_count = agent_activity.denial_count + EXCLUDED.denial_count,
+        updated_at = NOW();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_update_agent_activity
+    AFTER INSERT ON receipt_events
+    FOR EACH ROW
+    EXECUTE FUNCTION update_agent_activity_on_event();
+
+-- Backfill from existing receipts
+INSERT INTO agent_activity (agent_id, first_seen_at, last_seen_at, request_count, denial_count)
+SELECT agent_id, MIN(timestamp), MAX(timestamp), COUNT(*),
+       COUNT(*) FILTER (WHERE policy_allowed = false)
+FROM receipt_events
+WHERE agent_id IS NOT NULL AND event_type <> 'trace_seal'
+GROUP BY agent_id
+ON CONFLICT (agent_id) DO NOTHING;
@@ -0,0 +1,11 @@
+-- Migration 007: Trust violation escalation
+-- Tracks consecutive trust threshold violations per agent. The high-severity risk event opened once an
+-- agent crosses the escalation threshold goes in the Identity Registry's trust_risk_events table.
+
+CREATE TABLE IF NOT EXISTS agent_trust_violations (
+    agent_id VARCHAR(255) PRIMARY KEY,
+    consecutive_violations INTEGER NOT NULL DEFAULT 0,
+    first_violation_at TIMESTAMPTZ,
+    last_violation_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,9 @@
+-- Migration 008: Per-phase gateway latency
+-- Records how long identity validation, policy evaluation and the upstream
+-- call took for each gateway request.
+
+ALTER TABLE receipt_events
+ADD COLUMN IF NOT EXISTS identity_ms INTEGER,
+ADD COLUMN IF NOT EXISTS policy_ms INTEGER,
+ADD COLUMN IF NOT EXISTS upstream_ms INTEGER,
+ADD COLUMN IF NOT EXISTS total_ms INTEGER;
@@ -0,0 +1,6 @@
+-- Migration 009: S3 archive location
+-- Records the object key each receipt was archived under so clients can be
+-- handed a presigned download URL.
+
+ALTER TABLE receipt_events
+ADD COLUMN IF NOT EXISTS s3_key TEXT;
@@ -0,0 +1,6 @@
+-- Migration 010: Per-tenant archive buckets
+-- Tenants with data-residency requirements archive to their own bucket, so the
+-- bucket is recorded alongside the object key.
+
+ALTER TABLE receipt_events
+ADD COLUMN IF NOT EXISTS s3_bucket TEXT;
@@ -0,0 +1,10 @@
+-- Migration 011: Delegation chains
+-- Records the principals an agent acted on behalf of (nearest first) so
+-- accountability can be traced back to the supervising agent or human.
+
+ALTER TABLE receipt_events
+ADD COLUMN IF NOT EXISTS on_behalf_of JSONB;
+
+CREATE INDEX IF NOT EXISTS idx_receipt_events_on_behalf_of
+ON receipt_events USING GIN(on_behalf_of)
+WHERE on_behalf_of IS NOT NULL;
@@ -0,0 +1,6 @@
+-- Migration 012: Body hash lookup
+-- Supports finding the traces that processed a known payload by its SHA-256 hash.
+
+CREATE INDEX IF NOT EXISTS idx_receipt_events_body_hash
+ON receipt_events(request_body_hash)
+WHERE request_body_hash IS NOT NULL;
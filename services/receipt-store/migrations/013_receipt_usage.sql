@@ -0,0 +1,45 @@
+-- Migration 013: Token and cost accounting
+-- Records the LLM usage reported for each request and keeps running totals
+-- per trace; tenant totals are aggregated from receipts over a window.
+
+ALTER TABLE receipt_events
+ADD COLUMN IF NOT EXISTS usage_input_tokens BIGINT,
+ADD COLUMN IF NOT EXISTS usage_output_tokens BIGINT,
+ADD COLUMN IF NOT EXISTS usage_cost NUMERIC(20, 8);
+
+ALTER TABLE traces
+ADD COLUMN IF NOT EXISTS total_input_tokens BIGINT NOT NULL DEFAULT 0,
+ADD COLUMN IF NOT EXISTS total_output_tokens BIGINT NOT NULL DEFAULT 0,
+ADD COLUMN IF NOT EXISTS total_cost NUMERIC(20, 8) NOT NULL DEFAULT 0;
+
+-- Tenant cost queries scan only receipts that reported usage
+CREATE INDEX IF NOT EXISTS idx_receipt_events_usage
+ON receipt_events(trace_id, timestamp)
+WHERE usage_input_tokens IS NOT NULL
+   OR usage_output_tokens IS NOT NULL
+   OR usage_cost IS NOT NULL;
+
+-- Function to add a receipt's usage to its trace totals
+CREATE OR REPLACE FUNCTION update_trace_usage_on_event()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE traces
+    SET
+        total_input_tokens = total_input_tokens + COALESCE(NEW.usage_input_tokens, 0),
+        total_output_tokens = total_output_tokens + COALESCE(NEW.usage_output_tokens, 0),
+        total_cost = total_cost + COALESCE(NEW.usage_cost, 0),
+        updated_at = NOW()
+    WHERE trace_id = NEW.trace_id;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_update_trace_usage ON receipt_events;
+
+CREATE TRIGGER trigger_update_trace_usage
+    AFTER INSERT ON receipt_events
+    FOR EACH ROW
+    WHEN (NEW.usage_input_tokens IS NOT NULL
+          OR NEW.usage_output_tokens IS NOT NULL
+          OR NEW.usage_cost IS NOT NULL)
+    EXECUTE FUNCTION update_trace_usage_on_event();
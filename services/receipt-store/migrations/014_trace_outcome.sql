@@ -0,0 +1,88 @@
+-- Migration 014: Trace outcome rollup
+-- Counts each trace's allowed, denied and errored receipts as they arrive and keeps an overall
+-- outcome derived from them, so traces can be categorized and filtered without reading events.
+-- A receipt is errored when the gateway failed it with a 5xx (identity or policy unavailable,
+-- upstream failure, deadline exceeded); seal receipts don't count.
+
+ALTER TABLE traces
+ADD COLUMN IF NOT EXISTS allowed_receipt_count INTEGER NOT NULL DEFAULT 0,
+ADD COLUMN IF NOT EXISTS denied_receipt_count INTEGER NOT NULL DEFAULT 0,
+ADD COLUMN IF NOT EXISTS errored_receipt_count INTEGER NOT NULL DEFAULT 0,
+-- fully_allowed, partially_denied, fully_denied or errored; NULL until the trace has a receipt
+ADD COLUMN IF NOT EXISTS outcome VARCHAR(20);
+
+CREATE INDEX IF NOT EXISTS idx_traces_outcome ON traces(outcome, last_event_at DESC);
+
+-- Whether a receipt records a gateway failure rather than a decision
+CREATE OR REPLACE FUNCTION receipt_errored(metadata JSONB)
+RETURNS BOOLEAN AS $$
+    SELECT CASE
+        WHEN jsonb_typeof(metadata -> 'status_code') = 'number'
+            THEN (metadata ->> 'status_code')::numeric >= 500
+        ELSE false
+    END;
+$$ LANGUAGE sql IMMUTABLE;
+
+-- Overall outcome of a trace from its receipt counts
+CREATE OR REPLACE FUNCTION trace_outcome(allowed INTEGER, denied INTEGER, errored INTEGER)
+RETURNS VARCHAR AS $$
+    SELECT CASE
+        WHEN errored > 0 THEN 'errored'
+        WHEN allowed + denied = 0 THEN NULL
+        WHEN denied = 0 THEN 'fully_allowed'
+        WHEN allowed = 0 THEN 'fully_denied'
+        ELSE 'partially_denied'
+    END;
+$$ LANGUAGE sql IMMUTABLE;
+
+-- Function to add a receipt to its trace's outcome counts
+CREATE OR REPLACE FUNCTION update_trace_outcome_on_event()
+RETURNS TRIGGER AS $$
+DECLARE
+    errored INTEGER := CASE WHEN receipt_errored(NEW.metadata) THEN 1 ELSE 0 END;
+    denied INTEGER := CASE WHEN errored = 0 AND NEW.policy_allowed = false THEN 1 ELSE 0 END;
+    allowed INTEGER := 1 - errored - denied;
+BEGIN
+    UPDATE traces
+    SET
+        allowed_receipt_count = allowed_receipt_count + allowed,
+        denied_receipt_count = denied_receipt_count + denied,
+        errored_receipt_count = errored_receipt_count + errored,
+        outcome = trace_outcome(
+            allowed_receipt_count + allowed,
+            denied_receipt_count + denied,
+            errored_receipt_count + errored
+        ),
+        updated_at = NOW()
+    WHERE trace_id = NEW.trace_id;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_update_trace_outcome ON receipt_events;
+
+CREATE TRIGGER trigger_update_trace_outcome
+    AFTER INSERT ON receipt_events
+    FOR EACH ROW
+    WHEN (NEW.event_type <> 'trace_seal')
+    EXECUTE FUNCTION update_trace_outcome_on_event();
+
+-- Backfill traces recorded before the rollup existed
+WITH counts AS (
+    SELECT
+        trace_id,
+        COUNT(*) FILTER (WHERE NOT receipt_errored(metadata) AND policy_allowed IS DISTINCT FROM false) AS allowed,
+        COUNT(*) FILTER (WHERE NOT receipt_errored(metadata) AND policy_allowed = false) AS denied,
+        COUNT(*) FILTER (WHERE receipt_errored(metadata)) AS errored
+    FROM receipt_events
+    WHERE event_type <> 'trace_seal'
+    GROUP BY trace_id
+)
+UPDATE traces t
+SET
+    allowed_receipt_count = c.allowed,
+    denied_receipt_count = c.denied,
+    errored_receipt_count = c.errored,
+    outcome = trace_outcome(c.allowed::int, c.denied::int, c.errored::int)
+FROM counts c
+WHERE t.trace_id = c.trace_id;
@@ -0,0 +1,43 @@
+-- Migration 015: Out-of-row receipt metadata
+-- Metadata larger than RECEIPT_METADATA_OVERFLOW_BYTES is stored here instead of in the
+-- receipt_events row (both the metadata column and full_receipt), keeping the hot rows small.
+-- Reads that return metadata join it back in.
+
+CREATE TABLE IF NOT EXISTS receipt_metadata (
+    receipt_id UUID PRIMARY KEY REFERENCES receipt_events(receipt_id) ON DELETE CASCADE,
+    metadata JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Serialized size of metadata moved out of row; NULL when it is stored inline
+ALTER TABLE receipt_events
+ADD COLUMN IF NOT EXISTS metadata_size_bytes INTEGER;
+
+-- The outcome rollup reads overflowed metadata from its own table. The overflow row is written in
+-- the same statement as the receipt, so it is visible by the time this AFTER trigger runs
+CREATE OR REPLACE FUNCTION update_trace_outcome_on_event()
+RETURNS TRIGGER AS $$
+DECLARE
+    event_metadata JSONB := CASE
+        WHEN NEW.metadata_size_bytes IS NULL THEN NEW.metadata
+        ELSE (SELECT m.metadata FROM receipt_metadata m WHERE m.receipt_id = NEW.receipt_id)
+    END;
+    errored INTEGER := CASE WHEN receipt_errored(event_metadata) THEN 1 ELSE 0 END;
+    denied INTEGER := CASE WHEN errored = 0 AND NEW.policy_allowed = false THEN 1 ELSE 0 END;
+    allowed INTEGER := 1 - errored - denied;
+BEGIN
+    UPDATE traces
+    SET
+        allowed_receipt_count = allowed_receipt_count + allowed,
+        denied_receipt_count = denied_receipt_count + denied,
+        errored_receipt_count = errored_receipt_count + errored,
+        outcome = trace_outcome(
+            allowed_receipt_count + allowed,
+            denied_receipt_count + denied,
+            errored_receipt_count + errored
+        ),
+        updated_at = NOW()
+    WHERE trace_id = NEW.trace_id;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,41 @@
+-- Migration 016: Completing inactive traces
+-- The inactivity sweeper marks traces 'completed' once no event has arrived for a while,
+-- recording when. A later event reopens an unsealed trace; sealed traces reject events.
+
+ALTER TABLE traces
+ADD COLUMN IF NOT EXISTS completed_at TIMESTAMPTZ;
+
+-- The sweeper scans active traces by age
+CREATE INDEX IF NOT EXISTS idx_traces_active_last_event
+ON traces(last_event_at) WHERE status = 'active';
+
+CREATE OR REPLACE FUNCTION update_trace_on_event()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE traces
+    SET
+        event_count = event_count + 1,
+        last_event_at = NEW.timestamp,
+        policy_deny_count = policy_deny_count + CASE WHEN NEW.policy_allowed = false THEN 1 ELSE 0 END,
+        status = CASE WHEN sealed_at IS NULL THEN 'active' ELSE status END,
+        completed_at = CASE WHEN sealed_at IS NULL THEN NULL ELSE completed_at END,
+        updated_at = NOW()
+    WHERE trace_id = NEW.trace_id;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION update_trace_on_external_event()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE traces
+    SET
+        event_count = event_count + 1,
+        last_event_at = NEW.timestamp,
+        status = CASE WHEN sealed_at IS NULL THEN 'active' ELSE status END,
+        completed_at = CASE WHEN sealed_at IS NULL THEN NULL ELSE completed_at END,
+        updated_at = NOW()
+    WHERE trace_id = NEW.trace_id;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,8 @@
+-- Migration 017: Trace list keyset pagination
+-- Trace listings page by (last_event_at, trace_id), newest first; this index serves both the
+-- cursor predicate and the sort, and supersedes the single-column one.
+
+CREATE INDEX IF NOT EXISTS idx_traces_last_event_keyset
+ON traces(last_event_at DESC, trace_id DESC);
+
+DROP INDEX IF EXISTS idx_traces_last_event_at;
@@ -0,0 +1,19 @@
+-- Migration 018: Closed trace statuses
+-- A trace is 'active' until it is closed (by POST /v1/traces/:id/close, the inactivity sweeper or
+-- sealing). It then records how it finished: 'error' if any receipt was a gateway failure, 'denied'
+-- if any request was denied, otherwise 'completed'. A later event reopens an unsealed trace.
+
+-- Status of a trace as it is closed, from its counts
+CREATE OR REPLACE FUNCTION closed_trace_status(policy_deny_count INTEGER, errored_receipt_count INTEGER)
+RETURNS VARCHAR AS $$
+    SELECT CASE
+        WHEN errored_receipt_count > 0 THEN 'error'
+        WHEN policy_deny_count > 0 THEN 'denied'
+        ELSE 'completed'
+    END;
+$$ LANGUAGE sql IMMUTABLE;
+
+-- Traces already closed before this migration were all marked 'completed'
+UPDATE traces
+SET status = closed_trace_status(policy_deny_count, errored_receipt_count)
+WHERE status = 'completed';
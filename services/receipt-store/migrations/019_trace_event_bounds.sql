@@ -0,0 +1,55 @@
+-- Migration 019: Trace time bounds under backdated events
+-- Receipts may carry a client-supplied occurred_at up to RECEIPT_MAX_BACKDATE_SECS in the past, and
+-- external events their source system's timestamp, so events don't arrive in time order. A late event
+-- must not move last_event_at backwards (making an active trace look stale to the sweeper) and an
+-- early one extends started_at. Event and denial counts are unchanged: they are kept by these
+-- triggers in the inserting transaction.
+
+CREATE OR REPLACE FUNCTION update_trace_on_event()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE traces
+    SET
+        event_count = event_count + 1,
+        started_at = LEAST(started_at, NEW.timestamp),
+        last_event_at = GREATEST(last_event_at, NEW.timestamp),
+        policy_deny_count = policy_deny_count + CASE WHEN NEW.policy_allowed = false THEN 1 ELSE 0 END,
+        status = CASE WHEN sealed_at IS NULL THEN 'active' ELSE status END,
+        completed_at = CASE WHEN sealed_at IS NULL THEN NULL ELSE completed_at END,
+        updated_at = NOW()
+    WHERE trace_id = NEW.trace_id;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION update_trace_on_external_event()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE traces
+    SET
+        event_count = event_count + 1,
+        started_at = LEAST(started_at, NEW.timestamp),
+        last_event_at = GREATEST(last_event_at, NEW.timestamp),
+        status = CASE WHEN sealed_at IS NULL THEN 'active' ELSE status END,
+        completed_at = CASE WHEN sealed_at IS NULL THEN NULL ELSE completed_at END,
+        updated_at = NOW()
+    WHERE trace_id = NEW.trace_id;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Repair traces whose bounds an out-of-order event already moved
+UPDATE traces t
+SET started_at = LEAST(t.started_at, b.first_at),
+    last_event_at = GREATEST(t.last_event_at, b.last_at)
+FROM (
+    SELECT trace_id, MIN(timestamp) AS first_at, MAX(timestamp) AS last_at
+    FROM (
+        SELECT trace_id, timestamp FROM receipt_events
+        UNION ALL
+        SELECT trace_id, timestamp FROM external_events
+    ) events
+    GROUP BY trace_id
+) b
+WHERE b.trace_id = t.trace_id
+  AND (b.first_at < t.started_at OR b.last_at > t.last_event_at);
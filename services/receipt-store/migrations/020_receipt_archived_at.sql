@@ -0,0 +1,17 @@
+-- Migration 020: Receipt archive confirmation
+-- The retention purge only deletes a trace once every one of its receipts is known to be in S3.
+-- `archived_at` records when the archive write was confirmed; receipts archived before this
+-- migration already have their key recorded, so they are taken as archived at creation.
+
+ALTER TABLE receipt_events
+ADD COLUMN IF NOT EXISTS archived_at TIMESTAMPTZ;
+
+UPDATE receipt_events
+SET archived_at = created_at
+WHERE archived_at IS NULL
+  AND s3_key IS NOT NULL;
+
+-- Purge eligibility looks for a trace's unarchived receipts
+CREATE INDEX IF NOT EXISTS idx_receipt_events_unarchived
+ON receipt_events(trace_id)
+WHERE archived_at IS NULL;
@@ -0,0 +1,7 @@
+-- Migration 021: Receipt signatures
+-- With a signing key configured, each receipt's hash is signed with Ed25519. The public key is kept
+-- beside the signature so receipts signed before a key rotation still verify.
+
+ALTER TABLE receipt_events
+ADD COLUMN IF NOT EXISTS signature TEXT,
+ADD COLUMN IF NOT EXISTS signature_public_key TEXT;
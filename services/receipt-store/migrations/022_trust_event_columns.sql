@@ -0,0 +1,35 @@
+-- Migration 022: Trust event columns
+-- The trust_events table from migration 003 never matched the events v2 receipts record, so storing a
+-- v2 receipt that carried a trust evaluation failed and nothing was ever written to it. Recreate it with
+-- the columns the receipt store writes and reads. Refuse to run rather than drop rows something else wrote.
+
+DO $$
+BEGIN
+    IF EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'trust_events' AND column_name = 'entity_type'
+    ) THEN
+        IF EXISTS (SELECT 1 FROM trust_events) THEN
+            RAISE EXCEPTION 'trust_events holds rows in the migration 003 layout; migrate them by hand';
+        END IF;
+        DROP TABLE trust_events;
+    END IF;
+END $$;
+
+CREATE TABLE IF NOT EXISTS trust_events (
+    event_id UUID PRIMARY KEY,
+    trace_id UUID NOT NULL REFERENCES traces(trace_id),
+    agent_id VARCHAR(255) NOT NULL,
+    event_type VARCHAR(50) NOT NULL,
+    timestamp TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    previous_score DECIMAL(5,4),
+    new_score DECIMAL(5,4) NOT NULL,
+    threshold DECIMAL(5,4) NOT NULL,
+    passed BOOLEAN NOT NULL,
+    action_taken VARCHAR(50),
+    details JSONB NOT NULL DEFAULT '{}'
+);
+
+CREATE INDEX IF NOT EXISTS idx_trust_events_trace ON trust_events(trace_id, timestamp);
+CREATE INDEX IF NOT EXISTS idx_trust_events_agent ON trust_events(agent_id, timestamp DESC);
+CREATE INDEX IF NOT EXISTS idx_trust_events_type ON trust_events(event_type);
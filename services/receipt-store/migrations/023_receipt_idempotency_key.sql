@@ -0,0 +1,9 @@
+-- Migration 023: Receipt idempotency keys
+-- Producers retrying a receipt send the same key, and the receipt first stored under it is returned
+-- instead of a second one being chained in. Receipts sent without a key are never deduplicated.
+
+ALTER TABLE receipt_events
+ADD COLUMN IF NOT EXISTS idempotency_key VARCHAR(255);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_receipt_events_idempotency_key
+ON receipt_events(idempotency_key);
@@ -0,0 +1,5 @@
+-- Migration 024: Receipt export index
+-- The receipt export pages through a tenant's receipts in (timestamp, receipt_id) order.
+
+CREATE INDEX IF NOT EXISTS idx_receipt_events_tenant_export
+    ON receipt_events(tenant_id, "timestamp", receipt_id);
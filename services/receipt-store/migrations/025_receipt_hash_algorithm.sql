@@ -0,0 +1,12 @@
+-- Migration 025: Receipt hash algorithms
+-- Receipts can be hashed with sha256, sha512 or blake3, and the algorithm is recorded beside the hash so
+-- verification uses the one it was written with. Existing receipts are sha256. A sha512 hash is 128 hex
+-- characters, so the hash columns are widened to hold one.
+
+ALTER TABLE receipt_events
+ADD COLUMN IF NOT EXISTS hash_algorithm VARCHAR(16) NOT NULL DEFAULT 'sha256',
+ALTER COLUMN receipt_hash TYPE VARCHAR(128),
+ALTER COLUMN previous_receipt_hash TYPE VARCHAR(128);
+
+ALTER TABLE receipts
+ALTER COLUMN receipt_hash TYPE VARCHAR(128);
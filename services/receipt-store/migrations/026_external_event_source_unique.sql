@@ -0,0 +1,14 @@
+-- Migration 026: Unique external event sources
+-- An event is identified by its source system's reference, so a sync retried concurrently can't ingest it
+-- twice. Copies already ingested are dropped, keeping the first.
+
+DELETE FROM external_events e
+USING external_events first
+WHERE e.source_system = first.source_system
+  AND e.source_id = first.source_id
+  AND (e.created_at, e.event_id) > (first.created_at, first.event_id);
+
+DROP INDEX IF EXISTS idx_external_events_source;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_external_events_source
+ON external_events(source_system, source_id);
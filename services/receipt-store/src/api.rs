@@ -1,16 +1,37 @@
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
+    body::Body,
+    extract::{Path, Query, Request, State},
+    handler::Handler,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
 };
+use pathwell_common::api_version::ApiVersion;
+use pathwell_common::json_body::JsonBody;
+use pathwell_common::pagination::Cursor;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::receipt::{ReceiptRequest, ReceiptRequestV2, ExternalEventRequest, TrustEvent};
-use crate::store::ReceiptStore;
-use crate::queries::{QueryService, TraceQuery, TraceListResponse, TraceDetailResponse, TimelineEvent, DecisionTree};
+use crate::receipt::{
+    ReceiptRequest, ReceiptRequestV2, ExternalEventRequest, ExternalEventResult,
+    ExternalEventStatus, ReceiptBatchResult, TrustEvent,
+};
+use crate::store::{ReceiptStore, StoredReceipt, TraceStateError};
+use crate::queries::{
+    QueryService, TraceQuery, TraceListResponse, TraceDetailResponse, TimelineEvent,
+    TimelineQuery, TIMELINE_OUTCOMES, DecisionTreeQuery, ActivityQuery, AgentActivityResponse, TraceLatencyResponse,
+    ChainVerificationResponse, ReceiptValidationResponse, validate_receipt_hash, ReceiptDetailResponse, ReceiptSignatureResponse, TraceMerkleResponse, MerkleProofResponse, ExternalEventListResponse, ExternalEventRow,
+    CostQuery, TenantCostResponse, StatsQuery, StatsResponse, TrustTrendQuery, TrustTrendResponse, TRUST_TREND_BUCKETS, RiskEventQuery, RiskEventListResponse, RISK_EVENT_SORTS,
+    MAX_DESCENDANT_TENANTS, MAX_SEARCH_LEN, TRACE_CURSOR_SORT, TRACE_OUTCOMES,
+};
+use crate::rate_limit::API_KEY_HEADER;
+use crate::retention::{preview_purge, purge, PurgePreviewQuery, PurgePreviewResponse, PurgeQuery, PurgeResponse};
+use crate::selftest::{run_selftest, SelfTestResponse};
 use crate::db;
+use crate::diagram::{self, DiagramFormat};
+use crate::export::{stream_receipts, ExportFormat, ExportQuery};
+use crate::receipt_body::ReceiptBody;
+use crate::validation::{FieldError, ValidationError};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct StoreReceiptResponse {
@@ -24,6 +45,20 @@ pub struct StoreReceiptResponse {
 pub struct ErrorResponse {
     pub error: String,
     pub message: String,
+    /// Field-level errors for `validation_error` responses
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+impl ErrorResponse {
+    /// An error without field-level detail
+    pub fn new(error: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            error: error.into(),
+            message: message.into(),
+            fields: Vec::new(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -33,6 +68,40 @@ pub struct ExternalEventResponse {
     pub status: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExternalEventBatchResponse {
+    pub accepted: usize,
+    pub duplicates: usize,
+    pub rejected: usize,
+    pub results: Vec<ExternalEventResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReceiptBatchResponse {
+    pub stored: usize,
+    /// Receipts whose idempotency key was already stored
+    pub duplicates: usize,
+    pub rejected: usize,
+    pub results: Vec<ReceiptBatchResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SealTraceResponse {
+    pub trace_id: String,
+    pub seal_root: String,
+    pub receipt_count: usize,
+    pub seal_receipt_id: String,
+    pub seal_receipt_hash: String,
+    pub sealed_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CloseTraceResponse {
+    pub trace_id: String,
+    pub status: String,
+    pub completed_at: String,
+}
+
 /// V2 receipt response with trust context
 #[derive(Debug, Serialize, Deserialize)]
 pub struct StoreReceiptResponseV2 {
@@ -45,6 +114,13 @@ pub struct StoreReceiptResponseV2 {
     pub warnings: Vec<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveUrlResponse {
+    pub receipt_id: String,
+    pub url: String,
+    pub expires_at: String,
+}
+
 /// Trust events response
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TrustEventsResponse {
@@ -53,80 +129,248 @@ pub struct TrustEventsResponse {
     pub total_violations: i32,
 }
 
+fn error_response(status: StatusCode, error: &str, message: impl Into<String>) -> (StatusCode, Json<ErrorResponse>) {
+    (status, Json(ErrorResponse::new(error, message)))
+}
+
+/// Map a store error to a response, surfacing validation and trace-state failures
+fn store_error(e: anyhow::Error) -> (StatusCode, Json<ErrorResponse>) {
+    if let Some(t) = e.downcast_ref::<TraceStateError>() {
+        let (status, code) = match t {
+            TraceStateError::NotFound(_) => (StatusCode::NOT_FOUND, "trace_not_found"),
+            TraceStateError::Sealed(_) => (StatusCode::CONFLICT, "trace_sealed"),
+            TraceStateError::AlreadyClosed(..) => (StatusCode::CONFLICT, "invalid_transition"),
+        };
+        return (status, Json(ErrorResponse::new(code, t.to_string())));
+    }
+
+    if let Some(v) = e.downcast_ref::<ValidationError>() {
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(ErrorResponse {
+                error: "validation_error".to_string(),
+                message: v.to_string(),
+                fields: v.errors.clone(),
+            }),
+        );
+    }
+
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse::new("storage_error", e.to_string())))
+}
+
 // ============= Write Endpoints =============
 
 pub async fn store_receipt(
     State(store): State<Arc<ReceiptStore>>,
-    Json(payload): Json<ReceiptRequest>,
+    ReceiptBody(payload): ReceiptBody<ReceiptRequest>,
 ) -> Result<Json<StoreReceiptResponse>, (StatusCode, Json<ErrorResponse>)> {
     match store.store_receipt(payload).await {
-        Ok(receipt) => Ok(Json(StoreReceiptResponse {
+        Ok(StoredReceipt { receipt, stored }) => Ok(Json(StoreReceiptResponse {
             receipt_id: receipt.receipt_id.to_string(),
             receipt_hash: receipt.receipt_hash.clone(),
             trace_id: receipt.trace_id.to_string(),
-            stored: true,
+            stored,
         })),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "storage_error".to_string(),
-                message: e.to_string(),
-            }),
-        )),
+        Err(e) => Err(store_error(e)),
     }
 }
 
+/// Store many receipts in one transaction, chained in request order
+pub async fn store_receipts_batch(
+    State(store): State<Arc<ReceiptStore>>,
+    JsonBody(payload): JsonBody<Vec<ReceiptRequest>>,
+) -> Result<Json<ReceiptBatchResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let results = store.store_receipts_batch(payload).await.map_err(store_error)?;
+    let stored = results.iter().filter(|r| r.stored).count();
+    let rejected = results.iter().filter(|r| r.error.is_some()).count();
+
+    Ok(Json(ReceiptBatchResponse {
+        stored,
+        duplicates: results.len() - stored - rejected,
+        rejected,
+        results,
+    }))
+}
+
 pub async fn ingest_external_event(
     State(store): State<Arc<ReceiptStore>>,
-    Json(payload): Json<ExternalEventRequest>,
+    JsonBody(payload): JsonBody<ExternalEventRequest>,
 ) -> Result<Json<ExternalEventResponse>, (StatusCode, Json<ErrorResponse>)> {
     match store.store_external_event(payload).await {
         Ok(event) => Ok(Json(ExternalEventResponse {
             event_id: event.event_id.to_string(),
             trace_id: event.trace_id.to_string(),
-            status: "accepted".to_string(),
+            status: if event.stored { "accepted" } else { "duplicate" }.to_string(),
         })),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "storage_error".to_string(),
-                message: e.to_string(),
-            }),
-        )),
+        Err(e) => Err(store_error(e)),
     }
 }
 
+pub async fn ingest_external_events_batch(
+    State(store): State<Arc<ReceiptStore>>,
+    JsonBody(payload): JsonBody<Vec<ExternalEventRequest>>,
+) -> Result<Json<ExternalEventBatchResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let results = store.store_external_events_batch(payload).await.map_err(store_error)?;
+
+    let count = |status: ExternalEventStatus| results.iter().filter(|r| r.status == status).count();
+
+    Ok(Json(ExternalEventBatchResponse {
+        accepted: count(ExternalEventStatus::Accepted),
+        duplicates: count(ExternalEventStatus::Duplicate),
+        rejected: count(ExternalEventStatus::Rejected),
+        results,
+    }))
+}
+
+/// Close an active trace, recording how it finished
+pub async fn close_trace(
+    State(store): State<Arc<ReceiptStore>>,
+    Path(trace_id): Path<Uuid>,
+) -> Result<Json<CloseTraceResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if store.db_pool().is_none() {
+        return Err(error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "database_unavailable",
+            "Database not configured",
+        ));
+    }
+
+    let (status, completed_at) = store.close_trace(trace_id).await.map_err(store_error)?;
+
+    Ok(Json(CloseTraceResponse {
+        trace_id: trace_id.to_string(),
+        status,
+        completed_at: completed_at.to_rfc3339(),
+    }))
+}
+
+/// Seal a completed trace with a final Merkle-root receipt
+pub async fn seal_trace(
+    State(store): State<Arc<ReceiptStore>>,
+    Path(trace_id): Path<Uuid>,
+) -> Result<Json<SealTraceResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if store.db_pool().is_none() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::new("database_unavailable", "Database not configured")),
+        ));
+    }
+
+    let seal = store.seal_trace(trace_id).await.map_err(store_error)?;
+
+    Ok(Json(SealTraceResponse {
+        trace_id: seal.trace_id.to_string(),
+        seal_root: seal.seal_root,
+        receipt_count: seal.receipt_count,
+        seal_receipt_id: seal.seal_receipt_id.to_string(),
+        seal_receipt_hash: seal.seal_receipt_hash,
+        sealed_at: seal.sealed_at.to_rfc3339(),
+    }))
+}
+
 // ============= Read Endpoints =============
 
 pub async fn list_traces(
     State(store): State<Arc<ReceiptStore>>,
-    Query(params): Query<TraceQuery>,
+    Query(mut params): Query<TraceQuery>,
 ) -> Result<Json<TraceListResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if let Some(ref search) = params.search {
+        let search = search.trim();
+        if search.is_empty() {
+            return Err(error_response(StatusCode::BAD_REQUEST, "invalid_search", "search must not be empty"));
+        }
+        if search.chars().count() > MAX_SEARCH_LEN {
+            return Err(error_response(
+                StatusCode::BAD_REQUEST,
+                "invalid_search",
+                format!("search must be at most {} characters", MAX_SEARCH_LEN),
+            ));
+        }
+        params.search = Some(search.to_string());
+    }
+    if let Some(ref outcome) = params.outcome {
+        if !TRACE_OUTCOMES.contains(&outcome.as_str()) {
+            return Err(error_response(
+                StatusCode::BAD_REQUEST,
+                "invalid_outcome",
+                format!("outcome must be one of {}", TRACE_OUTCOMES.join(", ")),
+            ));
+        }
+    }
+    let tenant_ids = trace_tenant_scope(&params)?;
+    let cursor = params
+        .cursor
+        .as_deref()
+        .map(|c| {
+            Cursor::decode(c, TRACE_CURSOR_SORT).ok_or_else(|| {
+                error_response(StatusCode::BAD_REQUEST, "invalid_cursor", "cursor is malformed")
+            })
+        })
+        .transpose()?;
     let pool = match store.db_pool() {
         Some(p) => p.clone(),
         None => return Err((
             StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse {
-                error: "database_unavailable".to_string(),
-                message: "Database not configured".to_string(),
-            }),
+            Json(ErrorResponse::new("database_unavailable", "Database not configured")),
         )),
     };
 
     let query_service = QueryService::new(pool);
 
-    match query_service.list_traces(params).await {
+    match query_service.list_traces(params, tenant_ids, cursor).await {
         Ok(response) => Ok(Json(response)),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "query_error".to_string(),
-                message: e.to_string(),
-            }),
+            Json(ErrorResponse::new("query_error", e.to_string())),
         )),
     }
 }
 
+/// The tenants a trace listing is scoped to: `tenant_id`, plus its descendants with `include_descendants`
+fn trace_tenant_scope(params: &TraceQuery) -> Result<Option<Vec<Uuid>>, (StatusCode, Json<ErrorResponse>)> {
+    let include_descendants = params.include_descendants.unwrap_or(false);
+    let Some(tenant_id) = params.tenant_id else {
+        if include_descendants || params.descendant_tenant_ids.is_some() {
+            return Err(error_response(
+                StatusCode::BAD_REQUEST,
+                "invalid_tenant_scope",
+                "include_descendants and descendant_tenant_ids require tenant_id",
+            ));
+        }
+        return Ok(None);
+    };
+
+    let mut tenant_ids = vec![tenant_id];
+    match (include_descendants, params.descendant_tenant_ids.as_deref()) {
+        (_, None) => {}
+        (false, Some(_)) => {
+            return Err(error_response(
+                StatusCode::BAD_REQUEST,
+                "invalid_tenant_scope",
+                "descendant_tenant_ids requires include_descendants=true",
+            ));
+        }
+        (true, Some(ids)) => {
+            for id in ids.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+                let id = id.parse().map_err(|_| error_response(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_tenant_scope",
+                    format!("descendant_tenant_ids: '{}' is not a UUID", id),
+                ))?;
+                tenant_ids.push(id);
+            }
+            if tenant_ids.len() - 1 > MAX_DESCENDANT_TENANTS {
+                return Err(error_response(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_tenant_scope",
+                    format!("descendant_tenant_ids must list at most {} tenants", MAX_DESCENDANT_TENANTS),
+                ));
+            }
+        }
+    }
+    Ok(Some(tenant_ids))
+}
+
 pub async fn get_trace(
     State(store): State<Arc<ReceiptStore>>,
     Path(trace_id): Path<Uuid>,
@@ -135,10 +379,7 @@ pub async fn get_trace(
         Some(p) => p.clone(),
         None => return Err((
             StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse {
-                error: "database_unavailable".to_string(),
-                message: "Database not configured".to_string(),
-            }),
+            Json(ErrorResponse::new("database_unavailable", "Database not configured")),
         )),
     };
 
@@ -148,17 +389,11 @@ pub async fn get_trace(
         Ok(Some(response)) => Ok(Json(response)),
         Ok(None) => Err((
             StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "not_found".to_string(),
-                message: format!("Trace {} not found", trace_id),
-            }),
+            Json(ErrorResponse::new("not_found", format!("Trace {} not found", trace_id))),
         )),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "query_error".to_string(),
-                message: e.to_string(),
-            }),
+            Json(ErrorResponse::new("query_error", e.to_string())),
         )),
     }
 }
@@ -166,28 +401,85 @@ pub async fn get_trace(
 pub async fn get_trace_timeline(
     State(store): State<Arc<ReceiptStore>>,
     Path(trace_id): Path<Uuid>,
+    Query(params): Query<TimelineQuery>,
 ) -> Result<Json<Vec<TimelineEvent>>, (StatusCode, Json<ErrorResponse>)> {
+    let success = match params.outcome.as_deref().unwrap_or("all") {
+        "allowed" => Some(true),
+        "denied" => Some(false),
+        "all" => None,
+        _ => return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "invalid_outcome",
+            format!("outcome must be one of {}", TIMELINE_OUTCOMES.join(", ")),
+        )),
+    };
+
     let pool = match store.db_pool() {
         Some(p) => p.clone(),
         None => return Err((
             StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse {
-                error: "database_unavailable".to_string(),
-                message: "Database not configured".to_string(),
-            }),
+            Json(ErrorResponse::new("database_unavailable", "Database not configured")),
         )),
     };
 
     let query_service = QueryService::new(pool);
 
-    match query_service.get_timeline(trace_id).await {
+    match query_service.get_timeline(trace_id, success).await {
         Ok(timeline) => Ok(Json(timeline)),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "query_error".to_string(),
-                message: e.to_string(),
-            }),
+            Json(ErrorResponse::new("query_error", e.to_string())),
+        )),
+    }
+}
+
+pub async fn get_trace_latency(
+    State(store): State<Arc<ReceiptStore>>,
+    Path(trace_id): Path<Uuid>,
+) -> Result<Json<TraceLatencyResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let pool = match store.db_pool() {
+        Some(p) => p.clone(),
+        None => return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::new("database_unavailable", "Database not configured")),
+        )),
+    };
+
+    let query_service = QueryService::new(pool);
+
+    match query_service.get_trace_latency(trace_id).await {
+        Ok(latency) => Ok(Json(latency)),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new("query_error", e.to_string())),
+        )),
+    }
+}
+
+/// Verify a trace's receipts against the hash chain
+pub async fn verify_trace_chain(
+    State(store): State<Arc<ReceiptStore>>,
+    Path(trace_id): Path<Uuid>,
+) -> Result<Json<ChainVerificationResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let pool = match store.db_pool() {
+        Some(p) => p.clone(),
+        None => return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::new("database_unavailable", "Database not configured")),
+        )),
+    };
+
+    let query_service = QueryService::new(pool);
+
+    match query_service.verify_chain(trace_id).await {
+        Ok(Some(report)) => Ok(Json(report)),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new("not_found", format!("Trace {} not found", trace_id))),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new("query_error", e.to_string())),
         )),
     }
 }
@@ -195,28 +487,39 @@ pub async fn get_trace_timeline(
 pub async fn get_trace_decisions(
     State(store): State<Arc<ReceiptStore>>,
     Path(trace_id): Path<Uuid>,
-) -> Result<Json<DecisionTree>, (StatusCode, Json<ErrorResponse>)> {
+    Query(params): Query<DecisionTreeQuery>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let format = match params.format.as_deref() {
+        None => DiagramFormat::Json,
+        Some(value) => DiagramFormat::parse(value).ok_or_else(|| error_response(
+            StatusCode::BAD_REQUEST,
+            "invalid_format",
+            format!("Unknown format '{}', expected json, dot or mermaid", value),
+        ))?,
+    };
+
     let pool = match store.db_pool() {
         Some(p) => p.clone(),
         None => return Err((
             StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse {
-                error: "database_unavailable".to_string(),
-                message: "Database not configured".to_string(),
-            }),
+            Json(ErrorResponse::new("database_unavailable", "Database not configured")),
         )),
     };
 
     let query_service = QueryService::new(pool);
 
-    match query_service.build_decision_tree(trace_id).await {
-        Ok(tree) => Ok(Json(tree)),
+    match query_service.build_decision_tree(trace_id, params.collapse).await {
+        Ok(tree) => {
+            let body = match format {
+                DiagramFormat::Json => return Ok(Json(tree).into_response()),
+                DiagramFormat::Dot => diagram::to_dot(&tree),
+                DiagramFormat::Mermaid => diagram::to_mermaid(&tree),
+            };
+            Ok(([(header::CONTENT_TYPE, format.content_type())], body).into_response())
+        }
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "query_error".to_string(),
-                message: e.to_string(),
-            }),
+            Json(ErrorResponse::new("query_error", e.to_string())),
         )),
     }
 }
@@ -229,10 +532,7 @@ pub async fn lookup_by_correlation(
         Some(p) => p.clone(),
         None => return Err((
             StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse {
-                error: "database_unavailable".to_string(),
-                message: "Database not configured".to_string(),
-            }),
+            Json(ErrorResponse::new("database_unavailable", "Database not configured")),
         )),
     };
 
@@ -243,17 +543,11 @@ pub async fn lookup_by_correlation(
         Ok(Some(t)) => t,
         Ok(None) => return Err((
             StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "not_found".to_string(),
-                message: format!("No trace found with correlation ID: {}", correlation_id),
-            }),
+            Json(ErrorResponse::new("not_found", format!("No trace found with correlation ID: {}", correlation_id))),
         )),
         Err(e) => return Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "query_error".to_string(),
-                message: e.to_string(),
-            }),
+            Json(ErrorResponse::new("query_error", e.to_string())),
         )),
     };
 
@@ -262,27 +556,383 @@ pub async fn lookup_by_correlation(
         Ok(Some(response)) => Ok(Json(response)),
         Ok(None) => Err((
             StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "not_found".to_string(),
-                message: format!("Trace details not found for correlation ID: {}", correlation_id),
-            }),
+            Json(ErrorResponse::new("not_found", format!("Trace details not found for correlation ID: {}", correlation_id))),
         )),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "query_error".to_string(),
-                message: e.to_string(),
-            }),
+            Json(ErrorResponse::new("query_error", e.to_string())),
         )),
     }
 }
 
+/// An agent's trust score over time, per receipt or averaged per hour or day
+pub async fn get_agent_trust_trend(
+    State(store): State<Arc<ReceiptStore>>,
+    Path(agent_id): Path<String>,
+    Query(params): Query<TrustTrendQuery>,
+) -> Result<Json<TrustTrendResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if let (Some(from), Some(to)) = (params.from, params.to) {
+        if from >= to {
+            return Err(error_response(
+                StatusCode::BAD_REQUEST,
+                "invalid_window",
+                "`from` must be before `to`",
+            ));
+        }
+    }
+    if let Some(ref bucket) = params.bucket {
+        if !TRUST_TREND_BUCKETS.contains(&bucket.as_str()) {
+            return Err(error_response(
+                StatusCode::BAD_REQUEST,
+                "invalid_bucket",
+                format!("bucket must be one of {}", TRUST_TREND_BUCKETS.join(", ")),
+            ));
+        }
+    }
+
+    let pool = match store.db_pool() {
+        Some(p) => p.clone(),
+        None => return Err(error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "database_unavailable",
+            "Database not configured",
+        )),
+    };
+
+    QueryService::new(pool)
+        .get_trust_trend(&agent_id, params)
+        .await
+        .map(Json)
+        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, "query_error", e.to_string()))
+}
+
+/// Token and cost totals for a tenant over a window
+pub async fn get_tenant_cost(
+    State(store): State<Arc<ReceiptStore>>,
+    Path(tenant_id): Path<Uuid>,
+    Query(params): Query<CostQuery>,
+) -> Result<Json<TenantCostResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if let (Some(from), Some(to)) = (params.from, params.to) {
+        if from >= to {
+            return Err(error_response(
+                StatusCode::BAD_REQUEST,
+                "invalid_window",
+                "`from` must be before `to`",
+            ));
+        }
+    }
+
+    let pool = match store.db_pool() {
+        Some(p) => p.clone(),
+        None => return Err(error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "database_unavailable",
+            "Database not configured",
+        )),
+    };
+
+    QueryService::new(pool)
+        .get_tenant_cost(tenant_id, params)
+        .await
+        .map(Json)
+        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, "query_error", e.to_string()))
+}
+
+/// Headline trace and receipt numbers for a window, served from a short-lived cache when fresh
+pub async fn get_stats(
+    State(store): State<Arc<ReceiptStore>>,
+    Query(params): Query<StatsQuery>,
+) -> Result<Json<StatsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if let (Some(from), Some(to)) = (params.from, params.to) {
+        if from >= to {
+            return Err(error_response(
+                StatusCode::BAD_REQUEST,
+                "invalid_window",
+                "`from` must be before `to`",
+            ));
+        }
+    }
+
+    let pool = match store.db_pool() {
+        Some(p) => p.clone(),
+        None => return Err(error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "database_unavailable",
+            "Database not configured",
+        )),
+    };
+
+    if let Some(stats) = store.stats_cache().get(&params) {
+        return Ok(Json(stats));
+    }
+    let stats = QueryService::new(pool)
+        .get_stats(params.clone())
+        .await
+        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, "query_error", e.to_string()))?;
+    store.stats_cache().insert(params, stats.clone());
+    Ok(Json(stats))
+}
+
+/// Stream a tenant's receipts as a file download, NDJSON by default or `format=csv`
+pub async fn export_receipts(
+    State(store): State<Arc<ReceiptStore>>,
+    Query(params): Query<ExportQuery>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    if let (Some(from), Some(to)) = (params.from, params.to) {
+        if from >= to {
+            return Err(error_response(
+                StatusCode::BAD_REQUEST,
+                "invalid_window",
+                "`from` must be before `to`",
+            ));
+        }
+    }
+    let format = match params.format.as_deref() {
+        None => ExportFormat::Ndjson,
+        Some(value) => ExportFormat::parse(value).ok_or_else(|| error_response(
+            StatusCode::BAD_REQUEST,
+            "invalid_format",
+            format!("Unknown format '{}', expected ndjson or csv", value),
+        ))?,
+    };
+
+    let pool = match store.db_pool() {
+        Some(p) => p.clone(),
+        None => return Err(error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "database_unavailable",
+            "Database not configured",
+        )),
+    };
+
+    let disposition = format!(
+        "attachment; filename=\"receipts-{}.{}\"",
+        params.tenant_id,
+        format.extension()
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        Body::from_stream(stream_receipts(pool, params, format)),
+    )
+        .into_response())
+}
+
+pub async fn list_risk_events(
+    State(store): State<Arc<ReceiptStore>>,
+    Query(params): Query<RiskEventQuery>,
+) -> Result<Json<RiskEventListResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let sort = params.sort.clone().unwrap_or_else(|| "severity".to_string());
+    if !RISK_EVENT_SORTS.contains(&sort.as_str()) {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "invalid_sort",
+            format!("sort must be one of {}", RISK_EVENT_SORTS.join(", ")),
+        ));
+    }
+    let cursor = params
+        .cursor
+        .as_deref()
+        .map(|c| {
+            Cursor::decode(c, &sort).ok_or_else(|| {
+                error_response(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_cursor",
+                    "cursor is malformed or was issued for another sort",
+                )
+            })
+        })
+        .transpose()?;
+
+    let pool = match store.db_pool() {
+        Some(p) => p.clone(),
+        None => return Err(error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "database_unavailable",
+            "Database not configured",
+        )),
+    };
+
+    QueryService::new(pool)
+        .list_risk_events(params, &sort, cursor)
+        .await
+        .map(Json)
+        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, "query_error", e.to_string()))
+}
+
+pub async fn get_agent_activity(
+    State(store): State<Arc<ReceiptStore>>,
+    Path(agent_id): Path<String>,
+    Query(params): Query<ActivityQuery>,
+) -> Result<Json<AgentActivityResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let pool = match store.db_pool() {
+        Some(p) => p.clone(),
+        None => return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::new("database_unavailable", "Database not configured")),
+        )),
+    };
+
+    let query_service = QueryService::new(pool);
+
+    match query_service.get_agent_activity(&agent_id, params).await {
+        Ok(Some(response)) => Ok(Json(response)),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new("not_found", format!("No activity recorded for agent: {}", agent_id))),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new("query_error", e.to_string())),
+        )),
+    }
+}
+
+/// Merkle tree over a trace's receipt hashes
+pub async fn get_trace_merkle(
+    State(store): State<Arc<ReceiptStore>>,
+    Path(trace_id): Path<Uuid>,
+) -> Result<Json<TraceMerkleResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let pool = match store.db_pool() {
+        Some(p) => p.clone(),
+        None => return Err(error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "database_unavailable",
+            "Database not configured",
+        )),
+    };
+
+    QueryService::new(pool)
+        .compute_merkle_root(trace_id)
+        .await
+        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, "query_error", e.to_string()))?
+        .map(Json)
+        .ok_or_else(|| error_response(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            format!("Trace {} not found", trace_id),
+        ))
+}
+
+/// Proof that a receipt is a leaf of its trace's Merkle tree
+pub async fn get_receipt_merkle_proof(
+    State(store): State<Arc<ReceiptStore>>,
+    Path(receipt_id): Path<Uuid>,
+) -> Result<Json<MerkleProofResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let pool = match store.db_pool() {
+        Some(p) => p.clone(),
+        None => return Err(error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "database_unavailable",
+            "Database not configured",
+        )),
+    };
+
+    QueryService::new(pool)
+        .get_merkle_proof(receipt_id)
+        .await
+        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, "query_error", e.to_string()))?
+        .map(Json)
+        .ok_or_else(|| error_response(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            format!("Receipt {} not found, or is a trace seal", receipt_id),
+        ))
+}
+
+/// Recompute the hash of a receipt the caller holds and compare it with the hash it carries
+pub async fn validate_receipt(
+    JsonBody(receipt): JsonBody<serde_json::Value>,
+) -> Result<Json<ReceiptValidationResponse>, (StatusCode, Json<ErrorResponse>)> {
+    validate_receipt_hash(&receipt)
+        .map(Json)
+        .map_err(|e| error_response(StatusCode::UNPROCESSABLE_ENTITY, "invalid_receipt", e.to_string()))
+}
+
+/// A receipt's Ed25519 signature and public key, checked against its stored hash
+pub async fn get_receipt_signature(
+    State(store): State<Arc<ReceiptStore>>,
+    Path(receipt_id): Path<Uuid>,
+) -> Result<Json<ReceiptSignatureResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let pool = match store.db_pool() {
+        Some(p) => p.clone(),
+        None => return Err(error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "database_unavailable",
+            "Database not configured",
+        )),
+    };
+
+    QueryService::new(pool)
+        .get_receipt_signature(receipt_id)
+        .await
+        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, "query_error", e.to_string()))?
+        .map(Json)
+        .ok_or_else(|| error_response(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            format!("Receipt {} not found", receipt_id),
+        ))
+}
+
+pub async fn list_trace_external_events(
+    State(store): State<Arc<ReceiptStore>>,
+    Path(trace_id): Path<Uuid>,
+) -> Result<Json<ExternalEventListResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let pool = match store.db_pool() {
+        Some(p) => p.clone(),
+        None => return Err(error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "database_unavailable",
+            "Database not configured",
+        )),
+    };
+
+    QueryService::new(pool)
+        .list_external_events(trace_id)
+        .await
+        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, "query_error", e.to_string()))?
+        .map(Json)
+        .ok_or_else(|| error_response(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            format!("Trace {} not found", trace_id),
+        ))
+}
+
+pub async fn get_external_event(
+    State(store): State<Arc<ReceiptStore>>,
+    Path(event_id): Path<Uuid>,
+) -> Result<Json<ExternalEventRow>, (StatusCode, Json<ErrorResponse>)> {
+    let pool = match store.db_pool() {
+        Some(p) => p.clone(),
+        None => return Err(error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "database_unavailable",
+            "Database not configured",
+        )),
+    };
+
+    QueryService::new(pool)
+        .get_external_event(event_id)
+        .await
+        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, "query_error", e.to_string()))?
+        .map(Json)
+        .ok_or_else(|| error_response(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            format!("External event {} not found", event_id),
+        ))
+}
+
 // ============= V2 Endpoints (Phase 1) =============
 
 /// Store a v2 receipt with trust and attribution context
 pub async fn store_receipt_v2(
     State(store): State<Arc<ReceiptStore>>,
-    Json(payload): Json<ReceiptRequestV2>,
+    ReceiptBody(payload): ReceiptBody<ReceiptRequestV2>,
 ) -> Result<Json<StoreReceiptResponseV2>, (StatusCode, Json<ErrorResponse>)> {
     match store.store_receipt_v2(payload).await {
         Ok(receipt) => {
@@ -303,13 +953,19 @@ pub async fn store_receipt_v2(
                 warnings,
             }))
         }
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "storage_error".to_string(),
-                message: e.to_string(),
-            }),
-        )),
+        Err(e) => Err(store_error(e)),
+    }
+}
+
+/// Store a receipt with the v1 or v2 handler, whichever `Accept-Version` asks for
+pub async fn store_receipt_negotiated(
+    version: ApiVersion,
+    State(store): State<Arc<ReceiptStore>>,
+    request: Request,
+) -> Response {
+    match version {
+        ApiVersion::V1 => store_receipt.call(request, store).await,
+        ApiVersion::V2 => store_receipt_v2.call(request, store).await,
     }
 }
 
@@ -322,14 +978,11 @@ pub async fn get_trace_trust_events(
         Some(p) => p.clone(),
         None => return Err((
             StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse {
-                error: "database_unavailable".to_string(),
-                message: "Database not configured".to_string(),
-            }),
+            Json(ErrorResponse::new("database_unavailable", "Database not configured")),
         )),
     };
 
-    match db::get_trust_events_for_trace(&pool, trace_id).await {
+    match db::get_trust_events_for_trace(&pool, trace_id, store.trust_score_precision()).await {
         Ok(events) => {
             let total_violations = events
                 .iter()
@@ -344,11 +997,200 @@ pub async fn get_trace_trust_events(
         }
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "query_error".to_string(),
-                message: e.to_string(),
-            }),
+            Json(ErrorResponse::new("query_error", e.to_string())),
+        )),
+    }
+}
+
+// ============= Archive Endpoints =============
+
+/// Whether a caller may download a receipt given its audit visibility scope
+fn archive_access_allowed(
+    location: &db::ReceiptArchiveLocation,
+    caller_tenant: Option<Uuid>,
+    caller_agent: Option<&str>,
+) -> bool {
+    let tenant_matches = match location.tenant_id {
+        Some(tenant_id) => caller_tenant == Some(tenant_id),
+        None => true,
+    };
+
+    match location.visibility_scope.as_deref() {
+        Some("public") => true,
+        Some("private") => tenant_matches && caller_agent == Some(location.agent_id.as_str()),
+        _ => tenant_matches,
+    }
+}
+
+/// Get a time-limited presigned URL for downloading a receipt's S3 archive
+/// A single receipt as it was persisted, with its chain hashes
+pub async fn get_receipt(
+    State(store): State<Arc<ReceiptStore>>,
+    Path(receipt_id): Path<Uuid>,
+) -> Result<Json<ReceiptDetailResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let pool = match store.db_pool() {
+        Some(p) => p.clone(),
+        None => return Err(error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "database_unavailable",
+            "Database not configured",
         )),
+    };
+
+    QueryService::new(pool)
+        .get_receipt(receipt_id)
+        .await
+        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, "query_error", e.to_string()))?
+        .map(Json)
+        .ok_or_else(|| error_response(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            format!("Receipt {} not found", receipt_id),
+        ))
+}
+
+pub async fn get_receipt_archive_url(
+    State(store): State<Arc<ReceiptStore>>,
+    Path(receipt_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Json<ArchiveUrlResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let pool = match store.db_pool() {
+        Some(p) => p.clone(),
+        None => return Err(error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "database_unavailable",
+            "Database not configured",
+        )),
+    };
+
+    let location = db::get_receipt_archive_location(&pool, receipt_id)
+        .await
+        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, "query_error", e.to_string()))?
+        .ok_or_else(|| error_response(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            format!("Receipt {} not found", receipt_id),
+        ))?;
+
+    let caller_tenant = headers
+        .get("x-pathwell-tenant-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| Uuid::parse_str(v).ok());
+    let caller_agent = headers
+        .get("x-pathwell-agent-id")
+        .and_then(|v| v.to_str().ok());
+
+    if !archive_access_allowed(&location, caller_tenant, caller_agent) {
+        return Err(error_response(
+            StatusCode::FORBIDDEN,
+            "forbidden",
+            format!("Not authorized to access receipt {}", receipt_id),
+        ));
     }
+
+    let key = location.s3_key.ok_or_else(|| error_response(
+        StatusCode::NOT_FOUND,
+        "not_archived",
+        format!("Receipt {} has not been archived to S3", receipt_id),
+    ))?;
+
+    let url = store
+        .presign_archive_url(location.s3_bucket.as_deref(), &key)
+        .await
+        .map_err(|e| error_response(StatusCode::BAD_GATEWAY, "presign_error", e.to_string()))?;
+    let expires_at = chrono::Utc::now()
+        + chrono::Duration::seconds(store.archive_url_expiry_secs() as i64);
+
+    Ok(Json(ArchiveUrlResponse {
+        receipt_id: receipt_id.to_string(),
+        url,
+        expires_at: expires_at.to_rfc3339(),
+    }))
 }
 
+/// What the retention purge would delete now, by tenant, without deleting anything
+pub async fn get_purge_preview(
+    State(store): State<Arc<ReceiptStore>>,
+    Query(params): Query<PurgePreviewQuery>,
+) -> Result<Json<PurgePreviewResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let pool = match store.db_pool() {
+        Some(p) => p.clone(),
+        None => return Err(error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "database_unavailable",
+            "Database not configured",
+        )),
+    };
+
+    preview_purge(&pool, store.retention(), params.sample_size)
+        .await
+        .map(Json)
+        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, "query_error", e.to_string()))
+}
+
+/// Delete the traces past retention whose receipts are all archived, now; admin key required
+pub async fn run_purge(
+    State(store): State<Arc<ReceiptStore>>,
+    headers: HeaderMap,
+    Query(params): Query<PurgeQuery>,
+) -> Result<Json<PurgeResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let authorized = headers
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|key| store.is_admin_key(key));
+    if !authorized {
+        return Err(error_response(
+            StatusCode::UNAUTHORIZED,
+            "unauthorized",
+            "An admin X-Api-Key is required",
+        ));
+    }
+
+    let pool = match store.db_pool() {
+        Some(p) => p.clone(),
+        None => return Err(error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "database_unavailable",
+            "Database not configured",
+        )),
+    };
+
+    purge(&pool, store.retention(), params.before, store.retention_purge().batch_size)
+        .await
+        .map(Json)
+        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, "query_error", e.to_string()))
+}
+
+/// Ingestion counters in the Prometheus text format
+pub async fn get_metrics(State(store): State<Arc<ReceiptStore>>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        store.metrics().render(),
+    )
+}
+
+/// Store, read back and chain-verify a synthetic receipt, and check every sink; `503` if any step fails
+pub async fn get_selftest(
+    State(store): State<Arc<ReceiptStore>>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, Json<SelfTestResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let authorized = headers
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|key| store.is_admin_key(key));
+    if !authorized {
+        return Err(error_response(
+            StatusCode::UNAUTHORIZED,
+            "unauthorized",
+            "An admin X-Api-Key is required",
+        ));
+    }
+
+    let report = run_selftest(&store).await;
+    let status = if report.healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    Ok((status, Json(report)))
+}
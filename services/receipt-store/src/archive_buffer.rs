@@ -0,0 +1,77 @@
+//! Batched S3 archival. With `S3_ARCHIVE_BATCH_SIZE` above 1, stored receipts are queued in memory per
+//! bucket rather than written one object each, and a background task writes each bucket's queue as
+//! newline-delimited JSON objects of up to that many receipts. It flushes every
+//! `S3_ARCHIVE_FLUSH_INTERVAL_SECS`, and as soon as a bucket has a full batch. Receipts still queued at
+//! shutdown are flushed before the process exits.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::store::ReceiptStore;
+
+/// Receipts waiting to be archived, by the bucket of the sink they belong to
+pub struct ArchiveBuffer {
+    batch_size: usize,
+    pending: Mutex<HashMap<String, Vec<(Uuid, String)>>>,
+    batch_ready: Notify,
+}
+
+impl ArchiveBuffer {
+    pub fn new(batch_size: usize) -> Self {
+        Self {
+            batch_size,
+            pending: Mutex::new(HashMap::new()),
+            batch_ready: Notify::new(),
+        }
+    }
+
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Queue a serialized receipt for `bucket`, waking the flusher once the bucket has a full batch
+    pub fn push(&self, bucket: &str, receipt_id: Uuid, receipt_json: String) {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let queue = pending.entry(bucket.to_string()).or_default();
+        queue.push((receipt_id, receipt_json));
+        if queue.len() >= self.batch_size {
+            self.batch_ready.notify_one();
+        }
+    }
+
+    /// Everything queued, by bucket, leaving the buffer empty
+    pub fn take(&self) -> Vec<(String, Vec<(Uuid, String)>)> {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.drain().filter(|(_, queue)| !queue.is_empty()).collect()
+    }
+}
+
+/// Start flushing queued receipts in the background, if archival is batched
+pub fn spawn_flusher(store: Arc<ReceiptStore>) {
+    let Some(batch_size) = store.archive_buffer().map(ArchiveBuffer::batch_size) else {
+        return;
+    };
+    let interval = Duration::from_secs(store.archive_flush_interval_secs());
+    info!(
+        "S3 archival batched: up to {} receipts per object, flushed every {}s",
+        batch_size,
+        interval.as_secs()
+    );
+
+    tokio::spawn(async move {
+        let Some(buffer) = store.archive_buffer() else {
+            return;
+        };
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = buffer.batch_ready.notified() => {}
+            }
+            store.flush_archive().await;
+        }
+    });
+}
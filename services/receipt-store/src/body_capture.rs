@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::config::BodyCaptureConfig;
+use crate::receipt::RequestInfo;
+
+/// Whether `content_type` matches an allowlist entry (`type/subtype`, `type/*` or `*/*`);
+/// parameters such as `charset` are ignored
+pub fn content_type_allowed(allowlist: &[String], content_type: &str) -> bool {
+    let media_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    let Some((kind, _)) = media_type.split_once('/') else {
+        return false;
+    };
+
+    allowlist.iter().any(|entry| {
+        let entry = entry.trim().to_ascii_lowercase();
+        entry == "*/*"
+            || entry == media_type
+            || entry.strip_suffix("/*").is_some_and(|prefix| prefix == kind)
+    })
+}
+
+/// Decides which captured request bodies are kept, per tenant
+pub struct BodyCapturePolicy {
+    content_types: Vec<String>,
+    tenant_content_types: HashMap<Uuid, Vec<String>>,
+}
+
+impl BodyCapturePolicy {
+    pub fn new(config: &BodyCaptureConfig) -> Self {
+        Self {
+            content_types: config.content_types.clone(),
+            tenant_content_types: config.tenant_content_types.clone(),
+        }
+    }
+
+    /// Whether any tenant has its own allowlist (otherwise the tenant lookup can be skipped)
+    pub fn has_tenant_overrides(&self) -> bool {
+        !self.tenant_content_types.is_empty()
+    }
+
+    fn allowlist_for(&self, tenant_id: Option<Uuid>) -> &[String] {
+        tenant_id
+            .and_then(|id| self.tenant_content_types.get(&id))
+            .unwrap_or(&self.content_types)
+    }
+
+    /// Drop a captured body whose content type isn't allowed for the tenant, keeping only its hash
+    pub fn apply(&self, tenant_id: Option<Uuid>, request: &mut RequestInfo) {
+        if request.body.is_none() {
+            return;
+        }
+        let allowed = request
+            .content_type
+            .as_deref()
+            .is_some_and(|ct| content_type_allowed(self.allowlist_for(tenant_id), ct));
+        if !allowed {
+            tracing::debug!(
+                "Dropping captured {} body; only its hash is stored",
+                request.content_type.as_deref().unwrap_or("untyped")
+            );
+            request.body = None;
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Canonical JSON for receipt hashing, following the JSON Canonicalization Scheme (RFC 8785): object keys
+//! sorted by their UTF-16 code units, no insignificant whitespace, numbers written the way ECMAScript
+//! writes an IEEE 754 double, and minimal string escaping. Strings and keys are additionally normalized to
+//! Unicode NFC, so text that renders the same hashes the same whichever normalization a producer sent.
+//! The result is UTF-8, and any JCS implementation that applies NFC first reproduces it byte for byte.
+//! An object whose distinct keys normalize to the same NFC key has no canonical form and is rejected.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+use std::fmt::Write;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::validation::ValidationError;
+
+/// How a receipt's fields were serialized before hashing, recorded on the receipt so it can be verified
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashCanonicalization {
+    /// serde_json's own output; receipts stored before canonicalization was recorded use it
+    #[default]
+    Legacy,
+    /// RFC 8785 JCS over NFC-normalized strings
+    Jcs,
+}
+
+impl HashCanonicalization {
+    pub fn from_env_value(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "legacy" => Some(HashCanonicalization::Legacy),
+            "jcs" => Some(HashCanonicalization::Jcs),
+            _ => None,
+        }
+    }
+
+    /// Bytes to hash for `value`
+    pub fn serialize(&self, value: &Value) -> Result<String> {
+        match self {
+            HashCanonicalization::Legacy => Ok(serde_json::to_string(value)?),
+            HashCanonicalization::Jcs => Ok(to_canonical_json(value)?),
+        }
+    }
+}
+
+/// Serialize `value` in canonical form, or reject it if two keys of one object collide once normalized
+pub fn to_canonical_json(value: &Value) -> Result<String, ValidationError> {
+    let mut out = String::new();
+    write_value(&mut out, value)?;
+    Ok(out)
+}
+
+fn write_value(out: &mut String, value: &Value) -> Result<(), ValidationError> {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => write_number(out, n),
+        Value::String(s) => write_string(out, s),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, item)?;
+            }
+            out.push(']');
+        }
+        Value::Object(fields) => {
+            let mut entries: Vec<(String, &Value)> =
+                fields.iter().map(|(k, v)| (k.nfc().collect(), v)).collect();
+            entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            // Emitting both would produce duplicate keys, and keeping either would hash away the other's value
+            if let Some(pair) = entries.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+                return Err(ValidationError::new(
+                    "receipt",
+                    format!("object has more than one key that normalizes to {:?}", pair[0].0),
+                ));
+            }
+            out.push('{');
+            for (i, (key, item)) in entries.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_escaped(out, key);
+                out.push(':');
+                write_value(out, item)?;
+            }
+            out.push('}');
+        }
+    }
+    Ok(())
+}
+
+fn write_string(out: &mut String, s: &str) {
+    let normalized: String = s.nfc().collect();
+    write_escaped(out, &normalized);
+}
+
+/// Quote a string, escaping only what JSON requires, with the short forms where they exist
+fn write_escaped(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0c}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Every JSON number is treated as a double, as JCS requires, so integers beyond 2^53 lose precision
+fn write_number(out: &mut String, n: &Number) {
+    let value = n
+        .as_f64()
+        .or_else(|| n.as_i64().map(|i| i as f64))
+        .or_else(|| n.as_u64().map(|u| u as f64))
+        .unwrap_or(0.0);
+    out.push_str(&ecmascript_number(value));
+}
+
+/// A double as ECMAScript's `Number.prototype.toString` writes it: the shortest digits that round-trip,
+/// in plain notation for magnitudes from 1e-6 up to 1e21 and exponent notation outside them
+fn ecmascript_number(value: f64) -> String {
+    if value == 0.0 || !value.is_finite() {
+        // -0 is written as 0; JSON has no non-finite numbers, so they can't reach here from a Value
+        return "0".to_string();
+    }
+
+    let sign = if value < 0.0 { "-" } else { "" };
+    // `{:e}` gives the shortest round-trip digits, as `d.ddde<exp>`
+    let scientific = format!("{:e}", value.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let exponent: i32 = exponent.parse().unwrap();
+    let k = digits.len() as i32;
+    // Decimal point position: value = 0.digits * 10^n
+    let n = exponent + 1;
+
+    let body = if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat((-n) as usize), digits)
+    } else {
+        let e = n - 1;
+        let e_sign = if e < 0 { "-" } else { "+" };
+        if k == 1 {
+            format!("{}e{}{}", digits, e_sign, e.abs())
+        } else {
+            format!("{}.{}e{}{}", &digits[..1], &digits[1..], e_sign, e.abs())
+        }
+    };
+    format!("{}{}", sign, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn numbers_follow_ecmascript() {
+        // Vectors from RFC 8785 section 3.2.2.3 and appendix B
+        let cases: &[(f64, &str)] = &[
+            (0.0, "0"),
+            (-0.0, "0"),
+            (1e21, "1e+21"),
+            (1e20, "100000000000000000000"),
+            (1e-6, "0.000001"),
+            (1e-7, "1e-7"),
+            (5e-324, "5e-324"),
+            (-5e-324, "-5e-324"),
+            (1.7976931348623157e308, "1.7976931348623157e+308"),
+            (333333333.3333333, "333333333.3333333"),
+            (1e30, "1e+30"),
+            (4.5, "4.5"),
+            (0.002, "0.002"),
+            (1e-27, "1e-27"),
+            (9007199254740992.0, "9007199254740992"),
+            (295147905179352830000.0, "295147905179352830000"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(ecmascript_number(*value), *expected, "{:e}", value);
+        }
+    }
+
+    #[test]
+    fn negative_zero_from_json_is_zero() {
+        let value: Value = serde_json::from_str("[-0, -0.0]").unwrap();
+        assert_eq!(to_canonical_json(&value).unwrap(), "[0,0]");
+    }
+
+    #[test]
+    fn keys_sort_by_utf16_code_units() {
+        // The RFC 8785 section 3.2.3 vector, except that U+FB33 (which NFC decomposes) is swapped for U+FF21.
+        // U+1F600 is the surrogate pair D83D DE00, so it sorts before U+FF21 despite its larger code point
+        let value = json!({
+            "\u{20ac}": "Euro Sign",
+            "\r": "Carriage Return",
+            "\u{ff21}": "Fullwidth Latin Capital Letter A",
+            "1": "One",
+            "\u{1f600}": "Emoji: Grinning Face",
+            "\u{80}": "Control",
+            "\u{f6}": "Latin Small Letter O With Diaeresis",
+        });
+        assert_eq!(
+            to_canonical_json(&value).unwrap(),
+            concat!(
+                "{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{80}\":\"Control\",",
+                "\"\u{f6}\":\"Latin Small Letter O With Diaeresis\",\"\u{20ac}\":\"Euro Sign\",",
+                "\"\u{1f600}\":\"Emoji: Grinning Face\",\"\u{ff21}\":\"Fullwidth Latin Capital Letter A\"}"
+            )
+        );
+    }
+
+    #[test]
+    fn nfd_and_nfc_keys_canonicalize_the_same() {
+        let nfc = json!({ "caf\u{e9}": "cr\u{e8}me" });
+        let nfd = json!({ "cafe\u{301}": "cre\u{300}me" });
+        assert_eq!(to_canonical_json(&nfc).unwrap(), to_canonical_json(&nfd).unwrap());
+        assert_eq!(to_canonical_json(&nfd).unwrap(), "{\"caf\u{e9}\":\"cr\u{e8}me\"}");
+    }
+
+    #[test]
+    fn keys_colliding_after_nfc_are_rejected() {
+        let value = json!({ "nested": { "caf\u{e9}": 1, "cafe\u{301}": 2 } });
+        assert!(to_canonical_json(&value).is_err());
+        assert!(HashCanonicalization::Jcs.serialize(&value).is_err());
+        // Legacy serialization never normalized keys, so the same value still hashes
+        assert!(HashCanonicalization::Legacy.serialize(&value).is_ok());
+    }
+}
@@ -0,0 +1,376 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+use pathwell_common::scoring::DEFAULT_SCORE_PRECISION;
+
+use crate::canonical::HashCanonicalization;
+use crate::hashing;
+
+/// S3 rejects presigned URLs valid for longer than 7 days
+const MAX_ARCHIVE_URL_EXPIRY_SECS: u64 = 7 * 24 * 3600;
+
+/// Dedicated Kafka/S3 destinations for a tenant with data-residency requirements
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantSinkConfig {
+    pub s3_bucket: String,
+    pub s3_region: String,
+    pub kafka_brokers: String,
+    /// Defaults to the global `KAFKA_TOPIC`
+    #[serde(default)]
+    pub kafka_topic: Option<String>,
+}
+
+/// Token bucket settings for one receipt source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// Sustained receipts per second
+    pub requests_per_second: f64,
+    /// Receipts that may be written at once before the sustained rate applies
+    pub burst: u32,
+}
+
+/// Write-endpoint limits: a default for every source plus per-source overrides
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    /// Applies to sources without an override; `None` leaves them unlimited
+    pub default: Option<RateLimit>,
+    /// Keyed by API key, `event_source.service` or `source_system`
+    pub sources: HashMap<String, RateLimit>,
+}
+
+impl RateLimitConfig {
+    fn from_env() -> Self {
+        let default = std::env::var("RECEIPT_RATE_LIMIT_PER_SEC")
+            .ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .map(|requests_per_second| RateLimit {
+                requests_per_second,
+                burst: std::env::var("RECEIPT_RATE_LIMIT_BURST")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(requests_per_second.ceil().max(1.0) as u32),
+            });
+        let sources = std::env::var("RECEIPT_SOURCE_RATE_LIMITS")
+            .ok()
+            .map(|v| {
+                serde_json::from_str(&v)
+                    .expect("RECEIPT_SOURCE_RATE_LIMITS must be a JSON object keyed by source")
+            })
+            .unwrap_or_default();
+        Self { default, sources }
+    }
+}
+
+/// Content types whose captured request bodies are kept in receipts
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BodyCaptureConfig {
+    /// `type/subtype`, `type/*` or `*/*`; bodies of other types keep only their hash
+    pub content_types: Vec<String>,
+    /// Per-tenant allowlists replacing `content_types` for that tenant's receipts
+    pub tenant_content_types: HashMap<Uuid, Vec<String>>,
+}
+
+/// Body types kept when `BODY_CAPTURE_CONTENT_TYPES` is unset
+const DEFAULT_BODY_CAPTURE_CONTENT_TYPES: &str = "application/json,text/*";
+
+impl BodyCaptureConfig {
+    fn from_env() -> Self {
+        Self {
+            content_types: std::env::var("BODY_CAPTURE_CONTENT_TYPES")
+                .unwrap_or_else(|_| DEFAULT_BODY_CAPTURE_CONTENT_TYPES.to_string())
+                .split(',')
+                .map(|t| t.trim().to_lowercase())
+                .filter(|t| !t.is_empty())
+                .collect(),
+            tenant_content_types: std::env::var("TENANT_BODY_CAPTURE_CONTENT_TYPES")
+                .ok()
+                .map(|v| {
+                    serde_json::from_str(&v).expect(
+                        "TENANT_BODY_CAPTURE_CONTENT_TYPES must be a JSON object keyed by tenant ID",
+                    )
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// How long traces are kept after their last event, globally and per tenant
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetentionConfig {
+    /// Applies to traces of tenants without an override; `None` keeps them forever
+    pub default_days: Option<u32>,
+    /// Per-tenant retention replacing `default_days` for that tenant's traces
+    pub tenant_days: HashMap<Uuid, u32>,
+}
+
+impl RetentionConfig {
+    fn from_env() -> Self {
+        Self {
+            default_days: std::env::var("RETENTION_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            tenant_days: std::env::var("TENANT_RETENTION_DAYS")
+                .ok()
+                .map(|v| {
+                    serde_json::from_str(&v)
+                        .expect("TENANT_RETENTION_DAYS must be a JSON object keyed by tenant ID")
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// When the retention purge runs in the background
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionPurgeConfig {
+    /// Time between purges; `None` leaves purging to the admin endpoint
+    pub interval_secs: Option<u64>,
+    /// Traces deleted per transaction
+    pub batch_size: i64,
+}
+
+impl RetentionPurgeConfig {
+    fn from_env() -> Self {
+        Self {
+            interval_secs: std::env::var("RETENTION_PURGE_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|secs| *secs > 0),
+            batch_size: std::env::var("RETENTION_PURGE_BATCH_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(500)
+                .max(1),
+        }
+    }
+}
+
+/// When the sweeper completes traces that have gone quiet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceSweepConfig {
+    /// Traces with no event for this long are completed; `None` disables the sweeper
+    pub inactivity_secs: Option<u64>,
+    /// Time between sweeps
+    pub interval_secs: u64,
+    /// Traces completed per statement
+    pub batch_size: i64,
+    /// Also seal each trace the sweeper completes
+    pub seal: bool,
+}
+
+impl TraceSweepConfig {
+    fn from_env() -> Self {
+        Self {
+            inactivity_secs: std::env::var("TRACE_INACTIVITY_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|secs| *secs > 0),
+            interval_secs: std::env::var("TRACE_SWEEP_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60)
+                .max(1),
+            batch_size: std::env::var("TRACE_SWEEP_BATCH_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(500)
+                .max(1),
+            seal: std::env::var("TRACE_SWEEP_SEAL")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub kafka_brokers: String,
+    pub kafka_topic: String,
+    /// Extra topics receipts are also sent to, keyed by event type or `denied`
+    pub kafka_topic_routes: HashMap<String, String>,
+    /// Topic v2 trust threshold violations are published to; `None` publishes none
+    pub kafka_trust_topic: Option<String>,
+    pub s3_bucket: String,
+    pub s3_region: String,
+    /// Gzip archived objects; applies to every sink, tenant buckets included
+    pub s3_compress: bool,
+    pub database_url: Option<String>,
+    pub port: u16,
+    /// How far in the future a client-supplied `occurred_at` may be
+    pub max_future_skew_secs: i64,
+    /// How far in the past a client-supplied `occurred_at` may be (buffered/retried submissions)
+    pub max_backdate_secs: i64,
+    /// How far in the future an external event's `timestamp` may be
+    pub external_event_max_future_skew_secs: i64,
+    /// Reject external events for unknown traces with a validation error before attempting the insert
+    pub external_event_require_trace: bool,
+    /// Consecutive trust violations (within the window) before escalating to a block
+    pub trust_escalation_threshold: i32,
+    /// Violations further apart than this restart the consecutive count
+    pub trust_escalation_window_secs: i64,
+    /// Lifetime of presigned archive download URLs (capped at the S3 maximum of 7 days)
+    pub archive_url_expiry_secs: u64,
+    /// Receipts archived to S3 together as one newline-delimited object; 1 archives each receipt on its own
+    pub archive_batch_size: usize,
+    /// Longest a batched receipt waits in memory before its batch is written, full or not
+    pub archive_flush_interval_secs: u64,
+    /// Decimal places trust scores are rounded to when stored and returned
+    pub trust_score_precision: u32,
+    /// Per-tenant sink overrides; tenants not listed use the global Kafka/S3 settings
+    pub tenant_sinks: HashMap<Uuid, TenantSinkConfig>,
+    /// Per-source ingestion limits on the write endpoints
+    pub rate_limits: RateLimitConfig,
+    /// Which captured request bodies are kept, globally and per tenant
+    pub body_capture: BodyCaptureConfig,
+    /// Which traces the purge removes
+    pub retention: RetentionConfig,
+    /// Whether, and how fast, the purge runs on its own
+    pub retention_purge: RetentionPurgeConfig,
+    /// Which traces the inactivity sweeper completes
+    pub trace_sweep: TraceSweepConfig,
+    /// How often a replica running a background job proves it still holds the job's lock
+    pub job_heartbeat_secs: u64,
+    /// Reject receipts carrying fields the receipt format doesn't define, rather than ignoring them
+    pub strict_receipt_fields: bool,
+    /// Receipt metadata larger than this many bytes (serialized) is stored out of the `receipt_events`
+    /// row; `None` keeps all metadata inline
+    pub metadata_overflow_bytes: Option<usize>,
+    /// How new receipts are serialized for hashing
+    pub hash_canonicalization: HashCanonicalization,
+    /// Digest new receipts are hashed with: `sha256`, `sha512` or `blake3`
+    pub hash_algorithm: String,
+    /// How long `GET /v1/stats` results are served from memory before being recomputed; 0 disables caching
+    pub stats_cache_ttl_secs: u64,
+    /// Hex-encoded Ed25519 secret key receipts are signed with; `None` leaves receipts unsigned
+    #[serde(skip_serializing)]
+    pub signing_key: Option<String>,
+    /// API keys allowed to call the admin endpoints (`/v1/selftest`, `/v1/admin/purge`)
+    #[serde(skip_serializing)]
+    pub admin_api_keys: Vec<String>,
+}
+
+impl Config {
+    pub fn from_env() -> Self {
+        Self {
+            kafka_brokers: std::env::var("KAFKA_BROKERS")
+                .unwrap_or_else(|_| "localhost:9092".to_string()),
+            kafka_topic: std::env::var("KAFKA_TOPIC")
+                .unwrap_or_else(|_| "pathwell-receipts".to_string()),
+            kafka_topic_routes: std::env::var("KAFKA_TOPIC_ROUTES")
+                .ok()
+                .map(|v| {
+                    serde_json::from_str(&v)
+                        .expect("KAFKA_TOPIC_ROUTES must be a JSON object keyed by event type")
+                })
+                .unwrap_or_default(),
+            kafka_trust_topic: std::env::var("KAFKA_TRUST_TOPIC").ok().filter(|v| !v.is_empty()),
+            s3_bucket: std::env::var("S3_BUCKET")
+                .unwrap_or_else(|_| "pathwell-receipts".to_string()),
+            s3_region: std::env::var("S3_REGION")
+                .unwrap_or_else(|_| "us-east-1".to_string()),
+            s3_compress: std::env::var("S3_ARCHIVE_COMPRESS")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(true),
+            database_url: std::env::var("DATABASE_URL").ok(),
+            port: std::env::var("PORT")
+                .unwrap_or_else(|_| "3003".to_string())
+                .parse()
+                .unwrap_or(3003),
+            max_future_skew_secs: std::env::var("RECEIPT_MAX_FUTURE_SKEW_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(300),
+            max_backdate_secs: std::env::var("RECEIPT_MAX_BACKDATE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(86400),
+            external_event_max_future_skew_secs: std::env::var("EXTERNAL_EVENT_MAX_FUTURE_SKEW_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(300),
+            external_event_require_trace: std::env::var("EXTERNAL_EVENT_REQUIRE_TRACE")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            trust_escalation_threshold: std::env::var("TRUST_ESCALATION_THRESHOLD")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3),
+            trust_escalation_window_secs: std::env::var("TRUST_ESCALATION_WINDOW_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3600),
+            archive_url_expiry_secs: std::env::var("ARCHIVE_URL_EXPIRY_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(900)
+                .clamp(1, MAX_ARCHIVE_URL_EXPIRY_SECS),
+            archive_batch_size: std::env::var("S3_ARCHIVE_BATCH_SIZE")
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or(1)
+                .max(1),
+            archive_flush_interval_secs: std::env::var("S3_ARCHIVE_FLUSH_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(5)
+                .max(1),
+            trust_score_precision: std::env::var("TRUST_SCORE_PRECISION")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_SCORE_PRECISION),
+            tenant_sinks: std::env::var("TENANT_SINKS")
+                .ok()
+                .map(|v| {
+                    // A silently ignored override would send regulated data to the default sink
+                    serde_json::from_str(&v).expect("TENANT_SINKS must be a JSON object keyed by tenant ID")
+                })
+                .unwrap_or_default(),
+            rate_limits: RateLimitConfig::from_env(),
+            body_capture: BodyCaptureConfig::from_env(),
+            retention: RetentionConfig::from_env(),
+            retention_purge: RetentionPurgeConfig::from_env(),
+            trace_sweep: TraceSweepConfig::from_env(),
+            job_heartbeat_secs: std::env::var("BACKGROUND_JOB_HEARTBEAT_SECS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|secs| *secs > 0)
+                .unwrap_or(10),
+            strict_receipt_fields: std::env::var("RECEIPT_STRICT_FIELDS")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            metadata_overflow_bytes: std::env::var("RECEIPT_METADATA_OVERFLOW_BYTES")
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .or(Some(16384))
+                .filter(|bytes| *bytes > 0),
+            hash_canonicalization: std::env::var("RECEIPT_HASH_CANONICALIZATION")
+                .ok()
+                .and_then(|v| HashCanonicalization::from_env_value(&v))
+                .unwrap_or(HashCanonicalization::Jcs),
+            hash_algorithm: std::env::var("RECEIPT_HASH_ALGORITHM")
+                .ok()
+                .map(|v| v.trim().to_ascii_lowercase())
+                .inspect(|v| {
+                    assert!(
+                        hashing::is_supported(v),
+                        "RECEIPT_HASH_ALGORITHM must be one of: {}",
+                        hashing::HASH_ALGORITHMS.join(", ")
+                    )
+                })
+                .unwrap_or_else(hashing::default_hash_algorithm),
+            stats_cache_ttl_secs: std::env::var("STATS_CACHE_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            signing_key: std::env::var("RECEIPT_SIGNING_KEY")
+                .ok()
+                .filter(|v| !v.trim().is_empty()),
+            admin_api_keys: std::env::var("ADMIN_API_KEYS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|k| k.trim().to_string())
+                .filter(|k| !k.is_empty())
+                .collect(),
+        }
+    }
+}
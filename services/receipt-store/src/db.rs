@@ -1,42 +1,46 @@
-use sqlx::PgPool;
+use sqlx::{PgExecutor, PgPool};
 use anyhow::Result;
 use uuid::Uuid;
 use rust_decimal::Decimal;
-use rust_decimal::prelude::ToPrimitive;
+use pathwell_common::scoring::{score_to_decimal, score_to_f64};
 
-use crate::receipt::{Receipt, ReceiptV2, ExternalEvent, EventType, TrustEvent, TrustEventType};
+use crate::receipt::{
+    Receipt, ReceiptV2, ExternalEvent, TrustEvent, TrustEventType, TrustRiskEvent,
+    LatencyBreakdown,
+};
 
 /// Get the hash of the most recent receipt for hash chaining
-pub async fn get_latest_receipt_hash(pool: &PgPool) -> Result<Option<String>> {
+pub async fn get_latest_receipt_hash<'e>(executor: impl PgExecutor<'e>) -> Result<Option<String>> {
     let result: Option<(String,)> = sqlx::query_as(
         "SELECT receipt_hash FROM receipts ORDER BY timestamp DESC LIMIT 1"
     )
-    .fetch_optional(pool)
+    .fetch_optional(executor)
     .await?;
 
     Ok(result.map(|row| row.0))
 }
 
-/// Store receipt hash for quick lookup (backwards compatibility)
-pub async fn store_receipt_hash(
-    pool: &PgPool,
+/// Store receipt hash for quick lookup (backwards compatibility). Stamped with the wall clock rather than
+/// `NOW()` so receipts stored in one transaction keep their order for `get_latest_receipt_hash`
+pub async fn store_receipt_hash<'e>(
+    executor: impl PgExecutor<'e>,
     receipt_id: Uuid,
     receipt_hash: &str,
 ) -> Result<()> {
     sqlx::query(
-        "INSERT INTO receipts (receipt_id, receipt_hash, timestamp) VALUES ($1, $2, NOW())
+        "INSERT INTO receipts (receipt_id, receipt_hash, timestamp) VALUES ($1, $2, clock_timestamp())
          ON CONFLICT (receipt_id) DO NOTHING"
     )
     .bind(receipt_id)
     .bind(receipt_hash)
-    .execute(pool)
+    .execute(executor)
     .await?;
 
     Ok(())
 }
 
 /// Create or update a trace record
-pub async fn upsert_trace(pool: &PgPool, receipt: &Receipt) -> Result<()> {
+pub async fn upsert_trace<'e>(executor: impl PgExecutor<'e>, receipt: &Receipt) -> Result<()> {
     sqlx::query(
         r#"
         INSERT INTO traces (
@@ -44,51 +48,117 @@ pub async fn upsert_trace(pool: &PgPool, receipt: &Receipt) -> Result<()> {
             event_count, policy_deny_count, initiating_agent_id,
             initiating_developer_id, initiating_enterprise_id
         ) VALUES ($1, $2, $3, $3, 'active', 0, 0, $4, $5, $6)
-        ON CONFLICT (trace_id) DO NOTHING
+        ON CONFLICT (trace_id) DO UPDATE SET
+            initiating_agent_id = EXCLUDED.initiating_agent_id,
+            initiating_developer_id = EXCLUDED.initiating_developer_id,
+            initiating_enterprise_id = EXCLUDED.initiating_enterprise_id
+        WHERE traces.initiating_agent_id IS NULL
         "#
     )
     .bind(receipt.trace_id)
     .bind(&receipt.correlation_id)
     .bind(receipt.timestamp)
     .bind(&receipt.agent_id)
-    .bind(&receipt.identity_result.developer_id)
-    .bind(&receipt.identity_result.enterprise_id)
-    .execute(pool)
+    .bind(receipt.identity_result.developer_id)
+    .bind(receipt.identity_result.enterprise_id)
+    .execute(executor)
     .await?;
 
     Ok(())
 }
 
-/// Store a full receipt event
-pub async fn store_receipt_event(pool: &PgPool, receipt: &Receipt) -> Result<()> {
-    let event_type_str = match receipt.event_type {
-        EventType::GatewayRequest => "gateway_request",
-        EventType::PolicyEvaluation => "policy_evaluation",
-        EventType::IdentityValidation => "identity_validation",
-        EventType::ExternalEvent => "external_event",
-        EventType::HumanAction => "human_action",
+/// A stored receipt event, as it was written (with any out-of-row metadata restored)
+pub async fn get_receipt_event<'e>(executor: impl PgExecutor<'e>, receipt_id: Uuid) -> Result<Option<Receipt>> {
+    let row: Option<(serde_json::Value,)> = sqlx::query_as(
+        r#"
+        SELECT CASE WHEN m.metadata IS NULL THEN e.full_receipt
+                    ELSE jsonb_set(e.full_receipt, '{metadata}', m.metadata) END
+        FROM receipt_events e
+        LEFT JOIN receipt_metadata m ON m.receipt_id = e.receipt_id
+        WHERE e.receipt_id = $1
+        "#
+    )
+    .bind(receipt_id)
+    .fetch_optional(executor)
+    .await?;
+
+    row.map(|(full_receipt,)| serde_json::from_value(full_receipt).map_err(Into::into))
+        .transpose()
+}
+
+/// Split metadata larger than `overflow_bytes` off a receipt's row: it is removed from `full_receipt`
+/// and returned, with its serialized size, to be stored in `receipt_metadata`
+fn split_overflow_metadata<'a>(
+    metadata: Option<&'a serde_json::Value>,
+    full_receipt: &mut serde_json::Value,
+    overflow_bytes: Option<usize>,
+) -> Result<Option<(&'a serde_json::Value, i32)>> {
+    let (Some(metadata), Some(limit)) = (metadata, overflow_bytes) else {
+        return Ok(None);
     };
+    let size = serde_json::to_vec(metadata)?.len();
+    if size <= limit {
+        return Ok(None);
+    }
+    if let Some(fields) = full_receipt.as_object_mut() {
+        fields.remove("metadata");
+    }
+    Ok(Some((metadata, i32::try_from(size).unwrap_or(i32::MAX))))
+}
 
-    let full_receipt = serde_json::to_value(receipt)?;
+/// Store a full receipt event. Returns `false`, storing nothing, when a receipt with the same
+/// `idempotency_key` is already stored
+pub async fn store_receipt_event<'e>(
+    executor: impl PgExecutor<'e>,
+    receipt: &Receipt,
+    idempotency_key: Option<&str>,
+    metadata_overflow_bytes: Option<usize>,
+) -> Result<bool> {
+
+    let mut full_receipt = serde_json::to_value(receipt)?;
+    let overflow = split_overflow_metadata(receipt.metadata.as_ref(), &mut full_receipt, metadata_overflow_bytes)?;
     let headers_json = serde_json::to_value(&receipt.request.headers)?;
+    let latency = LatencyColumns::from(receipt.latency.as_ref());
+    let on_behalf_of = if receipt.on_behalf_of.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_value(&receipt.on_behalf_of)?)
+    };
+    let usage = receipt.usage.as_ref();
 
-    sqlx::query(
+    // The overflow row is written in the same statement, so the receipt is never stored without it
+    let inserted: Option<(Uuid,)> = sqlx::query_as(
         r#"
-        INSERT INTO receipt_events (
-            receipt_id, trace_id, correlation_id, span_id, parent_span_id,
-            timestamp, event_type, event_source_system, event_source_service, event_source_version,
-            agent_id, developer_id, enterprise_id,
-            request_method, request_path, request_headers, request_body_hash,
-            policy_allowed, policy_version, policy_evaluation_ms, identity_valid,
-            metadata, full_receipt, receipt_hash, previous_receipt_hash
-        ) VALUES (
-            $1, $2, $3, $4, $5,
-            $6, $7, $8, $9, $10,
-            $11, $12, $13,
-            $14, $15, $16, $17,
-            $18, $19, $20, $21,
-            $22, $23, $24, $25
+        WITH inserted AS (
+            INSERT INTO receipt_events (
+                receipt_id, trace_id, correlation_id, span_id, parent_span_id,
+                timestamp, event_type, event_source_system, event_source_service, event_source_version,
+                agent_id, developer_id, enterprise_id,
+                request_method, request_path, request_headers, request_body_hash,
+                policy_allowed, policy_version, policy_evaluation_ms, identity_valid,
+                metadata, full_receipt, receipt_hash, previous_receipt_hash,
+                received_at, identity_ms, policy_ms, upstream_ms, total_ms, on_behalf_of,
+                usage_input_tokens, usage_output_tokens, usage_cost, metadata_size_bytes,
+                signature, signature_public_key, idempotency_key, hash_algorithm
+            ) VALUES (
+                $1, $2, $3, $4, $5,
+                $6, $7, $8, $9, $10,
+                $11, $12, $13,
+                $14, $15, $16, $17,
+                $18, $19, $20, $21,
+                $22, $23, $24, $25,
+                $26, $27, $28, $29, $30, $31,
+                $32, $33, $34, $36,
+                $37, $38, $39, $40
+            )
+            ON CONFLICT (idempotency_key) DO NOTHING
+            RETURNING receipt_id
+        ),
+        overflow AS (
+            INSERT INTO receipt_metadata (receipt_id, metadata)
+            SELECT receipt_id, $35 FROM inserted WHERE $35::jsonb IS NOT NULL
         )
+        SELECT receipt_id FROM inserted
         "#
     )
     .bind(receipt.receipt_id)
@@ -97,13 +167,13 @@ pub async fn store_receipt_event(pool: &PgPool, receipt: &Receipt) -> Result<()>
     .bind(receipt.span_id)
     .bind(receipt.parent_span_id)
     .bind(receipt.timestamp)
-    .bind(event_type_str)
+    .bind(receipt.event_type.as_str())
     .bind(&receipt.event_source.system)
     .bind(&receipt.event_source.service)
     .bind(&receipt.event_source.version)
     .bind(&receipt.agent_id)
-    .bind(&receipt.identity_result.developer_id)
-    .bind(&receipt.identity_result.enterprise_id)
+    .bind(receipt.identity_result.developer_id)
+    .bind(receipt.identity_result.enterprise_id)
     .bind(&receipt.request.method)
     .bind(&receipt.request.path)
     .bind(&headers_json)
@@ -112,23 +182,101 @@ pub async fn store_receipt_event(pool: &PgPool, receipt: &Receipt) -> Result<()>
     .bind(&receipt.policy_result.policy_version)
     .bind(receipt.policy_result.evaluation_time_ms as i32)
     .bind(receipt.identity_result.valid)
-    .bind(&receipt.metadata)
+    .bind(if overflow.is_some() { None } else { receipt.metadata.as_ref() })
     .bind(&full_receipt)
     .bind(&receipt.receipt_hash)
     .bind(&receipt.previous_receipt_hash)
-    .execute(pool)
+    .bind(receipt.received_at)
+    .bind(latency.identity_ms)
+    .bind(latency.policy_ms)
+    .bind(latency.upstream_ms)
+    .bind(latency.total_ms)
+    .bind(on_behalf_of)
+    .bind(usage.and_then(|u| u.input_tokens).map(|t| t as i64))
+    .bind(usage.and_then(|u| u.output_tokens).map(|t| t as i64))
+    .bind(usage.and_then(|u| u.cost).and_then(|c| Decimal::try_from(c).ok()).map(|c| c.round_dp(8)))
+    .bind(overflow.map(|(metadata, _)| metadata))
+    .bind(overflow.map(|(_, size_bytes)| size_bytes))
+    .bind(&receipt.signature)
+    .bind(&receipt.signature_public_key)
+    .bind(idempotency_key)
+    .bind(&receipt.hash_algorithm)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(inserted.is_some())
+}
+
+/// The receipt stored under a client's idempotency key, with out-of-row metadata joined back in
+pub async fn get_receipt_by_idempotency_key<'e>(
+    executor: impl PgExecutor<'e>,
+    idempotency_key: &str,
+) -> Result<Option<Receipt>> {
+    let row: Option<(serde_json::Value,)> = sqlx::query_as(
+        r#"
+        SELECT CASE WHEN m.metadata IS NULL THEN e.full_receipt
+                    ELSE jsonb_set(e.full_receipt, '{metadata}', m.metadata) END
+        FROM receipt_events e
+        LEFT JOIN receipt_metadata m ON m.receipt_id = e.receipt_id
+        WHERE e.idempotency_key = $1
+        "#
+    )
+    .bind(idempotency_key)
+    .fetch_optional(executor)
+    .await?;
+
+    row.map(|(receipt,)| serde_json::from_value(receipt).map_err(Into::into))
+        .transpose()
+}
+
+/// Gateway latency phases as nullable integer columns
+struct LatencyColumns {
+    identity_ms: Option<i32>,
+    policy_ms: Option<i32>,
+    upstream_ms: Option<i32>,
+    total_ms: Option<i32>,
+}
+
+impl From<Option<&LatencyBreakdown>> for LatencyColumns {
+    fn from(latency: Option<&LatencyBreakdown>) -> Self {
+        Self {
+            identity_ms: latency.and_then(|l| l.identity_ms).map(|ms| ms as i32),
+            policy_ms: latency.and_then(|l| l.policy_ms).map(|ms| ms as i32),
+            upstream_ms: latency.and_then(|l| l.upstream_ms).map(|ms| ms as i32),
+            total_ms: latency.map(|l| l.total_ms as i32),
+        }
+    }
+}
+
+/// Start the trace an external event belongs to, if no receipt has yet. The row has no initiating agent
+/// until the trace's first receipt fills it in; counts and time bounds are kept by the insert trigger
+pub async fn upsert_trace_for_external_event<'e>(executor: impl PgExecutor<'e>, event: &ExternalEvent) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO traces (
+            trace_id, correlation_id, started_at, last_event_at, status,
+            event_count, policy_deny_count
+        ) VALUES ($1, $2, $3, $3, 'active', 0, 0)
+        ON CONFLICT (trace_id) DO NOTHING
+        "#
+    )
+    .bind(event.trace_id)
+    .bind(&event.correlation_id)
+    .bind(event.timestamp)
+    .execute(executor)
     .await?;
 
     Ok(())
 }
 
-/// Store an external event
-pub async fn store_external_event(pool: &PgPool, event: &ExternalEvent) -> Result<()> {
+/// Store an external event, unless one with the same `source_system` and `source_id` is already stored.
+/// Returns whether it was inserted
+pub async fn store_external_event<'e>(executor: impl PgExecutor<'e>, event: &ExternalEvent) -> Result<bool> {
     let actor_type = event.actor.as_ref().map(|a| format!("{:?}", a.actor_type).to_lowercase());
     let actor_id = event.actor.as_ref().map(|a| a.actor_id.clone());
     let actor_display_name = event.actor.as_ref().and_then(|a| a.display_name.clone());
 
-    sqlx::query(
+    let result = sqlx::query(
         r#"
         INSERT INTO external_events (
             event_id, trace_id, correlation_id,
@@ -136,12 +284,13 @@ pub async fn store_external_event(pool: &PgPool, event: &ExternalEvent) -> Resul
             actor_type, actor_id, actor_display_name,
             payload, metadata
         ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        ON CONFLICT (source_system, source_id) DO NOTHING
         "#
     )
     .bind(event.event_id)
     .bind(event.trace_id)
     .bind(&event.correlation_id)
-    .bind(&event.event_type)
+    .bind(event.event_type.as_str())
     .bind(&event.source_system)
     .bind(&event.source_id)
     .bind(event.timestamp)
@@ -150,7 +299,157 @@ pub async fn store_external_event(pool: &PgPool, event: &ExternalEvent) -> Resul
     .bind(actor_display_name)
     .bind(&event.payload)
     .bind(&event.metadata)
-    .execute(pool)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Find an already-ingested external event by its source system reference, returning its event and trace IDs
+pub async fn find_external_event_by_source<'e>(
+    executor: impl PgExecutor<'e>,
+    source_system: &str,
+    source_id: &str,
+) -> Result<Option<(Uuid, Uuid)>> {
+    let result = sqlx::query_as(
+        "SELECT event_id, trace_id FROM external_events WHERE source_system = $1 AND source_id = $2"
+    )
+    .bind(source_system)
+    .bind(source_id)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(result)
+}
+
+/// Check whether a trace exists
+pub async fn trace_exists<'e>(executor: impl PgExecutor<'e>, trace_id: Uuid) -> Result<bool> {
+    let result: Option<(Uuid,)> = sqlx::query_as(
+        "SELECT trace_id FROM traces WHERE trace_id = $1"
+    )
+    .bind(trace_id)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(result.is_some())
+}
+
+/// Get the tenant a trace belongs to, if any
+pub async fn get_trace_tenant_id(pool: &PgPool, trace_id: Uuid) -> Result<Option<Uuid>> {
+    let result: Option<(Option<Uuid>,)> = sqlx::query_as(
+        "SELECT tenant_id FROM traces WHERE trace_id = $1"
+    )
+    .bind(trace_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(result.and_then(|row| row.0))
+}
+
+// ========================================
+// Trace Sealing
+// ========================================
+
+/// Whether a trace has been sealed. Inside a transaction the trace row stays share-locked until it ends, so
+/// a concurrent seal (which takes `FOR UPDATE`) waits for the caller's inserts
+pub async fn is_trace_sealed<'e>(executor: impl PgExecutor<'e>, trace_id: Uuid) -> Result<bool> {
+    let result: Option<(bool,)> = sqlx::query_as(
+        "SELECT sealed_at IS NOT NULL FROM traces WHERE trace_id = $1 FOR SHARE"
+    )
+    .bind(trace_id)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(result.map(|row| row.0).unwrap_or(false))
+}
+
+/// Lock a trace row for sealing, returning whether it is already sealed (None if missing)
+pub async fn lock_trace_for_seal<'e>(executor: impl PgExecutor<'e>, trace_id: Uuid) -> Result<Option<bool>> {
+    let result: Option<(bool,)> = sqlx::query_as(
+        "SELECT sealed_at IS NOT NULL FROM traces WHERE trace_id = $1 FOR UPDATE"
+    )
+    .bind(trace_id)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(result.map(|row| row.0))
+}
+
+/// Lock a trace row for closing: its status and whether it is sealed, or `None` for an unknown trace
+pub async fn lock_trace_for_close<'e>(
+    executor: impl PgExecutor<'e>,
+    trace_id: Uuid,
+) -> Result<Option<(String, bool)>> {
+    let result: Option<(String, bool)> = sqlx::query_as(
+        "SELECT status, sealed_at IS NOT NULL FROM traces WHERE trace_id = $1 FOR UPDATE"
+    )
+    .bind(trace_id)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(result)
+}
+
+/// Close an active trace, returning its closed status and when it was closed
+pub async fn close_trace<'e>(
+    executor: impl PgExecutor<'e>,
+    trace_id: Uuid,
+) -> Result<(String, chrono::DateTime<chrono::Utc>)> {
+    let row: (String, chrono::DateTime<chrono::Utc>) = sqlx::query_as(
+        r#"
+        UPDATE traces
+        SET status = closed_trace_status(policy_deny_count, errored_receipt_count),
+            completed_at = NOW(),
+            updated_at = NOW()
+        WHERE trace_id = $1
+        RETURNING status, completed_at
+        "#
+    )
+    .bind(trace_id)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(row)
+}
+
+/// Receipt hashes for a trace in seal order (timestamp, then receipt_id)
+pub async fn get_trace_receipt_hashes<'e>(executor: impl PgExecutor<'e>, trace_id: Uuid) -> Result<Vec<String>> {
+    let rows: Vec<(String,)> = sqlx::query_as(
+        "SELECT receipt_hash FROM receipt_events WHERE trace_id = $1 ORDER BY timestamp ASC, receipt_id ASC"
+    )
+    .bind(trace_id)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| row.0).collect())
+}
+
+/// Close a trace and record its seal
+pub async fn mark_trace_sealed<'e>(
+    executor: impl PgExecutor<'e>,
+    trace_id: Uuid,
+    seal_root: &str,
+    receipt_count: i32,
+    seal_receipt_id: Uuid,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE traces
+        SET status = closed_trace_status(policy_deny_count, errored_receipt_count),
+            completed_at = COALESCE(completed_at, NOW()),
+            sealed_at = NOW(),
+            seal_root = $2,
+            seal_receipt_count = $3,
+            seal_receipt_id = $4,
+            updated_at = NOW()
+        WHERE trace_id = $1
+        "#
+    )
+    .bind(trace_id)
+    .bind(seal_root)
+    .bind(receipt_count)
+    .bind(seal_receipt_id)
+    .execute(executor)
     .await?;
 
     Ok(())
@@ -161,10 +460,12 @@ pub async fn store_external_event(pool: &PgPool, event: &ExternalEvent) -> Resul
 // ========================================
 
 /// Create or update a trace record with trust metrics (v2)
-pub async fn upsert_trace_v2(pool: &PgPool, receipt: &ReceiptV2) -> Result<()> {
-    let trust_score = receipt.trust_snapshot.as_ref().map(|ts| {
-        Decimal::try_from(ts.composite_score).unwrap_or(Decimal::new(5, 1))
-    });
+pub async fn upsert_trace_v2<'e>(executor: impl PgExecutor<'e>, receipt: &ReceiptV2, precision: u32) -> Result<()> {
+    let trust_score = receipt
+        .trust_snapshot
+        .as_ref()
+        .map(|ts| score_to_decimal(ts.composite_score, precision))
+        .transpose()?;
 
     sqlx::query(
         r#"
@@ -175,7 +476,11 @@ pub async fn upsert_trace_v2(pool: &PgPool, receipt: &ReceiptV2) -> Result<()> {
             tenant_id, min_trust_score, avg_trust_score, trust_violations
         ) VALUES ($1, $2, $3, $3, 'active', 0, 0, $4, $5, $6, $7, $8, $8, 0)
         ON CONFLICT (trace_id) DO UPDATE SET
-            last_event_at = EXCLUDED.last_event_at,
+            last_event_at = GREATEST(traces.last_event_at, EXCLUDED.last_event_at),
+            initiating_agent_id = COALESCE(traces.initiating_agent_id, EXCLUDED.initiating_agent_id),
+            initiating_developer_id = COALESCE(traces.initiating_developer_id, EXCLUDED.initiating_developer_id),
+            initiating_enterprise_id = COALESCE(traces.initiating_enterprise_id, EXCLUDED.initiating_enterprise_id),
+            tenant_id = COALESCE(traces.tenant_id, EXCLUDED.tenant_id),
             min_trust_score = LEAST(traces.min_trust_score, EXCLUDED.min_trust_score),
             avg_trust_score = (COALESCE(traces.avg_trust_score, 0) * traces.event_count + COALESCE(EXCLUDED.avg_trust_score, 0)) / (traces.event_count + 1)
         "#
@@ -184,33 +489,35 @@ pub async fn upsert_trace_v2(pool: &PgPool, receipt: &ReceiptV2) -> Result<()> {
     .bind(&receipt.correlation_id)
     .bind(receipt.timestamp)
     .bind(&receipt.agent_id)
-    .bind(&receipt.identity_result.developer_id)
-    .bind(&receipt.identity_result.enterprise_id)
+    .bind(receipt.identity_result.developer_id)
+    .bind(receipt.identity_result.enterprise_id)
     .bind(receipt.tenant_id)
     .bind(trust_score)
-    .execute(pool)
+    .execute(executor)
     .await?;
 
     Ok(())
 }
 
 /// Store a full receipt event with trust and attribution (v2)
-pub async fn store_receipt_event_v2(pool: &PgPool, receipt: &ReceiptV2) -> Result<()> {
-    let event_type_str = match receipt.event_type {
-        EventType::GatewayRequest => "gateway_request",
-        EventType::PolicyEvaluation => "policy_evaluation",
-        EventType::IdentityValidation => "identity_validation",
-        EventType::ExternalEvent => "external_event",
-        EventType::HumanAction => "human_action",
-    };
+pub async fn store_receipt_event_v2<'e>(
+    executor: impl PgExecutor<'e>,
+    receipt: &ReceiptV2,
+    precision: u32,
+    metadata_overflow_bytes: Option<usize>,
+) -> Result<()> {
 
-    let full_receipt = serde_json::to_value(receipt)?;
+    let mut full_receipt = serde_json::to_value(receipt)?;
+    let overflow = split_overflow_metadata(receipt.metadata.as_ref(), &mut full_receipt, metadata_overflow_bytes)?;
     let headers_json = serde_json::to_value(&receipt.request.headers)?;
+    let latency = LatencyColumns::from(receipt.latency.as_ref());
 
     // Extract trust score as decimal
-    let trust_score = receipt.trust_snapshot.as_ref().map(|ts| {
-        Decimal::try_from(ts.composite_score).unwrap_or(Decimal::new(5, 1))
-    });
+    let trust_score = receipt
+        .trust_snapshot
+        .as_ref()
+        .map(|ts| score_to_decimal(ts.composite_score, precision))
+        .transpose()?;
 
     // Extract trust dimensions as JSON
     let trust_dimensions = receipt.trust_snapshot.as_ref().map(|ts| {
@@ -221,9 +528,19 @@ pub async fn store_receipt_event_v2(pool: &PgPool, receipt: &ReceiptV2) -> Resul
     let attribution = receipt.attribution_snapshot.as_ref().map(|attr| {
         serde_json::to_value(attr).unwrap_or(serde_json::Value::Null)
     });
+    let on_behalf_of = if receipt.on_behalf_of.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_value(&receipt.on_behalf_of)?)
+    };
+    let usage = receipt.usage.as_ref();
 
     sqlx::query(
         r#"
+        WITH overflow AS (
+            INSERT INTO receipt_metadata (receipt_id, metadata)
+            SELECT $1, $39 WHERE $39::jsonb IS NOT NULL
+        )
         INSERT INTO receipt_events (
             receipt_id, trace_id, correlation_id, span_id, parent_span_id,
             timestamp, event_type, event_source_system, event_source_service, event_source_version,
@@ -231,7 +548,10 @@ pub async fn store_receipt_event_v2(pool: &PgPool, receipt: &ReceiptV2) -> Resul
             request_method, request_path, request_headers, request_body_hash,
             policy_allowed, policy_version, policy_evaluation_ms, identity_valid,
             metadata, full_receipt, receipt_hash, previous_receipt_hash,
-            tenant_id, trust_score_at_event, trust_dimensions_at_event, attribution
+            tenant_id, trust_score_at_event, trust_dimensions_at_event, attribution,
+            received_at, identity_ms, policy_ms, upstream_ms, total_ms, on_behalf_of,
+            usage_input_tokens, usage_output_tokens, usage_cost, metadata_size_bytes,
+            signature, signature_public_key, hash_algorithm
         ) VALUES (
             $1, $2, $3, $4, $5,
             $6, $7, $8, $9, $10,
@@ -239,7 +559,10 @@ pub async fn store_receipt_event_v2(pool: &PgPool, receipt: &ReceiptV2) -> Resul
             $14, $15, $16, $17,
             $18, $19, $20, $21,
             $22, $23, $24, $25,
-            $26, $27, $28, $29
+            $26, $27, $28, $29,
+            $30, $31, $32, $33, $34, $35,
+            $36, $37, $38, $40,
+            $41, $42, $43
         )
         "#
     )
@@ -249,13 +572,13 @@ pub async fn store_receipt_event_v2(pool: &PgPool, receipt: &ReceiptV2) -> Resul
     .bind(receipt.span_id)
     .bind(receipt.parent_span_id)
     .bind(receipt.timestamp)
-    .bind(event_type_str)
+    .bind(receipt.event_type.as_str())
     .bind(&receipt.event_source.system)
     .bind(&receipt.event_source.service)
     .bind(&receipt.event_source.version)
     .bind(&receipt.agent_id)
-    .bind(&receipt.identity_result.developer_id)
-    .bind(&receipt.identity_result.enterprise_id)
+    .bind(receipt.identity_result.developer_id)
+    .bind(receipt.identity_result.enterprise_id)
     .bind(&receipt.request.method)
     .bind(&receipt.request.path)
     .bind(&headers_json)
@@ -264,7 +587,7 @@ pub async fn store_receipt_event_v2(pool: &PgPool, receipt: &ReceiptV2) -> Resul
     .bind(&receipt.policy_result.policy_version)
     .bind(receipt.policy_result.evaluation_time_ms as i32)
     .bind(receipt.identity_result.valid)
-    .bind(&receipt.metadata)
+    .bind(if overflow.is_some() { None } else { receipt.metadata.as_ref() })
     .bind(&full_receipt)
     .bind(&receipt.receipt_hash)
     .bind(&receipt.previous_receipt_hash)
@@ -272,14 +595,28 @@ pub async fn store_receipt_event_v2(pool: &PgPool, receipt: &ReceiptV2) -> Resul
     .bind(trust_score)
     .bind(trust_dimensions)
     .bind(attribution)
-    .execute(pool)
+    .bind(receipt.received_at)
+    .bind(latency.identity_ms)
+    .bind(latency.policy_ms)
+    .bind(latency.upstream_ms)
+    .bind(latency.total_ms)
+    .bind(on_behalf_of)
+    .bind(usage.and_then(|u| u.input_tokens).map(|t| t as i64))
+    .bind(usage.and_then(|u| u.output_tokens).map(|t| t as i64))
+    .bind(usage.and_then(|u| u.cost).and_then(|c| Decimal::try_from(c).ok()).map(|c| c.round_dp(8)))
+    .bind(overflow.map(|(metadata, _)| metadata))
+    .bind(overflow.map(|(_, size_bytes)| size_bytes))
+    .bind(&receipt.signature)
+    .bind(&receipt.signature_public_key)
+    .bind(&receipt.hash_algorithm)
+    .execute(executor)
     .await?;
 
     Ok(())
 }
 
 /// Store a trust event for auditing
-pub async fn store_trust_event(pool: &PgPool, event: &TrustEvent) -> Result<()> {
+pub async fn store_trust_event<'e>(executor: impl PgExecutor<'e>, event: &TrustEvent, precision: u32) -> Result<()> {
     let event_type_str = match event.event_type {
         TrustEventType::ScoreChecked => "score_checked",
         TrustEventType::ThresholdViolation => "threshold_violation",
@@ -287,12 +624,13 @@ pub async fn store_trust_event(pool: &PgPool, event: &TrustEvent) -> Result<()>
         TrustEventType::ScoreUpdated => "score_updated",
     };
 
-    let previous_score = event.previous_score.map(|s| {
-        Decimal::try_from(s).unwrap_or(Decimal::new(5, 1))
-    });
+    let previous_score = event
+        .previous_score
+        .map(|s| score_to_decimal(s, precision))
+        .transpose()?;
 
-    let new_score = Decimal::try_from(event.new_score).unwrap_or(Decimal::new(5, 1));
-    let threshold = Decimal::try_from(event.threshold).unwrap_or(Decimal::new(3, 1));
+    let new_score = score_to_decimal(event.new_score, precision)?;
+    let threshold = score_to_decimal(event.threshold, precision)?;
 
     sqlx::query(
         r#"
@@ -313,14 +651,18 @@ pub async fn store_trust_event(pool: &PgPool, event: &TrustEvent) -> Result<()>
     .bind(event.passed)
     .bind(&event.action_taken)
     .bind(&event.details)
-    .execute(pool)
+    .execute(executor)
     .await?;
 
     Ok(())
 }
 
 /// Get trust events for a trace
-pub async fn get_trust_events_for_trace(pool: &PgPool, trace_id: Uuid) -> Result<Vec<TrustEvent>> {
+pub async fn get_trust_events_for_trace(
+    pool: &PgPool,
+    trace_id: Uuid,
+    precision: u32,
+) -> Result<Vec<TrustEvent>> {
     let rows: Vec<TrustEventRow> = sqlx::query_as(
         r#"
         SELECT event_id, trace_id, agent_id, event_type, timestamp,
@@ -334,7 +676,9 @@ pub async fn get_trust_events_for_trace(pool: &PgPool, trace_id: Uuid) -> Result
     .fetch_all(pool)
     .await?;
 
-    Ok(rows.into_iter().map(|row| row.into()).collect())
+    rows.into_iter()
+        .map(|row| row.into_trust_event(precision))
+        .collect()
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -352,33 +696,36 @@ struct TrustEventRow {
     details: serde_json::Value,
 }
 
-impl From<TrustEventRow> for TrustEvent {
-    fn from(row: TrustEventRow) -> Self {
-        let event_type = match row.event_type.as_str() {
+impl TrustEventRow {
+    fn into_trust_event(self, precision: u32) -> Result<TrustEvent> {
+        let event_type = match self.event_type.as_str() {
             "threshold_violation" => TrustEventType::ThresholdViolation,
             "trust_warning" => TrustEventType::TrustWarning,
             "score_updated" => TrustEventType::ScoreUpdated,
             _ => TrustEventType::ScoreChecked,
         };
 
-        TrustEvent {
-            event_id: row.event_id,
-            trace_id: row.trace_id,
-            agent_id: row.agent_id,
+        Ok(TrustEvent {
+            event_id: self.event_id,
+            trace_id: self.trace_id,
+            agent_id: self.agent_id,
             event_type,
-            timestamp: row.timestamp,
-            previous_score: row.previous_score.and_then(|d| d.to_f64()),
-            new_score: row.new_score.to_f64().unwrap_or(0.5),
-            threshold: row.threshold.to_f64().unwrap_or(0.3),
-            passed: row.passed,
-            action_taken: row.action_taken,
-            details: row.details,
-        }
+            timestamp: self.timestamp,
+            previous_score: self
+                .previous_score
+                .map(|d| score_to_f64(d, precision))
+                .transpose()?,
+            new_score: score_to_f64(self.new_score, precision)?,
+            threshold: score_to_f64(self.threshold, precision)?,
+            passed: self.passed,
+            action_taken: self.action_taken,
+            details: self.details,
+        })
     }
 }
 
 /// Update trace trust violations count
-pub async fn increment_trust_violations(pool: &PgPool, trace_id: Uuid) -> Result<()> {
+pub async fn increment_trust_violations<'e>(executor: impl PgExecutor<'e>, trace_id: Uuid) -> Result<()> {
     sqlx::query(
         r#"
         UPDATE traces SET trust_violations = COALESCE(trust_violations, 0) + 1
@@ -386,8 +733,158 @@ pub async fn increment_trust_violations(pool: &PgPool, trace_id: Uuid) -> Result
         "#
     )
     .bind(trace_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Record a trust violation for an agent, returning its consecutive violation count.
+/// The count restarts when the previous violation is older than `window_secs`.
+pub async fn record_trust_violation<'e>(
+    executor: impl PgExecutor<'e>,
+    agent_id: &str,
+    at: chrono::DateTime<chrono::Utc>,
+    window_secs: i64,
+) -> Result<i32> {
+    let (count,): (i32,) = sqlx::query_as(
+        r#"
+        INSERT INTO agent_trust_violations (agent_id, consecutive_violations, first_violation_at, last_violation_at)
+        VALUES ($1, 1, $2, $2)
+        ON CONFLICT (agent_id) DO UPDATE SET
+            consecutive_violations = CASE
+                WHEN agent_trust_violations.consecutive_violations = 0
+                  OR agent_trust_violations.last_violation_at < $2 - make_interval(secs => $3)
+                THEN 1
+                ELSE agent_trust_violations.consecutive_violations + 1
+            END,
+            first_violation_at = CASE
+                WHEN agent_trust_violations.consecutive_violations = 0
+                  OR agent_trust_violations.last_violation_at < $2 - make_interval(secs => $3)
+                THEN $2
+                ELSE agent_trust_violations.first_violation_at
+            END,
+            last_violation_at = $2,
+            updated_at = NOW()
+        RETURNING consecutive_violations
+        "#
+    )
+    .bind(agent_id)
+    .bind(at)
+    .bind(window_secs as f64)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(count)
+}
+
+/// Reset an agent's consecutive violation count after a passing trust check
+pub async fn reset_trust_violations<'e>(executor: impl PgExecutor<'e>, agent_id: &str) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE agent_trust_violations
+        SET consecutive_violations = 0, updated_at = NOW()
+        WHERE agent_id = $1 AND consecutive_violations > 0
+        "#
+    )
+    .bind(agent_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Store a risk event raised by trust escalation in the Identity Registry's `trust_risk_events`, against the
+/// agent's registry ID. Returns false, storing nothing, if the agent isn't registered
+pub async fn store_risk_event<'e>(executor: impl PgExecutor<'e>, event: &TrustRiskEvent) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO trust_risk_events (
+            id, entity_type, entity_id, risk_type, severity, description, evidence, trace_id, created_at
+        )
+        SELECT $1, 'agent', a.id, $3, $4::risk_severity, $5, $6, $7, $8
+        FROM agents a
+        WHERE a.agent_id = $2
+        "#
+    )
+    .bind(event.risk_id)
+    .bind(&event.agent_id)
+    .bind(&event.risk_type)
+    .bind(&event.severity)
+    .bind(&event.description)
+    .bind(&event.evidence)
+    .bind(event.trace_id)
+    .bind(event.created_at)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Record the S3 bucket and object key a receipt was archived under, confirming it archived
+pub async fn set_receipt_s3_location(
+    pool: &PgPool,
+    receipt_id: Uuid,
+    s3_bucket: &str,
+    s3_key: &str,
+) -> Result<()> {
+    sqlx::query(
+        "UPDATE receipt_events SET s3_bucket = $2, s3_key = $3, archived_at = NOW() WHERE receipt_id = $1",
+    )
+    .bind(receipt_id)
+    .bind(s3_bucket)
+    .bind(s3_key)
     .execute(pool)
     .await?;
 
     Ok(())
 }
+
+/// Record the batch object a set of receipts was archived to together
+pub async fn set_receipts_s3_location(
+    pool: &PgPool,
+    receipt_ids: &[Uuid],
+    s3_bucket: &str,
+    s3_key: &str,
+) -> Result<()> {
+    sqlx::query(
+        "UPDATE receipt_events SET s3_bucket = $2, s3_key = $3, archived_at = NOW() WHERE receipt_id = ANY($1)",
+    )
+    .bind(receipt_ids)
+    .bind(s3_bucket)
+    .bind(s3_key)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Archive location and access scope of a receipt
+#[derive(Debug, sqlx::FromRow)]
+pub struct ReceiptArchiveLocation {
+    pub agent_id: String,
+    pub tenant_id: Option<Uuid>,
+    pub visibility_scope: Option<String>,
+    pub s3_bucket: Option<String>,
+    pub s3_key: Option<String>,
+}
+
+/// Look up where a receipt was archived and who may read it
+pub async fn get_receipt_archive_location(
+    pool: &PgPool,
+    receipt_id: Uuid,
+) -> Result<Option<ReceiptArchiveLocation>> {
+    let location = sqlx::query_as::<_, ReceiptArchiveLocation>(
+        r#"
+        SELECT agent_id, tenant_id, attribution->>'audit_visibility_scope' AS visibility_scope,
+               s3_bucket, s3_key
+        FROM receipt_events
+        WHERE receipt_id = $1
+        "#
+    )
+    .bind(receipt_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(location)
+}
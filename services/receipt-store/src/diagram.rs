@@ -0,0 +1,134 @@
+use std::fmt::Write;
+
+use crate::queries::DecisionTree;
+
+const ALLOW_FILL: &str = "#c8e6c9";
+const ALLOW_STROKE: &str = "#2e7d32";
+const DENY_FILL: &str = "#ffcdd2";
+const DENY_STROKE: &str = "#c62828";
+
+/// Renderable formats for a trace's decision tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagramFormat {
+    Json,
+    Dot,
+    Mermaid,
+}
+
+impl DiagramFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "dot" => Some(Self::Dot),
+            "mermaid" => Some(Self::Mermaid),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Dot => "text/vnd.graphviz; charset=utf-8",
+            Self::Mermaid => "text/plain; charset=utf-8",
+        }
+    }
+}
+
+fn dot_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Mermaid has no escape character; quotes are written as entity codes
+fn mermaid_escape(value: &str) -> String {
+    value.replace('"', "#quot;")
+}
+
+/// Mermaid treats `-` as part of link syntax, so node IDs use underscores
+fn mermaid_id(id: &str) -> String {
+    id.replace('-', "_")
+}
+
+/// Serialize a decision tree as a Graphviz DOT digraph, green for allow and red for deny
+pub fn to_dot(tree: &DecisionTree) -> String {
+    let mut out = String::from("digraph decisions {\n    rankdir=LR;\n    node [shape=box, style=filled];\n");
+
+    for node in &tree.nodes {
+        let (fill, stroke) = if node.outcome {
+            (ALLOW_FILL, ALLOW_STROKE)
+        } else {
+            (DENY_FILL, DENY_STROKE)
+        };
+        let _ = writeln!(
+            out,
+            "    \"{}\" [label=\"{}\", fillcolor=\"{}\", color=\"{}\"];",
+            dot_escape(&node.id),
+            dot_escape(&node.label),
+            fill,
+            stroke
+        );
+    }
+
+    for edge in &tree.edges {
+        let _ = match edge.label {
+            Some(ref label) => writeln!(
+                out,
+                "    \"{}\" -> \"{}\" [label=\"{}\"];",
+                dot_escape(&edge.from),
+                dot_escape(&edge.to),
+                dot_escape(label)
+            ),
+            None => writeln!(
+                out,
+                "    \"{}\" -> \"{}\";",
+                dot_escape(&edge.from),
+                dot_escape(&edge.to)
+            ),
+        };
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+/// Serialize a decision tree as a Mermaid flowchart, green for allow and red for deny
+pub fn to_mermaid(tree: &DecisionTree) -> String {
+    let mut out = String::from("flowchart LR\n");
+
+    for node in &tree.nodes {
+        let _ = writeln!(
+            out,
+            "    {}[\"{}\"]",
+            mermaid_id(&node.id),
+            mermaid_escape(&node.label)
+        );
+    }
+
+    for edge in &tree.edges {
+        let _ = match edge.label {
+            Some(ref label) => writeln!(
+                out,
+                "    {} -->|\"{}\"| {}",
+                mermaid_id(&edge.from),
+                mermaid_escape(label),
+                mermaid_id(&edge.to)
+            ),
+            None => writeln!(out, "    {} --> {}", mermaid_id(&edge.from), mermaid_id(&edge.to)),
+        };
+    }
+
+    let _ = writeln!(out, "    classDef allow fill:{},stroke:{}", ALLOW_FILL, ALLOW_STROKE);
+    let _ = writeln!(out, "    classDef deny fill:{},stroke:{}", DENY_FILL, DENY_STROKE);
+    for (class, outcome) in [("allow", true), ("deny", false)] {
+        let ids: Vec<String> = tree
+            .nodes
+            .iter()
+            .filter(|n| n.outcome == outcome)
+            .map(|n| mermaid_id(&n.id))
+            .collect();
+        if !ids.is_empty() {
+            let _ = writeln!(out, "    class {} {}", ids.join(","), class);
+        }
+    }
+
+    out
+}
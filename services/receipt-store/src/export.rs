@@ -0,0 +1,217 @@
+//! Bulk receipt export for a tenant, streamed rather than built in memory. Receipts are read from
+//! `receipt_events` a page at a time, keyset paginated on `(timestamp, receipt_id)`, and each page is
+//! written to the response before the next is read, so memory stays flat however many rows match.
+//!
+//! A receipt belongs to a tenant as it does for cost reporting: by its own `tenant_id`, else its trace's.
+//! A database error after the response has started can't change its status, so it aborts the body;
+//! clients should treat a response that ends without a complete last line as failed.
+
+use anyhow::Result;
+use axum::body::Bytes;
+use chrono::{DateTime, Utc};
+use futures_util::stream::{self, Stream};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::borrow::Cow;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Receipts read from the database per page
+const EXPORT_PAGE_SIZE: i64 = 1000;
+
+/// Columns of a CSV export, in order
+const CSV_HEADER: &str = "receipt_id,trace_id,timestamp,agent_id,request_method,request_path,\
+policy_allowed,policy_version,identity_valid,receipt_hash,previous_receipt_hash";
+
+/// Query parameters for a receipt export; `[from, to)` is unbounded on either side when unset
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    pub tenant_id: Uuid,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// `ndjson` (default) or `csv`
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One full receipt, as stored, per line
+    Ndjson,
+    /// A header row, then the key fields of one receipt per row
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "ndjson" => Some(Self::Ndjson),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Ndjson => "application/x-ndjson",
+            Self::Csv => "text/csv; charset=utf-8",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Ndjson => "ndjson",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ExportRow {
+    receipt_id: Uuid,
+    trace_id: Uuid,
+    timestamp: DateTime<Utc>,
+    agent_id: Option<String>,
+    request_method: Option<String>,
+    request_path: Option<String>,
+    policy_allowed: Option<bool>,
+    policy_version: Option<String>,
+    identity_valid: Option<bool>,
+    receipt_hash: String,
+    previous_receipt_hash: Option<String>,
+    /// The full receipt with out-of-row metadata joined back in; only read for NDJSON
+    receipt: Option<serde_json::Value>,
+}
+
+/// Where an export has got to
+struct ExportState {
+    pool: PgPool,
+    query: ExportQuery,
+    format: ExportFormat,
+    /// `(timestamp, receipt_id)` of the last receipt written
+    after: Option<(DateTime<Utc>, Uuid)>,
+    header_written: bool,
+    done: bool,
+}
+
+/// The tenant's receipts in `format`, oldest first, one chunk per page
+pub fn stream_receipts(
+    pool: PgPool,
+    query: ExportQuery,
+    format: ExportFormat,
+) -> impl Stream<Item = Result<Bytes>> {
+    let state = ExportState {
+        pool,
+        query,
+        format,
+        after: None,
+        header_written: false,
+        done: false,
+    };
+
+    stream::unfold(state, |mut state| async move {
+        if state.done {
+            return None;
+        }
+        match state.next_chunk().await {
+            Ok(Some(chunk)) => Some((Ok(chunk), state)),
+            Ok(None) => None,
+            Err(e) => {
+                warn!("Receipt export for tenant {} failed: {}", state.query.tenant_id, e);
+                state.done = true;
+                Some((Err(e), state))
+            }
+        }
+    })
+}
+
+impl ExportState {
+    /// The next page rendered in the export's format, or `None` once there is nothing left to write
+    async fn next_chunk(&mut self) -> Result<Option<Bytes>> {
+        let rows: Vec<ExportRow> = sqlx::query_as(
+            r#"
+            SELECT e.receipt_id, e.trace_id, e.timestamp, e.agent_id, e.request_method, e.request_path,
+                   e.policy_allowed, e.policy_version, e.identity_valid, e.receipt_hash,
+                   e.previous_receipt_hash,
+                   CASE WHEN NOT $6 THEN NULL
+                        WHEN m.metadata IS NULL THEN e.full_receipt
+                        ELSE jsonb_set(e.full_receipt, '{metadata}', m.metadata) END AS receipt
+            FROM receipt_events e
+            JOIN traces t ON t.trace_id = e.trace_id
+            LEFT JOIN receipt_metadata m ON m.receipt_id = e.receipt_id
+            WHERE COALESCE(e.tenant_id, t.tenant_id) = $1
+              AND ($2::timestamptz IS NULL OR e.timestamp >= $2)
+              AND ($3::timestamptz IS NULL OR e.timestamp < $3)
+              AND ($4::timestamptz IS NULL OR (e.timestamp, e.receipt_id) > ($4, $5))
+            ORDER BY e.timestamp, e.receipt_id
+            LIMIT $7
+            "#
+        )
+        .bind(self.query.tenant_id)
+        .bind(self.query.from)
+        .bind(self.query.to)
+        .bind(self.after.map(|(timestamp, _)| timestamp))
+        .bind(self.after.map(|(_, receipt_id)| receipt_id))
+        .bind(self.format == ExportFormat::Ndjson)
+        .bind(EXPORT_PAGE_SIZE)
+        .fetch_all(&self.pool)
+        .await?;
+
+        self.done = (rows.len() as i64) < EXPORT_PAGE_SIZE;
+        if let Some(last) = rows.last() {
+            self.after = Some((last.timestamp, last.receipt_id));
+        }
+
+        let mut chunk = String::new();
+        if self.format == ExportFormat::Csv && !self.header_written {
+            // Written even for an empty export, so the file always has its columns
+            self.header_written = true;
+            chunk.push_str(CSV_HEADER);
+            chunk.push('\n');
+        }
+        for row in &rows {
+            match self.format {
+                ExportFormat::Ndjson => {
+                    let receipt = row.receipt.as_ref().unwrap_or(&serde_json::Value::Null);
+                    chunk.push_str(&serde_json::to_string(receipt)?);
+                    chunk.push('\n');
+                }
+                ExportFormat::Csv => write_csv_row(&mut chunk, row),
+            }
+        }
+
+        Ok((!chunk.is_empty()).then(|| Bytes::from(chunk)))
+    }
+}
+
+fn write_csv_row(out: &mut String, row: &ExportRow) {
+    let bool_field = |value: Option<bool>| value.map(|v| v.to_string()).unwrap_or_default();
+    let fields = [
+        row.receipt_id.to_string(),
+        row.trace_id.to_string(),
+        row.timestamp.to_rfc3339(),
+        row.agent_id.clone().unwrap_or_default(),
+        row.request_method.clone().unwrap_or_default(),
+        row.request_path.clone().unwrap_or_default(),
+        bool_field(row.policy_allowed),
+        row.policy_version.clone().unwrap_or_default(),
+        bool_field(row.identity_valid),
+        row.receipt_hash.clone(),
+        row.previous_receipt_hash.clone().unwrap_or_default(),
+    ];
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&csv_field(field));
+    }
+    out.push('\n');
+}
+
+/// A CSV field, quoted (with quotes doubled) when it holds a comma, quote or line break
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
@@ -0,0 +1,34 @@
+//! Digest algorithms receipts can be hashed with. The algorithm is recorded on each receipt (and in its
+//! `receipt_events` row) as `hash_algorithm`, so verification recomputes a hash the way it was written
+//! whatever the store is configured to use now. Receipts stored before the field existed are sha256.
+//! Digests are hex encoded: 64 characters for sha256 and blake3, 128 for sha512.
+
+use anyhow::{bail, Result};
+use sha2::{Digest, Sha256, Sha512};
+
+/// The algorithm receipts without a recorded `hash_algorithm` were hashed with
+pub const DEFAULT_HASH_ALGORITHM: &str = "sha256";
+
+/// Every algorithm `digest` accepts
+pub const HASH_ALGORITHMS: [&str; 3] = ["sha256", "sha512", "blake3"];
+
+/// The hex-encoded digest of `bytes` under `algo`
+pub fn digest(algo: &str, bytes: &[u8]) -> Result<String> {
+    let hash = match algo {
+        "sha256" => hex::encode(Sha256::digest(bytes)),
+        "sha512" => hex::encode(Sha512::digest(bytes)),
+        "blake3" => blake3::hash(bytes).to_hex().to_string(),
+        other => bail!("unsupported hash algorithm: {}", other),
+    };
+    Ok(hash)
+}
+
+/// Whether `algo` is one `digest` accepts
+pub fn is_supported(algo: &str) -> bool {
+    HASH_ALGORITHMS.contains(&algo)
+}
+
+/// Serde default for receipts stored before `hash_algorithm` was recorded
+pub fn default_hash_algorithm() -> String {
+    DEFAULT_HASH_ALGORITHM.to_string()
+}
@@ -0,0 +1,122 @@
+//! Periodic background jobs that are safe to run on every replica. Each run of a job happens while
+//! holding a Postgres advisory lock named after it, so one replica at a time does the work and the
+//! others skip that tick.
+//!
+//! The lock is a session lock held on its own connection, so it lasts only as long as the holder's
+//! session. While a job runs, the holder heartbeats on that connection, and the session asks the server
+//! to probe the holder with TCP keepalives at the same pace. A holder that crashes or hangs therefore
+//! loses its session, and with it the lock, within a few heartbeats, and another replica takes over.
+//! A holder whose heartbeat fails abandons the run, since it can no longer be sure the lock is still its own.
+
+use anyhow::{anyhow, Result};
+use sqlx::{PgConnection, PgPool};
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info, warn};
+
+/// First key of every job's advisory lock; the second is the hash of the job's name
+const JOB_LOCK_NAMESPACE: i32 = 0x5057_4A42;
+
+/// Keepalive probes the server sends an unresponsive holder before it drops the session
+const KEEPALIVE_PROBES: u32 = 3;
+
+/// A job run every `interval` on whichever replica gets its lock
+#[derive(Debug, Clone)]
+pub struct Job {
+    /// Names the job's lock, so it must be the same on every replica and unique among jobs
+    pub name: &'static str,
+    pub interval: Duration,
+    /// How often the lock holder proves it is alive while the job runs
+    pub heartbeat: Duration,
+}
+
+/// Start running `run` every `job.interval`, skipping ticks while another replica holds the job's lock
+pub fn spawn<F, Fut>(pool: PgPool, job: Job, mut run: F)
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<()>> + Send,
+{
+    info!(
+        "Background job {}: every {}s, heartbeat {}s",
+        job.name,
+        job.interval.as_secs(),
+        job.heartbeat.as_secs()
+    );
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(job.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            match run_exclusive(&pool, &job, run()).await {
+                Ok(Some(())) => {}
+                Ok(None) => debug!("Background job {} skipped; another replica holds the lock", job.name),
+                Err(e) => warn!("Background job {} failed: {}", job.name, e),
+            }
+        }
+    });
+}
+
+/// Run `work` under the job's lock: its result, or `None` when another replica holds the lock
+async fn run_exclusive<T>(pool: &PgPool, job: &Job, work: impl Future<Output = Result<T>>) -> Result<Option<T>> {
+    let mut conn = pool.acquire().await?;
+
+    // Ignored on Unix-socket connections, where the server notices a dead client anyway
+    let heartbeat_secs = job.heartbeat.as_secs().max(1).to_string();
+    sqlx::query(
+        "SELECT set_config('tcp_keepalives_idle', $1, false),
+                set_config('tcp_keepalives_interval', $1, false),
+                set_config('tcp_keepalives_count', $2, false)",
+    )
+    .bind(&heartbeat_secs)
+    .bind(KEEPALIVE_PROBES.to_string())
+    .execute(&mut *conn)
+    .await?;
+
+    let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1, hashtext($2))")
+        .bind(JOB_LOCK_NAMESPACE)
+        .bind(job.name)
+        .fetch_one(&mut *conn)
+        .await?;
+    if !locked {
+        return Ok(None);
+    }
+
+    let outcome = tokio::select! {
+        result = work => Ok(result),
+        lost = heartbeat(&mut conn, job.heartbeat) => Err(lost),
+    };
+
+    match outcome {
+        Ok(result) => {
+            let unlocked = sqlx::query("SELECT pg_advisory_unlock($1, hashtext($2))")
+                .bind(JOB_LOCK_NAMESPACE)
+                .bind(job.name)
+                .execute(&mut *conn)
+                .await;
+            if unlocked.is_err() {
+                // Closing the session releases the lock
+                drop(conn.detach());
+            }
+            result.map(Some)
+        }
+        Err(lost) => {
+            // Close the session in case it is still alive, so the lock is released either way
+            drop(conn.detach());
+            Err(lost)
+        }
+    }
+}
+
+/// Ping the lock's session every `every` until a ping fails or goes unanswered for as long
+async fn heartbeat(conn: &mut PgConnection, every: Duration) -> anyhow::Error {
+    loop {
+        tokio::time::sleep(every).await;
+        match tokio::time::timeout(every, sqlx::query("SELECT 1").execute(&mut *conn)).await {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => return anyhow!("lock heartbeat failed, abandoning the run: {}", e),
+            Err(_) => return anyhow!("lock heartbeat timed out, abandoning the run"),
+        }
+    }
+}
@@ -1,15 +1,23 @@
 use rdkafka::config::ClientConfig;
-use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
 use anyhow::Result;
+use std::collections::HashMap;
 use tracing::{info, error};
 
+use crate::receipt::EventType;
+
+/// Fan-out key for receipts whose request was denied, whatever their event type
+pub const DENIED_ROUTE: &str = "denied";
+
 pub struct KafkaProducer {
     producer: FutureProducer,
     topic: String,
+    /// Extra topics by event type (or `denied`), sent to in addition to the main topic
+    routes: HashMap<String, String>,
 }
 
 impl KafkaProducer {
-    pub fn new(brokers: &str, topic: &str) -> Result<Self> {
+    pub fn new(brokers: &str, topic: &str, routes: HashMap<String, String>) -> Result<Self> {
         let producer: FutureProducer = ClientConfig::new()
             .set("bootstrap.servers", brokers)
             .set("message.timeout.ms", "5000")
@@ -19,26 +27,99 @@ impl KafkaProducer {
         Ok(Self {
             producer,
             topic: topic.to_string(),
+            routes,
         })
     }
 
-    pub async fn send_receipt(&self, receipt_json: &str) -> Result<()> {
+    /// Confirm the brokers answer and know the main topic, without producing anything
+    pub async fn check(&self) -> Result<()> {
+        let producer = self.producer.clone();
         let topic = self.topic.clone();
+        // Metadata requests block the calling thread
+        tokio::task::spawn_blocking(move || {
+            let metadata = producer
+                .client()
+                .fetch_metadata(Some(&topic), std::time::Duration::from_secs(5))?;
+            match metadata.topics().iter().find(|t| t.name() == topic) {
+                Some(t) => match t.error() {
+                    None => Ok(()),
+                    Some(e) => Err(anyhow::anyhow!("Kafka topic {} unavailable: {:?}", topic, e)),
+                },
+                None => Err(anyhow::anyhow!("Kafka topic {} not found", topic)),
+            }
+        })
+        .await?
+    }
+
+    /// Wait up to `timeout` for messages still queued in the producer to be delivered, returning how many
+    /// were queued. Requests await their own sends, so at shutdown this only finds messages whose request
+    /// was cut short
+    pub async fn flush(&self, timeout: std::time::Duration) -> Result<usize> {
+        let producer = self.producer.clone();
+        // Flushing blocks the calling thread
+        tokio::task::spawn_blocking(move || {
+            let queued = producer.in_flight_count().max(0) as usize;
+            producer.flush(timeout)?;
+            Ok(queued)
+        })
+        .await?
+    }
+
+    /// Send under a random key, so consecutive messages spread across partitions with no ordering
+    /// between them. Used for external events; receipts go through `send_receipt_keyed`
+    pub async fn send_receipt(&self, receipt_json: &str, event_type: &EventType, denied: bool) -> Result<()> {
         let key = uuid::Uuid::new_v4().to_string();
-        let record = FutureRecord::to(&topic)
-            .key(&key)
-            .payload(receipt_json);
-
-        match self.producer.send(record, std::time::Duration::from_secs(0)).await {
-            Ok(_) => {
-                info!("Receipt sent to Kafka topic: {}", self.topic);
-                Ok(())
+        self.send_receipt_keyed(&key, receipt_json, event_type, denied).await
+    }
+
+    /// Send to the main topic and to every fan-out topic routed for the event type, or for
+    /// denials when `denied`; each topic gets the receipt once, under `key`. Messages with the same
+    /// key land on the same partition, so keying by trace ID keeps a trace's receipts in order
+    pub async fn send_receipt_keyed(
+        &self,
+        key: &str,
+        receipt_json: &str,
+        event_type: &EventType,
+        denied: bool,
+    ) -> Result<()> {
+        let mut topics = vec![self.topic.as_str()];
+        let routed = [Some(event_type.as_str()), denied.then_some(DENIED_ROUTE)];
+        for route in routed.into_iter().flatten() {
+            if let Some(topic) = self.routes.get(route) {
+                if !topics.contains(&topic.as_str()) {
+                    topics.push(topic);
+                }
             }
-            Err((e, _)) => {
-                error!("Failed to send receipt to Kafka: {}", e);
-                Err(anyhow::anyhow!("Kafka send error: {}", e))
+        }
+
+        let mut failed = Vec::new();
+        for topic in topics {
+            match self.send_to_topic(topic, key, receipt_json).await {
+                Ok(()) => info!("Receipt sent to Kafka topic: {}", topic),
+                Err(e) => {
+                    error!("Failed to send receipt to Kafka topic {}: {}", topic, e);
+                    failed.push(format!("{}: {}", topic, e));
+                }
             }
         }
+
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("Kafka send error: {}", failed.join("; ")))
+        }
     }
-}
 
+    /// Send one message to `topic` on these brokers, outside the receipt topic and its fan-out routes
+    pub async fn send_to_topic(&self, topic: &str, key: &str, payload: &str) -> Result<()> {
+        let record = FutureRecord::to(topic)
+            .key(key)
+            .payload(payload);
+
+        self.producer
+            .send(record, std::time::Duration::from_secs(0))
+            .await
+            .map(|_| ())
+            .map_err(|(e, _)| e.into())
+    }
+}
@@ -1,13 +1,16 @@
 use anyhow::Result;
-use tracing::info;
-use tracing_subscriber;
+use tracing::{info, warn};
 use axum::{
+    middleware,
     routing::{get, post},
     Router,
 };
 use std::sync::Arc;
 use tower_http::cors::{CorsLayer, Any};
 
+mod canonical;
+mod hashing;
+mod config;
 mod receipt;
 mod kafka_producer;
 mod s3_archiver;
@@ -15,15 +18,39 @@ mod db;
 mod store;
 mod api;
 mod queries;
+mod merkle;
+mod diagram;
+mod sinks;
+mod validation;
+mod rate_limit;
+mod body_capture;
+mod receipt_body;
+mod request_id;
+mod retention;
+mod selftest;
+mod jobs;
+mod metrics;
+mod archive_buffer;
+mod sweeper;
+mod signing;
+mod export;
+mod stats_cache;
 
 use api::{
-    store_receipt, store_receipt_v2, ingest_external_event,
-    list_traces, get_trace, get_trace_timeline, get_trace_decisions, lookup_by_correlation,
-    get_trace_trust_events,
+    store_receipt, store_receipts_batch, store_receipt_v2, store_receipt_negotiated, ingest_external_event,
+    ingest_external_events_batch, list_traces, get_trace, get_trace_timeline, get_trace_decisions, lookup_by_correlation,
+    get_trace_trust_events, close_trace, get_trace_merkle, get_receipt_merkle_proof, get_receipt_signature, validate_receipt, list_trace_external_events, get_external_event, seal_trace, get_agent_activity, get_agent_trust_trend, get_trace_latency, verify_trace_chain,
+    get_receipt, get_receipt_archive_url, get_tenant_cost, list_risk_events, get_purge_preview, run_purge,
+    export_receipts, get_stats, get_selftest, get_metrics,
 };
+use config::Config;
 use store::ReceiptStore;
-use kafka_producer::KafkaProducer;
-use s3_archiver::S3Archiver;
+use sinks::SinkRouter;
+use rate_limit::{limit_writes, RateLimiter};
+use request_id::request_id;
+
+/// How long shutdown waits for each Kafka producer to deliver what is still queued
+const KAFKA_SHUTDOWN_FLUSH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -31,42 +58,39 @@ async fn main() -> Result<()> {
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .init();
 
-    let kafka_brokers = std::env::var("KAFKA_BROKERS")
-        .unwrap_or_else(|_| "localhost:9092".to_string());
-    let kafka_topic = std::env::var("KAFKA_TOPIC")
-        .unwrap_or_else(|_| "pathwell-receipts".to_string());
-
-    let s3_bucket = std::env::var("S3_BUCKET")
-        .unwrap_or_else(|_| "pathwell-receipts".to_string());
-    let s3_region = std::env::var("S3_REGION")
-        .unwrap_or_else(|_| "us-east-1".to_string());
+    let config = Config::from_env();
 
-    let database_url = std::env::var("DATABASE_URL");
-    let db_pool = if let Ok(url) = database_url {
-        Some(sqlx::PgPool::connect(&url).await?)
+    let db_pool = if let Some(ref url) = config.database_url {
+        Some(sqlx::PgPool::connect(url).await?)
     } else {
         None
     };
 
-    let port = std::env::var("PORT")
-        .unwrap_or_else(|_| "3003".to_string())
-        .parse::<u16>()
-        .unwrap_or(3003);
+    let port = config.port;
 
     info!("Starting Receipt Store service on port {}", port);
-    info!("Kafka brokers: {}, topic: {}", kafka_brokers, kafka_topic);
-    info!("S3 bucket: {}, region: {}", s3_bucket, s3_region);
+    info!("Kafka brokers: {}, topic: {}", config.kafka_brokers, config.kafka_topic);
+    for (route, topic) in &config.kafka_topic_routes {
+        info!("Kafka fan-out: {} receipts also sent to {}", route, topic);
+    }
+    info!("S3 bucket: {}, region: {}", config.s3_bucket, config.s3_region);
 
-    // Initialize Kafka producer
-    let kafka = KafkaProducer::new(&kafka_brokers, &kafka_topic)?;
-    info!("Kafka producer initialized");
+    // Initialize Kafka producers and S3 archivers (global plus per-tenant overrides)
+    let sinks = SinkRouter::from_config(&config).await?;
+    info!("Sinks initialized ({} tenant override(s))", config.tenant_sinks.len());
 
-    // Initialize S3 archiver
-    let s3 = S3Archiver::new(&s3_bucket, &s3_region).await?;
-    info!("S3 archiver initialized");
+    let limiter = Arc::new(RateLimiter::new(config.rate_limits.clone()));
+    info!("Rate limits configured for {} source(s)", config.rate_limits.sources.len());
 
     // Create receipt store
-    let store = Arc::new(ReceiptStore::new(kafka, s3, db_pool));
+    let store = Arc::new(ReceiptStore::new(sinks, db_pool, config));
+    match store.signer() {
+        Some(signer) => info!("Signing receipts with Ed25519 public key {}", signer.public_key()),
+        None => info!("Receipt signing disabled (RECEIPT_SIGNING_KEY not set)"),
+    }
+    sweeper::spawn(store.clone());
+    retention::spawn(store.clone());
+    archive_buffer::spawn_flusher(store.clone());
 
     // CORS layer for dashboard
     let cors = CorsLayer::new()
@@ -74,45 +98,145 @@ async fn main() -> Result<()> {
         .allow_methods(Any)
         .allow_headers(Any);
 
-    // Create router with all endpoints
-    let app = Router::new()
+    // Write endpoints, rate limited per source
+    let writes = Router::new()
         // V1 Write endpoints
         .route("/v1/receipts", post(store_receipt))
+        .route("/v1/receipts/batch", post(store_receipts_batch))
         .route("/v1/events/external", post(ingest_external_event))
+        .route("/v1/events/external/batch", post(ingest_external_events_batch))
+        .route("/v1/traces/:trace_id/close", post(close_trace))
+        .route("/v1/traces/:trace_id/seal", post(seal_trace))
+        // V2 Write endpoints
+        .route("/v2/receipts", post(store_receipt_v2))
+        // Unversioned: Accept-Version picks v1 or v2
+        .route("/receipts", post(store_receipt_negotiated))
+        .route_layer(middleware::from_fn_with_state(limiter, limit_writes));
+
+    // Create router with all endpoints
+    let app = Router::new()
+        .merge(writes)
         // V1 Read endpoints
         .route("/v1/traces", get(list_traces))
         .route("/v1/traces/:trace_id", get(get_trace))
         .route("/v1/traces/:trace_id/timeline", get(get_trace_timeline))
         .route("/v1/traces/:trace_id/decisions", get(get_trace_decisions))
+        .route("/v1/traces/:trace_id/latency", get(get_trace_latency))
+        .route("/v1/traces/:trace_id/verify", get(verify_trace_chain))
+        .route("/v1/traces/:trace_id/merkle", get(get_trace_merkle))
+        .route("/v1/traces/:trace_id/external-events", get(list_trace_external_events))
+        .route("/v1/events/external/:event_id", get(get_external_event))
         .route("/v1/lookup/:correlation_id", get(lookup_by_correlation))
+        .route("/v1/agents/:agent_id/activity", get(get_agent_activity))
+        .route("/v1/agents/:agent_id/trust-trend", get(get_agent_trust_trend))
+        .route("/v1/tenants/:tenant_id/cost", get(get_tenant_cost))
+        .route("/v1/risk-events", get(list_risk_events))
+        .route("/v1/export/receipts", get(export_receipts))
+        .route("/v1/stats", get(get_stats))
+        .route("/v1/receipts/validate", post(validate_receipt))
+        .route("/v1/receipts/:receipt_id", get(get_receipt))
+        .route("/v1/receipts/:receipt_id/archive-url", get(get_receipt_archive_url))
+        .route("/v1/receipts/:receipt_id/merkle-proof", get(get_receipt_merkle_proof))
+        .route("/v1/receipts/:receipt_id/signature", get(get_receipt_signature))
+        .route("/v1/admin/purge/preview", get(get_purge_preview))
+        .route("/v1/admin/purge", post(run_purge))
+        .route("/v1/selftest", get(get_selftest))
         // V2 Endpoints (Phase 1 - Trust & Attribution)
-        .route("/v2/receipts", post(store_receipt_v2))
         .route("/v1/traces/:trace_id/trust-events", get(get_trace_trust_events))
-        // Health check
+        // Health check and metrics
         .route("/health", get(health_check))
+        .route("/metrics", get(get_metrics))
         .layer(cors)
-        .with_state(store);
+        .layer(middleware::from_fn(request_id))
+        .with_state(store.clone());
 
     // Start server
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
     info!("Receipt Store listening on 0.0.0.0:{}", port);
     info!("API endpoints:");
     info!("  POST /v1/receipts - Store receipt");
+    info!("  POST /v1/receipts/batch - Store receipts in bulk, chained in order");
     info!("  POST /v1/events/external - Ingest external event");
+    info!("  POST /v1/events/external/batch - Ingest external events in bulk");
+    info!("  POST /v1/traces/:trace_id/close - Close an active trace");
+    info!("  POST /v1/traces/:trace_id/seal - Seal a completed trace");
     info!("  GET  /v1/traces - List traces");
     info!("  GET  /v1/traces/:trace_id - Get trace detail");
     info!("  GET  /v1/traces/:trace_id/timeline - Get timeline");
-    info!("  GET  /v1/traces/:trace_id/decisions - Get decision tree");
+    info!("  GET  /v1/traces/:trace_id/decisions - Get decision tree (?format=json|dot|mermaid)");
+    info!("  GET  /v1/traces/:trace_id/latency - Get per-phase latency breakdown");
+    info!("  GET  /v1/traces/:trace_id/verify - Verify the trace's receipts against the hash chain");
+    info!("  GET  /v1/traces/:trace_id/merkle - Merkle root and leaves over the trace's receipt hashes");
+    info!("  GET  /v1/traces/:trace_id/external-events - List a trace's external events as stored");
+    info!("  GET  /v1/events/external/:event_id - Get a single external event as stored");
     info!("  GET  /v1/lookup/:correlation_id - Lookup by correlation ID");
+    info!("  GET  /v1/agents/:agent_id/activity - Agent last-seen and request counts");
+    info!("  GET  /v1/agents/:agent_id/trust-trend - Agent trust score over time (?bucket=hour|day)");
+    info!("  GET  /v1/tenants/:tenant_id/cost - Tenant token and cost totals over a window");
+    info!("  GET  /v1/risk-events - List risk events (filtered, keyset-paginated)");
+    info!("  GET  /v1/receipts/:receipt_id - Get a single receipt as stored");
+    info!("  GET  /v1/receipts/:receipt_id/archive-url - Presigned S3 download URL");
+    info!("  GET  /v1/receipts/:receipt_id/merkle-proof - Inclusion proof for a receipt in its trace's Merkle tree");
+    info!("  GET  /v1/receipts/:receipt_id/signature - Ed25519 signature over the receipt hash, and its public key");
+    info!("  GET  /v1/admin/purge/preview - Traces and receipts past retention (nothing deleted)");
+    info!("  POST /v1/admin/purge - Delete archived traces past retention (?before=, admin key)");
+    info!("  GET  /v1/selftest - Store, read back and verify a synthetic receipt (admin key)");
+    info!("  GET  /metrics - Ingestion counters (Prometheus format)");
     info!("V2 endpoints (Phase 1):");
     info!("  POST /v2/receipts - Store receipt with trust/attribution");
     info!("  GET  /v1/traces/:trace_id/trust-events - Get trust events");
-
-    axum::serve(listener, app).await?;
+    info!("  POST /receipts - Store receipt as v1 or v2, by Accept-Version (default 1)");
+
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await?;
+
+    // Receipts still waiting for a batched archive write, or in a producer's queue, would otherwise be lost
+    let archived = store.flush_archive().await;
+    let mut sent = 0;
+    for (tenant_id, sink) in store.sinks().all() {
+        match sink.kafka.flush(KAFKA_SHUTDOWN_FLUSH_TIMEOUT).await {
+            Ok(queued) => sent += queued,
+            Err(e) => warn!(
+                "Kafka producer{} not flushed before exiting: {}",
+                tenant_id.map(|id| format!(" for tenant {}", id)).unwrap_or_default(),
+                e
+            ),
+        }
+    }
+    info!(
+        "Drained {} queued receipt(s) to S3 and {} queued Kafka message(s) before exiting",
+        archived, sent
+    );
 
     Ok(())
 }
 
+/// Resolves on Ctrl+C or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    info!("Shutting down");
+}
+
 async fn health_check() -> &'static str {
     "OK"
 }
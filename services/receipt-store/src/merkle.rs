@@ -0,0 +1,82 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Which side of the running hash a proof sibling goes on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SiblingPosition {
+    Left,
+    Right,
+}
+
+/// One step of an inclusion proof: hash the running value with `hash` on the given side
+#[derive(Debug, Clone, Serialize)]
+pub struct ProofStep {
+    pub hash: String,
+    pub position: SiblingPosition,
+}
+
+/// Compute a SHA-256 Merkle root over hex-encoded leaf hashes (e.g. receipt hashes).
+/// An unpaired node at any level is hashed with itself. Returns `None` for no leaves, and an error for a
+/// leaf that isn't hex.
+pub fn merkle_root(leaves: &[String]) -> Result<Option<String>> {
+    if leaves.is_empty() {
+        return Ok(None);
+    }
+
+    let mut level = decode_leaves(leaves)?;
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+
+    Ok(Some(hex::encode(&level[0])))
+}
+
+/// The sibling hashes, leaf to root, proving the leaf at `index` is included under `merkle_root(leaves)`.
+/// Returns `None` when `index` is out of range; a single leaf is its own root and needs no steps.
+pub fn merkle_proof(leaves: &[String], index: usize) -> Result<Option<Vec<ProofStep>>> {
+    if index >= leaves.len() {
+        return Ok(None);
+    }
+
+    let mut level = decode_leaves(leaves)?;
+    let mut index = index;
+    let mut proof = Vec::new();
+    while level.len() > 1 {
+        let step = if index.is_multiple_of(2) {
+            // An unpaired node is hashed with itself, so it is its own sibling
+            let sibling = level.get(index + 1).unwrap_or(&level[index]);
+            ProofStep { hash: hex::encode(sibling), position: SiblingPosition::Right }
+        } else {
+            ProofStep { hash: hex::encode(&level[index - 1]), position: SiblingPosition::Left }
+        };
+        proof.push(step);
+        level = next_level(&level);
+        index /= 2;
+    }
+
+    Ok(Some(proof))
+}
+
+fn decode_leaves(leaves: &[String]) -> Result<Vec<Vec<u8>>> {
+    leaves
+        .iter()
+        .enumerate()
+        .map(|(i, leaf)| hex::decode(leaf).with_context(|| format!("Merkle leaf {} is not a hex-encoded hash", i)))
+        .collect()
+}
+
+fn next_level(level: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    level
+        .chunks(2)
+        .map(|pair| {
+            let left = &pair[0];
+            let right = pair.get(1).unwrap_or(left);
+            let mut hasher = Sha256::new();
+            hasher.update(left);
+            hasher.update(right);
+            hasher.finalize().to_vec()
+        })
+        .collect()
+}
@@ -0,0 +1,76 @@
+//! Ingestion counters, served in the Prometheus text format at `GET /metrics`. Counters live for the
+//! life of the process and are per replica; Prometheus sums them across replicas and handles restarts.
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    receipts_stored: AtomicU64,
+    external_events_ingested: AtomicU64,
+    kafka_send_failures: AtomicU64,
+    s3_archive_failures: AtomicU64,
+    trust_violations_recorded: AtomicU64,
+}
+
+impl Metrics {
+    pub fn receipt_stored(&self) {
+        self.receipts_stored.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn external_event_ingested(&self) {
+        self.external_events_ingested.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn kafka_send_failed(&self) {
+        self.kafka_send_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn s3_archive_failed(&self, receipts: u64) {
+        self.s3_archive_failures.fetch_add(receipts, Ordering::Relaxed);
+    }
+
+    pub fn trust_violation_recorded(&self) {
+        self.trust_violations_recorded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// All counters in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let counters = [
+            (
+                "receipt_store_receipts_stored_total",
+                "Receipts stored, v1 and v2, including those stored in batches",
+                &self.receipts_stored,
+            ),
+            (
+                "receipt_store_external_events_ingested_total",
+                "External events accepted, singly or in batches",
+                &self.external_events_ingested,
+            ),
+            (
+                "receipt_store_kafka_send_failures_total",
+                "Receipts and events that could not be sent to Kafka",
+                &self.kafka_send_failures,
+            ),
+            (
+                "receipt_store_s3_archive_failures_total",
+                "Receipts that could not be archived to S3",
+                &self.s3_archive_failures,
+            ),
+            (
+                "receipt_store_trust_violations_recorded_total",
+                "v2 receipts recording a failed trust threshold check",
+                &self.trust_violations_recorded,
+            ),
+        ];
+
+        let mut out = String::new();
+        for (name, help, value) in counters {
+            // Writing to a String can't fail
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            let _ = writeln!(out, "{} {}", name, value.load(Ordering::Relaxed));
+        }
+        out
+    }
+}
@@ -1,8 +1,14 @@
 use anyhow::Result;
 use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use pathwell_common::pagination::{page_size, Cursor};
+
+use crate::merkle::{merkle_proof, merkle_root, ProofStep};
+use crate::receipt::{EventType, Receipt, ReceiptV2, TrustDimensions};
+use crate::signing::verify_signature;
 
 /// Query parameters for trace listing
 #[derive(Debug, Deserialize)]
@@ -10,13 +16,46 @@ pub struct TraceQuery {
     pub correlation_id: Option<String>,
     pub agent_id: Option<String>,
     pub enterprise_id: Option<String>,
+    /// Only traces of this tenant
+    pub tenant_id: Option<Uuid>,
+    /// With `tenant_id`, also the traces of the tenants in `descendant_tenant_ids`
+    pub include_descendants: Option<bool>,
+    /// Comma-separated IDs of `tenant_id`'s descendant tenants. The receipt store doesn't hold the tenant
+    /// tree, so the caller resolves them from the identity registry's tenant hierarchy
+    pub descendant_tenant_ids: Option<String>,
     pub status: Option<String>,
     pub from: Option<DateTime<Utc>>,
     pub to: Option<DateTime<Utc>>,
+    /// Only traces containing a receipt whose request body has this SHA-256 hash
+    pub body_hash: Option<String>,
+    /// Only traces with (`true`) or without (`false`) any trust violation
+    pub has_trust_violations: Option<bool>,
+    /// Only traces with at least this many trust violations
+    pub min_violations: Option<i32>,
+    /// Only traces with this outcome (one of `TRACE_OUTCOMES`)
+    pub outcome: Option<String>,
+    /// Only traces with a receipt whose request path or agent ID contains this text, ignoring case
+    pub search: Option<String>,
     pub limit: Option<i64>,
+    /// Ignored when `cursor` is set
     pub offset: Option<i64>,
+    /// `next_cursor` from the previous page
+    pub cursor: Option<String>,
 }
 
+/// Longest `search` term a trace listing accepts, in characters
+pub const MAX_SEARCH_LEN: usize = 128;
+
+/// Most descendant tenants a trace listing accepts in `descendant_tenant_ids`
+pub const MAX_DESCENDANT_TENANTS: usize = 1000;
+
+/// The sort trace list cursors are issued for: newest `last_event_at` first, keyed in microseconds
+pub const TRACE_CURSOR_SORT: &str = "last_event_at";
+
+/// Overall outcomes a trace can have, from the allow/deny/error mix of its receipts. `errored` wins
+/// if any receipt records a gateway failure (a 5xx)
+pub const TRACE_OUTCOMES: [&str; 4] = ["fully_allowed", "partially_denied", "fully_denied", "errored"];
+
 /// Trace summary for list view
 #[derive(Debug, Serialize, sqlx::FromRow)]
 pub struct TraceSummary {
@@ -30,6 +69,14 @@ pub struct TraceSummary {
     pub initiating_agent_id: Option<String>,
     pub initiating_developer_id: Option<Uuid>,
     pub enterprise_id: Option<String>,
+    /// Usage summed over the trace's receipts
+    pub total_input_tokens: i64,
+    pub total_output_tokens: i64,
+    pub total_cost: f64,
+    /// Trust threshold violations recorded on the trace
+    pub trust_violations: i32,
+    /// One of `TRACE_OUTCOMES`; `None` until the trace has a receipt
+    pub outcome: Option<String>,
 }
 
 /// Response for trace list
@@ -39,6 +86,8 @@ pub struct TraceListResponse {
     pub total: i64,
     pub limit: i64,
     pub offset: i64,
+    /// Pass as `cursor` to fetch the next page; absent on the last page
+    pub next_cursor: Option<String>,
 }
 
 /// Timeline event for visualization
@@ -46,7 +95,7 @@ pub struct TraceListResponse {
 pub struct TimelineEvent {
     pub event_id: Uuid,
     pub timestamp: DateTime<Utc>,
-    pub event_type: String,
+    pub event_type: EventType,
     pub source_system: String,
     pub source_service: String,
     pub agent_id: Option<String>,
@@ -85,54 +134,398 @@ pub struct DecisionEdge {
     pub label: Option<String>,
 }
 
+/// A span in a trace's call tree, with the spans it called
+#[derive(Debug, Serialize)]
+pub struct SpanNode {
+    pub span_id: Uuid,
+    pub parent_span_id: Option<Uuid>,
+    pub receipt_id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub event_type: EventType,
+    pub agent_id: Option<String>,
+    pub summary: String,
+    pub outcome: EventOutcome,
+    /// The span names a parent that isn't in the trace, or its parents loop back to it, so it is
+    /// listed as a root instead
+    pub orphaned: bool,
+    pub children: Vec<SpanNode>,
+}
+
 /// Full trace detail response
 #[derive(Debug, Serialize)]
 pub struct TraceDetailResponse {
     pub trace: TraceSummary,
     pub timeline: Vec<TimelineEvent>,
     pub decision_tree: DecisionTree,
+    /// Root spans, oldest first, each with the spans it called nested under it
+    pub span_tree: Vec<SpanNode>,
+    /// Trust scores recorded on the trace's receipts; `None` when none recorded one (v1-only traces)
+    pub trust_summary: Option<TraceTrustSummary>,
+}
+
+/// Composite trust scores across a trace's v2 receipts, with the latest dimensions breakdown
+#[derive(Debug, Serialize)]
+pub struct TraceTrustSummary {
+    /// Receipts that recorded a composite score
+    pub scored_receipts: i64,
+    pub min_score: f64,
+    pub max_score: f64,
+    pub avg_score: f64,
+    /// Dimensions recorded on the latest receipt that has them
+    pub latest_dimensions: Option<TrustDimensions>,
+    pub latest_dimensions_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct TraceTrustRow {
+    scored_receipts: i64,
+    min_score: Option<f64>,
+    max_score: Option<f64>,
+    avg_score: Option<f64>,
+    latest_dimensions: Option<serde_json::Value>,
+    latest_dimensions_at: Option<DateTime<Utc>>,
+}
+
+/// Gateway latency for a single span
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SpanLatency {
+    pub span_id: Uuid,
+    pub receipt_id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub identity_ms: Option<i32>,
+    pub policy_ms: Option<i32>,
+    pub upstream_ms: Option<i32>,
+    pub total_ms: Option<i32>,
+}
+
+/// Summed latency per phase across a trace
+#[derive(Debug, Default, Serialize)]
+pub struct LatencyTotals {
+    pub identity_ms: i64,
+    pub policy_ms: i64,
+    pub upstream_ms: i64,
+    pub total_ms: i64,
+}
+
+/// Latency breakdown for a trace
+#[derive(Debug, Serialize)]
+pub struct TraceLatencyResponse {
+    pub trace_id: Uuid,
+    pub spans: Vec<SpanLatency>,
+    pub totals: LatencyTotals,
+}
+
+/// A single stored receipt, as persisted
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ReceiptDetailResponse {
+    pub receipt_id: Uuid,
+    pub trace_id: Uuid,
+    /// The receipt as stored, with out-of-row metadata joined back in
+    pub receipt: serde_json::Value,
+    pub receipt_hash: String,
+    pub previous_receipt_hash: Option<String>,
+    /// Trust score and attribution recorded with v2 receipts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trust_score_at_event: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attribution: Option<serde_json::Value>,
+}
+
+/// A receipt's signature and the key it verifies against
+#[derive(Debug, Serialize)]
+pub struct ReceiptSignatureResponse {
+    pub receipt_id: Uuid,
+    pub receipt_hash: String,
+    pub algorithm: &'static str,
+    /// `None` for receipts stored without a signing key
+    pub signature: Option<String>,
+    pub public_key: Option<String>,
+    /// Whether the signature verifies over `receipt_hash`; `None` when unsigned
+    pub verified: Option<bool>,
+}
+
+/// A receipt hash in a trace's Merkle tree, in leaf order
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct MerkleLeaf {
+    pub receipt_id: Uuid,
+    pub receipt_hash: String,
+}
+
+/// Merkle tree over a trace's receipt hashes, leaves in the order sealing uses
+#[derive(Debug, Serialize)]
+pub struct TraceMerkleResponse {
+    pub trace_id: Uuid,
+    /// `None` while the trace has no receipts
+    pub root: Option<String>,
+    pub leaf_count: usize,
+    pub leaves: Vec<MerkleLeaf>,
+    /// Root recorded when the trace was sealed; matches `root` unless its receipts changed since
+    pub seal_root: Option<String>,
+}
+
+/// Sibling hashes proving a receipt is a leaf of its trace's Merkle tree
+#[derive(Debug, Serialize)]
+pub struct MerkleProofResponse {
+    pub receipt_id: Uuid,
+    pub trace_id: Uuid,
+    pub receipt_hash: String,
+    pub leaf_index: usize,
+    pub leaf_count: usize,
+    pub root: String,
+    /// Leaf to root
+    pub proof: Vec<ProofStep>,
+    pub seal_root: Option<String>,
+}
+
+/// Why a receipt failed chain verification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainBreak {
+    /// The stored receipt could not be read back as a v1 or v2 receipt
+    UnreadableReceipt,
+    /// Recomputing the hash over the stored fields does not give the stored hash
+    HashMismatch,
+    /// `previous_receipt_hash` names no stored receipt
+    BrokenLink,
+}
+
+/// Result of verifying a trace's receipts against the hash chain
+#[derive(Debug, Serialize)]
+pub struct ChainVerificationResponse {
+    pub trace_id: Uuid,
+    pub valid: bool,
+    pub receipt_count: usize,
+    /// Receipts verified before the first break (all of them when `valid`)
+    pub verified_count: usize,
+    pub first_broken_receipt_id: Option<Uuid>,
+    pub reason: Option<ChainBreak>,
+}
+
+/// Result of recomputing a receipt's hash from the receipt itself
+#[derive(Debug, Serialize)]
+pub struct ReceiptValidationResponse {
+    /// Whether `computed_hash` is `stored_hash`
+    pub valid: bool,
+    pub computed_hash: String,
+    /// The `receipt_hash` the receipt carries
+    pub stored_hash: String,
+}
+
+/// Query parameters for a trace timeline
+#[derive(Debug, Deserialize)]
+pub struct TimelineQuery {
+    /// One of `TIMELINE_OUTCOMES`; `all` when unset
+    pub outcome: Option<String>,
+}
+
+/// Outcomes a timeline can be filtered to
+pub const TIMELINE_OUTCOMES: [&str; 3] = ["allowed", "denied", "all"];
+
+/// Query parameters for the decision tree (`format` is json, dot or mermaid)
+#[derive(Debug, Deserialize)]
+pub struct DecisionTreeQuery {
+    pub format: Option<String>,
+    /// Share one identity node per agent and one policy node per policy version across the trace
+    #[serde(default)]
+    pub collapse: bool,
+}
+
+/// Query parameters for agent activity
+#[derive(Debug, Deserialize)]
+pub struct ActivityQuery {
+    pub window_hours: Option<i64>,
+}
+
+/// Agent activity summary: lifetime counts plus counts within the requested window
+#[derive(Debug, Serialize)]
+pub struct AgentActivityResponse {
+    pub agent_id: String,
+    pub first_seen_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub total_requests: i64,
+    pub total_denials: i64,
+    pub window_hours: i64,
+    pub window_requests: i64,
+    pub window_denials: i64,
+}
+
+/// Query parameters for tenant cost (defaults to the last 30 days)
+#[derive(Debug, Deserialize)]
+pub struct CostQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// Usage summed over a tenant's receipts within a window
+#[derive(Debug, Serialize)]
+pub struct TenantCostResponse {
+    pub tenant_id: Uuid,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// Receipts that reported usage
+    pub request_count: i64,
+    pub trace_count: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cost: f64,
+}
+
+/// Query parameters for headline stats (defaults to the last 30 days, every tenant)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+pub struct StatsQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub tenant_id: Option<Uuid>,
+}
+
+/// Headline numbers for a window
+#[derive(Debug, Clone, Serialize)]
+pub struct StatsResponse {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub tenant_id: Option<Uuid>,
+    /// Traces started in the window
+    pub trace_count: i64,
+    /// Receipts in the window, seal receipts aside
+    pub receipt_count: i64,
+    pub allowed_count: i64,
+    pub denied_count: i64,
+    /// Mean policy evaluation time of the receipts that recorded one; 0 without any
+    pub avg_policy_evaluation_ms: f64,
+    pub distinct_agents: i64,
+    /// When these numbers were computed; they may be served from cache for a short while after
+    pub computed_at: DateTime<Utc>,
+}
+
+/// Intervals `GET /v1/agents/:agent_id/trust-trend` can average scores over
+pub const TRUST_TREND_BUCKETS: [&str; 2] = ["hour", "day"];
+
+/// Most points an unbucketed trust trend returns; longer windows should be bucketed
+pub const MAX_TRUST_TREND_POINTS: i64 = 5000;
+
+/// Query parameters for an agent's trust trend (defaults to the last 30 days, unbucketed)
+#[derive(Debug, Deserialize)]
+pub struct TrustTrendQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// One of `TRUST_TREND_BUCKETS`; each point is then the mean score over that interval
+    pub bucket: Option<String>,
+}
+
+/// One point of a trust trend: a receipt's score, or the mean score of a bucket's receipts
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TrustTrendPoint {
+    /// The receipt's time, or the start of the bucket (UTC)
+    pub timestamp: DateTime<Utc>,
+    pub trust_score: f64,
+    /// The receipt's trace; unset for buckets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<Uuid>,
+    /// Receipts averaged into the bucket; unset for single receipts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub receipt_count: Option<i64>,
+}
+
+/// An agent's trust scores as its receipts recorded them, oldest first
+#[derive(Debug, Serialize)]
+pub struct TrustTrendResponse {
+    pub agent_id: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub bucket: Option<String>,
+    pub points: Vec<TrustTrendPoint>,
+    /// The window held more than `MAX_TRUST_TREND_POINTS` receipts, so only the oldest are returned
+    pub truncated: bool,
+}
+
+/// Orderings offered by `GET /v1/risk-events`
+pub const RISK_EVENT_SORTS: [&str; 3] = ["severity", "score", "age"];
+
+/// Filters, sort and keyset position for risk event listing
+#[derive(Debug, Deserialize)]
+pub struct RiskEventQuery {
+    pub severity: Option<String>,
+    pub status: Option<String>,
+    /// Type of entity the risk was raised against, e.g. `agent`
+    pub entity_type: Option<String>,
+    pub agent_id: Option<String>,
+    /// Tenant of the trace the risk was raised on
+    pub tenant_id: Option<Uuid>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// `severity` (most severe then newest, the default), `score` (lowest trust score first;
+    /// events without one last) or `age` (oldest first)
+    pub sort: Option<String>,
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+/// Risk event for list view
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct RiskEventSummary {
+    pub risk_id: Uuid,
+    pub entity_type: String,
+    pub entity_id: Uuid,
+    /// Set when the entity is an agent
+    pub agent_id: Option<String>,
+    pub trace_id: Option<Uuid>,
+    pub tenant_id: Option<Uuid>,
+    pub risk_type: String,
+    pub severity: String,
+    pub status: String,
+    pub description: String,
+    pub evidence: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip)]
+    pub sort_key: f64,
+}
+
+/// Response for risk event listing
+#[derive(Debug, Serialize)]
+pub struct RiskEventListResponse {
+    pub risk_events: Vec<RiskEventSummary>,
+    /// Events matching the filters, across all pages
+    pub total: i64,
+    pub limit: i64,
+    /// Pass as `cursor` to fetch the next page; absent on the last page
+    pub next_cursor: Option<String>,
 }
 
 /// Raw receipt event from database
 #[derive(Debug, sqlx::FromRow)]
 pub struct ReceiptEventRow {
-    pub id: Uuid,
     pub receipt_id: Uuid,
-    pub trace_id: Uuid,
-    pub correlation_id: Option<String>,
     pub span_id: Uuid,
     pub parent_span_id: Option<Uuid>,
     pub timestamp: DateTime<Utc>,
-    pub event_type: String,
+    #[sqlx(try_from = "String")]
+    pub event_type: EventType,
     pub event_source_system: String,
     pub event_source_service: String,
-    pub event_source_version: Option<String>,
     pub agent_id: Option<String>,
     pub developer_id: Option<Uuid>,
     pub enterprise_id: Option<Uuid>,
     pub request_method: Option<String>,
     pub request_path: Option<String>,
-    pub request_headers: Option<serde_json::Value>,
     pub request_body_hash: Option<String>,
     pub policy_allowed: Option<bool>,
     pub policy_version: Option<String>,
     pub policy_evaluation_ms: Option<i32>,
     pub identity_valid: Option<bool>,
-    pub metadata: Option<serde_json::Value>,
+    /// The stored receipt, with any overflowed metadata restored
     pub full_receipt: serde_json::Value,
     pub receipt_hash: String,
-    pub previous_receipt_hash: Option<String>,
-    pub created_at: DateTime<Utc>,
 }
 
-/// Raw external event from database
-#[derive(Debug, sqlx::FromRow)]
+/// Raw external event from database, served as stored
+#[derive(Debug, Serialize, sqlx::FromRow)]
 pub struct ExternalEventRow {
     pub id: Uuid,
     pub event_id: Uuid,
     pub trace_id: Uuid,
     pub correlation_id: Option<String>,
-    pub event_type: String,
+    #[sqlx(try_from = "String")]
+    pub event_type: EventType,
     pub source_system: String,
     pub source_id: String,
     pub timestamp: DateTime<Utc>,
@@ -144,6 +537,13 @@ pub struct ExternalEventRow {
     pub created_at: DateTime<Utc>,
 }
 
+/// A trace's external events, oldest first
+#[derive(Debug, Serialize)]
+pub struct ExternalEventListResponse {
+    pub trace_id: Uuid,
+    pub events: Vec<ExternalEventRow>,
+}
+
 pub struct QueryService {
     pool: PgPool,
 }
@@ -153,17 +553,28 @@ impl QueryService {
         Self { pool }
     }
 
-    /// List traces with filtering and pagination
-    pub async fn list_traces(&self, params: TraceQuery) -> Result<TraceListResponse> {
-        let limit = params.limit.unwrap_or(50).min(100);
-        let offset = params.offset.unwrap_or(0);
+    /// List traces newest first. Pages by `cursor` (already validated) when given, otherwise by `offset`
+    /// List traces matching `params`; `tenant_ids`, when set, are the tenants whose traces are listed
+    pub async fn list_traces(
+        &self,
+        params: TraceQuery,
+        tenant_ids: Option<Vec<Uuid>>,
+        cursor: Option<Cursor>,
+    ) -> Result<TraceListResponse> {
+        let limit = page_size(params.limit);
+        let offset = if cursor.is_some() { 0 } else { params.offset.unwrap_or(0) };
+        // Receipts store lowercase hex digests
+        let body_hash = params.body_hash.as_deref().map(str::to_ascii_lowercase);
+        let search = params.search.as_deref().map(contains_pattern);
 
-        // Build dynamic query
-        let traces: Vec<TraceSummary> = sqlx::query_as(
+        // Build dynamic query. Ties on last_event_at break on trace_id so cursor pages never overlap
+        let mut traces: Vec<TraceSummary> = sqlx::query_as(
             r#"
             SELECT trace_id, correlation_id, status, started_at, last_event_at,
                    event_count, policy_deny_count, initiating_agent_id,
-                   initiating_developer_id, enterprise_id
+                   initiating_developer_id, enterprise_id,
+                   total_input_tokens, total_output_tokens, total_cost::float8 AS total_cost,
+                   COALESCE(trust_violations, 0) AS trust_violations, outcome
             FROM traces
             WHERE ($1::text IS NULL OR correlation_id = $1)
               AND ($2::text IS NULL OR initiating_agent_id = $2)
@@ -171,7 +582,22 @@ impl QueryService {
               AND ($4::text IS NULL OR status = $4)
               AND ($5::timestamptz IS NULL OR started_at >= $5)
               AND ($6::timestamptz IS NULL OR started_at <= $6)
-            ORDER BY last_event_at DESC
+              AND ($9::text IS NULL OR EXISTS (
+                  SELECT 1 FROM receipt_events e
+                  WHERE e.trace_id = traces.trace_id AND e.request_body_hash = $9
+              ))
+              AND ($10::bool IS NULL OR (COALESCE(trust_violations, 0) > 0) = $10)
+              AND ($11::int IS NULL OR COALESCE(trust_violations, 0) >= $11)
+              AND ($12::text IS NULL OR outcome = $12)
+              AND ($15::text IS NULL OR EXISTS (
+                  SELECT 1 FROM receipt_events e
+                  WHERE e.trace_id = traces.trace_id
+                    AND (e.request_path ILIKE $15 OR e.agent_id ILIKE $15)
+              ))
+              AND ($16::uuid[] IS NULL OR tenant_id = ANY($16))
+              AND ($13::float8 IS NULL OR (last_event_at, trace_id) <
+                   ('epoch'::timestamptz + $13 * interval '1 microsecond', $14::uuid))
+            ORDER BY last_event_at DESC, trace_id DESC
             LIMIT $7 OFFSET $8
             "#
         )
@@ -179,10 +605,18 @@ impl QueryService {
         .bind(&params.agent_id)
         .bind(&params.enterprise_id)
         .bind(&params.status)
-        .bind(&params.from)
-        .bind(&params.to)
-        .bind(limit)
+        .bind(params.from)
+        .bind(params.to)
+        .bind(limit + 1)
         .bind(offset)
+        .bind(&body_hash)
+        .bind(params.has_trust_violations)
+        .bind(params.min_violations)
+        .bind(&params.outcome)
+        .bind(cursor.as_ref().map(|c| c.key))
+        .bind(cursor.as_ref().map(|c| c.id))
+        .bind(&search)
+        .bind(&tenant_ids)
         .fetch_all(&self.pool)
         .await?;
 
@@ -197,22 +631,56 @@ impl QueryService {
               AND ($4::text IS NULL OR status = $4)
               AND ($5::timestamptz IS NULL OR started_at >= $5)
               AND ($6::timestamptz IS NULL OR started_at <= $6)
+              AND ($7::text IS NULL OR EXISTS (
+                  SELECT 1 FROM receipt_events e
+                  WHERE e.trace_id = traces.trace_id AND e.request_body_hash = $7
+              ))
+              AND ($8::bool IS NULL OR (COALESCE(trust_violations, 0) > 0) = $8)
+              AND ($9::int IS NULL OR COALESCE(trust_violations, 0) >= $9)
+              AND ($10::text IS NULL OR outcome = $10)
+              AND ($11::text IS NULL OR EXISTS (
+                  SELECT 1 FROM receipt_events e
+                  WHERE e.trace_id = traces.trace_id
+                    AND (e.request_path ILIKE $11 OR e.agent_id ILIKE $11)
+              ))
+              AND ($12::uuid[] IS NULL OR tenant_id = ANY($12))
             "#
         )
         .bind(&params.correlation_id)
         .bind(&params.agent_id)
         .bind(&params.enterprise_id)
         .bind(&params.status)
-        .bind(&params.from)
-        .bind(&params.to)
+        .bind(params.from)
+        .bind(params.to)
+        .bind(&body_hash)
+        .bind(params.has_trust_violations)
+        .bind(params.min_violations)
+        .bind(&params.outcome)
+        .bind(&search)
+        .bind(&tenant_ids)
         .fetch_one(&self.pool)
         .await?;
 
+        let next_cursor = if traces.len() as i64 > limit {
+            traces.truncate(limit as usize);
+            traces.last().map(|last| {
+                Cursor {
+                    sort: TRACE_CURSOR_SORT.to_string(),
+                    key: last.last_event_at.timestamp_micros() as f64,
+                    id: last.trace_id,
+                }
+                .encode()
+            })
+        } else {
+            None
+        };
+
         Ok(TraceListResponse {
             traces,
             total,
             limit,
             offset,
+            next_cursor,
         })
     }
 
@@ -222,7 +690,9 @@ impl QueryService {
             r#"
             SELECT trace_id, correlation_id, status, started_at, last_event_at,
                    event_count, policy_deny_count, initiating_agent_id,
-                   initiating_developer_id, enterprise_id
+                   initiating_developer_id, enterprise_id,
+                   total_input_tokens, total_output_tokens, total_cost::float8 AS total_cost,
+                   COALESCE(trust_violations, 0) AS trust_violations, outcome
             FROM traces
             WHERE trace_id = $1
             "#
@@ -240,7 +710,9 @@ impl QueryService {
             r#"
             SELECT trace_id, correlation_id, status, started_at, last_event_at,
                    event_count, policy_deny_count, initiating_agent_id,
-                   initiating_developer_id, enterprise_id
+                   initiating_developer_id, enterprise_id,
+                   total_input_tokens, total_output_tokens, total_cost::float8 AS total_cost,
+                   COALESCE(trust_violations, 0) AS trust_violations, outcome
             FROM traces
             WHERE correlation_id = $1
             "#
@@ -252,19 +724,499 @@ impl QueryService {
         Ok(trace)
     }
 
+    /// Get per-span gateway latency for a trace, with per-phase totals
+    pub async fn get_trace_latency(&self, trace_id: Uuid) -> Result<TraceLatencyResponse> {
+        let spans: Vec<SpanLatency> = sqlx::query_as(
+            r#"
+            SELECT span_id, receipt_id, timestamp, identity_ms, policy_ms, upstream_ms, total_ms
+            FROM receipt_events
+            WHERE trace_id = $1 AND total_ms IS NOT NULL
+            ORDER BY timestamp ASC
+            "#
+        )
+        .bind(trace_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut totals = LatencyTotals::default();
+        for span in &spans {
+            totals.identity_ms += span.identity_ms.unwrap_or(0) as i64;
+            totals.policy_ms += span.policy_ms.unwrap_or(0) as i64;
+            totals.upstream_ms += span.upstream_ms.unwrap_or(0) as i64;
+            totals.total_ms += span.total_ms.unwrap_or(0) as i64;
+        }
+
+        Ok(TraceLatencyResponse {
+            trace_id,
+            spans,
+            totals,
+        })
+    }
+
+    /// Get activity for an agent over the last `window_hours` (default 24, max 30 days)
+    pub async fn get_agent_activity(
+        &self,
+        agent_id: &str,
+        params: ActivityQuery,
+    ) -> Result<Option<AgentActivityResponse>> {
+        let window_hours = params.window_hours.unwrap_or(24).clamp(1, 24 * 30);
+
+        let activity: Option<(DateTime<Utc>, DateTime<Utc>, i64, i64)> = sqlx::query_as(
+            r#"
+            SELECT first_seen_at, last_seen_at, request_count, denial_count
+            FROM agent_activity
+            WHERE agent_id = $1
+            "#
+        )
+        .bind(agent_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let (first_seen_at, last_seen_at, total_requests, total_denials) = match activity {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+
+        let (window_requests, window_denials): (i64, i64) = sqlx::query_as(
+            r#"
+            SELECT COUNT(*), COUNT(*) FILTER (WHERE policy_allowed = false)
+            FROM receipt_events
+            WHERE agent_id = $1
+              AND event_type <> 'trace_seal'
+              AND timestamp >= NOW() - make_interval(hours => $2::int)
+            "#
+        )
+        .bind(agent_id)
+        .bind(window_hours as i32)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(Some(AgentActivityResponse {
+            agent_id: agent_id.to_string(),
+            first_seen_at,
+            last_seen_at,
+            total_requests,
+            total_denials,
+            window_hours,
+            window_requests,
+            window_denials,
+        }))
+    }
+
+    /// Sum a tenant's reported usage over `[from, to)`; v1 receipts count toward their trace's tenant
+    pub async fn get_tenant_cost(&self, tenant_id: Uuid, params: CostQuery) -> Result<TenantCostResponse> {
+        let to = params.to.unwrap_or_else(Utc::now);
+        let from = params.from.unwrap_or(to - chrono::Duration::days(30));
+
+        let (request_count, trace_count, input_tokens, output_tokens, cost): (i64, i64, i64, i64, f64) =
+            sqlx::query_as(
+                r#"
+                SELECT COUNT(*),
+                       COUNT(DISTINCT e.trace_id),
+                       COALESCE(SUM(e.usage_input_tokens), 0)::bigint,
+                       COALESCE(SUM(e.usage_output_tokens), 0)::bigint,
+                       COALESCE(SUM(e.usage_cost), 0)::float8
+                FROM receipt_events e
+                JOIN traces t ON t.trace_id = e.trace_id
+                WHERE COALESCE(e.tenant_id, t.tenant_id) = $1
+                  AND e.timestamp >= $2
+                  AND e.timestamp < $3
+                  AND (e.usage_input_tokens IS NOT NULL
+                       OR e.usage_output_tokens IS NOT NULL
+                       OR e.usage_cost IS NOT NULL)
+                "#
+            )
+            .bind(tenant_id)
+            .bind(from)
+            .bind(to)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(TenantCostResponse {
+            tenant_id,
+            from,
+            to,
+            request_count,
+            trace_count,
+            input_tokens,
+            output_tokens,
+            cost,
+        })
+    }
+
+    /// Trace and receipt totals over `[from, to)`, optionally for one tenant; all zeros for an empty window
+    pub async fn get_stats(&self, params: StatsQuery) -> Result<StatsResponse> {
+        let to = params.to.unwrap_or_else(Utc::now);
+        let from = params.from.unwrap_or(to - chrono::Duration::days(30));
+
+        let (trace_count,): (i64,) = sqlx::query_as(
+            r#"
+            SELECT COUNT(*)
+            FROM traces
+            WHERE started_at >= $1
+              AND started_at < $2
+              AND ($3::uuid IS NULL OR tenant_id = $3)
+            "#
+        )
+        .bind(from)
+        .bind(to)
+        .bind(params.tenant_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let (receipt_count, allowed_count, denied_count, avg_policy_evaluation_ms, distinct_agents):
+            (i64, i64, i64, f64, i64) = sqlx::query_as(
+            r#"
+            SELECT COUNT(*),
+                   COUNT(*) FILTER (WHERE e.policy_allowed),
+                   COUNT(*) FILTER (WHERE NOT e.policy_allowed),
+                   COALESCE(AVG(e.policy_evaluation_ms), 0)::float8,
+                   COUNT(DISTINCT e.agent_id)
+            FROM receipt_events e
+            JOIN traces t ON t.trace_id = e.trace_id
+            WHERE e.timestamp >= $1
+              AND e.timestamp < $2
+              AND e.event_type <> 'trace_seal'
+              AND ($3::uuid IS NULL OR COALESCE(e.tenant_id, t.tenant_id) = $3)
+            "#
+        )
+        .bind(from)
+        .bind(to)
+        .bind(params.tenant_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(StatsResponse {
+            from,
+            to,
+            tenant_id: params.tenant_id,
+            trace_count,
+            receipt_count,
+            allowed_count,
+            denied_count,
+            avg_policy_evaluation_ms,
+            distinct_agents,
+            computed_at: Utc::now(),
+        })
+    }
+
+    /// An agent's trust score over `[from, to)` from the receipts that recorded one; `bucket` is already validated
+    pub async fn get_trust_trend(&self, agent_id: &str, params: TrustTrendQuery) -> Result<TrustTrendResponse> {
+        let to = params.to.unwrap_or_else(Utc::now);
+        let from = params.from.unwrap_or(to - chrono::Duration::days(30));
+
+        let (points, truncated) = match params.bucket {
+            // Buckets are aligned to UTC whatever the session time zone
+            Some(ref bucket) => {
+                let points: Vec<TrustTrendPoint> = sqlx::query_as(
+                    r#"
+                    SELECT date_trunc($4, timestamp AT TIME ZONE 'UTC') AT TIME ZONE 'UTC' AS timestamp,
+                           AVG(trust_score_at_event)::float8 AS trust_score,
+                           NULL::uuid AS trace_id,
+                           COUNT(*) AS receipt_count
+                    FROM receipt_events
+                    WHERE agent_id = $1
+                      AND trust_score_at_event IS NOT NULL
+                      AND timestamp >= $2
+                      AND timestamp < $3
+                    GROUP BY 1
+                    ORDER BY 1
+                    "#
+                )
+                .bind(agent_id)
+                .bind(from)
+                .bind(to)
+                .bind(bucket)
+                .fetch_all(&self.pool)
+                .await?;
+                (points, false)
+            }
+            None => {
+                let mut points: Vec<TrustTrendPoint> = sqlx::query_as(
+                    r#"
+                    SELECT timestamp,
+                           trust_score_at_event::float8 AS trust_score,
+                           trace_id,
+                           NULL::bigint AS receipt_count
+                    FROM receipt_events
+                    WHERE agent_id = $1
+                      AND trust_score_at_event IS NOT NULL
+                      AND timestamp >= $2
+                      AND timestamp < $3
+                    ORDER BY timestamp, receipt_id
+                    LIMIT $4
+                    "#
+                )
+                .bind(agent_id)
+                .bind(from)
+                .bind(to)
+                .bind(MAX_TRUST_TREND_POINTS + 1)
+                .fetch_all(&self.pool)
+                .await?;
+                let truncated = points.len() as i64 > MAX_TRUST_TREND_POINTS;
+                points.truncate(MAX_TRUST_TREND_POINTS as usize);
+                (points, truncated)
+            }
+        };
+
+        Ok(TrustTrendResponse {
+            agent_id: agent_id.to_string(),
+            from,
+            to,
+            bucket: params.bucket,
+            points,
+            truncated,
+        })
+    }
+
+    /// List risk events with filtering and keyset pagination; `sort` and `cursor` are already validated
+    pub async fn list_risk_events(
+        &self,
+        params: RiskEventQuery,
+        sort: &str,
+        cursor: Option<Cursor>,
+    ) -> Result<RiskEventListResponse> {
+        let limit = page_size(params.limit);
+
+        // Every sort is ascending on a numeric key so one keyset predicate serves them all.
+        // Severity ranks dominate the (negated) creation time, putting the most severe and
+        // newest first; ties break on risk_id so pages never overlap
+        let mut risk_events: Vec<RiskEventSummary> = sqlx::query_as(
+            r#"
+            SELECT * FROM (
+                SELECT r.id AS risk_id, r.entity_type, r.entity_id, a.agent_id, r.trace_id, t.tenant_id, r.risk_type,
+                       r.severity::text AS severity, r.status::text AS status, r.description, r.evidence,
+                       r.created_at,
+                       CASE $1
+                           WHEN 'score' THEN COALESCE((r.evidence->>'trust_score')::float8, 2.0)
+                           WHEN 'age' THEN EXTRACT(EPOCH FROM r.created_at)::float8
+                           ELSE -(CASE r.severity
+                                      WHEN 'critical' THEN 4
+                                      WHEN 'high' THEN 3
+                                      WHEN 'medium' THEN 2
+                                      WHEN 'low' THEN 1
+                                      ELSE 0
+                                  END * 1e10 + EXTRACT(EPOCH FROM r.created_at))::float8
+                       END AS sort_key
+                FROM trust_risk_events r
+                LEFT JOIN agents a ON r.entity_type = 'agent' AND a.id = r.entity_id
+                LEFT JOIN traces t ON t.trace_id = r.trace_id
+                WHERE ($2::text IS NULL OR r.severity::text = $2)
+                  AND ($3::text IS NULL OR r.status::text = $3)
+                  AND ($4::text IS NULL OR r.entity_type = $4)
+                  AND ($5::text IS NULL OR a.agent_id = $5)
+                  AND ($6::uuid IS NULL OR t.tenant_id = $6)
+                  AND ($7::timestamptz IS NULL OR r.created_at >= $7)
+                  AND ($8::timestamptz IS NULL OR r.created_at <= $8)
+            ) risks
+            WHERE $9::float8 IS NULL OR (sort_key, risk_id) > ($9, $10::uuid)
+            ORDER BY sort_key, risk_id
+            LIMIT $11
+            "#
+        )
+        .bind(sort)
+        .bind(&params.severity)
+        .bind(&params.status)
+        .bind(&params.entity_type)
+        .bind(&params.agent_id)
+        .bind(params.tenant_id)
+        .bind(params.from)
+        .bind(params.to)
+        .bind(cursor.as_ref().map(|c| c.key))
+        .bind(cursor.as_ref().map(|c| c.id))
+        .bind(limit + 1)
+        .fetch_all(&self.pool)
+        .await?;
+
+        // Get total count
+        let (total,): (i64,) = sqlx::query_as(
+            r#"
+            SELECT COUNT(*)
+            FROM trust_risk_events r
+            LEFT JOIN agents a ON r.entity_type = 'agent' AND a.id = r.entity_id
+            LEFT JOIN traces t ON t.trace_id = r.trace_id
+            WHERE ($1::text IS NULL OR r.severity::text = $1)
+              AND ($2::text IS NULL OR r.status::text = $2)
+              AND ($3::text IS NULL OR r.entity_type = $3)
+              AND ($4::text IS NULL OR a.agent_id = $4)
+              AND ($5::uuid IS NULL OR t.tenant_id = $5)
+              AND ($6::timestamptz IS NULL OR r.created_at >= $6)
+              AND ($7::timestamptz IS NULL OR r.created_at <= $7)
+            "#
+        )
+        .bind(&params.severity)
+        .bind(&params.status)
+        .bind(&params.entity_type)
+        .bind(&params.agent_id)
+        .bind(params.tenant_id)
+        .bind(params.from)
+        .bind(params.to)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let next_cursor = if risk_events.len() as i64 > limit {
+            risk_events.truncate(limit as usize);
+            risk_events.last().map(|last| {
+                Cursor {
+                    sort: sort.to_string(),
+                    key: last.sort_key,
+                    id: last.risk_id,
+                }
+                .encode()
+            })
+        } else {
+            None
+        };
+
+        Ok(RiskEventListResponse {
+            risk_events,
+            total,
+            limit,
+            next_cursor,
+        })
+    }
+
+    /// Get a single receipt by ID
+    pub async fn get_receipt(&self, receipt_id: Uuid) -> Result<Option<ReceiptDetailResponse>> {
+        let receipt: Option<ReceiptDetailResponse> = sqlx::query_as(
+            r#"
+            SELECT e.receipt_id, e.trace_id,
+                   CASE WHEN m.metadata IS NULL THEN e.full_receipt
+                        ELSE jsonb_set(e.full_receipt, '{metadata}', m.metadata) END AS receipt,
+                   e.receipt_hash, e.previous_receipt_hash,
+                   e.trust_score_at_event::float8 AS trust_score_at_event,
+                   NULLIF(e.attribution, '{}'::jsonb) AS attribution
+            FROM receipt_events e
+            LEFT JOIN receipt_metadata m ON m.receipt_id = e.receipt_id
+            WHERE e.receipt_id = $1
+            "#
+        )
+        .bind(receipt_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(receipt)
+    }
+
+    /// A receipt's signature, checked against its stored hash, or `None` for an unknown receipt
+    pub async fn get_receipt_signature(&self, receipt_id: Uuid) -> Result<Option<ReceiptSignatureResponse>> {
+        let row: Option<(String, Option<String>, Option<String>)> = sqlx::query_as(
+            "SELECT receipt_hash, signature, signature_public_key FROM receipt_events WHERE receipt_id = $1"
+        )
+        .bind(receipt_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|(receipt_hash, signature, public_key)| {
+            let verified = match (&signature, &public_key) {
+                (Some(signature), Some(public_key)) => Some(verify_signature(&receipt_hash, signature, public_key)),
+                _ => None,
+            };
+            ReceiptSignatureResponse {
+                receipt_id,
+                receipt_hash,
+                algorithm: "ed25519",
+                signature,
+                public_key,
+                verified,
+            }
+        }))
+    }
+
+    /// Build the Merkle tree over a trace's receipt hashes, or `None` for an unknown trace. The trace's
+    /// seal receipt is not a leaf, so a sealed trace's root is the one recorded when it was sealed
+    pub async fn compute_merkle_root(&self, trace_id: Uuid) -> Result<Option<TraceMerkleResponse>> {
+        let seal_root: Option<(Option<String>,)> = sqlx::query_as(
+            "SELECT seal_root FROM traces WHERE trace_id = $1"
+        )
+        .bind(trace_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some((seal_root,)) = seal_root else {
+            return Ok(None);
+        };
+
+        let leaves = self.merkle_leaves(trace_id).await?;
+        let hashes: Vec<String> = leaves.iter().map(|leaf| leaf.receipt_hash.clone()).collect();
+
+        Ok(Some(TraceMerkleResponse {
+            trace_id,
+            root: merkle_root(&hashes)?,
+            leaf_count: leaves.len(),
+            leaves,
+            seal_root,
+        }))
+    }
+
+    /// Inclusion proof for a receipt in its trace's Merkle tree, or `None` if no receipt has the ID or it
+    /// is a seal receipt (which attests to the tree rather than belonging to it)
+    pub async fn get_merkle_proof(&self, receipt_id: Uuid) -> Result<Option<MerkleProofResponse>> {
+        let trace_id: Option<(Uuid,)> = sqlx::query_as(
+            "SELECT trace_id FROM receipt_events WHERE receipt_id = $1"
+        )
+        .bind(receipt_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some((trace_id,)) = trace_id else {
+            return Ok(None);
+        };
+        let Some(tree) = self.compute_merkle_root(trace_id).await? else {
+            return Ok(None);
+        };
+        let Some(leaf_index) = tree.leaves.iter().position(|leaf| leaf.receipt_id == receipt_id) else {
+            return Ok(None);
+        };
+
+        let hashes: Vec<String> = tree.leaves.iter().map(|leaf| leaf.receipt_hash.clone()).collect();
+        let (Some(root), Some(proof)) = (tree.root, merkle_proof(&hashes, leaf_index)?) else {
+            return Ok(None);
+        };
+
+        Ok(Some(MerkleProofResponse {
+            receipt_id,
+            trace_id,
+            receipt_hash: hashes[leaf_index].clone(),
+            leaf_index,
+            leaf_count: tree.leaf_count,
+            root,
+            proof,
+            seal_root: tree.seal_root,
+        }))
+    }
+
+    /// A trace's receipt hashes in the order sealing uses, seal receipts excluded
+    async fn merkle_leaves(&self, trace_id: Uuid) -> Result<Vec<MerkleLeaf>> {
+        let leaves: Vec<MerkleLeaf> = sqlx::query_as(
+            r#"
+            SELECT receipt_id, receipt_hash
+            FROM receipt_events
+            WHERE trace_id = $1 AND event_type <> 'trace_seal'
+            ORDER BY timestamp ASC, receipt_id ASC
+            "#
+        )
+        .bind(trace_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(leaves)
+    }
+
     /// Get receipt events for a trace
     pub async fn get_receipt_events(&self, trace_id: Uuid) -> Result<Vec<ReceiptEventRow>> {
         let events: Vec<ReceiptEventRow> = sqlx::query_as(
             r#"
-            SELECT id, receipt_id, trace_id, correlation_id, span_id, parent_span_id,
-                   timestamp, event_type, event_source_system, event_source_service, event_source_version,
-                   agent_id, developer_id, enterprise_id,
-                   request_method, request_path, request_headers, request_body_hash,
-                   policy_allowed, policy_version, policy_evaluation_ms, identity_valid,
-                   metadata, full_receipt, receipt_hash, previous_receipt_hash, created_at
-            FROM receipt_events
-            WHERE trace_id = $1
-            ORDER BY timestamp ASC
+            SELECT e.receipt_id, e.span_id, e.parent_span_id,
+                   e.timestamp, e.event_type, e.event_source_system, e.event_source_service,
+                   e.agent_id, e.developer_id, e.enterprise_id,
+                   e.request_method, e.request_path, e.request_body_hash,
+                   e.policy_allowed, e.policy_version, e.policy_evaluation_ms, e.identity_valid,
+                   CASE WHEN m.metadata IS NULL THEN e.full_receipt
+                        ELSE jsonb_set(e.full_receipt, '{metadata}', m.metadata) END AS full_receipt,
+                   e.receipt_hash
+            FROM receipt_events e
+            LEFT JOIN receipt_metadata m ON m.receipt_id = e.receipt_id
+            WHERE e.trace_id = $1
+            ORDER BY e.timestamp ASC
             "#
         )
         .bind(trace_id)
@@ -292,40 +1244,68 @@ impl QueryService {
         Ok(events)
     }
 
+    /// A trace's external events, or `None` for an unknown trace
+    pub async fn list_external_events(&self, trace_id: Uuid) -> Result<Option<ExternalEventListResponse>> {
+        if self.get_trace(trace_id).await?.is_none() {
+            return Ok(None);
+        }
+
+        let events = self.get_external_events(trace_id).await?;
+        Ok(Some(ExternalEventListResponse { trace_id, events }))
+    }
+
+    /// A single external event by its event ID
+    pub async fn get_external_event(&self, event_id: Uuid) -> Result<Option<ExternalEventRow>> {
+        let event: Option<ExternalEventRow> = sqlx::query_as(
+            r#"
+            SELECT id, event_id, trace_id, correlation_id, event_type, source_system, source_id,
+                   timestamp, actor_type, actor_id, actor_display_name, payload, metadata, created_at
+            FROM external_events
+            WHERE event_id = $1
+            "#
+        )
+        .bind(event_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(event)
+    }
+
     /// Build timeline from all events
-    pub async fn get_timeline(&self, trace_id: Uuid) -> Result<Vec<TimelineEvent>> {
+    /// A trace's receipts and external events, oldest first. With `success`, only events with that
+    /// outcome: receipts by their computed outcome, and external events only when their payload carries
+    /// an explicit boolean `success`, since an event without one records no decision either way
+    pub async fn get_timeline(&self, trace_id: Uuid, success: Option<bool>) -> Result<Vec<TimelineEvent>> {
         let receipt_events = self.get_receipt_events(trace_id).await?;
         let external_events = self.get_external_events(trace_id).await?;
 
         let mut timeline: Vec<TimelineEvent> = Vec::new();
 
+        // Span depth is used to keep parents ahead of children when timestamps tie
+        let span_depths = span_depths(&receipt_events);
+        let mut event_depths: HashMap<Uuid, usize> = HashMap::new();
+
         // Convert receipt events to timeline events
         for event in receipt_events {
-            let summary = format!(
-                "{} {} - {}",
-                event.request_method.as_deref().unwrap_or("?"),
-                event.request_path.as_deref().unwrap_or("?"),
-                if event.policy_allowed.unwrap_or(false) { "Allowed" } else { "Denied" }
+            event_depths.insert(
+                event.receipt_id,
+                span_depths.get(&event.span_id).copied().unwrap_or(0),
             );
 
+            let (summary, outcome) = describe_receipt(&event);
+            if success.is_some_and(|success| success != outcome.success) {
+                continue;
+            }
+
             timeline.push(TimelineEvent {
                 event_id: event.receipt_id,
                 timestamp: event.timestamp,
-                event_type: event.event_type.clone(),
+                event_type: event.event_type,
                 source_system: event.event_source_system,
                 source_service: event.event_source_service,
                 agent_id: event.agent_id,
                 summary,
-                outcome: EventOutcome {
-                    success: event.policy_allowed.unwrap_or(false) && event.identity_valid.unwrap_or(false),
-                    reason: if !event.policy_allowed.unwrap_or(true) {
-                        Some("Policy denied".to_string())
-                    } else if !event.identity_valid.unwrap_or(true) {
-                        Some("Identity invalid".to_string())
-                    } else {
-                        None
-                    },
-                },
+                outcome,
                 details: event.full_receipt,
             });
         }
@@ -335,70 +1315,143 @@ impl QueryService {
             let actor_name = event.actor_display_name
                 .or(event.actor_id.clone())
                 .unwrap_or_else(|| "System".to_string());
+            let explicit_success = event.payload.get("success").and_then(|s| s.as_bool());
+            if success.is_some() && explicit_success != success {
+                continue;
+            }
 
             timeline.push(TimelineEvent {
                 event_id: event.event_id,
                 timestamp: event.timestamp,
-                event_type: event.event_type.clone(),
+                summary: format!("{} by {} ({})", event.event_type, actor_name, event.source_system),
+                event_type: event.event_type,
                 source_system: event.source_system.clone(),
                 source_service: event.source_id,
                 agent_id: event.actor_id,
-                summary: format!("{} by {} ({})", event.event_type, actor_name, event.source_system),
                 outcome: EventOutcome {
-                    success: true,
+                    success: explicit_success.unwrap_or(true),
                     reason: None,
                 },
                 details: event.payload,
             });
         }
 
-        // Sort by timestamp
-        timeline.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        // Sort by timestamp, falling back to span parentage and then event ID so
+        // events with identical or skewed timestamps still order deterministically
+        timeline.sort_by(|a, b| {
+            let depth_a = event_depths.get(&a.event_id).copied().unwrap_or(0);
+            let depth_b = event_depths.get(&b.event_id).copied().unwrap_or(0);
+            a.timestamp
+                .cmp(&b.timestamp)
+                .then(depth_a.cmp(&depth_b))
+                .then(a.event_id.cmp(&b.event_id))
+        });
 
         Ok(timeline)
     }
 
     /// Build decision tree from receipt events
-    pub async fn build_decision_tree(&self, trace_id: Uuid) -> Result<DecisionTree> {
+    ///
+    /// With `collapse`, events by the same agent share one identity node and events under the same
+    /// policy version share one policy node; each event still gets its own action node. A shared node's
+    /// outcome is false if any of its events failed, and repeated edges between shared nodes are merged.
+    pub async fn build_decision_tree(&self, trace_id: Uuid, collapse: bool) -> Result<DecisionTree> {
         let events = self.get_receipt_events(trace_id).await?;
 
-        let mut nodes = Vec::new();
+        let mut nodes: Vec<DecisionNode> = Vec::new();
         let mut edges = Vec::new();
+        // Last node of the previous step, so steps chain regardless of their shape
+        let mut previous: Option<String> = None;
+        // Index in `nodes` of each shared node, by agent ID and by policy version (collapse only)
+        let mut identity_nodes: HashMap<String, usize> = HashMap::new();
+        let mut policy_nodes: HashMap<String, usize> = HashMap::new();
 
         for (i, event) in events.iter().enumerate() {
+            match &event.event_type {
+                // The seal attests to the trace rather than recording a decision
+                EventType::TraceSeal => continue,
+                EventType::Other(name) => {
+                    let event_node_id = format!("event-{}", i);
+                    nodes.push(DecisionNode {
+                        id: event_node_id.clone(),
+                        node_type: "event".to_string(),
+                        label: name.clone(),
+                        outcome: true,
+                        timestamp: event.timestamp,
+                        details: serde_json::json!({
+                            "event_type": name,
+                            "source_system": event.event_source_system,
+                            "source_service": event.event_source_service,
+                        }),
+                    });
+                    if let Some(prev) = previous.replace(event_node_id.clone()) {
+                        edges.push(DecisionEdge {
+                            from: prev,
+                            to: event_node_id,
+                            label: Some("next".to_string()),
+                        });
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+
             let identity_valid = event.identity_valid.unwrap_or(false);
             let policy_allowed = event.policy_allowed.unwrap_or(false);
 
+            let agent = event.agent_id.as_deref().unwrap_or("unknown");
+            let policy_version = event.policy_version.as_deref().unwrap_or("v1");
+
             // Identity node
-            let identity_node_id = format!("identity-{}", i);
-            nodes.push(DecisionNode {
-                id: identity_node_id.clone(),
-                node_type: "identity".to_string(),
-                label: format!("Identity: {}", event.agent_id.as_deref().unwrap_or("unknown")),
-                outcome: identity_valid,
-                timestamp: event.timestamp,
-                details: serde_json::json!({
-                    "agent_id": event.agent_id,
-                    "valid": identity_valid,
-                    "developer_id": event.developer_id,
-                    "enterprise_id": event.enterprise_id,
-                }),
-            });
+            let identity_node_id = match identity_nodes.get(agent).filter(|_| collapse) {
+                Some(&index) => {
+                    nodes[index].outcome &= identity_valid;
+                    nodes[index].id.clone()
+                }
+                None => {
+                    let id = format!("identity-{}", i);
+                    identity_nodes.insert(agent.to_string(), nodes.len());
+                    nodes.push(DecisionNode {
+                        id: id.clone(),
+                        node_type: "identity".to_string(),
+                        label: format!("Identity: {}", agent),
+                        outcome: identity_valid,
+                        timestamp: event.timestamp,
+                        details: serde_json::json!({
+                            "agent_id": event.agent_id,
+                            "valid": identity_valid,
+                            "developer_id": event.developer_id,
+                            "enterprise_id": event.enterprise_id,
+                        }),
+                    });
+                    id
+                }
+            };
 
             // Policy node
-            let policy_node_id = format!("policy-{}", i);
-            nodes.push(DecisionNode {
-                id: policy_node_id.clone(),
-                node_type: "policy".to_string(),
-                label: format!("Policy: {}", event.policy_version.as_deref().unwrap_or("v1")),
-                outcome: policy_allowed,
-                timestamp: event.timestamp,
-                details: serde_json::json!({
-                    "allowed": policy_allowed,
-                    "version": event.policy_version,
-                    "evaluation_ms": event.policy_evaluation_ms,
-                }),
-            });
+            let policy_node_id = match policy_nodes.get(policy_version).filter(|_| collapse) {
+                Some(&index) => {
+                    nodes[index].outcome &= policy_allowed;
+                    nodes[index].id.clone()
+                }
+                None => {
+                    let id = format!("policy-{}", i);
+                    policy_nodes.insert(policy_version.to_string(), nodes.len());
+                    nodes.push(DecisionNode {
+                        id: id.clone(),
+                        node_type: "policy".to_string(),
+                        label: format!("Policy: {}", policy_version),
+                        outcome: policy_allowed,
+                        timestamp: event.timestamp,
+                        details: serde_json::json!({
+                            "allowed": policy_allowed,
+                            "version": event.policy_version,
+                            "evaluation_ms": event.policy_evaluation_ms,
+                        }),
+                    });
+                    id
+                }
+            };
 
             // Action node (the actual request)
             let action_node_id = format!("action-{}", i);
@@ -432,16 +1485,21 @@ impl QueryService {
                 label: Some(if policy_allowed { "allowed" } else { "denied" }.to_string()),
             });
 
-            // Connect to previous action if exists
-            if i > 0 {
+            // Connect to the previous step if exists
+            if let Some(prev) = previous.replace(action_node_id) {
                 edges.push(DecisionEdge {
-                    from: format!("action-{}", i - 1),
+                    from: prev,
                     to: identity_node_id,
                     label: Some("next".to_string()),
                 });
             }
         }
 
+        if collapse {
+            let mut seen = HashSet::new();
+            edges.retain(|edge| seen.insert((edge.from.clone(), edge.to.clone(), edge.label.clone())));
+        }
+
         Ok(DecisionTree { nodes, edges })
     }
 
@@ -452,13 +1510,312 @@ impl QueryService {
             None => return Ok(None),
         };
 
-        let timeline = self.get_timeline(trace_id).await?;
-        let decision_tree = self.build_decision_tree(trace_id).await?;
+        let timeline = self.get_timeline(trace_id, None).await?;
+        let decision_tree = self.build_decision_tree(trace_id, false).await?;
+        let span_tree = build_span_tree(&self.get_receipt_events(trace_id).await?);
+        let trust_summary = self.get_trace_trust_summary(trace_id).await?;
 
         Ok(Some(TraceDetailResponse {
             trace,
             timeline,
             decision_tree,
+            span_tree,
+            trust_summary,
         }))
     }
+
+    /// Min, max and average composite trust score over a trace's receipts, and the dimensions of the
+    /// latest receipt that recorded them
+    pub async fn get_trace_trust_summary(&self, trace_id: Uuid) -> Result<Option<TraceTrustSummary>> {
+        let row: TraceTrustRow = sqlx::query_as(
+            r#"
+            SELECT s.scored_receipts, s.min_score, s.max_score, s.avg_score,
+                   d.trust_dimensions_at_event AS latest_dimensions,
+                   d.timestamp AS latest_dimensions_at
+            FROM (
+                SELECT COUNT(trust_score_at_event) AS scored_receipts,
+                       MIN(trust_score_at_event)::float8 AS min_score,
+                       MAX(trust_score_at_event)::float8 AS max_score,
+                       AVG(trust_score_at_event)::float8 AS avg_score
+                FROM receipt_events
+                WHERE trace_id = $1
+            ) s
+            LEFT JOIN LATERAL (
+                SELECT trust_dimensions_at_event, timestamp
+                FROM receipt_events
+                WHERE trace_id = $1 AND trust_dimensions_at_event IS NOT NULL
+                ORDER BY timestamp DESC, receipt_id DESC
+                LIMIT 1
+            ) d ON true
+            "#
+        )
+        .bind(trace_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let (Some(min_score), Some(max_score), Some(avg_score)) = (row.min_score, row.max_score, row.avg_score) else {
+            return Ok(None);
+        };
+        Ok(Some(TraceTrustSummary {
+            scored_receipts: row.scored_receipts,
+            min_score,
+            max_score,
+            avg_score,
+            latest_dimensions: row.latest_dimensions.and_then(|d| serde_json::from_value(d).ok()),
+            latest_dimensions_at: row.latest_dimensions_at,
+        }))
+    }
+
+    /// Verify a trace's receipts, in timestamp order, against the hash chain: each receipt's hash is
+    /// recomputed from its stored fields, and its `previous_receipt_hash` must name a stored receipt.
+    /// The chain runs across all traces, so a receipt's predecessor is usually in another trace; a
+    /// receipt without one is taken as the start of the chain. Stops at the first break
+    pub async fn verify_chain(&self, trace_id: Uuid) -> Result<Option<ChainVerificationResponse>> {
+        if self.get_trace(trace_id).await?.is_none() {
+            return Ok(None);
+        }
+
+        let events = self.get_receipt_events(trace_id).await?;
+        let in_trace: HashSet<&str> =
+            events.iter().map(|e| e.receipt_hash.as_str()).collect();
+
+        // Predecessors outside this trace, looked up in one query
+        let external: Vec<String> = events
+            .iter()
+            .filter_map(|e| e.full_receipt.get("previous_receipt_hash").and_then(|h| h.as_str()))
+            .filter(|h| !in_trace.contains(h))
+            .map(str::to_string)
+            .collect();
+        let known: HashSet<String> = sqlx::query_scalar(
+            "SELECT receipt_hash FROM receipts WHERE receipt_hash = ANY($1)"
+        )
+        .bind(&external)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .collect();
+
+        let mut report = ChainVerificationResponse {
+            trace_id,
+            valid: true,
+            receipt_count: events.len(),
+            verified_count: 0,
+            first_broken_receipt_id: None,
+            reason: None,
+        };
+
+        for event in &events {
+            let break_reason = match recompute_receipt_hash(&event.full_receipt) {
+                Err(_) => Some(ChainBreak::UnreadableReceipt),
+                Ok(recomputed) if recomputed.computed != event.receipt_hash => Some(ChainBreak::HashMismatch),
+                Ok(RecomputedHash { previous: Some(previous), .. })
+                    if !in_trace.contains(previous.as_str()) && !known.contains(&previous) =>
+                {
+                    Some(ChainBreak::BrokenLink)
+                }
+                Ok(_) => None,
+            };
+
+            if let Some(reason) = break_reason {
+                report.valid = false;
+                report.first_broken_receipt_id = Some(event.receipt_id);
+                report.reason = Some(reason);
+                break;
+            }
+            report.verified_count += 1;
+        }
+
+        Ok(Some(report))
+    }
+}
+
+/// Recompute `full_receipt`'s hash and compare it with the `receipt_hash` it carries. Nothing stored is
+/// read, so this checks any receipt a caller holds, as the store returned it, against its own fields
+pub fn validate_receipt_hash(full_receipt: &serde_json::Value) -> Result<ReceiptValidationResponse> {
+    let recomputed = recompute_receipt_hash(full_receipt)?;
+    Ok(ReceiptValidationResponse {
+        valid: recomputed.computed == recomputed.stored,
+        computed_hash: recomputed.computed,
+        stored_hash: recomputed.stored,
+    })
+}
+
+/// A receipt's hash recomputed from its fields, beside the hashes it carries
+struct RecomputedHash {
+    computed: String,
+    stored: String,
+    previous: Option<String>,
+}
+
+/// Recompute a receipt's hash exactly as it was computed on write. v2 receipts are told apart by their
+/// `trust_snapshot` field, which v1 receipts never carry
+fn recompute_receipt_hash(full_receipt: &serde_json::Value) -> Result<RecomputedHash> {
+    if full_receipt.get("trust_snapshot").is_some() {
+        let receipt: ReceiptV2 = serde_json::from_value(full_receipt.clone())?;
+        Ok(RecomputedHash {
+            computed: receipt.calculate_hash()?,
+            stored: receipt.receipt_hash,
+            previous: receipt.previous_receipt_hash,
+        })
+    } else {
+        let receipt: Receipt = serde_json::from_value(full_receipt.clone())?;
+        Ok(RecomputedHash {
+            computed: receipt.calculate_hash()?,
+            stored: receipt.receipt_hash,
+            previous: receipt.previous_receipt_hash,
+        })
+    }
+}
+
+/// Compute the depth of each span within a trace (root spans are depth 0).
+///
+/// Parents missing from the trace are treated as roots, and cycles are cut off
+/// once the walk exceeds the number of spans in the trace.
+fn span_depths(events: &[ReceiptEventRow]) -> HashMap<Uuid, usize> {
+    let parents: HashMap<Uuid, Option<Uuid>> = events
+        .iter()
+        .map(|e| (e.span_id, e.parent_span_id))
+        .collect();
+
+    let mut depths = HashMap::new();
+    for span_id in parents.keys() {
+        let mut depth = 0;
+        let mut current = parents.get(span_id).copied().flatten();
+        while let Some(parent) = current {
+            if depth >= parents.len() || !parents.contains_key(&parent) {
+                break;
+            }
+            depth += 1;
+            current = parents.get(&parent).copied().flatten();
+        }
+        depths.insert(*span_id, depth);
+    }
+
+    depths
+}
+
+/// A receipt's one-line summary and outcome, as shown in timelines and span trees
+fn describe_receipt(event: &ReceiptEventRow) -> (String, EventOutcome) {
+    match &event.event_type {
+        EventType::TraceSeal => (
+            "Trace sealed".to_string(),
+            EventOutcome { success: true, reason: None },
+        ),
+        // Unknown types carry no decision semantics we can interpret
+        EventType::Other(name) => (
+            format!("{} ({})", name, event.event_source_service),
+            EventOutcome { success: true, reason: None },
+        ),
+        _ => (
+            format!(
+                "{} {} - {}",
+                event.request_method.as_deref().unwrap_or("?"),
+                event.request_path.as_deref().unwrap_or("?"),
+                if event.policy_allowed.unwrap_or(false) { "Allowed" } else { "Denied" }
+            ),
+            EventOutcome {
+                success: event.policy_allowed.unwrap_or(false) && event.identity_valid.unwrap_or(false),
+                reason: if !event.policy_allowed.unwrap_or(true) {
+                    Some("Policy denied".to_string())
+                } else if !event.identity_valid.unwrap_or(true) {
+                    Some("Identity invalid".to_string())
+                } else {
+                    None
+                },
+            },
+        ),
+    }
+}
+
+/// Arrange a trace's receipts (in timestamp order) into span trees keyed on `parent_span_id`.
+///
+/// Receipts sharing a span hang their children under the span's first receipt. A span whose parent
+/// is missing from the trace becomes an orphaned root. Spans whose parents form a cycle are never
+/// reached from a root; each cycle is cut at its earliest receipt, which also becomes an orphaned root.
+/// The seal receipt attests to the trace rather than recording a call, so it is left out.
+fn build_span_tree(events: &[ReceiptEventRow]) -> Vec<SpanNode> {
+    let events: Vec<&ReceiptEventRow> = events
+        .iter()
+        .filter(|e| e.event_type != EventType::TraceSeal)
+        .collect();
+
+    let mut first_of_span: HashMap<Uuid, usize> = HashMap::new();
+    for (i, event) in events.iter().enumerate() {
+        first_of_span.entry(event.span_id).or_insert(i);
+    }
+    let parents: Vec<Option<usize>> = events
+        .iter()
+        .enumerate()
+        .map(|(i, e)| {
+            e.parent_span_id
+                .and_then(|p| first_of_span.get(&p).copied())
+                .filter(|&p| p != i)
+        })
+        .collect();
+
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); events.len()];
+    let mut roots = Vec::new();
+    let mut orphaned = vec![false; events.len()];
+    for (i, parent) in parents.iter().enumerate() {
+        match parent {
+            Some(p) => children[*p].push(i),
+            None => {
+                orphaned[i] = events[i].parent_span_id.is_some();
+                roots.push(i);
+            }
+        }
+    }
+
+    // Walk down from the roots; whatever isn't reached sits on a cycle or below one
+    let mut reached = vec![false; events.len()];
+    let mut stack = roots.clone();
+    loop {
+        while let Some(i) = stack.pop() {
+            reached[i] = true;
+            stack.extend(children[i].iter().copied());
+        }
+        let Some(cut) = reached.iter().position(|r| !r) else {
+            break;
+        };
+        if let Some(p) = parents[cut] {
+            children[p].retain(|&c| c != cut);
+        }
+        orphaned[cut] = true;
+        roots.push(cut);
+        stack.push(cut);
+    }
+    roots.sort_unstable();
+
+    // Build children before their parents: reverse pre-order
+    let mut preorder = Vec::with_capacity(events.len());
+    let mut stack: Vec<usize> = roots.iter().rev().copied().collect();
+    while let Some(i) = stack.pop() {
+        preorder.push(i);
+        stack.extend(children[i].iter().rev().copied());
+    }
+    let mut built: Vec<Option<SpanNode>> = events.iter().map(|_| None).collect();
+    for &i in preorder.iter().rev() {
+        let event = events[i];
+        let (summary, outcome) = describe_receipt(event);
+        built[i] = Some(SpanNode {
+            span_id: event.span_id,
+            parent_span_id: event.parent_span_id,
+            receipt_id: event.receipt_id,
+            timestamp: event.timestamp,
+            event_type: event.event_type.clone(),
+            agent_id: event.agent_id.clone(),
+            summary,
+            outcome,
+            orphaned: orphaned[i],
+            children: children[i].iter().filter_map(|&c| built[c].take()).collect(),
+        });
+    }
+
+    roots.iter().filter_map(|&r| built[r].take()).collect()
+}
+
+/// ILIKE pattern matching `term` anywhere, with its own wildcards taken literally
+fn contains_pattern(term: &str) -> String {
+    let escaped = term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
 }
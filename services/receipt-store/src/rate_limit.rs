@@ -0,0 +1,189 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::api::ErrorResponse;
+use crate::config::{RateLimit, RateLimitConfig};
+
+/// Matches axum's default request body limit, so peeking never accepts more than the handler would
+const MAX_PEEK_BYTES: usize = 2 * 1024 * 1024;
+
+/// Idle buckets are pruned once this many sources are being tracked
+const MAX_TRACKED_SOURCES: usize = 10_000;
+
+/// Header that identifies a producer by API key instead of its event source
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    /// Add the tokens earned since the last update, up to the burst
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.requests_per_second).min(limit.burst as f64);
+        self.updated = now;
+    }
+}
+
+/// Outcome of charging a source, reported back in the `X-RateLimit-*` headers
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitStatus {
+    pub limit: u32,
+    pub remaining: u32,
+    /// Seconds until the rejected write would fit; zero when it was allowed
+    pub retry_after_secs: u64,
+}
+
+/// Per-source token buckets for the write endpoints
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn limit_for(&self, source: &str) -> Option<&RateLimit> {
+        self.config.sources.get(source).or(self.config.default.as_ref())
+    }
+
+    /// Charge `cost` receipts to `source`; `None` means the source is unlimited
+    pub fn check(&self, source: &str, cost: u32) -> Option<Result<RateLimitStatus, RateLimitStatus>> {
+        let limit = self.limit_for(source)?;
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        if buckets.len() >= MAX_TRACKED_SOURCES && !buckets.contains_key(source) {
+            // A full bucket behaves exactly like a new one, so it carries no state worth keeping
+            buckets.retain(|name, bucket| match self.limit_for(name) {
+                Some(limit) => {
+                    bucket.refill(limit, now);
+                    bucket.tokens < limit.burst as f64
+                }
+                None => false,
+            });
+        }
+
+        let burst = limit.burst as f64;
+        let bucket = buckets.entry(source.to_string()).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        bucket.refill(limit, now);
+
+        let cost = cost as f64;
+        if bucket.tokens >= cost {
+            bucket.tokens -= cost;
+            return Some(Ok(RateLimitStatus {
+                limit: limit.burst,
+                remaining: bucket.tokens.floor() as u32,
+                retry_after_secs: 0,
+            }));
+        }
+
+        // A batch larger than the burst can never fit, so don't invite a retry
+        let retry_after_secs = if cost > burst || limit.requests_per_second <= 0.0 {
+            0
+        } else {
+            ((cost - bucket.tokens) / limit.requests_per_second).ceil() as u64
+        };
+        Some(Err(RateLimitStatus {
+            limit: limit.burst,
+            remaining: bucket.tokens.floor() as u32,
+            retry_after_secs,
+        }))
+    }
+}
+
+/// The producer a write is charged to, and how many receipts it carries
+fn identify_source(headers: &HeaderMap, body: &[u8]) -> (String, u32) {
+    let payload: serde_json::Value = serde_json::from_slice(body).unwrap_or_default();
+    // Batches are charged per event, to the source of their first event
+    let (first, cost) = match payload.as_array() {
+        Some(events) => (events.first().cloned().unwrap_or_default(), events.len().max(1)),
+        None => (payload, 1),
+    };
+
+    let source = headers
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+        .or_else(|| first.pointer("/event_source/service").and_then(|v| v.as_str()).map(str::to_string))
+        .or_else(|| first.get("source_system").and_then(|v| v.as_str()).map(str::to_string))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    (source, cost.min(u32::MAX as usize) as u32)
+}
+
+fn apply_headers(headers: &mut HeaderMap, status: &RateLimitStatus) {
+    headers.insert("x-ratelimit-limit", HeaderValue::from(status.limit));
+    headers.insert("x-ratelimit-remaining", HeaderValue::from(status.remaining));
+}
+
+fn rejection(status: StatusCode, error: &str, message: &str) -> Response {
+    (status, Json(ErrorResponse::new(error, message.to_string())))
+        .into_response()
+}
+
+/// Reject writes from sources over their limit with a 429
+pub async fn limit_writes(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (parts, body) = request.into_parts();
+    let bytes = match to_bytes(body, MAX_PEEK_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            return rejection(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "payload_too_large",
+                "Request body exceeds the maximum size",
+            )
+        }
+    };
+
+    let (source, cost) = identify_source(&parts.headers, &bytes);
+    let status = match limiter.check(&source, cost) {
+        None => return next.run(Request::from_parts(parts, Body::from(bytes))).await,
+        Some(Err(status)) => {
+            if parts.headers.contains_key(API_KEY_HEADER) {
+                tracing::warn!("Rate limited {} receipt(s) from an API key", cost);
+            } else {
+                tracing::warn!("Rate limited {} receipt(s) from source {}", cost, source);
+            }
+            let mut response = rejection(
+                StatusCode::TOO_MANY_REQUESTS,
+                "rate_limited",
+                "Receipt ingestion rate limit exceeded for this source",
+            );
+            apply_headers(response.headers_mut(), &status);
+            if status.retry_after_secs > 0 {
+                response
+                    .headers_mut()
+                    .insert("retry-after", HeaderValue::from(status.retry_after_secs));
+            }
+            return response;
+        }
+        Some(Ok(status)) => status,
+    };
+
+    let mut response = next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    apply_headers(response.headers_mut(), &status);
+    response
+}
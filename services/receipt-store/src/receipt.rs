@@ -1,23 +1,70 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
-use sha2::{Sha256, Digest};
-use hex;
+use anyhow::Result;
 
-/// Event types for categorizing receipt events
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "snake_case")]
+use crate::canonical::HashCanonicalization;
+use crate::hashing::{self, default_hash_algorithm, DEFAULT_HASH_ALGORITHM};
+use crate::signing::ReceiptSigner;
+
+/// Event types for categorizing receipt and external events.
+/// Stored and serialized as snake_case text; unrecognized values round-trip through `Other`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub enum EventType {
+    #[default]
     GatewayRequest,
     PolicyEvaluation,
     IdentityValidation,
     ExternalEvent,
     HumanAction,
+    TraceSeal,
+    Other(String),
 }
 
-impl Default for EventType {
-    fn default() -> Self {
-        EventType::GatewayRequest
+
+impl EventType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            EventType::GatewayRequest => "gateway_request",
+            EventType::PolicyEvaluation => "policy_evaluation",
+            EventType::IdentityValidation => "identity_validation",
+            EventType::ExternalEvent => "external_event",
+            EventType::HumanAction => "human_action",
+            EventType::TraceSeal => "trace_seal",
+            EventType::Other(value) => value,
+        }
+    }
+}
+
+impl From<String> for EventType {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "gateway_request" => EventType::GatewayRequest,
+            "policy_evaluation" => EventType::PolicyEvaluation,
+            "identity_validation" => EventType::IdentityValidation,
+            "external_event" => EventType::ExternalEvent,
+            "human_action" => EventType::HumanAction,
+            "trace_seal" => EventType::TraceSeal,
+            _ => EventType::Other(value),
+        }
+    }
+}
+
+impl std::fmt::Display for EventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for EventType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for EventType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(EventType::from)
     }
 }
 
@@ -63,16 +110,64 @@ pub struct Receipt {
     pub correlation_id: Option<String>,
     pub span_id: Uuid,
     pub parent_span_id: Option<Uuid>,
+    /// When the event occurred (client-supplied `occurred_at`, else receive time)
     pub timestamp: DateTime<Utc>,
+    /// When the receipt store received the event (not part of the hash)
+    pub received_at: DateTime<Utc>,
+    /// Per-phase gateway latency, when reported (not part of the hash)
+    pub latency: Option<LatencyBreakdown>,
     pub agent_id: String,
+    /// Principals the agent acted for, nearest first (verified by the gateway)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_behalf_of: Vec<DelegationLink>,
     pub event_type: EventType,
     pub event_source: EventSource,
     pub request: RequestInfo,
     pub policy_result: PolicyResult,
     pub identity_result: IdentityResult,
     pub metadata: Option<serde_json::Value>,
+    /// Tokens and cost the request consumed, when reported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
     pub receipt_hash: String,
     pub previous_receipt_hash: Option<String>,
+    /// How the hashed fields were serialized; absent (legacy) on receipts stored before it was recorded
+    #[serde(default)]
+    pub hash_canonicalization: HashCanonicalization,
+    /// Digest `receipt_hash` was computed with; absent (sha256) on receipts stored before it was recorded
+    #[serde(default = "default_hash_algorithm")]
+    pub hash_algorithm: String,
+    /// Ed25519 signature over `receipt_hash` (not part of the hash); absent when the store has no signing key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Public key `signature` verifies against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature_public_key: Option<String>,
+}
+
+/// Token and cost usage reported for an LLM-backed request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Usage {
+    pub input_tokens: Option<u64>,
+    pub output_tokens: Option<u64>,
+    /// Cost in the platform's billing currency
+    pub cost: Option<f64>,
+}
+
+/// One principal in an agent's on-behalf-of chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegationLink {
+    pub principal_type: String,
+    pub principal_id: String,
+}
+
+/// Time spent in each gateway phase for a request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyBreakdown {
+    pub identity_ms: Option<u64>,
+    pub policy_ms: Option<u64>,
+    pub upstream_ms: Option<u64>,
+    pub total_ms: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -81,6 +176,12 @@ pub struct RequestInfo {
     pub path: String,
     pub headers: std::collections::HashMap<String, String>,
     pub body_hash: Option<String>,
+    /// Request `Content-Type`, sent alongside a captured body
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Captured request body; dropped before hashing unless its content type is allowlisted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -97,23 +198,64 @@ pub struct IdentityResult {
     pub enterprise_id: Option<Uuid>,
 }
 
+/// What a receipt records about an event; `Receipt::new` (with v1 results) and `ReceiptV2::new`
+/// (with v2 results) add its id and hash
+pub struct ReceiptContent<P, I> {
+    pub trace_id: Uuid,
+    pub correlation_id: Option<String>,
+    pub span_id: Uuid,
+    pub parent_span_id: Option<Uuid>,
+    pub agent_id: String,
+    pub on_behalf_of: Vec<DelegationLink>,
+    pub event_type: EventType,
+    pub event_source: EventSource,
+    pub request: RequestInfo,
+    pub policy_result: P,
+    pub identity_result: I,
+    pub metadata: Option<serde_json::Value>,
+    /// Client-side event time; the receipt's `timestamp` falls back to `received_at`
+    pub occurred_at: Option<DateTime<Utc>>,
+    pub latency: Option<LatencyBreakdown>,
+    pub usage: Option<Usage>,
+}
+
+/// Where a new receipt joins the chain and how it is hashed and signed
+pub struct ReceiptSealing<'a> {
+    pub previous_receipt_hash: Option<String>,
+    pub received_at: DateTime<Utc>,
+    pub hash_canonicalization: HashCanonicalization,
+    pub hash_algorithm: &'a str,
+    pub signer: Option<&'a ReceiptSigner>,
+}
+
 impl Receipt {
-    pub fn new(
-        trace_id: Uuid,
-        correlation_id: Option<String>,
-        span_id: Uuid,
-        parent_span_id: Option<Uuid>,
-        agent_id: String,
-        event_type: EventType,
-        event_source: EventSource,
-        request: RequestInfo,
-        policy_result: PolicyResult,
-        identity_result: IdentityResult,
-        metadata: Option<serde_json::Value>,
-        previous_receipt_hash: Option<String>,
-    ) -> Self {
+    pub fn new(content: ReceiptContent<PolicyResult, IdentityResult>, sealing: ReceiptSealing<'_>) -> Result<Self> {
+        let ReceiptContent {
+            trace_id,
+            correlation_id,
+            span_id,
+            parent_span_id,
+            agent_id,
+            on_behalf_of,
+            event_type,
+            event_source,
+            request,
+            policy_result,
+            identity_result,
+            metadata,
+            occurred_at,
+            latency,
+            usage,
+        } = content;
+        let ReceiptSealing {
+            previous_receipt_hash,
+            received_at,
+            hash_canonicalization,
+            hash_algorithm,
+            signer,
+        } = sealing;
         let receipt_id = Uuid::new_v4();
-        let timestamp = Utc::now();
+        let timestamp = occurred_at.unwrap_or(received_at);
 
         // Create receipt without hash first
         let receipt = Self {
@@ -123,30 +265,38 @@ impl Receipt {
             span_id,
             parent_span_id,
             timestamp,
+            received_at,
+            latency,
             agent_id,
+            on_behalf_of,
             event_type,
             event_source,
             request,
             policy_result,
             identity_result,
             metadata,
+            usage,
             receipt_hash: String::new(), // Will be calculated
             previous_receipt_hash,
+            hash_canonicalization,
+            hash_algorithm: hash_algorithm.to_string(),
+            signature: None,
+            signature_public_key: None,
         };
 
-        // Calculate hash and create final receipt
-        let receipt_hash = receipt.calculate_hash();
-        Self {
+        // Calculate hash, sign it if a key is configured, and create final receipt
+        let receipt_hash = receipt.calculate_hash()?;
+        Ok(Self {
+            signature: signer.map(|s| s.sign(&receipt_hash)),
+            signature_public_key: signer.map(|s| s.public_key().to_string()),
             receipt_hash,
             ..receipt
-        }
+        })
     }
 
-    pub fn calculate_hash(&self) -> String {
-        let mut hasher = Sha256::new();
-
+    pub fn calculate_hash(&self) -> Result<String> {
         // Hash all fields except receipt_hash itself
-        let hash_data = serde_json::json!({
+        let mut hash_data = serde_json::json!({
             "receipt_id": self.receipt_id,
             "trace_id": self.trace_id,
             "correlation_id": self.correlation_id,
@@ -163,21 +313,24 @@ impl Receipt {
             "previous_receipt_hash": self.previous_receipt_hash,
         });
 
-        hasher.update(serde_json::to_string(&hash_data).unwrap().as_bytes());
-        hex::encode(hasher.finalize())
-    }
-
-    pub fn verify_chain(&self, previous_receipt: &Receipt) -> bool {
-        // Verify that previous receipt hash matches
-        if let Some(ref prev_hash) = self.previous_receipt_hash {
-            if prev_hash != &previous_receipt.receipt_hash {
-                return false;
-            }
+        // Only hashed when present so receipts without delegation keep their original hash
+        if !self.on_behalf_of.is_empty() {
+            hash_data["on_behalf_of"] = serde_json::json!(self.on_behalf_of);
+        }
+        if let Some(ref usage) = self.usage {
+            hash_data["usage"] = serde_json::json!(usage);
         }
-        
-        // Verify current receipt hash
-        let calculated_hash = self.calculate_hash();
-        calculated_hash == self.receipt_hash
+        // Legacy receipts predate the field, so only canonicalized ones hash it
+        if self.hash_canonicalization != HashCanonicalization::Legacy {
+            hash_data["hash_canonicalization"] = serde_json::json!(self.hash_canonicalization);
+        }
+        // Likewise only hashed when it isn't the algorithm every receipt used before it was recorded
+        if self.hash_algorithm != DEFAULT_HASH_ALGORITHM {
+            hash_data["hash_algorithm"] = serde_json::json!(self.hash_algorithm);
+        }
+
+        let bytes = self.hash_canonicalization.serialize(&hash_data)?;
+        hashing::digest(&self.hash_algorithm, bytes.as_bytes())
     }
 }
 
@@ -188,12 +341,20 @@ pub struct ReceiptRequest {
     pub span_id: Option<Uuid>,
     pub parent_span_id: Option<Uuid>,
     pub agent_id: String,
+    #[serde(default)]
+    pub on_behalf_of: Vec<DelegationLink>,
     pub event_type: Option<EventType>,
     pub event_source: Option<EventSource>,
     pub request: RequestInfo,
     pub policy_result: PolicyResult,
     pub identity_result: IdentityResult,
     pub metadata: Option<serde_json::Value>,
+    /// Client-side event time; defaults to the server receive time
+    pub occurred_at: Option<DateTime<Utc>>,
+    pub latency: Option<LatencyBreakdown>,
+    pub usage: Option<Usage>,
+    /// Client key for the logical request; a retry with the same key gets the receipt already stored
+    pub idempotency_key: Option<String>,
 }
 
 /// External event for integration with SAP, Salesforce, etc.
@@ -201,7 +362,7 @@ pub struct ReceiptRequest {
 pub struct ExternalEventRequest {
     pub trace_id: Uuid,
     pub correlation_id: Option<String>,
-    pub event_type: String,
+    pub event_type: EventType,
     pub source_system: String,
     pub source_id: String,
     pub timestamp: DateTime<Utc>,
@@ -216,7 +377,7 @@ pub struct ExternalEvent {
     pub event_id: Uuid,
     pub trace_id: Uuid,
     pub correlation_id: Option<String>,
-    pub event_type: String,
+    pub event_type: EventType,
     pub source_system: String,
     pub source_id: String,
     pub timestamp: DateTime<Utc>,
@@ -244,6 +405,71 @@ impl ExternalEvent {
     }
 }
 
+/// Final integrity seal over a completed trace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceSeal {
+    pub trace_id: Uuid,
+    pub seal_root: String,
+    pub receipt_count: usize,
+    pub seal_receipt_id: Uuid,
+    pub seal_receipt_hash: String,
+    pub sealed_at: DateTime<Utc>,
+}
+
+/// Status of a single item in a batch external-event ingestion
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExternalEventStatus {
+    Accepted,
+    Duplicate,
+    Rejected,
+}
+
+/// Per-item result of a batch external-event ingestion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalEventResult {
+    pub index: usize,
+    pub status: ExternalEventStatus,
+    pub event_id: Option<Uuid>,
+    pub error: Option<String>,
+}
+
+/// Per-item result of a batch receipt ingestion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReceiptBatchResult {
+    pub index: usize,
+    pub receipt_id: Option<Uuid>,
+    pub receipt_hash: Option<String>,
+    pub trace_id: Option<Uuid>,
+    pub stored: bool,
+    pub error: Option<String>,
+}
+
+impl ReceiptBatchResult {
+    pub fn rejected(index: usize, error: String) -> Self {
+        Self {
+            index,
+            receipt_id: None,
+            receipt_hash: None,
+            trace_id: None,
+            stored: false,
+            error: Some(error),
+        }
+    }
+
+    /// A receipt whose idempotency key was already stored, reported with the stored receipt's IDs
+    pub fn duplicate(index: usize, existing: &Receipt) -> Self {
+        Self {
+            index,
+            receipt_id: Some(existing.receipt_id),
+            receipt_hash: Some(existing.receipt_hash.clone()),
+            trace_id: Some(existing.trace_id),
+            stored: false,
+            error: None,
+        }
+    }
+}
+
 // ========================================
 // V2 Types (Phase 1 - Trust & Attribution)
 // ========================================
@@ -334,16 +560,39 @@ pub struct ReceiptV2 {
     pub correlation_id: Option<String>,
     pub span_id: Uuid,
     pub parent_span_id: Option<Uuid>,
+    /// When the event occurred (client-supplied `occurred_at`, else receive time)
     pub timestamp: DateTime<Utc>,
+    /// When the receipt store received the event (not part of the hash)
+    pub received_at: DateTime<Utc>,
+    /// Per-phase gateway latency, when reported (not part of the hash)
+    pub latency: Option<LatencyBreakdown>,
     pub agent_id: String,
+    /// Principals the agent acted for, nearest first (verified by the gateway)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_behalf_of: Vec<DelegationLink>,
     pub event_type: EventType,
     pub event_source: EventSource,
     pub request: RequestInfo,
     pub policy_result: PolicyResultV2,
     pub identity_result: IdentityResultV2,
     pub metadata: Option<serde_json::Value>,
+    /// Tokens and cost the request consumed, when reported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
     pub receipt_hash: String,
     pub previous_receipt_hash: Option<String>,
+    /// How the hashed fields were serialized; absent (legacy) on receipts stored before it was recorded
+    #[serde(default)]
+    pub hash_canonicalization: HashCanonicalization,
+    /// Digest `receipt_hash` was computed with; absent (sha256) on receipts stored before it was recorded
+    #[serde(default = "default_hash_algorithm")]
+    pub hash_algorithm: String,
+    /// Ed25519 signature over `receipt_hash` (not part of the hash); absent when the store has no signing key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Public key `signature` verifies against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature_public_key: Option<String>,
     // Phase 1 additions
     pub tenant_id: Option<Uuid>,
     pub trust_snapshot: Option<TrustContext>,
@@ -351,22 +600,33 @@ pub struct ReceiptV2 {
 }
 
 impl ReceiptV2 {
-    pub fn new(
-        trace_id: Uuid,
-        correlation_id: Option<String>,
-        span_id: Uuid,
-        parent_span_id: Option<Uuid>,
-        agent_id: String,
-        event_type: EventType,
-        event_source: EventSource,
-        request: RequestInfo,
-        policy_result: PolicyResultV2,
-        identity_result: IdentityResultV2,
-        metadata: Option<serde_json::Value>,
-        previous_receipt_hash: Option<String>,
-    ) -> Self {
+    pub fn new(content: ReceiptContent<PolicyResultV2, IdentityResultV2>, sealing: ReceiptSealing<'_>) -> Result<Self> {
+        let ReceiptContent {
+            trace_id,
+            correlation_id,
+            span_id,
+            parent_span_id,
+            agent_id,
+            on_behalf_of,
+            event_type,
+            event_source,
+            request,
+            policy_result,
+            identity_result,
+            metadata,
+            occurred_at,
+            latency,
+            usage,
+        } = content;
+        let ReceiptSealing {
+            previous_receipt_hash,
+            received_at,
+            hash_canonicalization,
+            hash_algorithm,
+            signer,
+        } = sealing;
         let receipt_id = Uuid::new_v4();
-        let timestamp = Utc::now();
+        let timestamp = occurred_at.unwrap_or(received_at);
 
         // Extract tenant and trust info from identity result
         let tenant_id = identity_result.tenant_id;
@@ -381,33 +641,40 @@ impl ReceiptV2 {
             span_id,
             parent_span_id,
             timestamp,
+            received_at,
+            latency,
             agent_id,
+            on_behalf_of,
             event_type,
             event_source,
             request,
             policy_result,
             identity_result,
             metadata,
+            usage,
             receipt_hash: String::new(),
             previous_receipt_hash,
+            hash_canonicalization,
+            hash_algorithm: hash_algorithm.to_string(),
+            signature: None,
+            signature_public_key: None,
             tenant_id,
             trust_snapshot,
             attribution_snapshot,
         };
 
-        // Calculate hash and create final receipt
-        let receipt_hash = receipt.calculate_hash();
-        Self {
+        // Calculate hash, sign it if a key is configured, and create final receipt
+        let receipt_hash = receipt.calculate_hash()?;
+        Ok(Self {
+            signature: signer.map(|s| s.sign(&receipt_hash)),
+            signature_public_key: signer.map(|s| s.public_key().to_string()),
             receipt_hash,
             ..receipt
-        }
+        })
     }
 
-    pub fn calculate_hash(&self) -> String {
-        use sha2::Digest;
-        let mut hasher = Sha256::new();
-
-        let hash_data = serde_json::json!({
+    pub fn calculate_hash(&self) -> Result<String> {
+        let mut hash_data = serde_json::json!({
             "receipt_id": self.receipt_id,
             "trace_id": self.trace_id,
             "correlation_id": self.correlation_id,
@@ -427,8 +694,24 @@ impl ReceiptV2 {
             "attribution_snapshot": self.attribution_snapshot,
         });
 
-        hasher.update(serde_json::to_string(&hash_data).unwrap().as_bytes());
-        hex::encode(hasher.finalize())
+        // Only hashed when present so receipts without delegation keep their original hash
+        if !self.on_behalf_of.is_empty() {
+            hash_data["on_behalf_of"] = serde_json::json!(self.on_behalf_of);
+        }
+        if let Some(ref usage) = self.usage {
+            hash_data["usage"] = serde_json::json!(usage);
+        }
+        // Legacy receipts predate the field, so only canonicalized ones hash it
+        if self.hash_canonicalization != HashCanonicalization::Legacy {
+            hash_data["hash_canonicalization"] = serde_json::json!(self.hash_canonicalization);
+        }
+        // Likewise only hashed when it isn't the algorithm every receipt used before it was recorded
+        if self.hash_algorithm != DEFAULT_HASH_ALGORITHM {
+            hash_data["hash_algorithm"] = serde_json::json!(self.hash_algorithm);
+        }
+
+        let bytes = self.hash_canonicalization.serialize(&hash_data)?;
+        hashing::digest(&self.hash_algorithm, bytes.as_bytes())
     }
 }
 
@@ -440,12 +723,18 @@ pub struct ReceiptRequestV2 {
     pub span_id: Option<Uuid>,
     pub parent_span_id: Option<Uuid>,
     pub agent_id: String,
+    #[serde(default)]
+    pub on_behalf_of: Vec<DelegationLink>,
     pub event_type: Option<EventType>,
     pub event_source: Option<EventSource>,
     pub request: RequestInfo,
     pub policy_result: PolicyResultV2,
     pub identity_result: IdentityResultV2,
     pub metadata: Option<serde_json::Value>,
+    /// Client-side event time; defaults to the server receive time
+    pub occurred_at: Option<DateTime<Utc>>,
+    pub latency: Option<LatencyBreakdown>,
+    pub usage: Option<Usage>,
 }
 
 /// Trust event for tracking trust score changes over time
@@ -464,6 +753,25 @@ pub struct TrustEvent {
     pub details: serde_json::Value,
 }
 
+/// A trust threshold violation as published to `KAFKA_TRUST_TOPIC`, for alerting consumers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustViolationAlert {
+    /// The violation's trust event
+    pub event_id: Uuid,
+    pub receipt_id: Uuid,
+    pub trace_id: Uuid,
+    pub agent_id: String,
+    pub tenant_id: Option<Uuid>,
+    pub timestamp: DateTime<Utc>,
+    pub trust_score: f64,
+    pub threshold: f64,
+    pub action_taken: Option<String>,
+    /// Violations in a row for the agent, this one included
+    pub consecutive_violations: i32,
+    /// Whether this violation escalated the action to a block
+    pub escalated: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum TrustEventType {
@@ -473,3 +781,15 @@ pub enum TrustEventType {
     ScoreUpdated,
 }
 
+/// High-severity risk opened when an agent's trust violations escalate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustRiskEvent {
+    pub risk_id: Uuid,
+    pub agent_id: String,
+    pub trace_id: Option<Uuid>,
+    pub risk_type: String,
+    pub severity: String,
+    pub description: String,
+    pub evidence: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
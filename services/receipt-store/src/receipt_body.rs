@@ -0,0 +1,42 @@
+//! Receipt bodies. Parsed like any other JSON body (see `pathwell_common::json_body`), except that in
+//! strict mode a field the receipt doesn't define is a 422 `unknown_fields`.
+
+use axum::{
+    async_trait,
+    extract::{FromRequest, Query, Request},
+    http::StatusCode,
+};
+use pathwell_common::json_body::{parse, read_body, reject, JsonBodyRejection};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::sync::Arc;
+
+use crate::store::ReceiptStore;
+
+/// A receipt body. Like `JsonBody`, but in strict mode, set by `RECEIPT_STRICT_FIELDS` or a
+/// request's `?strict=true`, a field the receipt doesn't define is rejected instead of ignored, so a
+/// producer's misspelled field is caught rather than silently dropped
+pub struct ReceiptBody<T>(pub T);
+
+#[derive(Debug, Default, Deserialize)]
+struct StrictQuery {
+    #[serde(default)]
+    strict: bool,
+}
+
+#[async_trait]
+impl<T> FromRequest<Arc<ReceiptStore>> for ReceiptBody<T>
+where
+    T: DeserializeOwned,
+{
+    type Rejection = JsonBodyRejection;
+
+    async fn from_request(req: Request, store: &Arc<ReceiptStore>) -> Result<Self, Self::Rejection> {
+        // The query can only turn strict mode on; a globally strict store stays strict
+        let Query(query) = Query::<StrictQuery>::try_from_uri(req.uri())
+            .map_err(|e| reject(StatusCode::BAD_REQUEST, "invalid_query", e.body_text(), None))?;
+        let strict = store.strict_receipt_fields() || query.strict;
+
+        let bytes = read_body(req, store).await?;
+        parse(&bytes, strict).map(ReceiptBody)
+    }
+}
@@ -0,0 +1,374 @@
+//! Trace retention: which traces have outlived their tenant's retention period, and deleting them.
+//!
+//! The selection lives here so the purge preview and the purge itself agree on exactly
+//! which traces (and with them, receipts) are eligible for deletion.
+//!
+//! A trace is only deleted once every one of its receipts has been confirmed archived to S3
+//! (`receipt_events.archived_at`); until then it is reported as awaiting archive and kept. The purge
+//! deletes a trace's receipt events, external events, trust events and attribution lineage along
+//! with the trace. The `receipts` hash rows stay, so the hash chain of later receipts still verifies.
+//!
+//! With `RETENTION_PURGE_INTERVAL_SECS` set, every replica runs the purge as a background job, so
+//! one replica purges at a time. Each batch of traces is deleted in its own transaction.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgArguments;
+use sqlx::query::QueryAs;
+use sqlx::{PgConnection, PgPool, Postgres};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::config::RetentionConfig;
+use crate::jobs::{self, Job};
+use crate::store::ReceiptStore;
+
+/// Traces kept in a preview's sample when `sample_size` isn't set
+const DEFAULT_SAMPLE_SIZE: i64 = 20;
+const MAX_SAMPLE_SIZE: i64 = 100;
+
+/// `eligible` CTE: traces whose last event is older than their retention period. A trace uses its
+/// tenant's override, else the default; with neither it is never eligible. `archived` is false while
+/// any of the trace's receipts is not yet confirmed archived, which keeps it from being deleted.
+/// Binds `$1` tenant IDs and `$2` their days (parallel arrays), `$3` the default days, `$4` now
+const ELIGIBLE_TRACES: &str = r#"
+    WITH overrides AS (
+        SELECT * FROM unnest($1::uuid[], $2::int[]) AS o(tenant_id, days)
+    ),
+    eligible AS (
+        SELECT t.trace_id, t.tenant_id, t.last_event_at,
+               COALESCE(o.days, $3::int) AS retention_days,
+               NOT EXISTS (
+                   SELECT 1 FROM receipt_events r
+                   WHERE r.trace_id = t.trace_id AND r.archived_at IS NULL
+               ) AS archived
+        FROM traces t
+        LEFT JOIN overrides o ON o.tenant_id = t.tenant_id
+        WHERE COALESCE(o.days, $3::int) IS NOT NULL
+          AND t.last_event_at < $4::timestamptz - make_interval(days => COALESCE(o.days, $3::int))
+    )
+"#;
+
+/// The retention policy resolved at one instant, ready to bind to `ELIGIBLE_TRACES`
+pub struct RetentionSelection {
+    tenant_ids: Vec<Uuid>,
+    tenant_days: Vec<i32>,
+    default_days: Option<i32>,
+    pub evaluated_at: DateTime<Utc>,
+}
+
+impl RetentionSelection {
+    pub fn new(config: &RetentionConfig, evaluated_at: DateTime<Utc>) -> Self {
+        let (tenant_ids, tenant_days) = config
+            .tenant_days
+            .iter()
+            .map(|(tenant_id, days)| (*tenant_id, *days as i32))
+            .unzip();
+        Self {
+            tenant_ids,
+            tenant_days,
+            default_days: config.default_days.map(|d| d as i32),
+            evaluated_at,
+        }
+    }
+
+    /// A query over `eligible` (`sql` follows the CTE), with the selection bound as `$1`-`$4`
+    pub fn query_as<'q, T>(&'q self, sql: &'q str) -> QueryAs<'q, Postgres, T, PgArguments>
+    where
+        T: for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow>,
+    {
+        sqlx::query_as(sql)
+            .bind(&self.tenant_ids)
+            .bind(&self.tenant_days)
+            .bind(self.default_days)
+            .bind(self.evaluated_at)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PurgePreviewQuery {
+    pub sample_size: Option<i64>,
+}
+
+/// What a purge would delete right now
+#[derive(Debug, Serialize)]
+pub struct PurgePreviewResponse {
+    pub evaluated_at: DateTime<Utc>,
+    pub policy: RetentionConfig,
+    pub trace_count: i64,
+    pub receipt_count: i64,
+    /// Traces past retention that are kept until all their receipts are archived
+    pub awaiting_archive_count: i64,
+    pub tenants: Vec<TenantPurgePreview>,
+    /// The oldest eligible traces
+    pub sample: Vec<PurgeCandidate>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TenantPurgePreview {
+    /// `None` for traces without a tenant
+    pub tenant_id: Option<Uuid>,
+    pub retention_days: i32,
+    pub trace_count: i64,
+    pub receipt_count: i64,
+    pub oldest_event_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PurgeCandidate {
+    pub trace_id: Uuid,
+    pub tenant_id: Option<Uuid>,
+    pub last_event_at: DateTime<Utc>,
+    pub retention_days: i32,
+    pub receipt_count: i64,
+}
+
+/// Count and sample what the purge would delete under `config`, deleting nothing
+pub async fn preview_purge(
+    pool: &PgPool,
+    config: &RetentionConfig,
+    sample_size: Option<i64>,
+) -> Result<PurgePreviewResponse> {
+    let selection = RetentionSelection::new(config, Utc::now());
+    let sample_size = sample_size.unwrap_or(DEFAULT_SAMPLE_SIZE).clamp(0, MAX_SAMPLE_SIZE);
+
+    let tenants_sql = format!(
+        r#"{ELIGIBLE_TRACES}
+        SELECT e.tenant_id, e.retention_days,
+               COUNT(DISTINCT e.trace_id) AS trace_count,
+               COUNT(r.receipt_id) AS receipt_count,
+               MIN(e.last_event_at) AS oldest_event_at
+        FROM eligible e
+        LEFT JOIN receipt_events r ON r.trace_id = e.trace_id
+        WHERE e.archived
+        GROUP BY e.tenant_id, e.retention_days
+        ORDER BY trace_count DESC, e.tenant_id
+        "#
+    );
+    let tenants: Vec<TenantPurgePreview> = selection
+        .query_as(&tenants_sql)
+        .fetch_all(pool)
+        .await?;
+
+    let sample_sql = format!(
+        r#"{ELIGIBLE_TRACES}
+        SELECT e.trace_id, e.tenant_id, e.last_event_at, e.retention_days,
+               (SELECT COUNT(*) FROM receipt_events r WHERE r.trace_id = e.trace_id) AS receipt_count
+        FROM eligible e
+        WHERE e.archived
+        ORDER BY e.last_event_at ASC, e.trace_id
+        LIMIT $5
+        "#
+    );
+    let sample: Vec<PurgeCandidate> = selection
+        .query_as(&sample_sql)
+        .bind(sample_size)
+        .fetch_all(pool)
+        .await?;
+
+    let awaiting_archive_count = count_awaiting_archive(pool, &selection, None).await?;
+
+    Ok(PurgePreviewResponse {
+        evaluated_at: selection.evaluated_at,
+        policy: config.clone(),
+        trace_count: tenants.iter().map(|t| t.trace_count).sum(),
+        receipt_count: tenants.iter().map(|t| t.receipt_count).sum(),
+        awaiting_archive_count,
+        tenants,
+        sample,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PurgeQuery {
+    /// Only purge eligible traces whose last event is before this instant
+    pub before: Option<DateTime<Utc>>,
+}
+
+/// What a purge deleted
+#[derive(Debug, Serialize)]
+pub struct PurgeResponse {
+    pub evaluated_at: DateTime<Utc>,
+    pub before: Option<DateTime<Utc>>,
+    /// Rows deleted, by table
+    pub deleted: PurgedRows,
+    /// Traces past retention that were kept because some of their receipts aren't archived yet
+    pub awaiting_archive_count: i64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PurgedRows {
+    pub traces: u64,
+    pub receipt_events: u64,
+    pub external_events: u64,
+    pub trust_events: u64,
+    pub attribution_lineage: u64,
+}
+
+impl PurgedRows {
+    fn add(&mut self, other: &PurgedRows) {
+        self.traces += other.traces;
+        self.receipt_events += other.receipt_events;
+        self.external_events += other.external_events;
+        self.trust_events += other.trust_events;
+        self.attribution_lineage += other.attribution_lineage;
+    }
+}
+
+/// Delete every eligible, fully archived trace under `config`, `batch_size` traces per transaction.
+/// With `before`, only traces whose last event is earlier than it; it narrows the policy, never widens it
+pub async fn purge(
+    pool: &PgPool,
+    config: &RetentionConfig,
+    before: Option<DateTime<Utc>>,
+    batch_size: i64,
+) -> Result<PurgeResponse> {
+    let selection = RetentionSelection::new(config, Utc::now());
+    let mut deleted = PurgedRows::default();
+
+    loop {
+        let (selected, batch) = purge_batch(pool, &selection, before, batch_size).await?;
+        deleted.add(&batch);
+        if selected < batch_size {
+            break;
+        }
+    }
+
+    let awaiting_archive_count = count_awaiting_archive(pool, &selection, before).await?;
+
+    Ok(PurgeResponse {
+        evaluated_at: selection.evaluated_at,
+        before,
+        deleted,
+        awaiting_archive_count,
+    })
+}
+
+/// Lock and delete up to `batch_size` of the oldest purgeable traces in one transaction, returning how
+/// many traces were selected and the rows deleted. Traces busy taking an event are left for the next batch
+async fn purge_batch(
+    pool: &PgPool,
+    selection: &RetentionSelection,
+    before: Option<DateTime<Utc>>,
+    batch_size: i64,
+) -> Result<(i64, PurgedRows)> {
+    let mut tx = pool.begin().await?;
+
+    let select_sql = format!(
+        r#"{ELIGIBLE_TRACES}
+        SELECT t.trace_id
+        FROM traces t
+        JOIN eligible e ON e.trace_id = t.trace_id
+        WHERE e.archived
+          AND ($5::timestamptz IS NULL OR e.last_event_at < $5)
+        ORDER BY e.last_event_at ASC, e.trace_id
+        LIMIT $6
+        FOR UPDATE OF t SKIP LOCKED
+        "#
+    );
+    let selected: Vec<(Uuid,)> = selection
+        .query_as(&select_sql)
+        .bind(before)
+        .bind(batch_size)
+        .fetch_all(&mut *tx)
+        .await?;
+    let selected_count = selected.len() as i64;
+
+    // With the traces locked no receipt can be added to them, so this catches any stored after the
+    // selection's snapshot was taken
+    let trace_ids: Vec<Uuid> = selected.into_iter().map(|(id,)| id).collect();
+    let unarchived: Vec<(Uuid,)> = sqlx::query_as(
+        "SELECT DISTINCT trace_id FROM receipt_events WHERE trace_id = ANY($1) AND archived_at IS NULL",
+    )
+    .bind(&trace_ids)
+    .fetch_all(&mut *tx)
+    .await?;
+    let trace_ids: Vec<Uuid> = trace_ids
+        .into_iter()
+        .filter(|id| !unarchived.iter().any(|(u,)| u == id))
+        .collect();
+
+    if trace_ids.is_empty() {
+        tx.commit().await?;
+        return Ok((selected_count, PurgedRows::default()));
+    }
+
+    // Children first: trust events point at receipt events, and everything points at the trace.
+    // Overflowed receipt metadata goes with its receipt event
+    let deleted = PurgedRows {
+        trust_events: delete_for_traces(&mut tx, "trust_events", &trace_ids).await?,
+        attribution_lineage: delete_for_traces(&mut tx, "attribution_lineage", &trace_ids).await?,
+        receipt_events: delete_for_traces(&mut tx, "receipt_events", &trace_ids).await?,
+        external_events: delete_for_traces(&mut tx, "external_events", &trace_ids).await?,
+        traces: delete_for_traces(&mut tx, "traces", &trace_ids).await?,
+    };
+
+    tx.commit().await?;
+    Ok((selected_count, deleted))
+}
+
+/// Delete `table`'s rows belonging to `trace_ids`, returning how many went
+async fn delete_for_traces(tx: &mut PgConnection, table: &str, trace_ids: &[Uuid]) -> Result<u64> {
+    let result = sqlx::query(&format!("DELETE FROM {} WHERE trace_id = ANY($1)", table))
+        .bind(trace_ids)
+        .execute(tx)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+/// Traces past retention (and before `before`, if given) kept because a receipt isn't archived yet
+async fn count_awaiting_archive(
+    pool: &PgPool,
+    selection: &RetentionSelection,
+    before: Option<DateTime<Utc>>,
+) -> Result<i64> {
+    let sql = format!(
+        r#"{ELIGIBLE_TRACES}
+        SELECT COUNT(*)
+        FROM eligible e
+        WHERE NOT e.archived
+          AND ($5::timestamptz IS NULL OR e.last_event_at < $5)
+        "#
+    );
+    let (count,): (i64,) = selection.query_as(&sql).bind(before).fetch_one(pool).await?;
+    Ok(count)
+}
+
+/// Start purging in the background, if a database is configured and both a retention policy and a
+/// purge interval are set
+pub fn spawn(store: Arc<ReceiptStore>) {
+    let config = store.retention().clone();
+    let purge_config = store.retention_purge().clone();
+    let (Some(pool), Some(interval_secs)) = (store.db_pool().cloned(), purge_config.interval_secs) else {
+        return;
+    };
+    if config.default_days.is_none() && config.tenant_days.is_empty() {
+        return;
+    }
+    info!(
+        "Retention purge: deleting archived traces past retention every {}s, {} per batch",
+        interval_secs, purge_config.batch_size
+    );
+
+    let job = Job {
+        name: "retention_purge",
+        interval: Duration::from_secs(interval_secs),
+        heartbeat: Duration::from_secs(store.job_heartbeat_secs()),
+    };
+    jobs::spawn(pool.clone(), job, move || {
+        let (pool, config, batch_size) = (pool.clone(), config.clone(), purge_config.batch_size);
+        async move {
+            let report = purge(&pool, &config, None, batch_size).await?;
+            if report.deleted.traces > 0 {
+                info!(
+                    "Retention purge deleted {} trace(s) and {} receipt event(s); {} trace(s) await archive",
+                    report.deleted.traces, report.deleted.receipt_events, report.awaiting_archive_count
+                );
+            }
+            Ok(())
+        }
+    });
+}
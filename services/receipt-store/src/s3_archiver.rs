@@ -1,17 +1,29 @@
 use aws_sdk_s3::Client as S3Client;
 use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::presigning::PresigningConfig;
 use anyhow::Result;
 use tracing::{info, error};
-use chrono::{Utc, Datelike, Timelike};
+use chrono::{DateTime, Utc, Datelike, Timelike};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use uuid::Uuid;
 
 pub struct S3Archiver {
     client: S3Client,
     bucket: String,
+    /// Gzip objects (stored with `Content-Encoding: gzip` and a `.gz` key suffix)
+    compress: bool,
+    /// Keeps this process's batch objects apart from other replicas' written in the same second
+    instance_id: Uuid,
+    batch_sequence: AtomicU64,
 }
 
 impl S3Archiver {
-    pub async fn new(bucket: &str, region: &str) -> Result<Self> {
-        let config = aws_config::from_env()
+    pub async fn new(bucket: &str, region: &str, compress: bool) -> Result<Self> {
+        let config = aws_config::defaults(aws_config::BehaviorVersion::latest())
             .region(aws_sdk_s3::config::Region::new(region.to_string()))
             .load()
             .await;
@@ -21,59 +33,156 @@ impl S3Archiver {
         Ok(Self {
             client,
             bucket: bucket.to_string(),
+            compress,
+            instance_id: Uuid::new_v4(),
+            batch_sequence: AtomicU64::new(0),
         })
     }
 
-    pub async fn archive_receipt(&self, receipt_json: &str) -> Result<()> {
-        // Create partition path: year/month/day/hour
+    pub fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    /// Confirm the bucket exists and is reachable with our credentials, without writing to it
+    pub async fn check(&self) -> Result<()> {
+        self.client
+            .head_bucket()
+            .bucket(&self.bucket)
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("S3 bucket {} unavailable: {}", self.bucket, e))?;
+        Ok(())
+    }
+
+    /// Archive a receipt, returning the object key it was written to
+    pub async fn archive_receipt(&self, receipt_id: Uuid, receipt_json: &str) -> Result<String> {
         let now = Utc::now();
-        let partition_path = format!(
-            "receipts/{:04}/{:02}/{:02}/{:02}",
-            now.year(),
-            now.month(),
-            now.day(),
-            now.hour()
+
+        // Generate object key with timestamp (receipt ID keeps same-second receipts apart)
+        let key = format!(
+            "{}/receipt_{}_{}.json{}",
+            partition_path(now),
+            now.timestamp(),
+            receipt_id,
+            self.suffix()
         );
 
-        // Generate object key with timestamp
+        self.put(&key, receipt_json.as_bytes().to_vec()).await?;
+        info!("Receipt archived to S3: s3://{}/{}", self.bucket, key);
+        Ok(key)
+    }
+
+    /// Archive receipts as one newline-delimited JSON object, one receipt per line in the order given,
+    /// returning the object key they were written to
+    pub async fn archive_batch(&self, receipts: &[(Uuid, String)]) -> Result<String> {
+        let now = Utc::now();
+        let sequence = self.batch_sequence.fetch_add(1, Ordering::Relaxed);
+
         let key = format!(
-            "{}/receipt_{}.json",
-            partition_path,
-            now.timestamp()
+            "{}/batch_{}_{}_{:06}.ndjson{}",
+            partition_path(now),
+            now.timestamp(),
+            self.instance_id.simple(),
+            sequence,
+            self.suffix()
         );
 
-        let body = ByteStream::from(receipt_json.as_bytes().to_vec());
+        let mut body = Vec::with_capacity(receipts.iter().map(|(_, json)| json.len() + 1).sum());
+        for (_, receipt_json) in receipts {
+            body.extend_from_slice(receipt_json.as_bytes());
+            body.push(b'\n');
+        }
+
+        self.put(&key, body).await?;
+        info!("{} receipts archived to S3: s3://{}/{}", receipts.len(), self.bucket, key);
+        Ok(key)
+    }
 
-        match self
-            .client
+    fn suffix(&self) -> &'static str {
+        if self.compress {
+            ".gz"
+        } else {
+            ""
+        }
+    }
+
+    /// Write an object, gzipping the whole body first when compression is on
+    async fn put(&self, key: &str, body: Vec<u8>) -> Result<()> {
+        let (body, content_encoding) = if self.compress {
+            (gzip(&body)?, Some("gzip"))
+        } else {
+            (body, None)
+        };
+
+        self.client
             .put_object()
             .bucket(&self.bucket)
-            .key(&key)
-            .body(body)
+            .key(key)
+            .set_content_encoding(content_encoding.map(str::to_string))
+            .body(ByteStream::from(body))
             .send()
             .await
-        {
-            Ok(_) => {
-                info!("Receipt archived to S3: s3://{}/{}", self.bucket, key);
-                Ok(())
-            }
-            Err(e) => {
-                error!("Failed to archive receipt to S3: {}", e);
-                Err(anyhow::anyhow!("S3 archive error: {}", e))
-            }
-        }
+            .map_err(|e| {
+                error!("Failed to archive to S3: {}", e);
+                anyhow::anyhow!("S3 archive error: {}", e)
+            })?;
+        Ok(())
     }
 
-    pub async fn batch_archive_receipts(&self, receipts: Vec<String>) -> Result<()> {
-        // For batch operations, we could use S3 multipart upload
-        // For MVP, we'll archive them individually
-        for receipt_json in receipts {
-            if let Err(e) = self.archive_receipt(&receipt_json).await {
-                error!("Failed to archive receipt in batch: {}", e);
-                // Continue with other receipts
-            }
-        }
-        Ok(())
+    /// Create a time-limited presigned GET URL for an archived object
+    pub async fn presign_get(&self, key: &str, expires_in: Duration) -> Result<String> {
+        let presigned = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .presigned(PresigningConfig::expires_in(expires_in)?)
+            .await?;
+
+        Ok(presigned.uri().to_string())
     }
 }
 
+fn gzip(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+/// Partition path for objects written at `now`: year/month/day/hour
+fn partition_path(now: DateTime<Utc>) -> String {
+    format!(
+        "receipts/{:04}/{:02}/{:02}/{:02}",
+        now.year(),
+        now.month(),
+        now.day(),
+        now.hour()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn gzip_round_trips_a_receipt() {
+        let receipt_json = serde_json::json!({
+            "receipt_id": Uuid::new_v4(),
+            "trace_id": Uuid::new_v4(),
+            "agent_id": "agent-1",
+            "event_type": "gateway_request",
+            "request": { "method": "POST", "path": "/v1/orders", "headers": {} },
+            "policy_result": { "allowed": true, "policy_version": "v1", "evaluation_time_ms": 3 },
+        })
+        .to_string();
+
+        let compressed = gzip(receipt_json.as_bytes()).unwrap();
+        assert_eq!(&compressed[..2], &[0x1f, 0x8b], "output should carry the gzip magic bytes");
+
+        let mut decompressed = String::new();
+        GzDecoder::new(compressed.as_slice()).read_to_string(&mut decompressed).unwrap();
+        assert_eq!(decompressed, receipt_json);
+    }
+}
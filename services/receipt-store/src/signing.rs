@@ -0,0 +1,54 @@
+//! Ed25519 receipt signatures. Hashing alone lets whoever holds the database rewrite a receipt and its
+//! hash chain; with `RECEIPT_SIGNING_KEY` set, each receipt's `receipt_hash` is also signed, so a consumer
+//! holding the public key can prove the receipt store produced it. The signed message is the ASCII bytes
+//! of the hex `receipt_hash`. Signatures and keys are hex encoded.
+
+use anyhow::{anyhow, Result};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+
+/// Signs receipt hashes with the store's private key
+pub struct ReceiptSigner {
+    key: SigningKey,
+    public_key: String,
+}
+
+impl ReceiptSigner {
+    /// A signer from a hex-encoded 32-byte Ed25519 secret key
+    pub fn from_hex(secret_key: &str) -> Result<Self> {
+        let bytes: [u8; 32] = hex::decode(secret_key.trim())?
+            .try_into()
+            .map_err(|_| anyhow!("expected a 32-byte key"))?;
+        let key = SigningKey::from_bytes(&bytes);
+        let public_key = hex::encode(key.verifying_key().as_bytes());
+        Ok(Self { key, public_key })
+    }
+
+    /// The hex-encoded public key signatures verify against
+    pub fn public_key(&self) -> &str {
+        &self.public_key
+    }
+
+    /// The hex-encoded signature over `receipt_hash`
+    pub fn sign(&self, receipt_hash: &str) -> String {
+        hex::encode(self.key.sign(receipt_hash.as_bytes()).to_bytes())
+    }
+}
+
+/// Whether `signature` is `public_key`'s signature over `receipt_hash`; malformed input doesn't verify
+pub fn verify_signature(receipt_hash: &str, signature: &str, public_key: &str) -> bool {
+    let Some(key) = hex::decode(public_key)
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+    else {
+        return false;
+    };
+    let Some(signature) = hex::decode(signature)
+        .ok()
+        .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+        .map(|bytes| Signature::from_bytes(&bytes))
+    else {
+        return false;
+    };
+    key.verify(receipt_hash.as_bytes(), &signature).is_ok()
+}
@@ -0,0 +1,81 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::kafka_producer::KafkaProducer;
+use crate::s3_archiver::S3Archiver;
+
+/// A Kafka topic and S3 bucket receipts are streamed and archived to
+pub struct Sink {
+    pub kafka: KafkaProducer,
+    pub s3: S3Archiver,
+}
+
+/// Selects the sink for a receipt by tenant, falling back to the global sink
+pub struct SinkRouter {
+    default: Sink,
+    tenants: HashMap<Uuid, Sink>,
+}
+
+impl SinkRouter {
+    pub async fn from_config(config: &Config) -> Result<Self> {
+        let default = Sink {
+            kafka: KafkaProducer::new(
+                &config.kafka_brokers,
+                &config.kafka_topic,
+                config.kafka_topic_routes.clone(),
+            )?,
+            s3: S3Archiver::new(&config.s3_bucket, &config.s3_region, config.s3_compress).await?,
+        };
+
+        let mut tenants = HashMap::new();
+        for (tenant_id, sink) in &config.tenant_sinks {
+            let topic = sink.kafka_topic.as_deref().unwrap_or(&config.kafka_topic);
+            tenants.insert(
+                *tenant_id,
+                Sink {
+                    // Fan-out topics live on the tenant's brokers too, so its receipts never leave them
+                    kafka: KafkaProducer::new(&sink.kafka_brokers, topic, config.kafka_topic_routes.clone())?,
+                    s3: S3Archiver::new(&sink.s3_bucket, &sink.s3_region, config.s3_compress).await?,
+                },
+            );
+            info!(
+                "Tenant {} sink: brokers {}, topic {}, bucket {} ({})",
+                tenant_id, sink.kafka_brokers, topic, sink.s3_bucket, sink.s3_region
+            );
+        }
+
+        Ok(Self { default, tenants })
+    }
+
+    /// Whether any tenant has its own sink (otherwise every receipt uses the default)
+    pub fn has_tenant_sinks(&self) -> bool {
+        !self.tenants.is_empty()
+    }
+
+    /// Every sink, with the tenant it belongs to (`None` for the default)
+    pub fn all(&self) -> impl Iterator<Item = (Option<Uuid>, &Sink)> {
+        std::iter::once((None, &self.default))
+            .chain(self.tenants.iter().map(|(id, sink)| (Some(*id), sink)))
+    }
+
+    pub fn for_tenant(&self, tenant_id: Option<Uuid>) -> &Sink {
+        tenant_id
+            .and_then(|id| self.tenants.get(&id))
+            .unwrap_or(&self.default)
+    }
+
+    /// Find the archiver that owns a bucket; receipts archived before buckets were
+    /// recorded have no bucket and belong to the default sink
+    pub fn archiver_for_bucket(&self, bucket: Option<&str>) -> Option<&S3Archiver> {
+        match bucket {
+            None => Some(&self.default.s3),
+            Some(bucket) => std::iter::once(&self.default)
+                .chain(self.tenants.values())
+                .map(|sink| &sink.s3)
+                .find(|s3| s3.bucket() == bucket),
+        }
+    }
+}
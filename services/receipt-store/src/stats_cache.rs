@@ -0,0 +1,47 @@
+//! Short-lived cache of `GET /v1/stats` results, keyed on the query parameters, so dashboards polling
+//! the same headline numbers don't each rerun the aggregate queries. Entries live in memory on each
+//! replica for `STATS_CACHE_TTL_SECS`; a TTL of 0 turns caching off.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::queries::{StatsQuery, StatsResponse};
+
+/// Distinct queries cached at once; past this, the cache starts over rather than grow unbounded
+const MAX_ENTRIES: usize = 1024;
+
+pub struct StatsCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<StatsQuery, (Instant, StatsResponse)>>,
+}
+
+impl StatsCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The stats computed for `query` within the last TTL, if any
+    pub fn get(&self, query: &StatsQuery) -> Option<StatsResponse> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get(query)
+            .filter(|(cached_at, _)| cached_at.elapsed() < self.ttl)
+            .map(|(_, stats)| stats.clone())
+    }
+
+    pub fn insert(&self, query: StatsQuery, stats: StatsResponse) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|_, (cached_at, _)| cached_at.elapsed() < self.ttl);
+        if entries.len() >= MAX_ENTRIES {
+            entries.clear();
+        }
+        entries.insert(query, (Instant::now(), stats));
+    }
+}
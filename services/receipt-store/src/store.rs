@@ -1,40 +1,360 @@
 use anyhow::Result;
 use sqlx::PgPool;
 use uuid::Uuid;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+
+use crate::archive_buffer::ArchiveBuffer;
+use crate::body_capture::BodyCapturePolicy;
+use crate::config::{Config, RetentionConfig, RetentionPurgeConfig, TraceSweepConfig};
+use crate::validation::{
+    validate_event_timestamp, validate_occurred_at, validate_on_behalf_of, validate_receipt_fields, validate_usage,
+    ReceiptFields, ValidationError, Validator,
+};
 
 use crate::receipt::{
     Receipt, ReceiptRequest, EventSource, ExternalEvent, ExternalEventRequest,
-    ReceiptV2, ReceiptRequestV2, TrustEvent, TrustEventType,
+    ExternalEventResult, ExternalEventStatus, ReceiptBatchResult, TraceSeal, EventType, RequestInfo,
+    PolicyResult, IdentityResult, TrustRiskEvent, ReceiptContent, ReceiptSealing,
+    ReceiptV2, ReceiptRequestV2, TrustEvent, TrustEventType, TrustViolationAlert,
 };
-use crate::kafka_producer::KafkaProducer;
-use crate::s3_archiver::S3Archiver;
+use crate::sinks::{Sink, SinkRouter};
 use crate::db;
+use crate::merkle::merkle_root;
+use crate::metrics::Metrics;
+use crate::stats_cache::StatsCache;
+use crate::signing::ReceiptSigner;
+
+/// Maximum number of events accepted by a single batch ingestion
+pub const MAX_EXTERNAL_EVENT_BATCH: usize = 500;
+
+/// Maximum number of receipts accepted by a single batch ingestion
+pub const MAX_RECEIPT_BATCH: usize = 500;
+
+/// Trace lifecycle errors surfaced to clients with specific statuses
+#[derive(Debug, thiserror::Error)]
+pub enum TraceStateError {
+    #[error("trace {0} not found")]
+    NotFound(Uuid),
+    #[error("trace {0} is sealed")]
+    Sealed(Uuid),
+    #[error("trace {0} is already closed as {1}")]
+    AlreadyClosed(Uuid, String),
+}
+
+/// A receipt as returned to the client that sent it
+pub struct StoredReceipt {
+    pub receipt: Receipt,
+    /// `false` when an earlier request with the same idempotency key already stored the receipt
+    pub stored: bool,
+}
+
+/// An external event as returned to the client that sent it
+pub struct StoredExternalEvent {
+    pub event_id: Uuid,
+    pub trace_id: Uuid,
+    /// `false` when an event with the same `source_system` and `source_id` was already ingested; the IDs
+    /// are then that event's
+    pub stored: bool,
+}
 
 pub struct ReceiptStore {
-    kafka: KafkaProducer,
-    s3: S3Archiver,
+    sinks: SinkRouter,
     db_pool: Option<PgPool>,
+    body_capture: BodyCapturePolicy,
+    config: Config,
+    metrics: Metrics,
+    /// Receipts waiting to be archived together; `None` archives each receipt as it is stored
+    archive_buffer: Option<ArchiveBuffer>,
+    /// Signs each receipt's hash; `None` leaves receipts unsigned
+    signer: Option<ReceiptSigner>,
+    stats_cache: StatsCache,
 }
 
 impl ReceiptStore {
-    pub fn new(
-        kafka: KafkaProducer,
-        s3: S3Archiver,
-        db_pool: Option<PgPool>,
-    ) -> Self {
-        Self { kafka, s3, db_pool }
+    pub fn new(sinks: SinkRouter, db_pool: Option<PgPool>, config: Config) -> Self {
+        Self {
+            sinks,
+            db_pool,
+            body_capture: BodyCapturePolicy::new(&config.body_capture),
+            archive_buffer: (config.archive_batch_size > 1).then(|| ArchiveBuffer::new(config.archive_batch_size)),
+            // An unusable key would otherwise leave receipts silently unsigned
+            signer: config.signing_key.as_deref().map(|key| {
+                ReceiptSigner::from_hex(key)
+                    .expect("RECEIPT_SIGNING_KEY must be a hex-encoded 32-byte Ed25519 secret key")
+            }),
+            stats_cache: StatsCache::new(std::time::Duration::from_secs(config.stats_cache_ttl_secs)),
+            config,
+            metrics: Metrics::default(),
+        }
     }
 
-    pub async fn store_receipt(&self, request: ReceiptRequest) -> Result<Receipt> {
+    pub async fn store_receipt(&self, mut request: ReceiptRequest) -> Result<StoredReceipt> {
+        // Reject malformed requests before anything is persisted
+        let received_at = Utc::now();
+        self.validate_receipt_request(&request, received_at)?;
+
+        // A retry gets the receipt its first attempt stored, even if the trace has been sealed since
+        let idempotency_key = request.idempotency_key.take();
+        if let (Some(ref pool), Some(ref key)) = (&self.db_pool, &idempotency_key) {
+            if let Some(receipt) = db::get_receipt_by_idempotency_key(pool, key).await? {
+                return Ok(StoredReceipt { receipt, stored: false });
+            }
+        }
+        if let (Some(ref pool), Some(trace_id)) = (&self.db_pool, request.trace_id) {
+            if db::is_trace_sealed(pool, trace_id).await? {
+                return Err(TraceStateError::Sealed(trace_id).into());
+            }
+        }
+
+        // Body capture and the sink look up the trace's tenant on other connections, so they run before
+        // the transaction rather than while it holds the trace lock
+        self.capture_body(&mut request).await?;
+        let trace_id = *request.trace_id.get_or_insert_with(Uuid::new_v4);
+        let sink = self.sink_for_trace(trace_id).await?;
+
+        // The seal is checked again under lock in the insert's transaction, so a seal can't land between them
+        let mut tx = match self.db_pool {
+            Some(ref pool) => Some(pool.begin().await?),
+            None => None,
+        };
+        if let Some(ref mut tx) = tx {
+            if db::is_trace_sealed(&mut **tx, trace_id).await? {
+                return Err(TraceStateError::Sealed(trace_id).into());
+            }
+        }
+
         // Get previous receipt hash for chain
-        let previous_hash = if let Some(ref pool) = self.db_pool {
-            db::get_latest_receipt_hash(pool).await?
-        } else {
-            None
+        let previous_hash = match tx {
+            Some(ref mut tx) => db::get_latest_receipt_hash(&mut **tx).await?,
+            None => None,
         };
 
-        // Generate or use provided trace context
+        let receipt = self.build_receipt(request, previous_hash, received_at)?;
+
+        // Serialize receipt
+        let receipt_json = serde_json::to_string(&receipt)?;
+
+        // Store in database if available
+        if let Some(mut tx) = tx {
+            // Ensure trace exists (create or update)
+            db::upsert_trace(&mut *tx, &receipt).await?;
+
+            // Store full receipt event, unless a concurrent retry stored it first
+            let inserted = db::store_receipt_event(
+                &mut *tx,
+                &receipt,
+                idempotency_key.as_deref(),
+                self.config.metadata_overflow_bytes,
+            )
+            .await?;
+            if let (false, Some(key)) = (inserted, idempotency_key.as_deref()) {
+                let receipt = db::get_receipt_by_idempotency_key(&mut *tx, key)
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("receipt for idempotency key {} vanished", key))?;
+                return Ok(StoredReceipt { receipt, stored: false });
+            }
+
+            // Store hash for chain verification (backwards compatibility)
+            db::store_receipt_hash(&mut *tx, receipt.receipt_id, &receipt.receipt_hash).await?;
+            tx.commit().await?;
+        }
+        self.metrics.receipt_stored();
+
+        self.publish_receipt(sink, &receipt, &receipt_json).await;
+
+        Ok(StoredReceipt { receipt, stored: true })
+    }
+
+    /// Store many receipts in one transaction. The chain head is read once and each receipt links to
+    /// the one before it in the batch; an invalid receipt or one for a sealed trace is reported and
+    /// left out of the chain without failing the rest. A receipt whose idempotency key is already
+    /// stored, earlier in the batch or before it, is reported with the stored receipt's IDs
+    pub async fn store_receipts_batch(&self, requests: Vec<ReceiptRequest>) -> Result<Vec<ReceiptBatchResult>> {
+        if requests.len() > MAX_RECEIPT_BATCH {
+            return Err(ValidationError::new(
+                "receipts",
+                format!("batch exceeds {} receipts", MAX_RECEIPT_BATCH),
+            )
+            .into());
+        }
+
+        let mut results = Vec::with_capacity(requests.len());
+        let mut stored = Vec::new();
+
+        // Validate and capture bodies first: body capture looks up trace tenants on other connections,
+        // which shouldn't wait inside the transaction
+        let mut valid = Vec::with_capacity(requests.len());
+        for (index, mut request) in requests.into_iter().enumerate() {
+            let received_at = Utc::now();
+            if let Err(e) = self.validate_receipt_request(&request, received_at) {
+                results.push(ReceiptBatchResult::rejected(index, e.to_string()));
+                continue;
+            }
+            self.capture_body(&mut request).await?;
+            valid.push((index, request, received_at));
+        }
+
+        let mut tx = match self.db_pool {
+            Some(ref pool) => Some(pool.begin().await?),
+            None => None,
+        };
+
+        let mut previous_hash = match tx {
+            Some(ref mut tx) => db::get_latest_receipt_hash(&mut **tx).await?,
+            None => None,
+        };
+
+        for (index, mut request, received_at) in valid {
+            let idempotency_key = request.idempotency_key.take();
+            if let (Some(ref mut tx), Some(ref key)) = (&mut tx, &idempotency_key) {
+                if let Some(existing) = db::get_receipt_by_idempotency_key(&mut **tx, key).await? {
+                    results.push(ReceiptBatchResult::duplicate(index, &existing));
+                    continue;
+                }
+            }
+
+            if let (Some(ref mut tx), Some(trace_id)) = (&mut tx, request.trace_id) {
+                if db::is_trace_sealed(&mut **tx, trace_id).await? {
+                    results.push(ReceiptBatchResult::rejected(
+                        index,
+                        TraceStateError::Sealed(trace_id).to_string(),
+                    ));
+                    continue;
+                }
+            }
+
+            let receipt = self.build_receipt(request, previous_hash.take(), received_at)?;
+
+            if let Some(ref mut tx) = tx {
+                db::upsert_trace(&mut **tx, &receipt).await?;
+                let inserted = db::store_receipt_event(
+                    &mut **tx,
+                    &receipt,
+                    idempotency_key.as_deref(),
+                    self.config.metadata_overflow_bytes,
+                )
+                .await?;
+                if let (false, Some(key)) = (inserted, idempotency_key.as_deref()) {
+                    // A concurrent request stored the key first; the next receipt links to this one's predecessor
+                    previous_hash = receipt.previous_receipt_hash.clone();
+                    let existing = db::get_receipt_by_idempotency_key(&mut **tx, key)
+                        .await?
+                        .ok_or_else(|| anyhow::anyhow!("receipt for idempotency key {} vanished", key))?;
+                    results.push(ReceiptBatchResult::duplicate(index, &existing));
+                    continue;
+                }
+                db::store_receipt_hash(&mut **tx, receipt.receipt_id, &receipt.receipt_hash).await?;
+            }
+
+            previous_hash = Some(receipt.receipt_hash.clone());
+            results.push(ReceiptBatchResult {
+                index,
+                receipt_id: Some(receipt.receipt_id),
+                receipt_hash: Some(receipt.receipt_hash.clone()),
+                trace_id: Some(receipt.trace_id),
+                stored: true,
+                error: None,
+            });
+            stored.push(receipt);
+        }
+
+        if let Some(tx) = tx {
+            tx.commit().await?;
+        }
+
+        // Fan out stored receipts only once the batch is committed
+        for receipt in &stored {
+            self.metrics.receipt_stored();
+            let sink = match self.sink_for_trace(receipt.trace_id).await {
+                Ok(sink) => sink,
+                Err(e) => {
+                    tracing::warn!("Failed to resolve sink for receipt {}: {}", receipt.receipt_id, e);
+                    continue;
+                }
+            };
+            let receipt_json = serde_json::to_string(receipt)?;
+            self.publish_receipt(sink, receipt, &receipt_json).await;
+        }
+
+        Ok(results)
+    }
+
+    fn validate_receipt_request(
+        &self,
+        request: &ReceiptRequest,
+        received_at: DateTime<Utc>,
+    ) -> std::result::Result<(), ValidationError> {
+        let mut validator = Validator::new();
+        validate_receipt_fields(
+            &mut validator,
+            ReceiptFields {
+                agent_id: &request.agent_id,
+                correlation_id: request.correlation_id.as_deref(),
+                event_type: request.event_type.as_ref(),
+                event_source: request.event_source.as_ref(),
+                request: &request.request,
+                policy_version: &request.policy_result.policy_version,
+                evaluation_time_ms: request.policy_result.evaluation_time_ms,
+            },
+        );
+        if let Some(ref key) = request.idempotency_key {
+            validator.required("idempotency_key", key, 255);
+        }
+        validate_on_behalf_of(&mut validator, &request.on_behalf_of);
+        if let Some(ref usage) = request.usage {
+            validate_usage(&mut validator, usage);
+        }
+        if let Some(occurred_at) = request.occurred_at {
+            validate_occurred_at(
+                &mut validator,
+                occurred_at,
+                received_at,
+                self.config.max_future_skew_secs,
+                self.config.max_backdate_secs,
+            );
+        }
+        validator.finish()
+    }
+
+    fn validate_external_event_request(
+        &self,
+        request: &ExternalEventRequest,
+        received_at: DateTime<Utc>,
+    ) -> std::result::Result<(), ValidationError> {
+        let mut validator = Validator::new();
+        validate_event_timestamp(
+            &mut validator,
+            request.timestamp,
+            received_at,
+            self.config.external_event_max_future_skew_secs,
+        );
+        validator.finish()
+    }
+
+    /// Apply the body capture policy to a v1 request's body
+    async fn capture_body(&self, request: &mut ReceiptRequest) -> Result<()> {
+        if request.request.body.is_none() {
+            return Ok(());
+        }
+        // v1 receipts belong to their trace's tenant; a new trace has none yet
+        let tenant_id = match (&self.db_pool, request.trace_id) {
+            (Some(ref pool), Some(trace_id)) if self.body_capture.has_tenant_overrides() => {
+                db::get_trace_tenant_id(pool, trace_id).await?
+            }
+            _ => None,
+        };
+        self.body_capture.apply(tenant_id, &mut request.request);
+        Ok(())
+    }
+
+    /// Create a receipt with hash chain and trace context, generating whatever context the request omits
+    fn build_receipt(
+        &self,
+        request: ReceiptRequest,
+        previous_hash: Option<String>,
+        received_at: DateTime<Utc>,
+    ) -> Result<Receipt> {
         let trace_id = request.trace_id.unwrap_or_else(Uuid::new_v4);
         let span_id = request.span_id.unwrap_or_else(Uuid::new_v4);
         let event_type = request.event_type.unwrap_or_default();
@@ -44,77 +364,534 @@ impl ReceiptStore {
             version: "1.0.0".to_string(),
         });
 
-        // Create receipt with hash chain and trace context
-        let receipt = Receipt::new(
-            trace_id,
-            request.correlation_id.clone(),
-            span_id,
-            request.parent_span_id,
-            request.agent_id,
-            event_type,
-            event_source,
-            request.request,
-            request.policy_result,
-            request.identity_result,
-            request.metadata,
-            previous_hash,
-        );
+        Receipt::new(
+            ReceiptContent {
+                trace_id,
+                correlation_id: request.correlation_id,
+                span_id,
+                parent_span_id: request.parent_span_id,
+                agent_id: request.agent_id,
+                on_behalf_of: request.on_behalf_of,
+                event_type,
+                event_source,
+                request: request.request,
+                policy_result: request.policy_result,
+                identity_result: request.identity_result,
+                metadata: request.metadata,
+                occurred_at: request.occurred_at,
+                latency: request.latency,
+                usage: request.usage,
+            },
+            self.sealing(previous_hash, received_at),
+        )
+    }
 
-        // Serialize receipt
-        let receipt_json = serde_json::to_string(&receipt)?;
+    /// Send a stored receipt to Kafka and archive it to S3, both best effort
+    async fn publish_receipt(&self, sink: &Sink, receipt: &Receipt, receipt_json: &str) {
+        let denied = !receipt.policy_result.allowed;
+        if let Err(e) = sink
+            .kafka
+            .send_receipt_keyed(&receipt.trace_id.to_string(), receipt_json, &receipt.event_type, denied)
+            .await
+        {
+            self.metrics.kafka_send_failed();
+            tracing::warn!("Failed to send receipt to Kafka: {}", e);
+        }
+
+        self.archive(sink, receipt.receipt_id, receipt_json).await;
+    }
+
+    pub async fn store_external_event(&self, request: ExternalEventRequest) -> Result<StoredExternalEvent> {
+        self.validate_external_event_request(&request, Utc::now())?;
+        let event = ExternalEvent::from_request(request);
 
-        // Store in database if available
         if let Some(ref pool) = self.db_pool {
-            // Ensure trace exists (create or update)
-            db::upsert_trace(pool, &receipt).await?;
+            if self.config.external_event_require_trace && !db::trace_exists(pool, event.trace_id).await? {
+                return Err(ValidationError::new("trace_id", format!("trace {} does not exist", event.trace_id)).into());
+            }
+        }
+        // Looked up before the transaction, which then doesn't hold the trace lock while it waits
+        let sink = self.sink_for_trace(event.trace_id).await?;
 
-            // Store full receipt event
-            db::store_receipt_event(pool, &receipt).await?;
+        if let Some(ref pool) = self.db_pool {
+            // The sealed check and the insert share a transaction, so a seal can't land between them
+            let mut tx = pool.begin().await?;
+            if db::is_trace_sealed(&mut *tx, event.trace_id).await? {
+                return Err(TraceStateError::Sealed(event.trace_id).into());
+            }
+            // An event can arrive before any receipt of its trace; the trace is started for it, so that
+            // the event is counted and moves `last_event_at` like any other
+            db::upsert_trace_for_external_event(&mut *tx, &event).await?;
+            if !db::store_external_event(&mut *tx, &event).await? {
+                // Rolling back drops any trace started for the duplicate
+                let (event_id, trace_id) = db::find_external_event_by_source(
+                    &mut *tx,
+                    &event.source_system,
+                    &event.source_id,
+                )
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("external event {}/{} vanished", event.source_system, event.source_id))?;
+                return Ok(StoredExternalEvent { event_id, trace_id, stored: false });
+            }
+            tx.commit().await?;
+        }
+        self.metrics.external_event_ingested();
 
-            // Store hash for chain verification (backwards compatibility)
-            db::store_receipt_hash(pool, receipt.receipt_id, &receipt.receipt_hash).await?;
+        // Also send to Kafka for streaming consumers
+        let event_json = serde_json::to_string(&event)?;
+        if let Err(e) = sink.kafka.send_receipt(&event_json, &event.event_type, false).await {
+            self.metrics.kafka_send_failed();
+            tracing::warn!("Failed to send external event to Kafka: {}", e);
         }
 
-        // Send to Kafka (non-blocking, best effort)
-        if let Err(e) = self.kafka.send_receipt(&receipt_json).await {
-            tracing::warn!("Failed to send receipt to Kafka: {}", e);
+        Ok(StoredExternalEvent {
+            event_id: event.event_id,
+            trace_id: event.trace_id,
+            stored: true,
+        })
+    }
+
+    /// Ingest many external events in one transaction, skipping already-synced
+    /// `source_system`+`source_id` pairs and rejecting events for unknown traces
+    pub async fn store_external_events_batch(
+        &self,
+        requests: Vec<ExternalEventRequest>,
+    ) -> Result<Vec<ExternalEventResult>> {
+        if requests.len() > MAX_EXTERNAL_EVENT_BATCH {
+            return Err(ValidationError::new(
+                "events",
+                format!("batch exceeds {} events", MAX_EXTERNAL_EVENT_BATCH),
+            )
+            .into());
         }
 
-        // Archive to S3 (non-blocking, best effort)
-        if let Err(e) = self.s3.archive_receipt(&receipt_json).await {
-            tracing::warn!("Failed to archive receipt to S3: {}", e);
+        let received_at = Utc::now();
+        let mut results = Vec::with_capacity(requests.len());
+        let mut accepted = Vec::new();
+        let mut seen = HashSet::new();
+
+        let mut tx = match self.db_pool {
+            Some(ref pool) => Some(pool.begin().await?),
+            None => None,
+        };
+
+        for (index, request) in requests.into_iter().enumerate() {
+            if let Err(e) = self.validate_external_event_request(&request, received_at) {
+                results.push(ExternalEventResult {
+                    index,
+                    status: ExternalEventStatus::Rejected,
+                    event_id: None,
+                    error: Some(e.to_string()),
+                });
+                continue;
+            }
+
+            // Duplicates within the batch itself
+            if !seen.insert((request.source_system.clone(), request.source_id.clone())) {
+                results.push(ExternalEventResult {
+                    index,
+                    status: ExternalEventStatus::Duplicate,
+                    event_id: None,
+                    error: None,
+                });
+                continue;
+            }
+
+            if let Some(ref mut tx) = tx {
+                if !db::trace_exists(&mut **tx, request.trace_id).await? {
+                    results.push(ExternalEventResult {
+                        index,
+                        status: ExternalEventStatus::Rejected,
+                        event_id: None,
+                        error: Some(TraceStateError::NotFound(request.trace_id).to_string()),
+                    });
+                    continue;
+                }
+
+                if db::is_trace_sealed(&mut **tx, request.trace_id).await? {
+                    results.push(ExternalEventResult {
+                        index,
+                        status: ExternalEventStatus::Rejected,
+                        event_id: None,
+                        error: Some(TraceStateError::Sealed(request.trace_id).to_string()),
+                    });
+                    continue;
+                }
+            }
+
+            let event = ExternalEvent::from_request(request);
+            if let Some(ref mut tx) = tx {
+                // An event already synced, or synced by a concurrent batch, inserts nothing
+                if !db::store_external_event(&mut **tx, &event).await? {
+                    let existing = db::find_external_event_by_source(
+                        &mut **tx,
+                        &event.source_system,
+                        &event.source_id,
+                    )
+                    .await?;
+                    results.push(ExternalEventResult {
+                        index,
+                        status: ExternalEventStatus::Duplicate,
+                        event_id: existing.map(|(event_id, _)| event_id),
+                        error: None,
+                    });
+                    continue;
+                }
+            }
+
+            results.push(ExternalEventResult {
+                index,
+                status: ExternalEventStatus::Accepted,
+                event_id: Some(event.event_id),
+                error: None,
+            });
+            accepted.push(event);
         }
 
-        Ok(receipt)
+        if let Some(tx) = tx {
+            tx.commit().await?;
+        }
+
+        // Stream accepted events only once the batch is committed
+        for event in &accepted {
+            self.metrics.external_event_ingested();
+            // Never fall back to the default sink when the tenant's can't be resolved
+            let sink = match self.sink_for_trace(event.trace_id).await {
+                Ok(sink) => sink,
+                Err(e) => {
+                    tracing::warn!("Failed to resolve sink for external event {}: {}", event.event_id, e);
+                    continue;
+                }
+            };
+            let event_json = serde_json::to_string(event)?;
+            if let Err(e) = sink.kafka.send_receipt(&event_json, &event.event_type, false).await {
+                self.metrics.kafka_send_failed();
+                tracing::warn!("Failed to send external event to Kafka: {}", e);
+            }
+        }
+
+        Ok(results)
     }
 
-    pub async fn store_external_event(&self, request: ExternalEventRequest) -> Result<ExternalEvent> {
-        let event = ExternalEvent::from_request(request);
+    /// Close an active trace, giving it the status it finished with (`completed`, `denied` or `error`).
+    /// Returns that status and when the trace was closed
+    pub async fn close_trace(&self, trace_id: Uuid) -> Result<(String, DateTime<Utc>)> {
+        let pool = self.db_pool.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Database not configured"))?;
+
+        let mut tx = pool.begin().await?;
+        match db::lock_trace_for_close(&mut *tx, trace_id).await? {
+            None => return Err(TraceStateError::NotFound(trace_id).into()),
+            Some((_, true)) => return Err(TraceStateError::Sealed(trace_id).into()),
+            Some((status, false)) if status != "active" => {
+                return Err(TraceStateError::AlreadyClosed(trace_id, status).into());
+            }
+            Some(_) => {}
+        }
+
+        let closed = db::close_trace(&mut *tx, trace_id).await?;
+        tx.commit().await?;
+        Ok(closed)
+    }
+
+    /// Seal a trace: compute the Merkle root over its receipts, append a terminal
+    /// seal receipt and mark the trace completed so further appends are rejected
+    pub async fn seal_trace(&self, trace_id: Uuid) -> Result<TraceSeal> {
+        let pool = self.db_pool.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Database not configured"))?;
+
+        let sink = self.sink_for_trace(trace_id).await?;
+        let mut tx = pool.begin().await?;
+
+        match db::lock_trace_for_seal(&mut *tx, trace_id).await? {
+            None => return Err(TraceStateError::NotFound(trace_id).into()),
+            Some(true) => return Err(TraceStateError::Sealed(trace_id).into()),
+            Some(false) => {}
+        }
+
+        let hashes = db::get_trace_receipt_hashes(&mut *tx, trace_id).await?;
+        let seal_root = merkle_root(&hashes)?
+            .ok_or_else(|| ValidationError::new("trace_id", "trace has no receipts to seal"))?;
+        let previous_hash = db::get_latest_receipt_hash(&mut *tx).await?;
+        let sealed_at = Utc::now();
+
+        let receipt = Receipt::new(
+            ReceiptContent {
+                trace_id,
+                correlation_id: None,
+                span_id: Uuid::new_v4(),
+                parent_span_id: None,
+                agent_id: "pathwell-receipt-store".to_string(),
+                on_behalf_of: Vec::new(),
+                event_type: EventType::TraceSeal,
+                event_source: EventSource {
+                    system: "pathwell".to_string(),
+                    service: "receipt-store".to_string(),
+                    version: "1.0.0".to_string(),
+                },
+                request: RequestInfo {
+                    method: "POST".to_string(),
+                    path: format!("/v1/traces/{}/seal", trace_id),
+                    headers: Default::default(),
+                    body_hash: None,
+                    content_type: None,
+                    body: None,
+                },
+                policy_result: PolicyResult {
+                    allowed: true,
+                    policy_version: "seal".to_string(),
+                    evaluation_time_ms: 0,
+                },
+                identity_result: IdentityResult {
+                    valid: true,
+                    developer_id: Uuid::nil(),
+                    enterprise_id: None,
+                },
+                metadata: Some(serde_json::json!({
+                    "seal_root": seal_root,
+                    "receipt_count": hashes.len(),
+                })),
+                occurred_at: None,
+                latency: None,
+                usage: None,
+            },
+            self.sealing(previous_hash, sealed_at),
+        )?;
+
+        db::store_receipt_event(&mut *tx, &receipt, None, self.config.metadata_overflow_bytes).await?;
+        db::store_receipt_hash(&mut *tx, receipt.receipt_id, &receipt.receipt_hash).await?;
+        db::mark_trace_sealed(&mut *tx, trace_id, &seal_root, hashes.len() as i32, receipt.receipt_id).await?;
+
+        tx.commit().await?;
+
+        let receipt_json = serde_json::to_string(&receipt)?;
+        if let Err(e) = sink
+            .kafka
+            .send_receipt_keyed(&trace_id.to_string(), &receipt_json, &receipt.event_type, false)
+            .await
+        {
+            self.metrics.kafka_send_failed();
+            tracing::warn!("Failed to send seal receipt to Kafka: {}", e);
+        }
+        self.archive(sink, receipt.receipt_id, &receipt_json).await;
+
+        Ok(TraceSeal {
+            trace_id,
+            seal_root,
+            receipt_count: hashes.len(),
+            seal_receipt_id: receipt.receipt_id,
+            seal_receipt_hash: receipt.receipt_hash,
+            sealed_at,
+        })
+    }
+
+    /// Sink for a trace's tenant; the lookup is skipped when no tenant has its own sink
+    async fn sink_for_trace(&self, trace_id: Uuid) -> Result<&Sink> {
+        let tenant_id = match self.db_pool {
+            Some(ref pool) if self.sinks.has_tenant_sinks() => {
+                db::get_trace_tenant_id(pool, trace_id).await?
+            }
+            _ => None,
+        };
+        Ok(self.sinks.for_tenant(tenant_id))
+    }
+
+    /// Archive a receipt to S3 and record where it was written (best effort). With batched archival
+    /// the receipt is only queued, and its location is recorded when its batch is flushed
+    async fn archive(&self, sink: &Sink, receipt_id: Uuid, receipt_json: &str) {
+        if let Some(ref buffer) = self.archive_buffer {
+            buffer.push(sink.s3.bucket(), receipt_id, receipt_json.to_string());
+            return;
+        }
+
+        let key = match sink.s3.archive_receipt(receipt_id, receipt_json).await {
+            Ok(key) => key,
+            Err(e) => {
+                self.metrics.s3_archive_failed(1);
+                tracing::warn!("Failed to archive receipt {} to S3: {}", receipt_id, e);
+                return;
+            }
+        };
 
         if let Some(ref pool) = self.db_pool {
-            db::store_external_event(pool, &event).await?;
+            if let Err(e) = db::set_receipt_s3_location(pool, receipt_id, sink.s3.bucket(), &key).await {
+                tracing::warn!("Failed to record S3 key for receipt {}: {}", receipt_id, e);
+            }
         }
+    }
 
-        // Also send to Kafka for streaming consumers
-        let event_json = serde_json::to_string(&event)?;
-        if let Err(e) = self.kafka.send_receipt(&event_json).await {
-            tracing::warn!("Failed to send external event to Kafka: {}", e);
+    /// Write every queued receipt to S3, one object per batch, and record where each was written (best
+    /// effort: a batch that fails to write is logged and dropped). Returns how many receipts were archived
+    pub async fn flush_archive(&self) -> usize {
+        let Some(ref buffer) = self.archive_buffer else {
+            return 0;
+        };
+
+        let mut archived = 0;
+        for (bucket, queued) in buffer.take() {
+            let Some(s3) = self.sinks.archiver_for_bucket(Some(&bucket)) else {
+                tracing::warn!("No sink for bucket {}; dropping {} queued receipts", bucket, queued.len());
+                continue;
+            };
+            for batch in queued.chunks(buffer.batch_size()) {
+                let key = match s3.archive_batch(batch).await {
+                    Ok(key) => key,
+                    Err(e) => {
+                        self.metrics.s3_archive_failed(batch.len() as u64);
+                        tracing::warn!("Failed to archive a batch of {} receipts to S3: {}", batch.len(), e);
+                        continue;
+                    }
+                };
+                archived += batch.len();
+
+                if let Some(ref pool) = self.db_pool {
+                    let receipt_ids: Vec<Uuid> = batch.iter().map(|(id, _)| *id).collect();
+                    if let Err(e) = db::set_receipts_s3_location(pool, &receipt_ids, &bucket, &key).await {
+                        tracing::warn!("Failed to record S3 key {} for {} receipts: {}", key, receipt_ids.len(), e);
+                    }
+                }
+            }
+        }
+        archived
+    }
+
+    /// Create a presigned download URL for an archived receipt
+    pub async fn presign_archive_url(&self, bucket: Option<&str>, key: &str) -> Result<String> {
+        let s3 = self.sinks.archiver_for_bucket(bucket).ok_or_else(|| {
+            anyhow::anyhow!("No sink configured for bucket {}", bucket.unwrap_or_default())
+        })?;
+        s3.presign_get(key, std::time::Duration::from_secs(self.config.archive_url_expiry_secs))
+            .await
+    }
+
+    pub fn trust_score_precision(&self) -> u32 {
+        self.config.trust_score_precision
+    }
+
+    pub fn archive_url_expiry_secs(&self) -> u64 {
+        self.config.archive_url_expiry_secs
+    }
+
+    pub fn retention(&self) -> &RetentionConfig {
+        &self.config.retention
+    }
+
+    pub fn retention_purge(&self) -> &RetentionPurgeConfig {
+        &self.config.retention_purge
+    }
+
+    pub fn trace_sweep(&self) -> &TraceSweepConfig {
+        &self.config.trace_sweep
+    }
+
+    pub fn job_heartbeat_secs(&self) -> u64 {
+        self.config.job_heartbeat_secs
+    }
+
+    pub fn strict_receipt_fields(&self) -> bool {
+        self.config.strict_receipt_fields
+    }
+
+    pub fn metadata_overflow_bytes(&self) -> Option<usize> {
+        self.config.metadata_overflow_bytes
+    }
+
+    /// How a receipt following `previous_receipt_hash` is hashed and signed with this store's settings
+    pub fn sealing(&self, previous_receipt_hash: Option<String>, received_at: DateTime<Utc>) -> ReceiptSealing<'_> {
+        ReceiptSealing {
+            previous_receipt_hash,
+            received_at,
+            hash_canonicalization: self.config.hash_canonicalization,
+            hash_algorithm: &self.config.hash_algorithm,
+            signer: self.signer(),
         }
+    }
+
+    pub fn archive_buffer(&self) -> Option<&ArchiveBuffer> {
+        self.archive_buffer.as_ref()
+    }
 
-        Ok(event)
+    pub fn archive_flush_interval_secs(&self) -> u64 {
+        self.config.archive_flush_interval_secs
+    }
+
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
     }
 
     pub fn db_pool(&self) -> Option<&PgPool> {
         self.db_pool.as_ref()
     }
 
+    pub fn sinks(&self) -> &SinkRouter {
+        &self.sinks
+    }
+
+    pub fn signer(&self) -> Option<&ReceiptSigner> {
+        self.signer.as_ref()
+    }
+
+    pub fn stats_cache(&self) -> &StatsCache {
+        &self.stats_cache
+    }
+
+    /// Whether `key` may call the admin endpoints
+    pub fn is_admin_key(&self, key: &str) -> bool {
+        self.config.admin_api_keys.iter().any(|k| k == key)
+    }
+
     /// Store a v2 receipt with trust and attribution context
-    pub async fn store_receipt_v2(&self, request: ReceiptRequestV2) -> Result<ReceiptV2> {
+    pub async fn store_receipt_v2(&self, mut request: ReceiptRequestV2) -> Result<ReceiptV2> {
+        // Reject malformed requests before anything is persisted
+        let received_at = Utc::now();
+        let mut validator = Validator::new();
+        validate_receipt_fields(
+            &mut validator,
+            ReceiptFields {
+                agent_id: &request.agent_id,
+                correlation_id: request.correlation_id.as_deref(),
+                event_type: request.event_type.as_ref(),
+                event_source: request.event_source.as_ref(),
+                request: &request.request,
+                policy_version: &request.policy_result.policy_version,
+                evaluation_time_ms: request.policy_result.evaluation_time_ms,
+            },
+        );
+        validate_on_behalf_of(&mut validator, &request.on_behalf_of);
+        if let Some(ref usage) = request.usage {
+            validate_usage(&mut validator, usage);
+        }
+        if let Some(occurred_at) = request.occurred_at {
+            validate_occurred_at(
+                &mut validator,
+                occurred_at,
+                received_at,
+                self.config.max_future_skew_secs,
+                self.config.max_backdate_secs,
+            );
+        }
+        validator.finish()?;
+
+        // The sealed check, the receipt and its trust bookkeeping share a transaction, so a failure partway
+        // can't leave a violation counted without its trust event (or a seal land before the insert)
+        let mut tx = match self.db_pool {
+            Some(ref pool) => Some(pool.begin().await?),
+            None => None,
+        };
+
+        if let (Some(ref mut tx), Some(trace_id)) = (&mut tx, request.trace_id) {
+            if db::is_trace_sealed(&mut **tx, trace_id).await? {
+                return Err(TraceStateError::Sealed(trace_id).into());
+            }
+        }
+
+        self.body_capture
+            .apply(request.identity_result.tenant_id, &mut request.request);
+
         // Get previous receipt hash for chain
-        let previous_hash = if let Some(ref pool) = self.db_pool {
-            db::get_latest_receipt_hash(pool).await?
-        } else {
-            None
+        let previous_hash = match tx {
+            Some(ref mut tx) => db::get_latest_receipt_hash(&mut **tx).await?,
+            None => None,
         };
 
         // Generate or use provided trace context
@@ -129,36 +906,70 @@ impl ReceiptStore {
 
         // Create v2 receipt with trust and attribution
         let receipt = ReceiptV2::new(
-            trace_id,
-            request.correlation_id.clone(),
-            span_id,
-            request.parent_span_id,
-            request.agent_id.clone(),
-            event_type,
-            event_source,
-            request.request,
-            request.policy_result.clone(),
-            request.identity_result.clone(),
-            request.metadata,
-            previous_hash,
-        );
+            ReceiptContent {
+                trace_id,
+                correlation_id: request.correlation_id.clone(),
+                span_id,
+                parent_span_id: request.parent_span_id,
+                agent_id: request.agent_id.clone(),
+                on_behalf_of: request.on_behalf_of,
+                event_type,
+                event_source,
+                request: request.request,
+                policy_result: request.policy_result.clone(),
+                identity_result: request.identity_result.clone(),
+                metadata: request.metadata,
+                occurred_at: request.occurred_at,
+                latency: request.latency,
+                usage: request.usage,
+            },
+            self.sealing(previous_hash, received_at),
+        )?;
 
         // Serialize receipt
         let receipt_json = serde_json::to_string(&receipt)?;
+        let sink = self.sinks.for_tenant(receipt.tenant_id);
+        let mut violation_alert = None;
 
         // Store in database if available
-        if let Some(ref pool) = self.db_pool {
+        if let Some(mut tx) = tx {
             // Ensure trace exists (create or update with trust metrics)
-            db::upsert_trace_v2(pool, &receipt).await?;
+            db::upsert_trace_v2(&mut *tx, &receipt, self.config.trust_score_precision).await?;
 
             // Store full receipt event with trust/attribution
-            db::store_receipt_event_v2(pool, &receipt).await?;
+            db::store_receipt_event_v2(
+                &mut *tx,
+                &receipt,
+                self.config.trust_score_precision,
+                self.config.metadata_overflow_bytes,
+            ).await?;
 
             // Store hash for chain verification
-            db::store_receipt_hash(pool, receipt.receipt_id, &receipt.receipt_hash).await?;
+            db::store_receipt_hash(&mut *tx, receipt.receipt_id, &receipt.receipt_hash).await?;
 
             // If there was a trust evaluation, store trust event
             if let Some(ref trust_eval) = request.policy_result.trust_evaluation {
+                let now = Utc::now();
+
+                // Track consecutive violations; past the threshold the action escalates to a block
+                let consecutive_violations = if trust_eval.passed {
+                    db::reset_trust_violations(&mut *tx, &request.agent_id).await?;
+                    0
+                } else {
+                    db::record_trust_violation(
+                        &mut *tx,
+                        &request.agent_id,
+                        now,
+                        self.config.trust_escalation_window_secs,
+                    ).await?
+                };
+                let escalated = consecutive_violations >= self.config.trust_escalation_threshold;
+                let action_taken = if escalated {
+                    Some("block".to_string())
+                } else {
+                    trust_eval.action_taken.clone()
+                };
+
                 let trust_event = TrustEvent {
                     event_id: Uuid::new_v4(),
                     trace_id,
@@ -170,36 +981,107 @@ impl ReceiptStore {
                     } else {
                         TrustEventType::ScoreChecked
                     },
-                    timestamp: Utc::now(),
+                    timestamp: now,
                     previous_score: None,
                     new_score: trust_eval.trust_score.unwrap_or(0.5),
                     threshold: trust_eval.threshold,
                     passed: trust_eval.passed,
-                    action_taken: trust_eval.action_taken.clone(),
+                    action_taken,
                     details: serde_json::json!({
                         "warnings": request.policy_result.warnings,
                         "tenant_policy": request.policy_result.tenant_policy_applied,
+                        "consecutive_violations": consecutive_violations,
+                        "escalated": escalated,
+                        "original_action": trust_eval.action_taken,
                     }),
                 };
-                db::store_trust_event(pool, &trust_event).await?;
+                db::store_trust_event(&mut *tx, &trust_event, self.config.trust_score_precision).await?;
+
+                // Open a risk event once, when the agent first crosses the threshold
+                if consecutive_violations == self.config.trust_escalation_threshold {
+                    let risk_event = TrustRiskEvent {
+                        risk_id: Uuid::new_v4(),
+                        agent_id: request.agent_id.clone(),
+                        trace_id: Some(trace_id),
+                        risk_type: "repeated_trust_violations".to_string(),
+                        severity: "high".to_string(),
+                        description: format!(
+                            "Agent {} exceeded the trust threshold {} consecutive times",
+                            request.agent_id, consecutive_violations
+                        ),
+                        evidence: serde_json::json!({
+                            "trust_event_id": trust_event.event_id,
+                            "trust_score": trust_eval.trust_score,
+                            "threshold": trust_eval.threshold,
+                            "window_secs": self.config.trust_escalation_window_secs,
+                        }),
+                        created_at: now,
+                    };
+                    if !db::store_risk_event(&mut *tx, &risk_event).await? {
+                        tracing::warn!(
+                            "Agent {} isn't registered; no risk event recorded for its escalation",
+                            request.agent_id
+                        );
+                    }
+                    tracing::warn!(
+                        "Escalated trust violations for agent {} to block ({} consecutive)",
+                        request.agent_id, consecutive_violations
+                    );
+                }
 
                 // Increment trust violations if threshold was not passed
                 if !trust_eval.passed {
-                    db::increment_trust_violations(pool, trace_id).await?;
+                    db::increment_trust_violations(&mut *tx, trace_id).await?;
+                    violation_alert = Some(TrustViolationAlert {
+                        event_id: trust_event.event_id,
+                        receipt_id: receipt.receipt_id,
+                        trace_id,
+                        agent_id: trust_event.agent_id,
+                        tenant_id: receipt.tenant_id,
+                        timestamp: now,
+                        trust_score: trust_event.new_score,
+                        threshold: trust_event.threshold,
+                        action_taken: trust_event.action_taken,
+                        consecutive_violations,
+                        escalated,
+                    });
                 }
             }
+
+            tx.commit().await?;
+        }
+
+        self.metrics.receipt_stored();
+        if violation_alert.is_some() {
+            self.metrics.trust_violation_recorded();
         }
 
         // Send to Kafka (non-blocking, best effort)
-        if let Err(e) = self.kafka.send_receipt(&receipt_json).await {
+        let denied = !receipt.policy_result.allowed;
+        if let Err(e) = sink
+            .kafka
+            .send_receipt_keyed(&receipt.trace_id.to_string(), &receipt_json, &receipt.event_type, denied)
+            .await
+        {
+            self.metrics.kafka_send_failed();
             tracing::warn!("Failed to send receipt to Kafka: {}", e);
         }
 
-        // Archive to S3 (non-blocking, best effort)
-        if let Err(e) = self.s3.archive_receipt(&receipt_json).await {
-            tracing::warn!("Failed to archive receipt to S3: {}", e);
+        // Alert on violations through their own topic, keyed by agent so an agent's violations stay in order
+        if let (Some(topic), Some(alert)) = (self.config.kafka_trust_topic.as_deref(), violation_alert) {
+            let sent = match serde_json::to_string(&alert) {
+                Ok(alert_json) => sink.kafka.send_to_topic(topic, &alert.agent_id, &alert_json).await,
+                Err(e) => Err(e.into()),
+            };
+            if let Err(e) = sent {
+                self.metrics.kafka_send_failed();
+                tracing::warn!("Failed to send trust violation to Kafka topic {}: {}", topic, e);
+            }
         }
 
+        // Archive to S3 (non-blocking, best effort)
+        self.archive(sink, receipt.receipt_id, &receipt_json).await;
+
         Ok(receipt)
     }
 }
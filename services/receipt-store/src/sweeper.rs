@@ -0,0 +1,169 @@
+//! Trace inactivity sweeper: traces stay `active` while events keep arriving, and nothing else
+//! closes them. With `TRACE_INACTIVITY_SECS` set, a background task periodically completes the
+//! traces that have had no event for that long, recomputing their aggregates from the stored
+//! events, and optionally seals them.
+//!
+//! Every replica runs the task as a background job, so one replica sweeps at a time. Completing a
+//! trace is a single guarded update, so an interrupted or repeated sweep leaves nothing half done.
+
+use anyhow::Result;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::TraceSweepConfig;
+use crate::jobs::{self, Job};
+use crate::store::{ReceiptStore, TraceStateError};
+use crate::validation::ValidationError;
+
+/// Complete up to `$2` active, unsealed traces whose last event is older than `$1` seconds,
+/// resetting their counts, outcome and usage totals from their stored events. Traces taking an
+/// event right now are skipped until the next sweep
+const COMPLETE_STALE_TRACES: &str = r#"
+    WITH stale AS (
+        SELECT trace_id
+        FROM traces
+        WHERE status = 'active'
+          AND sealed_at IS NULL
+          AND last_event_at < NOW() - make_interval(secs => $1::float8)
+        ORDER BY last_event_at
+        LIMIT $2
+        FOR UPDATE SKIP LOCKED
+    ),
+    receipt_totals AS (
+        SELECT
+            e.trace_id,
+            COUNT(*) AS receipts,
+            COUNT(*) FILTER (WHERE e.policy_allowed = false) AS denials,
+            COUNT(*) FILTER (WHERE e.event_type <> 'trace_seal'
+                               AND NOT receipt_errored(COALESCE(e.metadata, m.metadata))
+                               AND e.policy_allowed IS DISTINCT FROM false) AS allowed,
+            COUNT(*) FILTER (WHERE e.event_type <> 'trace_seal'
+                               AND NOT receipt_errored(COALESCE(e.metadata, m.metadata))
+                               AND e.policy_allowed = false) AS denied,
+            COUNT(*) FILTER (WHERE e.event_type <> 'trace_seal'
+                               AND receipt_errored(COALESCE(e.metadata, m.metadata))) AS errored,
+            COALESCE(SUM(e.usage_input_tokens), 0) AS input_tokens,
+            COALESCE(SUM(e.usage_output_tokens), 0) AS output_tokens,
+            COALESCE(SUM(e.usage_cost), 0) AS cost
+        FROM receipt_events e
+        LEFT JOIN receipt_metadata m ON m.receipt_id = e.receipt_id
+        WHERE e.trace_id IN (SELECT trace_id FROM stale)
+        GROUP BY e.trace_id
+    ),
+    external_totals AS (
+        SELECT trace_id, COUNT(*) AS events
+        FROM external_events
+        WHERE trace_id IN (SELECT trace_id FROM stale)
+        GROUP BY trace_id
+    )
+    UPDATE traces t
+    SET
+        status = closed_trace_status(COALESCE(r.denials, 0)::int, COALESCE(r.errored, 0)::int),
+        completed_at = NOW(),
+        event_count = COALESCE(r.receipts, 0) + COALESCE(x.events, 0),
+        policy_deny_count = COALESCE(r.denials, 0),
+        allowed_receipt_count = COALESCE(r.allowed, 0),
+        denied_receipt_count = COALESCE(r.denied, 0),
+        errored_receipt_count = COALESCE(r.errored, 0),
+        outcome = trace_outcome(
+            COALESCE(r.allowed, 0)::int,
+            COALESCE(r.denied, 0)::int,
+            COALESCE(r.errored, 0)::int
+        ),
+        total_input_tokens = COALESCE(r.input_tokens, 0),
+        total_output_tokens = COALESCE(r.output_tokens, 0),
+        total_cost = COALESCE(r.cost, 0),
+        updated_at = NOW()
+    FROM stale s
+    LEFT JOIN receipt_totals r ON r.trace_id = s.trace_id
+    LEFT JOIN external_totals x ON x.trace_id = s.trace_id
+    WHERE t.trace_id = s.trace_id
+    RETURNING t.trace_id
+"#;
+
+/// Up to `$1` closed traces that haven't been sealed yet, oldest first. Traces without
+/// receipts have nothing to seal
+const UNSEALED_COMPLETED_TRACES: &str = r#"
+    SELECT t.trace_id
+    FROM traces t
+    WHERE t.status <> 'active'
+      AND t.sealed_at IS NULL
+      AND t.completed_at IS NOT NULL
+      AND EXISTS (SELECT 1 FROM receipt_events e WHERE e.trace_id = t.trace_id)
+    ORDER BY t.completed_at
+    LIMIT $1
+"#;
+
+/// Start sweeping in the background, if a database is configured and the sweeper is enabled
+pub fn spawn(store: Arc<ReceiptStore>) {
+    let config = store.trace_sweep().clone();
+    let (Some(pool), Some(inactivity_secs)) = (store.db_pool().cloned(), config.inactivity_secs) else {
+        return;
+    };
+    info!(
+        "Trace sweeper: completing traces inactive for {}s, every {}s{}",
+        inactivity_secs,
+        config.interval_secs,
+        if config.seal { ", sealing them" } else { "" }
+    );
+
+    let job = Job {
+        name: "trace_sweep",
+        interval: Duration::from_secs(config.interval_secs),
+        heartbeat: Duration::from_secs(store.job_heartbeat_secs()),
+    };
+    jobs::spawn(pool.clone(), job, move || {
+        let (store, pool, config) = (store.clone(), pool.clone(), config.clone());
+        async move { sweep(&store, &pool, &config, inactivity_secs).await }
+    });
+}
+
+/// One sweep, run under the job's lock: complete the stale traces, then seal a batch if configured
+async fn sweep(store: &ReceiptStore, pool: &PgPool, config: &TraceSweepConfig, inactivity_secs: u64) -> Result<()> {
+    let completed = complete_stale_traces(pool, config, inactivity_secs).await?;
+    let sealed = if config.seal { seal_completed_traces(store, pool, config).await? } else { 0 };
+    if completed > 0 {
+        info!("Trace sweep completed {} trace(s), sealed {}", completed, sealed);
+    }
+    Ok(())
+}
+
+async fn complete_stale_traces(pool: &PgPool, config: &TraceSweepConfig, inactivity_secs: u64) -> Result<usize> {
+    let mut completed = 0;
+    loop {
+        let batch: Vec<Uuid> = sqlx::query_scalar(COMPLETE_STALE_TRACES)
+            .bind(inactivity_secs as f64)
+            .bind(config.batch_size)
+            .fetch_all(pool)
+            .await?;
+        completed += batch.len();
+        if (batch.len() as i64) < config.batch_size {
+            return Ok(completed);
+        }
+    }
+}
+
+/// Seal one batch of completed traces. Sealing is a separate step so traces completed by an earlier,
+/// interrupted sweep are still sealed; a trace that fails to seal is retried on the next sweep
+async fn seal_completed_traces(store: &ReceiptStore, pool: &PgPool, config: &TraceSweepConfig) -> Result<usize> {
+    let batch: Vec<Uuid> = sqlx::query_scalar(UNSEALED_COMPLETED_TRACES)
+        .bind(config.batch_size)
+        .fetch_all(pool)
+        .await?;
+
+    let mut sealed = 0;
+    for trace_id in batch {
+        match store.seal_trace(trace_id).await {
+            Ok(_) => sealed += 1,
+            // Sealed by hand in the meantime
+            Err(e)
+                if e.is::<ValidationError>()
+                    || matches!(e.downcast_ref::<TraceStateError>(), Some(TraceStateError::Sealed(_))) => {}
+            Err(e) => warn!("Trace sweep could not seal trace {}: {}", trace_id, e),
+        }
+    }
+    Ok(sealed)
+}
@@ -0,0 +1,226 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::receipt::{DelegationLink, EventSource, EventType, RequestInfo, Usage};
+
+const ALLOWED_METHODS: &[&str] = &["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"];
+const PRINCIPAL_TYPES: &[&str] = &["agent", "developer"];
+const MAX_DELEGATION_DEPTH: usize = 5;
+
+/// A single rejected request field
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// One or more rejected request fields, surfaced to clients as a 422
+#[derive(Debug, thiserror::Error)]
+#[error("{}", summary(.errors))]
+pub struct ValidationError {
+    pub errors: Vec<FieldError>,
+}
+
+fn summary(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(|e| format!("{}: {}", e.field, e.message))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+impl ValidationError {
+    pub fn new(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            errors: vec![FieldError {
+                field: field.to_string(),
+                message: message.into(),
+            }],
+        }
+    }
+}
+
+/// Collects field errors while validating a request
+#[derive(Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reject(&mut self, field: &str, message: impl Into<String>) {
+        self.errors.push(FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        });
+    }
+
+    pub fn required(&mut self, field: &str, value: &str, max_len: usize) {
+        if value.trim().is_empty() {
+            self.reject(field, "must not be empty");
+        } else {
+            self.max_len(field, value, max_len);
+        }
+    }
+
+    pub fn max_len(&mut self, field: &str, value: &str, max_len: usize) {
+        if value.len() > max_len {
+            self.reject(field, format!("must be at most {} characters", max_len));
+        }
+    }
+
+    pub fn finish(self) -> Result<(), ValidationError> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError { errors: self.errors })
+        }
+    }
+}
+
+/// The fields shared by v1 and v2 receipt requests
+#[derive(Clone, Copy)]
+pub struct ReceiptFields<'a> {
+    pub agent_id: &'a str,
+    pub correlation_id: Option<&'a str>,
+    pub event_type: Option<&'a EventType>,
+    pub event_source: Option<&'a EventSource>,
+    pub request: &'a RequestInfo,
+    pub policy_version: &'a str,
+    pub evaluation_time_ms: u64,
+}
+
+/// Validate the fields shared by v1 and v2 receipt requests (limits match the receipt_events columns)
+pub fn validate_receipt_fields(v: &mut Validator, fields: ReceiptFields<'_>) {
+    let ReceiptFields {
+        agent_id,
+        correlation_id,
+        event_type,
+        event_source,
+        request,
+        policy_version,
+        evaluation_time_ms,
+    } = fields;
+
+    v.required("agent_id", agent_id, 255);
+    if let Some(correlation_id) = correlation_id {
+        v.max_len("correlation_id", correlation_id, 255);
+    }
+    if let Some(event_type) = event_type {
+        v.required("event_type", event_type.as_str(), 50);
+    }
+    if let Some(source) = event_source {
+        v.required("event_source.system", &source.system, 100);
+        v.required("event_source.service", &source.service, 100);
+        v.max_len("event_source.version", &source.version, 50);
+    }
+
+    if !ALLOWED_METHODS.contains(&request.method.as_str()) {
+        v.reject("request.method", format!("must be one of {}", ALLOWED_METHODS.join(", ")));
+    }
+    if !request.path.starts_with('/') {
+        v.reject("request.path", "must start with '/'");
+    }
+    v.max_len("request.path", &request.path, 2048);
+    if let Some(ref body_hash) = request.body_hash {
+        if body_hash.len() != 64 || !body_hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            v.reject("request.body_hash", "must be a hex-encoded SHA-256 digest");
+        }
+    }
+    if let Some(ref body) = request.body {
+        // A captured body must be the one the hash (and so the receipt) vouches for
+        let digest = hex::encode(Sha256::digest(body.as_bytes()));
+        match request.body_hash {
+            None => v.reject("request.body_hash", "is required when request.body is captured"),
+            Some(ref body_hash) if !body_hash.eq_ignore_ascii_case(&digest) => {
+                v.reject("request.body", "does not match request.body_hash")
+            }
+            Some(_) => {}
+        }
+    }
+
+    v.required("policy_result.policy_version", policy_version, 50);
+    if evaluation_time_ms > i32::MAX as u64 {
+        v.reject("policy_result.evaluation_time_ms", "is out of range");
+    }
+}
+
+/// Validate an on-behalf-of chain (limits match the identity registry)
+pub fn validate_on_behalf_of(v: &mut Validator, on_behalf_of: &[DelegationLink]) {
+    if on_behalf_of.len() > MAX_DELEGATION_DEPTH {
+        v.reject(
+            "on_behalf_of",
+            format!("must have at most {} principals", MAX_DELEGATION_DEPTH),
+        );
+    }
+    for (i, link) in on_behalf_of.iter().enumerate() {
+        if !PRINCIPAL_TYPES.contains(&link.principal_type.as_str()) {
+            v.reject(
+                &format!("on_behalf_of[{}].principal_type", i),
+                format!("must be one of {}", PRINCIPAL_TYPES.join(", ")),
+            );
+        }
+        v.required(&format!("on_behalf_of[{}].principal_id", i), &link.principal_id, 255);
+    }
+}
+
+/// Validate reported usage (limits match the receipt_events columns)
+pub fn validate_usage(v: &mut Validator, usage: &Usage) {
+    let tokens = [
+        ("usage.input_tokens", usage.input_tokens),
+        ("usage.output_tokens", usage.output_tokens),
+    ];
+    for (field, value) in tokens {
+        if value.is_some_and(|t| t > i64::MAX as u64) {
+            v.reject(field, "is out of range");
+        }
+    }
+    if let Some(cost) = usage.cost {
+        if !cost.is_finite() || !(0.0..1e12).contains(&cost) {
+            v.reject("usage.cost", "must be a non-negative amount below 1e12");
+        }
+    }
+}
+
+/// Check that an external event's timestamp isn't further in the future than its source's clock could
+/// plausibly be off
+pub fn validate_event_timestamp(
+    v: &mut Validator,
+    timestamp: DateTime<Utc>,
+    received_at: DateTime<Utc>,
+    max_future_skew_secs: i64,
+) {
+    if timestamp > received_at + Duration::seconds(max_future_skew_secs) {
+        v.reject(
+            "timestamp",
+            format!("is more than {}s in the future", max_future_skew_secs),
+        );
+    }
+}
+
+/// Check that a client-supplied event time falls within the accepted skew window
+pub fn validate_occurred_at(
+    v: &mut Validator,
+    occurred_at: DateTime<Utc>,
+    received_at: DateTime<Utc>,
+    max_future_skew_secs: i64,
+    max_backdate_secs: i64,
+) {
+    if occurred_at > received_at + Duration::seconds(max_future_skew_secs) {
+        v.reject(
+            "occurred_at",
+            format!("is more than {}s in the future", max_future_skew_secs),
+        );
+    }
+
+    if occurred_at < received_at - Duration::seconds(max_backdate_secs) {
+        v.reject(
+            "occurred_at",
+            format!("is more than {}s in the past", max_backdate_secs),
+        );
+    }
+}